@@ -2,27 +2,100 @@ use crate::git::{
     AuthConfig, AuthManager, CloneManager, CloneOptions, CloneResult, CommitHistoryItem,
     RepositoryStatus,
 };
+use crate::utils::git_process::{GitProcessOutcome, GitProcesses};
+use crate::utils::progress::ProgressThrottle;
+use crate::utils::repo_lock::RepositoryLocks;
+use crate::utils::system_command::build_git_ssh_command;
 use git2::Repository;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::path::Path;
 
 use std::sync::Mutex;
-use tauri::{command, AppHandle, State, Window};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, Manager, State, Window};
 use tauri_plugin_store::StoreBuilder;
 
+/// 索引变更事件名称
+pub const INDEX_CHANGED_EVENT: &str = "repository-index-changed";
+
+/// 合并连续索引变更事件的等待时间
+const INDEX_EVENT_DEBOUNCE: Duration = Duration::from_millis(150);
+
 /// Git 命令状态管理
 pub struct GitState {
     pub clone_operations: Mutex<HashMap<String, bool>>,
+    /// 每个仓库尚未发送的索引变更
+    pending_index_changes: Mutex<HashMap<String, PendingIndexChange>>,
+    /// 仓库写操作锁，同一仓库的写操作排队执行
+    ///
+    /// 获取该锁的命令：暂存/取消暂存、提交、改写提交、储藏、拉取、推送、抓取、裁剪、
+    /// 合并及其继续/中止、状态清理、变基、垃圾回收，以及分支的创建、切换、删除和上游设置
+    pub repo_locks: RepositoryLocks,
+    /// 正在运行的系统Git子进程，可通过 `cancel_git_operation` 取消
+    pub git_processes: GitProcesses,
 }
 
 impl Default for GitState {
     fn default() -> Self {
         Self {
             clone_operations: Mutex::new(HashMap::new()),
+            pending_index_changes: Mutex::new(HashMap::new()),
+            repo_locks: RepositoryLocks::default(),
+            git_processes: GitProcesses::default(),
         }
     }
 }
 
+/// 等待合并发送的索引变更
+#[derive(Default)]
+struct PendingIndexChange {
+    /// 每次变更递增，用于判断等待期间是否有新的变更
+    generation: u64,
+    files: BTreeSet<String>,
+}
+
+/// 通知前端仓库索引已变更
+///
+/// 短时间内的多次变更会合并为一个事件，只在最后一次变更后等待期结束时发送
+fn notify_index_changed(app_handle: &AppHandle, repo_path: &str, files: &[String]) {
+    let generation = {
+        let state = app_handle.state::<GitState>();
+        let mut pending = state.pending_index_changes.lock().unwrap();
+        let change = pending.entry(repo_path.to_string()).or_default();
+        change.generation += 1;
+        change.files.extend(files.iter().cloned());
+        change.generation
+    };
+
+    let app_handle = app_handle.clone();
+    let repo_path = repo_path.to_string();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(INDEX_EVENT_DEBOUNCE).await;
+
+        let files = {
+            let state = app_handle.state::<GitState>();
+            let mut pending = state.pending_index_changes.lock().unwrap();
+            match pending.get(&repo_path) {
+                Some(change) if change.generation == generation => {
+                    pending.remove(&repo_path).map(|change| change.files)
+                }
+                // 等待期间有新的变更，由之后的任务发送
+                _ => None,
+            }
+        };
+
+        if let Some(files) = files {
+            let event = crate::git::types::IndexChangedEvent {
+                repo_path,
+                files: files.into_iter().collect(),
+            };
+            if let Err(e) = app_handle.emit(INDEX_CHANGED_EVENT, &event) {
+                log::warn!("发送索引变更事件失败: {}", e);
+            }
+        }
+    });
+}
+
 /// 克隆 Git 仓库
 #[command]
 pub async fn clone_repository(
@@ -54,6 +127,36 @@ pub async fn clone_repository(
     }
 }
 
+/// 智能克隆仓库：未指定认证信息的 HTTPS 仓库自动使用该域名已存储的 Token
+///
+/// 未找到 Token 时按公开仓库处理，不使用认证
+#[command]
+pub async fn smart_clone_repository(
+    app_handle: AppHandle,
+    window: Window,
+    mut options: CloneOptions,
+) -> Result<CloneResult, String> {
+    if options.auth.is_none() {
+        match get_token_for_url(&app_handle, &options.url).await {
+            Ok(Some(token)) => {
+                log::debug!("使用已存储的Token克隆仓库: {}", options.url);
+                options.auth = Some(AuthConfig {
+                    auth_type: crate::git::types::AuthType::Token,
+                    username: None,
+                    password: None,
+                    token: Some(token),
+                    ssh_key_path: None,
+                    ssh_key_passphrase: None,
+                });
+            }
+            Ok(None) => log::debug!("未找到可用的Token，按公开仓库克隆: {}", options.url),
+            Err(e) => log::warn!("获取Token失败，按公开仓库克隆: {}", e),
+        }
+    }
+
+    clone_repository(window, options).await
+}
+
 /// 验证仓库 URL
 #[command]
 pub async fn validate_repository_url(url: String) -> Result<bool, String> {
@@ -67,8 +170,8 @@ pub async fn validate_repository_url(url: String) -> Result<bool, String> {
     // 检查 URL 格式
     let is_valid = url.starts_with("http://")
         || url.starts_with("https://")
-        || url.starts_with("git@")
-        || url.starts_with("ssh://");
+        || url.starts_with("ssh://")
+        || crate::git::operations::split_scp_url(&url).is_some();
 
     if !is_valid {
         return Ok(false);
@@ -79,6 +182,58 @@ pub async fn validate_repository_url(url: String) -> Result<bool, String> {
     Ok(true)
 }
 
+/// 解析仓库 URL，返回协议、主机、所有者/仓库名及建议的克隆目录名
+#[command]
+pub async fn parse_repository_url(url: String) -> Result<crate::git::types::ParsedRepoUrl, String> {
+    log::debug!("解析仓库 URL: {}", url);
+    Ok(crate::git::operations::parse_repository_url(&url))
+}
+
+/// 测试仓库的连通性和认证
+#[command]
+pub async fn test_repository_access(
+    url: String,
+    auth: Option<AuthConfig>,
+) -> Result<crate::git::types::AccessResult, String> {
+    log::debug!("测试仓库访问: {}", url);
+
+    // 网络操作在阻塞任务中执行
+    tokio::task::spawn_blocking(move || {
+        crate::git::operations::test_repository_access(&url, auth.as_ref())
+    })
+    .await
+    .map_err(|e| {
+        log::error!("任务执行失败: {}", e);
+        format!("任务执行失败: {}", e)
+    })
+}
+
+/// 在不克隆的情况下列出远程分支和标签
+#[command]
+pub async fn list_remote_refs(
+    url: String,
+    auth: Option<AuthConfig>,
+) -> Result<Vec<crate::git::types::RemoteRef>, String> {
+    log::debug!("列出远程引用: {}", url);
+
+    let result = tokio::task::spawn_blocking(move || {
+        crate::git::operations::list_remote_refs(&url, auth.as_ref())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(refs)) => Ok(refs),
+        Ok(Err(e)) => {
+            log::error!("列出远程引用失败: {}", e);
+            Err(e.to_string())
+        }
+        Err(e) => {
+            log::error!("任务执行失败: {}", e);
+            Err(format!("任务执行失败: {}", e))
+        }
+    }
+}
+
 /// 检测认证类型
 #[command]
 pub async fn detect_auth_type(url: String) -> Result<String, String> {
@@ -104,6 +259,47 @@ pub async fn get_default_ssh_keys() -> Result<Vec<String>, String> {
     Ok(keys)
 }
 
+/// 生成 SSH 密钥对
+#[command]
+pub async fn generate_ssh_key(
+    key_type: String,
+    comment: String,
+    passphrase: Option<String>,
+    output_path: String,
+) -> Result<String, String> {
+    log::debug!("生成 SSH 密钥: {} ({})", output_path, key_type);
+
+    match AuthManager::generate_ssh_key(&key_type, &comment, passphrase.as_deref(), &output_path) {
+        Ok(public_key) => Ok(public_key),
+        Err(e) => {
+            log::error!("生成 SSH 密钥失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 列出所有 SSH 密钥
+#[command]
+pub async fn list_ssh_keys() -> Result<Vec<crate::git::types::SshKeyInfo>, String> {
+    log::debug!("列出 SSH 密钥");
+
+    let keys = AuthManager::list_ssh_keys();
+    Ok(keys)
+}
+
+/// 获取 SSH 公钥内容和指纹
+#[command]
+pub async fn get_public_key(
+    private_key_path: String,
+) -> Result<crate::git::types::PublicKeyInfo, String> {
+    log::debug!("读取 SSH 公钥: {}", private_key_path);
+
+    AuthManager::get_public_key(&private_key_path).map_err(|e| {
+        log::error!("读取 SSH 公钥失败: {}", e);
+        e.to_string()
+    })
+}
+
 /// 验证 SSH 密钥
 #[command]
 pub async fn validate_ssh_key(
@@ -121,14 +317,60 @@ pub async fn validate_ssh_key(
     }
 }
 
+/// 凭据索引存储文件（密钥环无法枚举条目，因此单独记录已存储的凭据）
+const CREDENTIAL_INDEX_STORE: &str = "credentials.dat";
+
+/// 在凭据索引中记录一条凭据
+fn record_credential_ref(app_handle: &AppHandle, url: &str, auth: &AuthConfig) {
+    let credential = AuthManager::credential_ref(url, auth);
+
+    let value = match serde_json::to_value(&credential) {
+        Ok(value) => value,
+        Err(e) => {
+            log::warn!("序列化凭据索引失败: {}", e);
+            return;
+        }
+    };
+
+    match StoreBuilder::new(app_handle, CREDENTIAL_INDEX_STORE).build() {
+        Ok(store) => {
+            store.set(AuthManager::credential_account(url), value);
+            if let Err(e) = store.save() {
+                log::warn!("保存凭据索引失败: {}", e);
+            }
+        }
+        Err(e) => log::warn!("无法打开凭据索引: {}", e),
+    }
+}
+
+/// 从凭据索引中移除一条凭据
+fn remove_credential_ref(app_handle: &AppHandle, url: &str) {
+    match StoreBuilder::new(app_handle, CREDENTIAL_INDEX_STORE).build() {
+        Ok(store) => {
+            store.delete(AuthManager::credential_account(url));
+            // 旧版索引以不区分协议的凭据键记录
+            store.delete(AuthManager::normalize_credential_key(url));
+            if let Err(e) = store.save() {
+                log::warn!("保存凭据索引失败: {}", e);
+            }
+        }
+        Err(e) => log::warn!("无法打开凭据索引: {}", e),
+    }
+}
+
 /// 存储认证凭据
 #[command]
-pub async fn store_credentials(url: String, auth: AuthConfig) -> Result<(), String> {
+pub async fn store_credentials(
+    app_handle: AppHandle,
+    url: String,
+    auth: AuthConfig,
+) -> Result<(), String> {
     log::debug!("存储认证凭据: {}", url);
 
     match AuthManager::store_credentials(&url, &auth) {
         Ok(()) => {
             log::info!("凭据存储成功");
+            record_credential_ref(&app_handle, &url, &auth);
             Ok(())
         }
         Err(e) => {
@@ -140,11 +382,21 @@ pub async fn store_credentials(url: String, auth: AuthConfig) -> Result<(), Stri
 
 /// 加载认证凭据
 #[command]
-pub async fn load_credentials(url: String) -> Result<Option<AuthConfig>, String> {
+pub async fn load_credentials(
+    app_handle: AppHandle,
+    url: String,
+) -> Result<Option<AuthConfig>, String> {
     log::debug!("加载认证凭据: {}", url);
 
-    match AuthManager::load_credentials(&url) {
-        Ok(auth) => Ok(auth),
+    match AuthManager::load_or_migrate_credentials(&url) {
+        Ok(Some((auth, migrated))) => {
+            // 迁移的旧版凭据在索引中还没有记录
+            if migrated {
+                record_credential_ref(&app_handle, &url, &auth);
+            }
+            Ok(Some(auth))
+        }
+        Ok(None) => Ok(None),
         Err(e) => {
             log::error!("凭据加载失败: {}", e);
             Err(e.to_string())
@@ -154,12 +406,13 @@ pub async fn load_credentials(url: String) -> Result<Option<AuthConfig>, String>
 
 /// 删除认证凭据
 #[command]
-pub async fn delete_credentials(url: String) -> Result<(), String> {
+pub async fn delete_credentials(app_handle: AppHandle, url: String) -> Result<(), String> {
     log::debug!("删除认证凭据: {}", url);
 
     match AuthManager::delete_credentials(&url) {
         Ok(()) => {
             log::info!("凭据删除成功");
+            remove_credential_ref(&app_handle, &url);
             Ok(())
         }
         Err(e) => {
@@ -169,6 +422,61 @@ pub async fn delete_credentials(url: String) -> Result<(), String> {
     }
 }
 
+/// 列出已存储的认证凭据（不包含敏感信息）
+#[command]
+pub async fn list_stored_credentials(
+    app_handle: AppHandle,
+) -> Result<Vec<crate::git::types::StoredCredentialRef>, String> {
+    log::debug!("列出已存储的认证凭据");
+
+    let store = StoreBuilder::new(&app_handle, CREDENTIAL_INDEX_STORE)
+        .build()
+        .map_err(|e| {
+            log::error!("无法读取凭据索引: {}", e);
+            format!("无法读取凭据索引: {}", e)
+        })?;
+
+    let mut credentials: Vec<crate::git::types::StoredCredentialRef> = store
+        .values()
+        .into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect();
+    credentials.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(credentials)
+}
+
+/// 删除所有已存储的认证凭据，返回删除的数量
+#[command]
+pub async fn delete_all_credentials(app_handle: AppHandle) -> Result<usize, String> {
+    log::debug!("删除所有认证凭据");
+
+    let credentials = list_stored_credentials(app_handle.clone()).await?;
+    let mut deleted = 0;
+    let mut failures = Vec::new();
+
+    for credential in &credentials {
+        match AuthManager::delete_credentials(&credential.url) {
+            Ok(()) => deleted += 1,
+            // 密钥环中已不存在的条目视为已删除
+            Err(crate::git::types::GitError::CredentialNotFound { .. }) => {}
+            Err(e) => {
+                log::error!("删除凭据失败: {} - {}", credential.key, e);
+                failures.push(format!("{}: {}", credential.key, e));
+                continue;
+            }
+        }
+        remove_credential_ref(&app_handle, &credential.url);
+    }
+
+    if failures.is_empty() {
+        log::info!("已删除 {} 条凭据", deleted);
+        Ok(deleted)
+    } else {
+        Err(format!("部分凭据删除失败: {}", failures.join("; ")))
+    }
+}
+
 /// 从 URL 提取用户名
 #[command]
 pub async fn extract_username_from_url(url: String) -> Result<Option<String>, String> {
@@ -178,6 +486,24 @@ pub async fn extract_username_from_url(url: String) -> Result<Option<String>, St
     Ok(username)
 }
 
+/// 回复 `credential-request` 事件，auth 为空表示用户取消输入
+#[command]
+pub async fn provide_credentials(
+    request_id: String,
+    auth: Option<AuthConfig>,
+) -> Result<(), String> {
+    log::debug!("收到凭据请求回复: {}", request_id);
+
+    let prompter =
+        crate::git::credential_prompt::current().ok_or_else(|| "凭据提示器未初始化".to_string())?;
+    if prompter.provide(&request_id, auth) {
+        Ok(())
+    } else {
+        log::warn!("凭据请求不存在或已超时: {}", request_id);
+        Err("凭据请求不存在或已超时".to_string())
+    }
+}
+
 /// 取消克隆操作
 #[command]
 pub async fn cancel_clone_operation(
@@ -192,6 +518,16 @@ pub async fn cancel_clone_operation(
     Ok(())
 }
 
+/// 取消正在运行的系统Git操作（fetch/push/pull），操作不存在或已结束时返回 false
+#[command]
+pub async fn cancel_git_operation(
+    operation_id: String,
+    state: State<'_, GitState>,
+) -> Result<bool, String> {
+    log::info!("取消系统Git操作: {}", operation_id);
+    Ok(state.git_processes.cancel(&operation_id))
+}
+
 /// 获取克隆操作状态
 #[command]
 pub async fn get_clone_operation_status(
@@ -239,40 +575,58 @@ pub async fn select_ssh_key_file() -> Result<Option<String>, String> {
 #[command]
 pub async fn validate_clone_directory(
     directory_path: String,
+    estimated_size: Option<u64>,
 ) -> Result<DirectoryValidation, String> {
     let path = Path::new(&directory_path);
+    let mut validation = check_clone_directory(path);
+    validation.available_space = crate::git::clone::available_space(path);
 
+    // 预计大小超过可用空间时提前提示，避免克隆到一半才因磁盘写满失败
+    if let (true, Some(estimated_size)) = (validation.is_valid, estimated_size) {
+        if let Err(e) = crate::git::clone::check_disk_space(path, estimated_size) {
+            validation.is_valid = false;
+            validation.message = e.to_string();
+        }
+    }
+
+    Ok(validation)
+}
+
+/// 检查克隆目标目录是否存在、为空且可写
+fn check_clone_directory(path: &Path) -> DirectoryValidation {
     // 检查目录是否存在
     if !path.exists() {
-        // 尝试创建目录
-        match std::fs::create_dir_all(path) {
-            Ok(_) => {
-                return Ok(DirectoryValidation {
-                    is_valid: true,
-                    is_empty: true,
-                    is_writable: true,
-                    message: "目录已创建".to_string(),
-                })
-            }
-            Err(e) => {
-                return Ok(DirectoryValidation {
-                    is_valid: false,
-                    is_empty: false,
-                    is_writable: false,
-                    message: format!("无法创建目录: {}", e),
-                })
-            }
-        }
+        // 尝试创建目录，失败时给出具体原因（父路径是文件、无权限、路径过长）
+        let created = crate::git::clone::prepare_clone_target(path).and_then(|_| {
+            std::fs::create_dir(path).map_err(|e| crate::git::clone::clone_target_io_error(e, path))
+        });
+        return match created {
+            Ok(_) => DirectoryValidation {
+                is_valid: true,
+                is_empty: true,
+                is_writable: true,
+                message: "目录已创建".to_string(),
+                available_space: None,
+            },
+            Err(e) => DirectoryValidation {
+                is_valid: false,
+                is_empty: false,
+                is_writable: false,
+                message: e.to_string(),
+                available_space: None,
+            },
+        };
     }
 
     // 检查是否为目录
     if !path.is_dir() {
-        return Ok(DirectoryValidation {
+        return DirectoryValidation {
             is_valid: false,
             is_empty: false,
             is_writable: false,
             message: "路径不是目录".to_string(),
-        });
+            available_space: None,
+        };
     }
 
     // 检查是否为空
@@ -295,12 +649,13 @@ pub async fn validate_clone_directory(
         "目录可用".to_string()
     };
 
-    Ok(DirectoryValidation {
+    DirectoryValidation {
         is_valid: is_empty && is_writable,
         is_empty,
         is_writable,
         message,
-    })
+        available_space: None,
+    }
 }
 
 /// 目录验证结果
@@ -310,6 +665,8 @@ pub struct DirectoryValidation {
     pub is_empty: bool,
     pub is_writable: bool,
     pub message: String,
+    /// 目录所在磁盘的可用空间（字节），无法获取时为空
+    pub available_space: Option<u64>,
 }
 
 /// 仓库信息结构
@@ -321,6 +678,29 @@ pub struct RepositoryInfo {
     pub is_valid: bool,
 }
 
+/// 初始化新仓库，返回初始分支名称
+///
+/// `default_branch` 未指定时使用全局配置的 `init.defaultBranch`
+#[command]
+pub async fn init_repository(
+    path: String,
+    default_branch: Option<String>,
+) -> Result<String, String> {
+    log::debug!(
+        "初始化仓库: {} (default_branch: {:?})",
+        path,
+        default_branch
+    );
+
+    match crate::git::operations::init_repository(&path, default_branch.as_deref()) {
+        Ok(branch) => Ok(branch),
+        Err(e) => {
+            log::error!("初始化仓库失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
 /// 验证指定路径是否为有效的 Git 仓库
 #[command]
 pub async fn is_git_repository(path: String) -> Result<bool, String> {
@@ -334,7 +714,7 @@ pub async fn is_git_repository(path: String) -> Result<bool, String> {
     }
 
     // 尝试打开 Git 仓库
-    match Repository::open(repo_path) {
+    match crate::git::operations::open_repository(&path) {
         Ok(_) => {
             log::debug!("路径是有效的 Git 仓库: {}", path);
             Ok(true)
@@ -346,6 +726,14 @@ pub async fn is_git_repository(path: String) -> Result<bool, String> {
     }
 }
 
+/// 获取路径所属仓库的根目录，便于前端规范化用户选择的路径
+#[command]
+pub async fn get_repository_root(path: String) -> Result<Option<String>, String> {
+    log::debug!("获取仓库根目录: {}", path);
+
+    Ok(crate::git::operations::get_repository_root(&path))
+}
+
 /// 获取仓库基本信息
 #[command]
 pub async fn get_repository_info(path: String) -> Result<RepositoryInfo, String> {
@@ -361,7 +749,7 @@ pub async fn get_repository_info(path: String) -> Result<RepositoryInfo, String>
         .to_string();
 
     // 尝试打开 Git 仓库
-    match Repository::open(repo_path) {
+    match crate::git::operations::open_repository(&path) {
         Ok(repo) => {
             // 获取远程 URL
             let remote_url = get_remote_url_internal(&repo);
@@ -393,9 +781,7 @@ pub async fn get_repository_info(path: String) -> Result<RepositoryInfo, String>
 pub async fn get_current_branch(path: String) -> Result<Option<String>, String> {
     log::debug!("获取当前分支: {}", path);
 
-    let repo_path = Path::new(&path);
-
-    match Repository::open(repo_path) {
+    match crate::git::operations::open_repository(&path) {
         Ok(repo) => {
             let branch = get_current_branch_internal(&repo);
             Ok(branch)
@@ -412,9 +798,7 @@ pub async fn get_current_branch(path: String) -> Result<Option<String>, String>
 pub async fn get_remote_url(path: String) -> Result<Option<String>, String> {
     log::debug!("获取远程 URL: {}", path);
 
-    let repo_path = Path::new(&path);
-
-    match Repository::open(repo_path) {
+    match crate::git::operations::open_repository(&path) {
         Ok(repo) => {
             let url = get_remote_url_internal(&repo);
             Ok(url)
@@ -451,8 +835,107 @@ pub async fn open_folder(path: String) -> Result<(), String> {
     }
 }
 
+/// 使用系统默认程序打开指定文件
+#[command]
+pub async fn open_file(path: String) -> Result<(), String> {
+    log::debug!("打开文件: {}", path);
+
+    let file_path = Path::new(&path);
+
+    // 检查路径是否为文件
+    if !file_path.exists() {
+        return Err(format!("路径不存在: {}", path));
+    }
+    if !file_path.is_file() {
+        return Err(format!("路径不是文件: {}", path));
+    }
+
+    match open::that(&path) {
+        Ok(_) => {
+            log::info!("成功打开文件: {}", path);
+            Ok(())
+        }
+        Err(e) => {
+            log::error!("打开文件失败: {}, 错误: {}", path, e);
+            Err(format!("打开文件失败: {}", e))
+        }
+    }
+}
+
+/// 在编辑器中打开指定文件
+///
+/// `editor` 为编辑器命令（如 `code`、`subl`，可带参数），未指定时依次使用
+/// `VISUAL`、`EDITOR` 环境变量，最后回退到 `code`；`line` 用于跳转到指定行
+#[command]
+pub async fn open_in_editor(
+    path: String,
+    editor: Option<String>,
+    line: Option<u32>,
+) -> Result<(), String> {
+    log::debug!("在编辑器中打开文件: {} (editor: {:?})", path, editor);
+
+    if !Path::new(&path).is_file() {
+        return Err(format!("路径不是文件: {}", path));
+    }
+
+    let non_empty = |value: String| (!value.trim().is_empty()).then_some(value);
+    let editor = editor
+        .and_then(non_empty)
+        .or_else(|| std::env::var("VISUAL").ok().and_then(non_empty))
+        .or_else(|| std::env::var("EDITOR").ok().and_then(non_empty))
+        .unwrap_or_else(|| "code".to_string());
+
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("code");
+    let mut cmd = crate::utils::system_command::create_hidden_command(program);
+    cmd.args(parts);
+    cmd.args(editor_file_args(program, &path, line));
+
+    match cmd.spawn() {
+        Ok(_) => {
+            log::info!("已在编辑器 {} 中打开文件: {}", program, path);
+            Ok(())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            log::error!("未找到编辑器: {}", program);
+            Err(format!(
+                "未找到编辑器 '{}'，请确认已安装并在 PATH 中",
+                program
+            ))
+        }
+        Err(e) => {
+            log::error!("启动编辑器失败: {}, 错误: {}", program, e);
+            Err(format!("启动编辑器失败: {}", e))
+        }
+    }
+}
+
 // 内部辅助函数
 
+/// 根据编辑器类型生成打开文件（及跳转行号）的参数
+fn editor_file_args(program: &str, path: &str, line: Option<u32>) -> Vec<String> {
+    let Some(line) = line else {
+        return vec![path.to_string()];
+    };
+
+    let name = Path::new(program)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(program)
+        .to_lowercase();
+
+    match name.as_str() {
+        // VS Code 系列使用 --goto file:line
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            vec!["--goto".to_string(), format!("{}:{}", path, line)]
+        }
+        // Sublime Text、Zed 等支持 file:line
+        "subl" | "zed" => vec![format!("{}:{}", path, line)],
+        // vim/emacs/nano 等终端编辑器使用 +line file
+        _ => vec![format!("+{}", line), path.to_string()],
+    }
+}
+
 /// 获取远程 URL（内部函数）
 fn get_remote_url_internal(repo: &Repository) -> Option<String> {
     // 首先尝试获取默认远程名称
@@ -517,66 +1000,545 @@ pub async fn get_repository_status(repo_path: String) -> Result<RepositoryStatus
     }
 }
 
-/// 暂存文件
+/// 修复仓库：清理过期的索引锁文件并检查仓库完整性
 #[command]
-pub async fn stage_files(repo_path: String, file_paths: Vec<String>) -> Result<(), String> {
-    log::debug!("暂存文件: {:?} in {}", file_paths, repo_path);
+pub async fn repair_repository(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+    stale_after_secs: Option<u64>,
+) -> Result<crate::git::types::RepairReport, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::info!("修复仓库: {}", repo_path);
 
-    match crate::git::operations::stage_files(&repo_path, &file_paths) {
-        Ok(()) => Ok(()),
+    let result = tokio::task::spawn_blocking(move || {
+        crate::git::operations::repair_repository(&repo_path, stale_after_secs)
+    })
+    .await
+    .map_err(|e| format!("修复仓库任务失败: {}", e))?;
+
+    result.map_err(|e| {
+        log::error!("修复仓库失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 计算仓库占用的磁盘空间（含 .git 目录）
+#[command]
+pub async fn get_repository_size(repo_path: String) -> Result<crate::git::types::RepoSize, String> {
+    log::debug!("计算仓库大小: {}", repo_path);
+
+    let result = tokio::task::spawn_blocking(move || {
+        crate::git::operations::get_repository_size(&repo_path)
+    })
+    .await
+    .map_err(|e| format!("计算仓库大小任务失败: {}", e))?;
+
+    result.map_err(|e| {
+        log::error!("计算仓库大小失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 获取变更数量统计（轻量，适合轮询提交按钮角标）
+#[command]
+pub async fn get_change_counts(
+    repo_path: String,
+) -> Result<crate::git::types::ChangeCounts, String> {
+    log::debug!("获取变更数量: {}", repo_path);
+
+    match crate::git::operations::get_change_counts(&repo_path) {
+        Ok(counts) => Ok(counts),
         Err(e) => {
-            log::error!("暂存文件失败: {}", e);
+            log::error!("获取变更数量失败: {}", e);
             Err(e.to_string())
         }
     }
 }
 
-/// 取消暂存文件
+/// 批量获取多个仓库的概要状态（并行执行）
 #[command]
-pub async fn unstage_files(repo_path: String, file_paths: Vec<String>) -> Result<(), String> {
-    log::debug!("取消暂存文件: {:?} in {}", file_paths, repo_path);
+pub async fn get_repositories_summary(
+    repo_paths: Vec<String>,
+) -> Result<Vec<crate::git::types::RepoSummary>, String> {
+    log::debug!("批量获取仓库概要状态: {} 个仓库", repo_paths.len());
+
+    let tasks = repo_paths.iter().cloned().map(|repo_path| {
+        tokio::task::spawn_blocking(move || {
+            crate::git::operations::get_repository_summary(&repo_path)
+        })
+    });
+    let results = futures::future::join_all(tasks).await;
+
+    // 单个仓库的任务失败只记录在对应条目中，不影响其他仓库
+    let summaries = repo_paths
+        .into_iter()
+        .zip(results)
+        .map(|(repo_path, result)| {
+            result.unwrap_or_else(|e| {
+                log::error!("获取仓库概要状态任务失败 {}: {}", repo_path, e);
+                crate::git::types::RepoSummary {
+                    path: repo_path,
+                    current_branch: String::new(),
+                    is_clean: false,
+                    ahead: 0,
+                    behind: 0,
+                    changed_count: 0,
+                    error: Some(format!("获取仓库概要状态任务失败: {}", e)),
+                }
+            })
+        })
+        .collect();
 
-    match crate::git::operations::unstage_files(&repo_path, &file_paths) {
-        Ok(()) => Ok(()),
+    Ok(summaries)
+}
+
+/// 获取仓库正在进行中的操作状态
+#[command]
+pub async fn get_repository_state(
+    repo_path: String,
+) -> Result<crate::git::types::RepoOperationState, String> {
+    log::debug!("获取仓库操作状态: {}", repo_path);
+
+    match crate::git::operations::get_repository_state(&repo_path) {
+        Ok(state) => Ok(state),
         Err(e) => {
-            log::error!("取消暂存文件失败: {}", e);
+            log::error!("获取仓库操作状态失败: {}", e);
             Err(e.to_string())
         }
     }
 }
 
-/// 创建提交
+/// 解析 HEAD、ORIG_HEAD、MERGE_HEAD 等特殊引用或任意引用，不存在时返回 None
 #[command]
-pub async fn create_commit(
+pub async fn resolve_ref(
     repo_path: String,
-    message: String,
-    description: Option<String>,
-    author_name: Option<String>,
-    author_email: Option<String>,
-    amend: Option<bool>,
-    signoff: Option<bool>,
-) -> Result<String, String> {
-    log::debug!("创建提交: {} in {}", message, repo_path);
+    name: String,
+) -> Result<Option<crate::git::types::RefInfo>, String> {
+    log::debug!("解析引用 {}: {}", name, repo_path);
 
-    let commit_options = crate::git::types::CommitOptions {
-        message,
-        description,
-        author_name,
-        author_email,
-        amend: amend.unwrap_or(false),
-        signoff: signoff.unwrap_or(false),
-    };
+    crate::git::operations::resolve_ref(&repo_path, &name).map_err(|e| {
+        log::error!("解析引用失败: {}", e);
+        e.to_string()
+    })
+}
 
-    match crate::git::operations::create_commit(&repo_path, &commit_options) {
-        Ok(commit_sha) => Ok(commit_sha),
+/// 中止正在进行的合并、变基、拣选或还原操作
+#[command]
+pub async fn abort_operation(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+) -> Result<crate::git::types::RepositoryStateKind, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("中止进行中的操作: {}", repo_path);
+
+    match crate::git::operations::abort_operation(&repo_path) {
+        Ok(state) => Ok(state),
         Err(e) => {
-            log::error!("创建提交失败: {}", e);
+            log::error!("中止操作失败: {}", e);
             Err(e.to_string())
         }
     }
 }
 
-/// 获取提交历史
+/// 清理进行中操作遗留的状态文件（不修改工作区和提交）
+#[command]
+pub async fn cleanup_repository_state(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+) -> Result<crate::git::types::RepositoryStateKind, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("清理仓库操作状态: {}", repo_path);
+
+    match crate::git::operations::cleanup_repository_state(&repo_path) {
+        Ok(state) => Ok(state),
+        Err(e) => {
+            log::error!("清理仓库操作状态失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 将指定分支合并到当前分支，冲突时保留合并状态以便解决后调用 continue_merge
+#[command]
+pub async fn merge_branch(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    branch_name: String,
+    author_name: Option<String>,
+    author_email: Option<String>,
+) -> Result<crate::git::types::SyncResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("合并分支: {} -> {}", branch_name, repo_path);
+
+    // 合并提交使用的身份，未指定时使用仓库配置
+    let identity = match (&author_name, &author_email) {
+        (Some(name), Some(email)) => Some((name.as_str(), email.as_str())),
+        _ => None,
+    };
+
+    match crate::git::operations::merge_branch(&repo_path, &branch_name, identity) {
+        Ok(result) => {
+            notify_index_changed(&app_handle, &repo_path, &[]);
+            Ok(result)
+        }
+        Err(e) => {
+            log::error!("合并分支失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 解决冲突后完成合并
+#[command]
+pub async fn continue_merge(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    message: Option<String>,
+) -> Result<crate::git::types::SyncResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("完成合并: {}", repo_path);
+
+    match crate::git::operations::continue_merge(&repo_path, message.as_deref()) {
+        Ok(result) => {
+            notify_index_changed(&app_handle, &repo_path, &[]);
+            Ok(result)
+        }
+        Err(e) => {
+            log::error!("完成合并失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 暂存文件
+#[command]
+pub async fn stage_files(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    file_paths: Vec<String>,
+) -> Result<(), String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("暂存文件: {:?} in {}", file_paths, repo_path);
+
+    match crate::git::operations::stage_files(&repo_path, &file_paths) {
+        Ok(()) => {
+            notify_index_changed(&app_handle, &repo_path, &file_paths);
+            Ok(())
+        }
+        Err(e) => {
+            log::error!("暂存文件失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 查找超过大小阈值的变更文件（默认阈值 50MB）
+#[command]
+pub async fn find_large_files(
+    repo_path: String,
+    threshold_bytes: Option<u64>,
+    include_staged: Option<bool>,
+) -> Result<Vec<crate::git::types::LargeFile>, String> {
+    log::debug!(
+        "检查大文件: {} (threshold: {:?})",
+        repo_path,
+        threshold_bytes
+    );
+
+    match crate::git::operations::find_large_files(
+        &repo_path,
+        threshold_bytes,
+        include_staged.unwrap_or(true),
+    ) {
+        Ok(files) => Ok(files),
+        Err(e) => {
+            log::error!("检查大文件失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 将忽略规则添加到 .gitignore
+#[command]
+pub async fn add_to_gitignore(repo_path: String, patterns: Vec<String>) -> Result<(), String> {
+    log::debug!("添加忽略规则: {:?} in {}", patterns, repo_path);
+
+    match crate::git::operations::add_to_gitignore(&repo_path, &patterns) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            log::error!("添加忽略规则失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 判断路径是否被忽略
+#[command]
+pub async fn is_path_ignored(repo_path: String, path: String) -> Result<bool, String> {
+    match crate::git::operations::is_path_ignored(&repo_path, &path) {
+        Ok(ignored) => Ok(ignored),
+        Err(e) => {
+            log::error!("检查忽略状态失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 获取单个路径的 Git 状态（已跟踪、已修改、已暂存、未跟踪、已忽略或冲突）
+#[command]
+pub async fn get_path_status(
+    repo_path: String,
+    path: String,
+) -> Result<crate::git::types::PathStatus, String> {
+    log::debug!("获取路径状态: {} in {}", path, repo_path);
+
+    crate::git::operations::get_path_status(&repo_path, &path).map_err(|e| {
+        log::error!("获取路径状态失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 取消暂存文件
+#[command]
+pub async fn unstage_files(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    file_paths: Vec<String>,
+) -> Result<(), String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("取消暂存文件: {:?} in {}", file_paths, repo_path);
+
+    match crate::git::operations::unstage_files(&repo_path, &file_paths) {
+        Ok(()) => {
+            notify_index_changed(&app_handle, &repo_path, &file_paths);
+            Ok(())
+        }
+        Err(e) => {
+            log::error!("取消暂存文件失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 创建提交
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_commit(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    message: String,
+    description: Option<String>,
+    author_name: Option<String>,
+    author_email: Option<String>,
+    amend: Option<bool>,
+    signoff: Option<bool>,
+    only_paths: Option<Vec<String>>,
+    author_date: Option<i64>,
+    committer_date: Option<i64>,
+    date_offset_minutes: Option<i32>,
+    trailers: Option<Vec<(String, String)>>,
+    stage_tracked: Option<bool>,
+    lint_rules: Option<crate::git::types::CommitLintRules>,
+    strict: Option<bool>,
+) -> Result<String, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("创建提交: {} in {}", message, repo_path);
+
+    // 严格模式下提交消息存在错误级别的问题时拒绝提交，未提供规则时使用默认规则
+    if strict.unwrap_or(false) {
+        let rules = lint_rules.unwrap_or_default();
+        let full_message = match description.as_deref() {
+            Some(description) if !description.is_empty() => {
+                format!("{}\n\n{}", message, description)
+            }
+            _ => message.clone(),
+        };
+        let errors: Vec<String> =
+            crate::git::operations::lint_commit_message(&full_message, &rules)
+                .into_iter()
+                .filter(|issue| issue.severity == crate::git::types::LintSeverity::Error)
+                .map(|issue| issue.message)
+                .collect();
+        if !errors.is_empty() {
+            log::error!("提交消息未通过检查: {:?}", errors);
+            return Err(format!("提交消息未通过检查: {}", errors.join("；")));
+        }
+    }
+
+    let commit_options = crate::git::types::CommitOptions {
+        message,
+        description,
+        author_name,
+        author_email,
+        amend: amend.unwrap_or(false),
+        signoff: signoff.unwrap_or(false),
+        only_paths,
+        author_date,
+        committer_date,
+        date_offset_minutes,
+        trailers,
+        stage_tracked: stage_tracked.unwrap_or(false),
+    };
+
+    match crate::git::operations::create_commit(&repo_path, &commit_options) {
+        Ok(commit_sha) => {
+            notify_index_changed(&app_handle, &repo_path, &[]);
+            Ok(commit_sha)
+        }
+        Err(e) => {
+            log::error!("创建提交失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 按规则检查提交消息，返回带严重程度的问题列表
+#[command]
+pub async fn validate_commit_message(
+    message: String,
+    rules: Option<crate::git::types::CommitLintRules>,
+) -> Result<Vec<crate::git::types::LintIssue>, String> {
+    log::debug!("检查提交消息: {:?}", rules);
+
+    Ok(crate::git::operations::lint_commit_message(
+        &message,
+        &rules.unwrap_or_default(),
+    ))
+}
+
+/// 验证提交签名，未签名的提交返回 signed: false
+#[command]
+pub async fn verify_commit_signature(
+    repo_path: String,
+    commit_sha: String,
+) -> Result<crate::git::types::SignatureVerification, String> {
+    log::debug!("验证提交签名: {} in {}", commit_sha, repo_path);
+
+    crate::git::operations::verify_commit_signature(&repo_path, &commit_sha).map_err(|e| {
+        log::error!("验证提交签名失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 设置提交身份（user.name / user.email），`global` 为 true 时写入全局配置
+#[command]
+pub async fn set_user_identity(
+    repo_path: String,
+    name: String,
+    email: String,
+    global: Option<bool>,
+) -> Result<(), String> {
+    log::debug!("设置提交身份: {} <{}> in {}", name, email, repo_path);
+
+    match crate::git::operations::set_user_identity(
+        &repo_path,
+        &name,
+        &email,
+        global.unwrap_or(false),
+    ) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            log::error!("设置提交身份失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 仅修改最近一次提交的消息
+#[command]
+pub async fn reword_head(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+    new_message: String,
+    force: Option<bool>,
+) -> Result<String, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("修改最近一次提交的消息: {}", repo_path);
+
+    let force = force.unwrap_or(false);
+    crate::git::operations::reword_head(&repo_path, &new_message, force).map_err(|e| {
+        log::error!("修改提交消息失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 获取提交消息模板（commit.template 或 .gitmessage）
+#[command]
+pub async fn get_commit_template(repo_path: String) -> Result<Option<String>, String> {
+    log::debug!("获取提交消息模板: {}", repo_path);
+
+    match crate::git::operations::get_commit_template(&repo_path) {
+        Ok(template) => Ok(template),
+        Err(e) => {
+            log::error!("获取提交消息模板失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 储藏当前变更
+#[command]
+pub async fn stash_save(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+    message: Option<String>,
+    include_untracked: Option<bool>,
+    keep_index: Option<bool>,
+) -> Result<String, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("储藏变更: {} (message: {:?})", repo_path, message);
+
+    match crate::git::operations::stash_save(
+        &repo_path,
+        message.as_deref(),
+        include_untracked.unwrap_or(false),
+        keep_index.unwrap_or(false),
+    ) {
+        Ok(stash_sha) => Ok(stash_sha),
+        Err(e) => {
+            log::error!("储藏变更失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 获取储藏列表
+#[command]
+pub async fn stash_list(repo_path: String) -> Result<Vec<crate::git::types::StashEntry>, String> {
+    log::debug!("获取储藏列表: {}", repo_path);
+
+    match crate::git::operations::stash_list(&repo_path) {
+        Ok(stashes) => Ok(stashes),
+        Err(e) => {
+            log::error!("获取储藏列表失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 查看储藏中变更的文件及统计
+#[command]
+pub async fn stash_show(
+    repo_path: String,
+    index: usize,
+) -> Result<Vec<crate::git::types::FileStatus>, String> {
+    log::debug!("查看储藏: {} (index: {})", repo_path, index);
+
+    match crate::git::operations::stash_show(&repo_path, index) {
+        Ok(files) => Ok(files),
+        Err(e) => {
+            log::error!("查看储藏失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 获取提交历史
 #[command]
 pub async fn get_commit_history(
     repo_path: String,
@@ -603,21 +1565,63 @@ pub async fn get_commit_history(
     }
 }
 
+/// 按过滤条件导出提交历史到 JSON 或 CSV 文件
+#[command]
+pub async fn export_commit_history(
+    repo_path: String,
+    output_path: String,
+    format: crate::git::types::HistoryExportFormat,
+    limit: Option<usize>,
+    filters: Option<crate::git::types::CommitHistoryFilter>,
+) -> Result<crate::git::types::HistoryExportResult, String> {
+    log::debug!(
+        "导出提交历史: {} -> {} ({:?}, limit: {:?})",
+        repo_path,
+        output_path,
+        format,
+        limit
+    );
+
+    let result = tokio::task::spawn_blocking(move || {
+        crate::git::operations::export_commit_history(
+            &repo_path,
+            &output_path,
+            format,
+            limit.unwrap_or(usize::MAX),
+            &filters.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| format!("导出提交历史任务失败: {}", e))?;
+
+    result.map_err(|e| {
+        log::error!("导出提交历史失败: {}", e);
+        e.to_string()
+    })
+}
+
 /// 获取文件差异
 #[command]
 pub async fn get_file_diff(
     repo_path: String,
     file_path: String,
     staged: Option<bool>,
+    whitespace: Option<crate::git::types::WhitespaceOptions>,
 ) -> Result<String, String> {
     log::debug!(
-        "获取文件差异: {} in {} (staged: {:?})",
+        "获取文件差异: {} in {} (staged: {:?}, whitespace: {:?})",
         file_path,
         repo_path,
-        staged
+        staged,
+        whitespace
     );
 
-    match crate::git::operations::get_file_diff(&repo_path, &file_path, staged.unwrap_or(false)) {
+    match crate::git::operations::get_file_diff(
+        &repo_path,
+        &file_path,
+        staged.unwrap_or(false),
+        &whitespace.unwrap_or_default(),
+    ) {
         Ok(diff) => Ok(diff),
         Err(e) => {
             log::error!("获取文件差异失败: {}", e);
@@ -626,15 +1630,79 @@ pub async fn get_file_diff(
     }
 }
 
+/// 获取工作区文件与指定提交之间的结构化差异
+#[command]
+pub async fn get_file_diff_against(
+    repo_path: String,
+    file_path: String,
+    commit_ref: String,
+    whitespace: Option<crate::git::types::WhitespaceOptions>,
+) -> Result<crate::git::types::FileDiff, String> {
+    log::debug!(
+        "获取文件与提交的差异: {} in {} (commit: {})",
+        file_path,
+        repo_path,
+        commit_ref
+    );
+
+    crate::git::operations::get_file_diff_against(
+        &repo_path,
+        &file_path,
+        &commit_ref,
+        &whitespace.unwrap_or_default(),
+    )
+    .map_err(|e| {
+        log::error!("获取文件与提交的差异失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 逐行追溯文件，跳过 `.git-blame-ignore-revs` 中列出的提交
+#[command]
+pub async fn blame_file(
+    repo_path: String,
+    file_path: String,
+    revision: Option<String>,
+) -> Result<Vec<crate::git::types::BlameLine>, String> {
+    log::debug!("追溯文件: {} in {} ({:?})", file_path, repo_path, revision);
+
+    tokio::task::spawn_blocking(move || {
+        crate::git::operations::blame_file(&repo_path, &file_path, revision.as_deref())
+    })
+    .await
+    .map_err(|e| {
+        log::error!("任务执行失败: {}", e);
+        format!("任务执行失败: {}", e)
+    })?
+    .map_err(|e| {
+        log::error!("追溯文件失败: {}", e);
+        e.to_string()
+    })
+}
+
 /// 获取远程变更（fetch操作）
 #[command]
 pub async fn fetch_remote(
+    git_state: State<'_, GitState>,
     repo_path: String,
     remote_name: Option<String>,
+    prune: Option<bool>,
+    refspecs: Option<Vec<String>>,
 ) -> Result<crate::git::types::SyncResult, String> {
-    log::debug!("获取远程变更: {} (remote: {:?})", repo_path, remote_name);
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!(
+        "获取远程变更: {} (remote: {:?}, prune: {:?})",
+        repo_path,
+        remote_name,
+        prune
+    );
 
-    match crate::git::operations::fetch_remote(&repo_path, remote_name.as_deref()) {
+    match crate::git::operations::fetch_remote(
+        &repo_path,
+        remote_name.as_deref(),
+        prune.unwrap_or(false),
+        refspecs.as_deref(),
+    ) {
         Ok(result) => Ok(result),
         Err(e) => {
             log::error!("获取远程变更失败: {}", e);
@@ -643,139 +1711,520 @@ pub async fn fetch_remote(
     }
 }
 
+/// 清理远程已删除的远程跟踪分支
+#[command]
+pub async fn prune_remote(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+    remote_name: Option<String>,
+) -> Result<crate::git::types::SyncResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!(
+        "清理远程跟踪分支: {} (remote: {:?})",
+        repo_path,
+        remote_name
+    );
+
+    match crate::git::operations::prune_remote(&repo_path, remote_name.as_deref()) {
+        Ok(result) => Ok(result),
+        Err(e) => {
+            log::error!("清理远程跟踪分支失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
 /// 智能获取远程变更（支持Token认证）
+///
+/// `ssh_key_path`/`passphrase` 指定SSH远程使用的私钥，HTTPS 远程忽略；
+/// `max_auth_attempts` 为最大认证尝试次数，未指定时使用默认值
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn smart_fetch_remote(
+    git_state: State<'_, GitState>,
     app_handle: AppHandle,
     repo_path: String,
     remote_name: Option<String>,
-) -> Result<crate::git::types::SyncResult, String> {
+    prune: Option<bool>,
+    refspecs: Option<Vec<String>>,
+    ssh_key_path: Option<String>,
+    passphrase: Option<String>,
+    max_auth_attempts: Option<u32>,
+) -> Result<crate::git::types::SyncResult, crate::git::types::GitError> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
     log::debug!(
-        "智能获取远程变更: {} (remote: {:?})",
+        "智能获取远程变更: {} (remote: {:?}, prune: {:?})",
         repo_path,
-        remote_name
+        remote_name,
+        prune
     );
 
     // 获取远程URL并提取域名
-    let token_cache = match get_token_for_repository(&app_handle, &repo_path).await {
-        Ok(token) => token,
-        Err(e) => {
-            log::warn!("获取Token失败，使用默认认证: {}", e);
-            None
+    let token_cache =
+        get_token_for_repository(&app_handle, &repo_path, remote_name.as_deref()).await;
+
+    if let Some(ref _token) = token_cache {
+        log::debug!("使用Token认证进行fetch操作");
+    } else {
+        log::debug!("使用默认认证进行fetch操作");
+    }
+
+    // 凭据回调可能阻塞等待用户输入，在阻塞任务中执行
+    let auth = crate::git::operations::AuthOptions {
+        ssh_key: crate::git::operations::ssh_key_auth(ssh_key_path, passphrase),
+        interactive: true,
+        max_attempts: max_auth_attempts,
+    };
+    let result = tokio::task::spawn_blocking(move || {
+        crate::git::operations::fetch_remote_with_auth(
+            &repo_path,
+            remote_name.as_deref(),
+            token_cache,
+            prune.unwrap_or(false),
+            refspecs.as_deref(),
+            auth,
+        )
+    })
+    .await
+    .map_err(|e| {
+        log::error!("任务执行失败: {}", e);
+        crate::git::types::GitError::Unknown {
+            message: format!("任务执行失败: {}", e),
+        }
+    })?;
+
+    result.map_err(|e| {
+        log::error!("智能获取远程变更失败: {}", e);
+        e
+    })
+}
+
+/// 拉取远程变更（pull操作）
+#[command]
+pub async fn pull_remote(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+    strategy: String,
+    author_name: Option<String>,
+    author_email: Option<String>,
+) -> Result<crate::git::types::SyncResult, crate::git::types::GitError> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("拉取远程变更: {} (strategy: {})", repo_path, strategy);
+
+    let pull_strategy = match strategy.as_str() {
+        "merge" => crate::git::types::PullStrategy::Merge,
+        "rebase" => crate::git::types::PullStrategy::Rebase,
+        _ => {
+            return Err(crate::git::types::GitError::Unknown {
+                message: "无效的拉取策略，支持: merge, rebase".to_string(),
+            });
         }
     };
 
-    if let Some(ref _token) = token_cache {
-        log::debug!("使用Token认证进行fetch操作");
-    } else {
-        log::debug!("使用默认认证进行fetch操作");
-    }
+    // 合并提交或变基时使用的身份，未指定时使用仓库配置
+    let identity = match (&author_name, &author_email) {
+        (Some(name), Some(email)) => Some((name.as_str(), email.as_str())),
+        _ => None,
+    };
+
+    // 返回结构化错误，前端可根据 no_upstream 等类型引导用户设置上游分支
+    crate::git::operations::pull_remote(&repo_path, pull_strategy, identity).map_err(|e| {
+        log::error!("拉取远程变更失败: {}", e);
+        e
+    })
+}
+
+/// 推送本地变更（push操作）
+#[command]
+pub async fn push_remote(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+    remote_name: Option<String>,
+    force: Option<bool>,
+) -> Result<crate::git::types::SyncResult, crate::git::types::GitError> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!(
+        "推送本地变更: {} (remote: {:?}, force: {:?})",
+        repo_path,
+        remote_name,
+        force
+    );
+
+    // 返回结构化错误，前端可根据 non_fast_forward 等类型提供先拉取再推送或强制推送的选项
+    crate::git::operations::push_remote(&repo_path, remote_name.as_deref(), force.unwrap_or(false))
+        .map_err(|e| {
+            log::error!("推送本地变更失败: {}", e);
+            e
+        })
+}
+
+/// 预览拉取会修改的文件及其增删行数（支持Token认证，不执行合并）
+///
+/// `fetch` 默认为 true；同时有未提交本地变更的文件会标记为可能冲突
+#[command]
+pub async fn preview_pull_changes(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    remote_name: Option<String>,
+    fetch: Option<bool>,
+) -> Result<Vec<crate::git::types::FileStatus>, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("预览拉取变更: {} (remote: {:?})", repo_path, remote_name);
+
+    let token_cache =
+        get_token_for_repository(&app_handle, &repo_path, remote_name.as_deref()).await;
+
+    tokio::task::spawn_blocking(move || {
+        crate::git::operations::preview_pull_changes(
+            &repo_path,
+            remote_name.as_deref(),
+            token_cache,
+            fetch.unwrap_or(true),
+        )
+    })
+    .await
+    .map_err(|e| {
+        log::error!("任务执行失败: {}", e);
+        format!("任务执行失败: {}", e)
+    })?
+    .map_err(|e| {
+        log::error!("预览拉取变更失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 获取远程变更并报告上游新增的提交（支持Token认证，不修改本地分支）
+#[command]
+pub async fn fetch_and_report(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    remote_name: Option<String>,
+) -> Result<crate::git::types::FetchReport, crate::git::types::GitError> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("获取远程新提交: {} (remote: {:?})", repo_path, remote_name);
+
+    let token_cache =
+        get_token_for_repository(&app_handle, &repo_path, remote_name.as_deref()).await;
+
+    tokio::task::spawn_blocking(move || {
+        crate::git::operations::fetch_and_report(&repo_path, remote_name.as_deref(), token_cache)
+    })
+    .await
+    .map_err(|e| {
+        log::error!("任务执行失败: {}", e);
+        crate::git::types::GitError::Unknown {
+            message: format!("任务执行失败: {}", e),
+        }
+    })?
+    .map_err(|e| {
+        log::error!("获取远程新提交失败: {}", e);
+        e
+    })
+}
+
+/// 推送进度事件名称
+const PUSH_PROGRESS_EVENT: &str = "push-progress";
+
+/// 智能推送本地变更（支持Token认证）
+///
+/// 推送过程中通过窗口发送 `push-progress` 事件报告打包和上传进度；
+/// `ssh_key_path`/`passphrase` 指定SSH远程使用的私钥，HTTPS 远程忽略；
+/// `max_auth_attempts` 为最大认证尝试次数，未指定时使用默认值
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn smart_push_remote(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    window: Window,
+    repo_path: String,
+    remote_name: Option<String>,
+    force: Option<bool>,
+    packbuilder_parallelism: Option<u32>,
+    ssh_key_path: Option<String>,
+    passphrase: Option<String>,
+    max_auth_attempts: Option<u32>,
+) -> Result<crate::git::types::SyncResult, crate::git::types::GitError> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!(
+        "智能推送本地变更: {} (remote: {:?}, force: {:?})",
+        repo_path,
+        remote_name,
+        force
+    );
+
+    // 获取远程URL并提取域名
+    let token_cache =
+        get_token_for_repository(&app_handle, &repo_path, remote_name.as_deref()).await;
+
+    if let Some(ref _token) = token_cache {
+        log::debug!("使用Token认证进行push操作");
+    } else {
+        log::debug!("使用默认认证进行push操作");
+    }
+
+    // 凭据回调可能阻塞等待用户输入，在阻塞任务中执行
+    let auth = crate::git::operations::AuthOptions {
+        ssh_key: crate::git::operations::ssh_key_auth(ssh_key_path, passphrase),
+        interactive: true,
+        max_attempts: max_auth_attempts,
+    };
+    let result = tokio::task::spawn_blocking(move || {
+        let mut throttle = ProgressThrottle::default();
+        crate::git::operations::push_remote_with_progress(
+            &repo_path,
+            remote_name.as_deref(),
+            force.unwrap_or(false),
+            token_cache,
+            auth,
+            packbuilder_parallelism,
+            |progress| {
+                // 每个阶段完成时的进度总是发送，其余按时间节流
+                let is_final = progress.total > 0 && progress.current == progress.total;
+                if throttle.should_emit(is_final) {
+                    let _ = window.emit(PUSH_PROGRESS_EVENT, &progress);
+                }
+            },
+        )
+    })
+    .await
+    .map_err(|e| {
+        log::error!("任务执行失败: {}", e);
+        crate::git::types::GitError::Unknown {
+            message: format!("任务执行失败: {}", e),
+        }
+    })?;
+
+    // 返回结构化错误，前端可根据 non_fast_forward 等类型提供先拉取再推送或强制推送的选项
+    result.map_err(|e| {
+        log::error!("智能推送本地变更失败: {}", e);
+        e
+    })
+}
+
+/// 提交并推送（支持Token认证）
+///
+/// 提交失败时返回错误；提交成功但推送失败时返回 `pushed: false` 及新提交的 SHA，
+/// 前端可据此只重试推送
+#[command]
+pub async fn commit_and_push(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    commit_options: crate::git::types::CommitOptions,
+    remote_name: Option<String>,
+    force: Option<bool>,
+) -> Result<crate::git::types::CommitAndPushResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!(
+        "提交并推送: {} in {} (remote: {:?}, force: {:?})",
+        commit_options.message,
+        repo_path,
+        remote_name,
+        force
+    );
+
+    let token_cache =
+        get_token_for_repository(&app_handle, &repo_path, remote_name.as_deref()).await;
+
+    let path = repo_path.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        crate::git::operations::commit_and_push(
+            &path,
+            &commit_options,
+            remote_name.as_deref(),
+            force.unwrap_or(false),
+            token_cache,
+        )
+    })
+    .await
+    .map_err(|e| {
+        log::error!("任务执行失败: {}", e);
+        format!("任务执行失败: {}", e)
+    })?
+    .map_err(|e| {
+        log::error!("提交失败: {}", e);
+        e.to_string()
+    })?;
+
+    // 提交已在本地创建，推送失败时只记录推送错误，由前端重试推送
+    if let Some(push_error) = &result.push_error {
+        log::error!(
+            "推送失败（提交 {} 已创建）: {}",
+            result.commit_sha,
+            push_error
+        );
+    }
+
+    notify_index_changed(&app_handle, &repo_path, &[]);
+    Ok(result)
+}
+
+/// 列出指定版本中某个目录下的条目（用于文件浏览器按需展开）
+#[command]
+pub async fn list_tree(
+    repo_path: String,
+    revision: Option<String>,
+    path: Option<String>,
+) -> Result<Vec<crate::git::types::TreeEntry>, String> {
+    log::debug!(
+        "列出树条目: {} (revision: {:?}, path: {:?})",
+        repo_path,
+        revision,
+        path
+    );
+
+    match crate::git::operations::list_tree(&repo_path, revision.as_deref(), path.as_deref()) {
+        Ok(entries) => Ok(entries),
+        Err(e) => {
+            log::error!("列出树条目失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 从指定提交恢复文件到工作区并暂存
+#[command]
+pub async fn restore_file_from_commit(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    commit_ref: String,
+    file_path: String,
+) -> Result<(), String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("从 {} 恢复文件: {} in {}", commit_ref, file_path, repo_path);
+
+    match crate::git::operations::restore_file_from_commit(&repo_path, &commit_ref, &file_path) {
+        Ok(()) => {
+            notify_index_changed(&app_handle, &repo_path, &[file_path]);
+            Ok(())
+        }
+        Err(e) => {
+            log::error!("恢复文件失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 获取指定版本中某个路径的类型、大小等元数据
+#[command]
+pub async fn stat_path(
+    repo_path: String,
+    revision: Option<String>,
+    path: String,
+) -> Result<crate::git::types::PathStat, String> {
+    log::debug!(
+        "获取路径元数据: {} in {} (revision: {:?})",
+        path,
+        repo_path,
+        revision
+    );
+
+    crate::git::operations::stat_path(&repo_path, revision.as_deref(), &path).map_err(|e| {
+        log::error!("获取路径元数据失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 获取两个引用的合并基准
+#[command]
+pub async fn get_merge_base(
+    repo_path: String,
+    ref_a: String,
+    ref_b: String,
+) -> Result<String, String> {
+    log::debug!("获取合并基准: {} ({} / {})", repo_path, ref_a, ref_b);
+
+    crate::git::operations::get_merge_base(&repo_path, &ref_a, &ref_b).map_err(|e| {
+        log::error!("获取合并基准失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 获取多个引用的所有合并基准
+#[command]
+pub async fn get_merge_bases(repo_path: String, refs: Vec<String>) -> Result<Vec<String>, String> {
+    log::debug!("获取合并基准: {} ({:?})", repo_path, refs);
 
-    match crate::git::operations::fetch_remote_with_token(
-        &repo_path,
-        remote_name.as_deref(),
-        token_cache,
-    ) {
-        Ok(result) => Ok(result),
-        Err(e) => {
-            log::error!("智能获取远程变更失败: {}", e);
-            Err(e.to_string())
-        }
-    }
+    crate::git::operations::get_merge_bases(&repo_path, &refs).map_err(|e| {
+        log::error!("获取合并基准失败: {}", e);
+        e.to_string()
+    })
 }
 
-/// 拉取远程变更（pull操作）
+/// 获取差异统计摘要（from 默认为 HEAD，to 为空时与工作区比较）
 #[command]
-pub async fn pull_remote(
+pub async fn get_diff_stat(
     repo_path: String,
-    strategy: String,
-) -> Result<crate::git::types::SyncResult, String> {
-    log::debug!("拉取远程变更: {} (strategy: {})", repo_path, strategy);
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<crate::git::types::DiffStat, String> {
+    log::debug!("获取差异统计: {} ({:?}..{:?})", repo_path, from, to);
+
+    crate::git::operations::get_diff_stat(&repo_path, from.as_deref(), to.as_deref()).map_err(|e| {
+        log::error!("获取差异统计失败: {}", e);
+        e.to_string()
+    })
+}
 
-    let pull_strategy = match strategy.as_str() {
-        "merge" => crate::git::types::PullStrategy::Merge,
-        "rebase" => crate::git::types::PullStrategy::Rebase,
-        _ => {
-            return Err("无效的拉取策略，支持: merge, rebase".to_string());
-        }
-    };
+/// 获取所有已暂存变更的结构化差异
+#[command]
+pub async fn get_staged_diff(
+    repo_path: String,
+    whitespace: Option<crate::git::types::WhitespaceOptions>,
+) -> Result<Vec<crate::git::types::FileDiff>, String> {
+    log::debug!("获取暂存区差异: {}", repo_path);
 
-    match crate::git::operations::pull_remote(&repo_path, pull_strategy) {
-        Ok(result) => Ok(result),
+    match crate::git::operations::get_staged_diff(&repo_path, &whitespace.unwrap_or_default()) {
+        Ok(diffs) => Ok(diffs),
         Err(e) => {
-            log::error!("拉取远程变更失败: {}", e);
+            log::error!("获取暂存区差异失败: {}", e);
             Err(e.to_string())
         }
     }
 }
 
-/// 推送本地变更（push操作）
+/// 获取所有未暂存变更的结构化差异
 #[command]
-pub async fn push_remote(
+pub async fn get_unstaged_diff(
     repo_path: String,
-    remote_name: Option<String>,
-    force: Option<bool>,
-) -> Result<crate::git::types::SyncResult, String> {
-    log::debug!(
-        "推送本地变更: {} (remote: {:?}, force: {:?})",
-        repo_path,
-        remote_name,
-        force
-    );
-
-    match crate::git::operations::push_remote(
-        &repo_path,
-        remote_name.as_deref(),
-        force.unwrap_or(false),
-    ) {
-        Ok(result) => Ok(result),
+    whitespace: Option<crate::git::types::WhitespaceOptions>,
+) -> Result<Vec<crate::git::types::FileDiff>, String> {
+    log::debug!("获取工作区差异: {}", repo_path);
 
+    match crate::git::operations::get_unstaged_diff(&repo_path, &whitespace.unwrap_or_default()) {
+        Ok(diffs) => Ok(diffs),
         Err(e) => {
-            log::error!("推送本地变更失败: {}", e);
+            log::error!("获取工作区差异失败: {}", e);
             Err(e.to_string())
         }
     }
 }
 
-/// 智能推送本地变更（支持Token认证）
+/// 预览推送结果（将要推送的提交以及是否需要强制推送）
 #[command]
-pub async fn smart_push_remote(
-    app_handle: AppHandle,
+pub async fn push_preview(
     repo_path: String,
     remote_name: Option<String>,
-    force: Option<bool>,
-) -> Result<crate::git::types::SyncResult, String> {
+    branch: Option<String>,
+) -> Result<crate::git::types::PushPreview, String> {
     log::debug!(
-        "智能推送本地变更: {} (remote: {:?}, force: {:?})",
+        "预览推送: {} (remote: {:?}, branch: {:?})",
         repo_path,
         remote_name,
-        force
+        branch
     );
 
-    // 获取远程URL并提取域名
-    let token_cache = match get_token_for_repository(&app_handle, &repo_path).await {
-        Ok(token) => token,
-        Err(e) => {
-            log::warn!("获取Token失败，使用默认认证: {}", e);
-            None
-        }
-    };
-
-    if let Some(ref _token) = token_cache {
-        log::debug!("使用Token认证进行push操作");
-    } else {
-        log::debug!("使用默认认证进行push操作");
-    }
-
-    match crate::git::operations::push_remote_with_token(
+    match crate::git::operations::push_preview(
         &repo_path,
         remote_name.as_deref(),
-        force.unwrap_or(false),
-        token_cache,
+        branch.as_deref(),
     ) {
-        Ok(result) => Ok(result),
+        Ok(preview) => Ok(preview),
         Err(e) => {
-            log::error!("智能推送本地变更失败: {}", e);
+            log::error!("预览推送失败: {}", e);
             Err(e.to_string())
         }
     }
@@ -797,6 +2246,59 @@ pub async fn get_remote_info(
     }
 }
 
+/// 查询远程默认分支并写入本地的 `refs/remotes/<remote>/HEAD`（访问网络）
+///
+/// `get_remote_info` 只读取本地引用，引用缺失时可调用此命令刷新
+#[command]
+pub async fn refresh_remote_default_branch(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    remote_name: Option<String>,
+) -> Result<Option<String>, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!(
+        "刷新远程默认分支: {} (remote: {:?})",
+        repo_path,
+        remote_name
+    );
+
+    let token_cache =
+        get_token_for_repository(&app_handle, &repo_path, remote_name.as_deref()).await;
+
+    tokio::task::spawn_blocking(move || {
+        crate::git::operations::refresh_remote_default_branch(
+            &repo_path,
+            remote_name.as_deref(),
+            token_cache,
+        )
+    })
+    .await
+    .map_err(|e| {
+        log::error!("任务执行失败: {}", e);
+        format!("任务执行失败: {}", e)
+    })?
+    .map_err(|e| {
+        log::error!("刷新远程默认分支失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 获取当前分支的上游跟踪状态
+///
+/// 未设置上游时返回错误，前端可据此提示设置上游分支
+#[command]
+pub async fn get_tracking_status(
+    repo_path: String,
+) -> Result<crate::git::types::TrackingStatus, String> {
+    log::debug!("获取跟踪状态: {}", repo_path);
+
+    crate::git::operations::get_tracking_status(&repo_path).map_err(|e| {
+        log::error!("获取跟踪状态失败: {}", e);
+        e.to_string()
+    })
+}
+
 // ==================== 新增：双协议认证系统 ====================
 
 /// 协议类型
@@ -817,23 +2319,56 @@ pub struct TokenConfig {
 }
 
 /// 检测远程仓库URL的协议类型
+///
+/// 需要 URL 或主机时请使用 `detect_repository_remote`，避免多次调用
 #[command]
 pub async fn detect_repository_protocol(repo_path: String) -> Result<String, String> {
     log::debug!("检测仓库协议类型: {}", repo_path);
 
+    detect_repository_remote(repo_path)
+        .await
+        .map(|remote| remote.protocol)
+}
+
+/// 仓库远程信息（协议、URL 和主机）
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct RepositoryRemoteInfo {
+    /// 协议类型（https、ssh 或 unknown）
+    pub protocol: String,
+    /// 远程 URL
+    pub url: String,
+    /// 远程主机，无法解析时为 None
+    pub host: Option<String>,
+}
+
+/// 根据远程URL判断协议类型
+fn classify_remote_protocol(url: &str) -> &'static str {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        "https"
+    } else if url.starts_with("ssh://") || crate::git::operations::split_scp_url(url).is_some() {
+        "ssh"
+    } else {
+        "unknown"
+    }
+}
+
+/// 一次性获取远程仓库的协议、URL 和主机，供认证流程使用
+#[command]
+pub async fn detect_repository_remote(repo_path: String) -> Result<RepositoryRemoteInfo, String> {
+    log::debug!("检测仓库远程信息: {}", repo_path);
+
     // 获取远程URL
     match get_remote_url(repo_path).await? {
         Some(url) => {
-            let protocol = if url.starts_with("https://") || url.starts_with("http://") {
-                "https"
-            } else if url.starts_with("git@") || url.starts_with("ssh://") {
-                "ssh"
-            } else {
-                "unknown"
-            };
+            let protocol = classify_remote_protocol(&url);
+            let host = extract_domain_from_url(url.clone()).await.ok();
 
             log::debug!("检测到协议类型: {} for URL: {}", protocol, url);
-            Ok(protocol.to_string())
+            Ok(RepositoryRemoteInfo {
+                protocol: protocol.to_string(),
+                url,
+                host,
+            })
         }
         None => {
             log::warn!("未找到远程URL");
@@ -847,35 +2382,7 @@ pub async fn detect_repository_protocol(repo_path: String) -> Result<String, Str
 pub async fn extract_domain_from_url(url: String) -> Result<String, String> {
     log::debug!("从URL提取域名: {}", url);
 
-    // 处理HTTPS URL
-    if url.starts_with("https://") || url.starts_with("http://") {
-        if let Ok(parsed_url) = url::Url::parse(&url) {
-            if let Some(host) = parsed_url.host_str() {
-                return Ok(host.to_string());
-            }
-        }
-    }
-
-    // 处理SSH URL (git@github.com:user/repo.git)
-    if url.starts_with("git@") {
-        if let Some(at_pos) = url.find('@') {
-            if let Some(colon_pos) = url[at_pos..].find(':') {
-                let domain = &url[at_pos + 1..at_pos + colon_pos];
-                return Ok(domain.to_string());
-            }
-        }
-    }
-
-    // 处理SSH URL (ssh://git@github.com/user/repo.git)
-    if url.starts_with("ssh://") {
-        if let Ok(parsed_url) = url::Url::parse(&url) {
-            if let Some(host) = parsed_url.host_str() {
-                return Ok(host.to_string());
-            }
-        }
-    }
-
-    Err("无法从URL提取域名".to_string())
+    crate::git::operations::extract_domain(&url).ok_or_else(|| "无法从URL提取域名".to_string())
 }
 
 /// 存储Personal Access Token
@@ -896,12 +2403,17 @@ pub async fn store_access_token(
         last_used: None,
     };
 
+    let value = serde_json::to_value(&token_config).map_err(|e| {
+        log::error!("序列化Token失败: {}", e);
+        format!("序列化Token失败: {}", e)
+    })?;
+
     // 使用Tauri Store API存储token
     let store = StoreBuilder::new(&app_handle, "tokens.dat").build();
 
     match store {
         Ok(store) => {
-            store.set(domain.clone(), serde_json::to_value(&token_config).unwrap());
+            store.set(domain.clone(), value);
             match store.save() {
                 Ok(_) => {
                     log::info!("Token存储成功: {}", domain);
@@ -1033,15 +2545,390 @@ pub async fn update_token_last_used(app_handle: AppHandle, domain: String) -> Re
     }
 }
 
+/// 将仓库指定版本导出为 tar/zip 归档
+///
+/// 优先使用 `git archive`，系统Git不可用时回退到 git2 生成 tar 归档
+#[command]
+pub async fn archive_repository(
+    repo_path: String,
+    revision: Option<String>,
+    format: crate::git::types::ArchiveFormat,
+    output_path: String,
+    prefix: Option<String>,
+) -> Result<crate::git::types::ArchiveResult, String> {
+    let revision = revision.unwrap_or_else(|| "HEAD".to_string());
+    let prefix = crate::git::operations::normalize_archive_prefix(prefix.as_deref());
+
+    log::debug!(
+        "导出归档: {} (revision: {}, format: {}, output: {})",
+        repo_path,
+        revision,
+        format.as_str(),
+        output_path
+    );
+
+    let mut cmd = crate::utils::system_command::create_hidden_command_async("git");
+    cmd.arg("archive")
+        .arg(format!("--format={}", format.as_str()))
+        .arg("-o")
+        .arg(&output_path);
+    if !prefix.is_empty() {
+        cmd.arg(format!("--prefix={}", prefix));
+    }
+    cmd.arg(&revision).current_dir(&repo_path);
+
+    match cmd.output().await {
+        Ok(output) => {
+            if !output.status.success() {
+                let error_msg = String::from_utf8_lossy(&output.stderr);
+                log::error!("Git archive失败: {}", error_msg);
+                return Err(format!("Git archive失败: {}", error_msg));
+            }
+
+            let size = std::fs::metadata(&output_path)
+                .map_err(|e| format!("读取归档文件失败: {}", e))?
+                .len();
+
+            Ok(crate::git::types::ArchiveResult { output_path, size })
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            log::warn!("系统Git不可用，使用内置实现导出归档");
+
+            if format != crate::git::types::ArchiveFormat::Tar {
+                return Err("系统Git不可用时仅支持导出 tar 格式".to_string());
+            }
+
+            let result = tokio::task::spawn_blocking(move || {
+                crate::git::operations::archive_repository_tar(
+                    &repo_path,
+                    &revision,
+                    &output_path,
+                    Some(&prefix),
+                )
+            })
+            .await
+            .map_err(|e| format!("导出归档任务失败: {}", e))?;
+
+            result.map_err(|e| {
+                log::error!("导出归档失败: {}", e);
+                e.to_string()
+            })
+        }
+        Err(e) => {
+            log::error!("执行Git命令失败: {}", e);
+            Err(format!("执行Git命令失败: {}", e))
+        }
+    }
+}
+
+/// 垃圾回收事件名称
+const GC_EVENT: &str = "repository-gc";
+
+/// git gc 的默认超时时间（秒），大仓库的 --aggressive 可能需要数分钟
+const DEFAULT_GC_TIMEOUT_SECS: u64 = 600;
+
+/// 优化仓库：执行 `git gc` 打包松散对象并清理不可达对象
+///
+/// 开始和结束时通过窗口发送 `repository-gc` 事件；需要系统Git
+#[command]
+pub async fn gc_repository(
+    git_state: State<'_, GitState>,
+    window: Window,
+    repo_path: String,
+    aggressive: bool,
+    timeout_secs: Option<u64>,
+) -> Result<crate::git::types::GcResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    let timeout_secs = timeout_secs.unwrap_or(DEFAULT_GC_TIMEOUT_SECS);
+    log::debug!(
+        "优化仓库: {} (aggressive: {}, timeout: {}s)",
+        repo_path,
+        aggressive,
+        timeout_secs
+    );
+
+    let before = get_repository_size(repo_path.clone()).await?;
+
+    let _ = window.emit(
+        GC_EVENT,
+        &crate::git::types::GcEvent {
+            repo_path: repo_path.clone(),
+            stage: crate::git::types::GcStage::Started,
+            success: None,
+            message: None,
+        },
+    );
+
+    let result = run_git_gc(&repo_path, aggressive, timeout_secs).await;
+
+    let _ = window.emit(
+        GC_EVENT,
+        &crate::git::types::GcEvent {
+            repo_path: repo_path.clone(),
+            stage: crate::git::types::GcStage::Finished,
+            success: Some(result.is_ok()),
+            message: result.as_ref().err().cloned(),
+        },
+    );
+    result?;
+
+    let after = get_repository_size(repo_path).await?;
+    Ok(crate::git::types::GcResult {
+        aggressive,
+        freed_bytes: before.git_dir_bytes.saturating_sub(after.git_dir_bytes),
+        before,
+        after,
+    })
+}
+
+/// 执行 `git gc`，超时后终止进程
+async fn run_git_gc(repo_path: &str, aggressive: bool, timeout_secs: u64) -> Result<(), String> {
+    let mut cmd = crate::utils::system_command::create_hidden_command_async("git");
+    cmd.arg("gc").arg("--quiet");
+    if aggressive {
+        cmd.arg("--aggressive");
+    }
+    cmd.current_dir(repo_path).kill_on_drop(true);
+
+    let output =
+        tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), cmd.output()).await;
+
+    match output {
+        Ok(Ok(output)) if output.status.success() => Ok(()),
+        Ok(Ok(output)) => {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            log::error!("Git gc失败: {}", error_msg);
+            Err(format!("Git gc失败: {}", error_msg))
+        }
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            log::error!("系统Git不可用，无法优化仓库");
+            Err("优化仓库需要安装系统Git (git required)".to_string())
+        }
+        Ok(Err(e)) => {
+            log::error!("执行Git命令失败: {}", e);
+            Err(format!("执行Git命令失败: {}", e))
+        }
+        Err(_) => {
+            log::error!("Git gc操作超时 ({}秒)", timeout_secs);
+            Err(format!("Git gc操作超时 ({}秒)", timeout_secs))
+        }
+    }
+}
+
+/// 最近打开仓库的存储文件
+const RECENT_REPOSITORIES_STORE: &str = "recent_repositories.dat";
+
+/// 最多保留的最近仓库数量
+const MAX_RECENT_REPOSITORIES: usize = 30;
+
+/// 记录最近打开的仓库，超出数量上限时移除最早打开的记录
+#[command]
+pub async fn add_recent_repository(
+    app_handle: AppHandle,
+    path: String,
+) -> Result<crate::git::types::RecentRepo, String> {
+    log::debug!("记录最近打开的仓库: {}", path);
+
+    let recent = crate::git::operations::recent_repository_info(&path).map_err(|e| {
+        log::error!("读取仓库信息失败: {}", e);
+        e.to_string()
+    })?;
+
+    let store = StoreBuilder::new(&app_handle, RECENT_REPOSITORIES_STORE)
+        .build()
+        .map_err(|e| {
+            log::error!("无法打开最近仓库存储: {}", e);
+            format!("无法打开最近仓库存储: {}", e)
+        })?;
+
+    let value = serde_json::to_value(&recent).map_err(|e| {
+        log::error!("序列化最近仓库记录失败: {}", e);
+        format!("序列化最近仓库记录失败: {}", e)
+    })?;
+    store.set(recent.path.clone(), value);
+
+    let mut entries: Vec<crate::git::types::RecentRepo> = store
+        .values()
+        .into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect();
+    if entries.len() > MAX_RECENT_REPOSITORIES {
+        entries.sort_by(|a, b| b.last_opened.cmp(&a.last_opened));
+        for stale in &entries[MAX_RECENT_REPOSITORIES..] {
+            store.delete(&stale.path);
+        }
+    }
+
+    store.save().map_err(|e| {
+        log::error!("保存最近仓库失败: {}", e);
+        format!("保存最近仓库失败: {}", e)
+    })?;
+
+    Ok(recent)
+}
+
+/// 获取最近打开的仓库（按最后打开时间倒序），已不存在或不再是仓库的记录会被移除
+#[command]
+pub async fn get_recent_repositories(
+    app_handle: AppHandle,
+) -> Result<Vec<crate::git::types::RecentRepo>, String> {
+    log::debug!("获取最近打开的仓库");
+
+    let store = StoreBuilder::new(&app_handle, RECENT_REPOSITORIES_STORE)
+        .build()
+        .map_err(|e| {
+            log::error!("无法读取最近仓库存储: {}", e);
+            format!("无法读取最近仓库存储: {}", e)
+        })?;
+
+    let mut recent = Vec::new();
+    let mut pruned = false;
+    for (key, value) in store.entries() {
+        match serde_json::from_value::<crate::git::types::RecentRepo>(value) {
+            Ok(entry) if Repository::open(&entry.path).is_ok() => recent.push(entry),
+            _ => {
+                log::debug!("移除失效的最近仓库记录: {}", key);
+                store.delete(&key);
+                pruned = true;
+            }
+        }
+    }
+
+    if pruned {
+        if let Err(e) = store.save() {
+            log::warn!("保存最近仓库失败: {}", e);
+        }
+    }
+
+    recent.sort_by(|a, b| b.last_opened.cmp(&a.last_opened));
+    Ok(recent)
+}
+
+/// 从最近仓库列表中移除仓库，返回是否存在该记录
+#[command]
+pub async fn remove_recent_repository(app_handle: AppHandle, path: String) -> Result<bool, String> {
+    log::debug!("移除最近打开的仓库: {}", path);
+
+    let store = StoreBuilder::new(&app_handle, RECENT_REPOSITORIES_STORE)
+        .build()
+        .map_err(|e| {
+            log::error!("无法打开最近仓库存储: {}", e);
+            format!("无法打开最近仓库存储: {}", e)
+        })?;
+
+    // 仓库仍存在时按根目录匹配，便于传入子目录
+    let key = crate::git::operations::get_repository_root(&path).unwrap_or(path);
+    let removed = store.delete(&key);
+    if removed {
+        store.save().map_err(|e| {
+            log::error!("保存最近仓库失败: {}", e);
+            format!("保存最近仓库失败: {}", e)
+        })?;
+    }
+
+    Ok(removed)
+}
+
 // ==================== 系统Git命令实现 ====================
 
+/// 扫描主机的SSH公钥并返回其指纹，便于用户在信任新主机前进行确认
+///
+/// `host` 支持 `example.com` 或 `example.com:2222` 形式
+#[command]
+pub async fn scan_host_key(host: String) -> Result<String, String> {
+    use tokio::io::AsyncWriteExt;
+
+    log::debug!("扫描主机密钥: {}", host);
+
+    let (hostname, port) = crate::git::operations::parse_host_key_target(&host).map_err(|e| {
+        log::error!("主机名校验失败: {}", e);
+        e.to_string()
+    })?;
+
+    let mut keyscan = crate::utils::system_command::create_hidden_command_async("ssh-keyscan");
+    keyscan.arg("-T").arg("10");
+    if let Some(port) = port {
+        keyscan.arg("-p").arg(port.to_string());
+    }
+    // 主机名放在 `--` 之后，不会被解析为选项
+    keyscan.arg("--").arg(&hostname);
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(15), keyscan.output())
+        .await
+        .map_err(|_| {
+            log::error!("扫描主机密钥超时: {}", host);
+            format!("扫描主机密钥超时: {}", host)
+        })?
+        .map_err(|e| {
+            log::error!("执行ssh-keyscan失败: {}", e);
+            format!("执行ssh-keyscan失败: {}", e)
+        })?;
+
+    let keys = String::from_utf8_lossy(&output.stdout).to_string();
+    if keys.trim().is_empty() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        log::error!("未能获取主机密钥 {}: {}", host, error_msg);
+        return Err(format!("未能获取主机密钥: {}", host));
+    }
+
+    // 通过 ssh-keygen 计算指纹
+    let mut keygen = crate::utils::system_command::create_hidden_command_async("ssh-keygen");
+    keygen
+        .arg("-lf")
+        .arg("-")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+
+    let mut child = keygen.spawn().map_err(|e| {
+        log::error!("执行ssh-keygen失败: {}", e);
+        format!("执行ssh-keygen失败: {}", e)
+    })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(keys.as_bytes())
+            .await
+            .map_err(|e| format!("写入主机密钥失败: {}", e))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("执行ssh-keygen失败: {}", e))?;
+
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        log::error!("计算主机密钥指纹失败: {}", error_msg);
+        return Err(format!("计算主机密钥指纹失败: {}", error_msg));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 将系统Git命令的失败信息包装为 `GitError::SystemGitFailed`
+fn system_git_failed(message: impl Into<String>) -> crate::git::types::GitError {
+    crate::git::types::GitError::SystemGitFailed {
+        message: message.into(),
+    }
+}
+
 /// 使用系统Git命令执行fetch操作（用于SSH协议）
+///
+/// 提供 `operation_id` 时可通过 `cancel_git_operation` 中途取消
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn fetch_remote_with_system_git(
+    git_state: State<'_, GitState>,
     repo_path: String,
     remote_name: Option<String>,
     ssh_key_path: Option<String>,
-) -> Result<crate::git::types::SyncResult, String> {
+    known_hosts_file: Option<String>,
+    strict_host_key_checking: Option<bool>,
+    respect_ssh_environment: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<crate::git::types::SyncResult, crate::git::types::GitError> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
     log::debug!(
         "使用系统Git执行fetch: {} (remote: {:?}, ssh_key: {:?})",
         repo_path,
@@ -1057,73 +2944,135 @@ pub async fn fetch_remote_with_system_git(
             Ok(name) => name,
             Err(e) => {
                 log::error!("检测默认远程名称失败: {}", e);
-                return Err(format!("检测默认远程名称失败: {}", e));
+                return Err(system_git_failed(format!("检测默认远程名称失败: {}", e)));
             }
         }
     };
 
     let mut cmd = crate::utils::system_command::create_hidden_command_async("git");
 
-    // 如果指定了SSH密钥，设置GIT_SSH_COMMAND环境变量
-    if let Some(ssh_key) = ssh_key_path {
-        let ssh_command = format!(
-            "ssh -i \"{}\" -o StrictHostKeyChecking=no -o ConnectTimeout=10 -o BatchMode=yes",
-            ssh_key
-        );
+    // 设置GIT_SSH_COMMAND环境变量（仅在选择了密钥时注入密钥和主机密钥校验选项）
+    if let Some(ssh_command) = build_git_ssh_command(
+        ssh_key_path.as_deref(),
+        known_hosts_file.as_deref(),
+        strict_host_key_checking,
+        respect_ssh_environment.unwrap_or(false),
+    ) {
         cmd.env("GIT_SSH_COMMAND", ssh_command);
-        log::debug!("使用SSH密钥: {}", ssh_key);
-    } else {
-        // 即使没有指定密钥，也设置超时和批处理模式
-        cmd.env(
-            "GIT_SSH_COMMAND",
-            "ssh -o ConnectTimeout=10 -o BatchMode=yes",
-        );
     }
 
     cmd.arg("fetch").arg(&remote).current_dir(&repo_path);
 
     // 添加30秒超时
-    let output = tokio::time::timeout(std::time::Duration::from_secs(30), cmd.output()).await;
+    let output = git_state
+        .git_processes
+        .run(operation_id.as_deref(), cmd, Duration::from_secs(30))
+        .await;
 
     match output {
-        Ok(Ok(output)) => {
+        Ok(GitProcessOutcome::Finished(output)) => {
             if output.status.success() {
-                // 获取ahead/behind状态
-                let (ahead, behind) = get_ahead_behind_with_git(&repo_path).await?;
+                crate::git::operations::record_last_sync(&repo_path);
+
+                // 获取ahead/behind状态（未配置上游时与刚获取的同名远程分支比较）
+                let (ahead, behind) =
+                    crate::git::operations::get_fetched_ahead_behind(&repo_path, &remote)?;
 
                 Ok(crate::git::types::SyncResult {
                     success: true,
                     message: "成功获取远程变更".to_string(),
-                    has_conflicts: false,
-                    conflict_files: vec![],
-                    ahead: ahead.max(0) as u32,
-                    behind: behind.max(0) as u32,
+                    ahead,
+                    behind,
+                    ..Default::default()
                 })
             } else {
                 let error_msg = String::from_utf8_lossy(&output.stderr);
                 log::error!("Git fetch失败: {}", error_msg);
-                Err(format!("Git fetch失败: {}", error_msg))
+                Err(system_git_failed(format!("Git fetch失败: {}", error_msg)))
             }
         }
-        Ok(Err(e)) => {
-            log::error!("执行Git命令失败: {}", e);
-            Err(format!("执行Git命令失败: {}", e))
+        Ok(GitProcessOutcome::Cancelled) => {
+            log::info!("Git fetch操作已取消");
+            Err(crate::git::types::GitError::OperationCancelled)
         }
-        Err(_) => {
+        Ok(GitProcessOutcome::TimedOut) => {
             log::error!("Git fetch操作超时 (30秒)");
-            Err("Git fetch操作超时，可能是SSH密钥认证失败或网络问题".to_string())
+            Err(system_git_failed(
+                "Git fetch操作超时，可能是SSH密钥认证失败或网络问题",
+            ))
+        }
+        Err(e) => {
+            log::error!("执行Git命令失败: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// 将浅克隆仓库补全为完整历史（通过系统Git执行 `git fetch --unshallow`）
+///
+/// 提供 `operation_id` 时可通过 `cancel_git_operation` 中途取消
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn unshallow(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+    remote_name: Option<String>,
+    ssh_key_path: Option<String>,
+    known_hosts_file: Option<String>,
+    strict_host_key_checking: Option<bool>,
+    respect_ssh_environment: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<crate::git::types::SyncResult, crate::git::types::GitError> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!(
+        "补全浅克隆历史: {} (remote: {:?}, ssh_key: {:?})",
+        repo_path,
+        remote_name,
+        ssh_key_path
+    );
+
+    let ssh_command = build_git_ssh_command(
+        ssh_key_path.as_deref(),
+        known_hosts_file.as_deref(),
+        strict_host_key_checking,
+        respect_ssh_environment.unwrap_or(false),
+    );
+
+    match crate::git::operations::unshallow(
+        &repo_path,
+        remote_name.as_deref(),
+        ssh_command.as_deref(),
+        &git_state.git_processes,
+        operation_id.as_deref(),
+        crate::git::operations::UNSHALLOW_TIMEOUT,
+    )
+    .await
+    {
+        Ok(result) => Ok(result),
+        Err(e) => {
+            log::error!("补全浅克隆历史失败: {}", e);
+            Err(e)
         }
     }
 }
 
 /// 使用系统Git命令执行push操作（用于SSH协议）
+///
+/// 提供 `operation_id` 时可通过 `cancel_git_operation` 中途取消
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn push_remote_with_system_git(
+    git_state: State<'_, GitState>,
     repo_path: String,
     remote_name: Option<String>,
     force: Option<bool>,
     ssh_key_path: Option<String>,
-) -> Result<crate::git::types::SyncResult, String> {
+    known_hosts_file: Option<String>,
+    strict_host_key_checking: Option<bool>,
+    respect_ssh_environment: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<crate::git::types::SyncResult, crate::git::types::GitError> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
     log::debug!(
         "使用系统Git执行push: {} (remote: {:?}, force: {:?}, ssh_key: {:?})",
         repo_path,
@@ -1140,31 +3089,27 @@ pub async fn push_remote_with_system_git(
             Ok(name) => name,
             Err(e) => {
                 log::error!("检测默认远程名称失败: {}", e);
-                return Err(format!("检测默认远程名称失败: {}", e));
+                return Err(system_git_failed(format!("检测默认远程名称失败: {}", e)));
             }
         }
     };
     let force_flag = force.unwrap_or(false);
 
     // 获取当前分支
-    let current_branch = get_current_branch_with_git(&repo_path).await?;
+    let current_branch = get_current_branch_with_git(&repo_path)
+        .await
+        .map_err(system_git_failed)?;
 
     let mut cmd = crate::utils::system_command::create_hidden_command_async("git");
 
-    // 如果指定了SSH密钥，设置GIT_SSH_COMMAND环境变量
-    if let Some(ssh_key) = ssh_key_path {
-        let ssh_command = format!(
-            "ssh -i \"{}\" -o StrictHostKeyChecking=no -o ConnectTimeout=10 -o BatchMode=yes",
-            ssh_key
-        );
+    // 设置GIT_SSH_COMMAND环境变量（仅在选择了密钥时注入密钥和主机密钥校验选项）
+    if let Some(ssh_command) = build_git_ssh_command(
+        ssh_key_path.as_deref(),
+        known_hosts_file.as_deref(),
+        strict_host_key_checking,
+        respect_ssh_environment.unwrap_or(false),
+    ) {
         cmd.env("GIT_SSH_COMMAND", ssh_command);
-        log::debug!("使用SSH密钥: {}", ssh_key);
-    } else {
-        // 即使没有指定密钥，也设置超时和批处理模式
-        cmd.env(
-            "GIT_SSH_COMMAND",
-            "ssh -o ConnectTimeout=10 -o BatchMode=yes",
-        );
     }
 
     cmd.arg("push");
@@ -1178,46 +3123,67 @@ pub async fn push_remote_with_system_git(
         .current_dir(&repo_path);
 
     // 添加30秒超时
-    let output = tokio::time::timeout(std::time::Duration::from_secs(30), cmd.output()).await;
+    let output = git_state
+        .git_processes
+        .run(operation_id.as_deref(), cmd, Duration::from_secs(30))
+        .await;
 
     match output {
-        Ok(Ok(output)) => {
+        Ok(GitProcessOutcome::Finished(output)) => {
             if output.status.success() {
+                crate::git::operations::record_last_sync(&repo_path);
+
                 // 获取ahead/behind状态
-                let (ahead, behind) = get_ahead_behind_with_git(&repo_path).await?;
+                let (ahead, behind) = get_ahead_behind_with_git(&repo_path)
+                    .await
+                    .map_err(system_git_failed)?;
 
                 Ok(crate::git::types::SyncResult {
                     success: true,
                     message: "成功推送到远程仓库".to_string(),
-                    has_conflicts: false,
-                    conflict_files: vec![],
                     ahead: ahead.max(0) as u32,
                     behind: behind.max(0) as u32,
+                    ..Default::default()
                 })
             } else {
                 let error_msg = String::from_utf8_lossy(&output.stderr);
                 log::error!("Git push失败: {}", error_msg);
-                Err(format!("Git push失败: {}", error_msg))
+                Err(system_git_failed(format!("Git push失败: {}", error_msg)))
             }
         }
-        Ok(Err(e)) => {
-            log::error!("执行Git命令失败: {}", e);
-            Err(format!("执行Git命令失败: {}", e))
+        Ok(GitProcessOutcome::Cancelled) => {
+            log::info!("Git push操作已取消");
+            Err(crate::git::types::GitError::OperationCancelled)
         }
-        Err(_) => {
+        Ok(GitProcessOutcome::TimedOut) => {
             log::error!("Git push操作超时 (30秒)");
-            Err("Git push操作超时，可能是SSH密钥认证失败或网络问题".to_string())
+            Err(system_git_failed(
+                "Git push操作超时，可能是SSH密钥认证失败或网络问题",
+            ))
+        }
+        Err(e) => {
+            log::error!("执行Git命令失败: {}", e);
+            Err(e)
         }
     }
 }
 
 /// 使用系统Git命令执行pull操作（用于SSH协议）
+///
+/// 提供 `operation_id` 时可通过 `cancel_git_operation` 中途取消
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn pull_remote_with_system_git(
+    git_state: State<'_, GitState>,
     repo_path: String,
     strategy: String,
     ssh_key_path: Option<String>,
-) -> Result<crate::git::types::SyncResult, String> {
+    known_hosts_file: Option<String>,
+    strict_host_key_checking: Option<bool>,
+    respect_ssh_environment: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<crate::git::types::SyncResult, crate::git::types::GitError> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
     log::debug!(
         "使用系统Git执行pull: {} (strategy: {}, ssh_key: {:?})",
         repo_path,
@@ -1227,20 +3193,17 @@ pub async fn pull_remote_with_system_git(
 
     let mut cmd = crate::utils::system_command::create_hidden_command_async("git");
 
-    // 如果指定了SSH密钥，设置GIT_SSH_COMMAND环境变量
-    if let Some(ssh_key) = ssh_key_path {
-        let ssh_command = format!(
-            "ssh -i \"{}\" -o StrictHostKeyChecking=no -o ConnectTimeout=10 -o BatchMode=yes",
-            ssh_key
-        );
+    // 输出按英文解析，固定语言环境
+    cmd.envs(crate::git::operations::SYSTEM_GIT_LOCALE_ENV);
+
+    // 设置GIT_SSH_COMMAND环境变量（仅在选择了密钥时注入密钥和主机密钥校验选项）
+    if let Some(ssh_command) = build_git_ssh_command(
+        ssh_key_path.as_deref(),
+        known_hosts_file.as_deref(),
+        strict_host_key_checking,
+        respect_ssh_environment.unwrap_or(false),
+    ) {
         cmd.env("GIT_SSH_COMMAND", ssh_command);
-        log::debug!("使用SSH密钥: {}", ssh_key);
-    } else {
-        // 即使没有指定密钥，也设置超时和批处理模式
-        cmd.env(
-            "GIT_SSH_COMMAND",
-            "ssh -o ConnectTimeout=10 -o BatchMode=yes",
-        );
     }
 
     cmd.arg("pull");
@@ -1253,60 +3216,248 @@ pub async fn pull_remote_with_system_git(
             // 默认行为，不需要额外参数
         }
         _ => {
-            return Err("无效的拉取策略，支持: merge, rebase".to_string());
+            return Err(crate::git::types::GitError::Unknown {
+                message: "无效的拉取策略，支持: merge, rebase".to_string(),
+            });
         }
     }
 
     cmd.current_dir(&repo_path);
 
+    // 记录拉取前的HEAD，用于统计拉取带来的变更
+    let old_head = crate::git::operations::open_repository(&repo_path)
+        .ok()
+        .and_then(|repo| repo.head().ok().and_then(|head| head.target()));
+
     // 添加30秒超时
-    let output = tokio::time::timeout(std::time::Duration::from_secs(30), cmd.output()).await;
+    let output = git_state
+        .git_processes
+        .run(operation_id.as_deref(), cmd, Duration::from_secs(30))
+        .await;
 
     match output {
-        Ok(Ok(output)) => {
+        Ok(GitProcessOutcome::Finished(output)) => {
+            use crate::git::operations::SystemPullOutcome;
+
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let summary = crate::git::operations::parse_system_pull_output(
+                output.status.success(),
+                &stdout,
+                &stderr,
+            );
+            log::debug!("Git pull结果: {:?}", summary);
+
+            match summary.outcome {
+                SystemPullOutcome::UpToDate
+                | SystemPullOutcome::FastForward
+                | SystemPullOutcome::Merged
+                | SystemPullOutcome::Rebased => {
+                    crate::git::operations::record_last_sync(&repo_path);
+
+                    // 获取ahead/behind状态
+                    let (ahead, behind) = get_ahead_behind_with_git(&repo_path)
+                        .await
+                        .map_err(system_git_failed)?;
+
+                    let mut result = crate::git::types::SyncResult {
+                        success: true,
+                        message: summary.message(),
+                        ahead: ahead.max(0) as u32,
+                        behind: behind.max(0) as u32,
+                        files_changed: summary.files_changed,
+                        insertions: summary.insertions,
+                        deletions: summary.deletions,
+                        ..Default::default()
+                    };
+
+                    if let (Some(old_head), Ok(repo)) = (
+                        old_head,
+                        crate::git::operations::open_repository(&repo_path),
+                    ) {
+                        crate::git::operations::apply_pull_diff_stats(&repo, old_head, &mut result);
+                    }
+
+                    Ok(result)
+                }
+                SystemPullOutcome::Conflict => {
+                    // 获取冲突文件列表
+                    let conflict_files = get_conflict_files_with_git(&repo_path)
+                        .await
+                        .map_err(system_git_failed)?;
+
+                    Ok(crate::git::types::SyncResult::conflicts(
+                        summary.message(),
+                        conflict_files,
+                    ))
+                }
+                SystemPullOutcome::Diverged => {
+                    let (ahead, behind) = get_ahead_behind_with_git(&repo_path)
+                        .await
+                        .unwrap_or((0, 0));
+
+                    Ok(crate::git::types::SyncResult {
+                        success: false,
+                        message: summary.message(),
+                        ahead: ahead.max(0) as u32,
+                        behind: behind.max(0) as u32,
+                        ..Default::default()
+                    })
+                }
+                SystemPullOutcome::Failed => {
+                    log::error!("Git pull失败: {}", stderr);
+                    Err(system_git_failed(format!("Git pull失败: {}", stderr)))
+                }
+            }
+        }
+        Ok(GitProcessOutcome::Cancelled) => {
+            log::info!("Git pull操作已取消");
+            Err(crate::git::types::GitError::OperationCancelled)
+        }
+        Ok(GitProcessOutcome::TimedOut) => {
+            log::error!("Git pull操作超时 (30秒)");
+            Err(system_git_failed(
+                "Git pull操作超时，可能是SSH密钥认证失败或网络问题",
+            ))
+        }
+        Err(e) => {
+            log::error!("执行Git命令失败: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// 按照给定的步骤执行交互式变基（重排、压缩、丢弃、改写提交）
+///
+/// 通过 `GIT_SEQUENCE_EDITOR` 写入预先生成的 todo 列表；需要修改提交信息的步骤
+/// 使用 `exec git commit --amend -F <file>` 完成，避免弹出编辑器。
+/// 因冲突暂停后通过 `continue_rebase` 继续，或通过 `abort_operation` 中止
+#[command]
+pub async fn rebase_interactive(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+    onto: String,
+    todo: Vec<crate::git::types::RebaseStep>,
+) -> Result<crate::git::types::SyncResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!(
+        "执行交互式变基: {} (onto: {}, 步骤数: {})",
+        repo_path,
+        onto,
+        todo.len()
+    );
+
+    if todo.is_empty() {
+        return Err("变基步骤不能为空".to_string());
+    }
+    // 以 - 开头的值会被 git 当作选项解析
+    if onto.trim().is_empty() || onto.starts_with('-') {
+        return Err(format!("无效的变基目标: {}", onto));
+    }
+
+    // 清理上一次已结束变基遗留的临时文件
+    crate::git::operations::cleanup_rebase_work_dir(&repo_path).map_err(|e| e.to_string())?;
+    let work_dir =
+        crate::git::operations::rebase_work_dir(&repo_path).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&work_dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+
+    let todo_path = match crate::git::operations::write_rebase_todo(&work_dir, &todo) {
+        Ok(path) => path,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&work_dir);
+            log::error!("生成变基步骤失败: {}", e);
+            return Err(e.to_string());
+        }
+    };
+
+    let mut cmd = crate::utils::system_command::create_hidden_command_async("git");
+    cmd.env(
+        "GIT_SEQUENCE_EDITOR",
+        format!(
+            "cp {}",
+            crate::git::operations::quote_shell_path(&todo_path)
+        ),
+    )
+    .env("GIT_EDITOR", "true")
+    .arg("rebase")
+    .arg("-i")
+    .arg(&onto)
+    .current_dir(&repo_path);
+
+    let output = cmd.output().await;
+    rebase_command_result(&repo_path, output, "交互式变基完成").await
+}
+
+/// 解决冲突并暂存后继续交互式变基
+#[command]
+pub async fn continue_rebase(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+) -> Result<crate::git::types::SyncResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("继续变基: {}", repo_path);
+
+    let mut cmd = crate::utils::system_command::create_hidden_command_async("git");
+    cmd.env("GIT_EDITOR", "true")
+        .arg("rebase")
+        .arg("--continue")
+        .current_dir(&repo_path);
+
+    let output = cmd.output().await;
+    rebase_command_result(&repo_path, output, "变基完成").await
+}
+
+/// 根据 `git rebase` 的执行结果生成同步结果
+///
+/// 变基结束（成功或失败后不再进行）时清理临时目录；因冲突暂停时保留，继续变基时仍需读取提交信息文件
+async fn rebase_command_result(
+    repo_path: &str,
+    output: std::io::Result<std::process::Output>,
+    success_message: &str,
+) -> Result<crate::git::types::SyncResult, String> {
+    let result = match output {
+        Ok(output) => {
             if output.status.success() {
-                // 获取ahead/behind状态
-                let (ahead, behind) = get_ahead_behind_with_git(&repo_path).await?;
+                let (ahead, behind) = get_ahead_behind_with_git(repo_path).await?;
 
                 Ok(crate::git::types::SyncResult {
                     success: true,
-                    message: "成功拉取远程变更".to_string(),
-                    has_conflicts: false,
-                    conflict_files: vec![],
+                    message: success_message.to_string(),
                     ahead: ahead.max(0) as u32,
                     behind: behind.max(0) as u32,
+                    ..Default::default()
                 })
             } else {
-                let error_msg = String::from_utf8_lossy(&output.stderr);
-
-                // 检查是否有冲突
-                if error_msg.contains("CONFLICT") || error_msg.contains("conflict") {
-                    // 获取冲突文件列表
-                    let conflict_files = get_conflict_files_with_git(&repo_path).await?;
-
-                    Ok(crate::git::types::SyncResult {
-                        success: false,
-                        message: "拉取时发现冲突".to_string(),
-                        has_conflicts: true,
+                let error_msg = format!(
+                    "{}{}",
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr)
+                );
+                let conflict_files = get_conflict_files_with_git(repo_path).await?;
+
+                // 检查是否因冲突而暂停
+                if !conflict_files.is_empty() || error_msg.contains("CONFLICT") {
+                    Ok(crate::git::types::SyncResult::conflicts(
+                        "变基时发现冲突",
                         conflict_files,
-                        ahead: 0,
-                        behind: 0,
-                    })
+                    ))
                 } else {
-                    log::error!("Git pull失败: {}", error_msg);
-                    Err(format!("Git pull失败: {}", error_msg))
+                    log::error!("Git rebase失败: {}", error_msg);
+                    Err(format!("Git rebase失败: {}", error_msg))
                 }
             }
         }
-        Ok(Err(e)) => {
+        Err(e) => {
             log::error!("执行Git命令失败: {}", e);
             Err(format!("执行Git命令失败: {}", e))
         }
-        Err(_) => {
-            log::error!("Git pull操作超时 (30秒)");
-            Err("Git pull操作超时，可能是SSH密钥认证失败或网络问题".to_string())
-        }
+    };
+
+    if let Err(e) = crate::git::operations::cleanup_rebase_work_dir(repo_path) {
+        log::warn!("清理变基临时目录失败: {}", e);
     }
+
+    result
 }
 
 // ==================== 辅助函数 ====================
@@ -1403,10 +3554,12 @@ async fn get_conflict_files_with_git(repo_path: &str) -> Result<Vec<String>, Str
 #[command]
 pub async fn list_branches(
     repo_path: String,
+    name_filter: Option<String>,
+    limit: Option<usize>,
 ) -> Result<Vec<crate::git::operations::BranchInfo>, String> {
-    log::debug!("获取分支列表: {}", repo_path);
+    log::debug!("获取分支列表: {} (filter: {:?})", repo_path, name_filter);
 
-    match crate::git::operations::list_branches(&repo_path) {
+    match crate::git::operations::list_branches(&repo_path, name_filter.as_deref(), limit) {
         Ok(branches) => {
             log::debug!("成功获取 {} 个分支", branches.len());
             Ok(branches)
@@ -1418,14 +3571,78 @@ pub async fn list_branches(
     }
 }
 
+/// 列出包含指定提交的分支，include_remote 为 true 时包含远程跟踪分支
+#[command]
+pub async fn branches_containing(
+    repo_path: String,
+    commit_sha: String,
+    include_remote: Option<bool>,
+) -> Result<Vec<String>, String> {
+    log::debug!("查找包含提交 {} 的分支: {}", commit_sha, repo_path);
+
+    crate::git::operations::branches_containing(
+        &repo_path,
+        &commit_sha,
+        include_remote.unwrap_or(false),
+    )
+    .map_err(|e| {
+        log::error!("查找包含提交的分支失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 检查分支、标签、引用名或（缩写）SHA 是否存在，存在时返回完整的提交SHA
+#[command]
+pub async fn ref_exists(repo_path: String, ref_or_sha: String) -> Result<Option<String>, String> {
+    log::debug!("检查引用是否存在: {} in {}", ref_or_sha, repo_path);
+
+    crate::git::operations::ref_exists(&repo_path, &ref_or_sha).map_err(|e| {
+        log::error!("检查引用失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 获取轻量分支列表（不含提交信息和 ahead/behind，用于快速渲染）
+#[command]
+pub async fn list_branches_lite(
+    repo_path: String,
+    name_filter: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<crate::git::operations::BranchLite>, String> {
+    log::debug!(
+        "获取轻量分支列表: {} (filter: {:?})",
+        repo_path,
+        name_filter
+    );
+
+    match crate::git::operations::list_branches_lite(&repo_path, name_filter.as_deref(), limit) {
+        Ok(branches) => Ok(branches),
+        Err(e) => {
+            log::error!("获取分支列表失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// 校验分支名称（用于输入时的实时提示），无效时返回具体原因
+#[command]
+pub async fn validate_branch_name(name: String) -> Result<bool, String> {
+    match crate::git::operations::validate_branch_name(&name) {
+        Ok(()) => Ok(true),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
 /// 创建新分支
 #[command]
 pub async fn create_branch(
+    git_state: State<'_, GitState>,
     repo_path: String,
     branch_name: String,
     from_commit: Option<String>,
     checkout: Option<bool>,
 ) -> Result<crate::git::operations::SwitchResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
     log::debug!(
         "创建分支: {} (from: {:?}, checkout: {:?})",
         repo_path,
@@ -1450,15 +3667,35 @@ pub async fn create_branch(
     }
 }
 
+/// 检出进度事件名称
+const CHECKOUT_PROGRESS_EVENT: &str = "checkout-progress";
+
 /// 切换分支
+///
+/// 检出工作区时通过窗口发送 `checkout-progress` 事件报告进度
 #[command]
 pub async fn switch_branch(
+    git_state: State<'_, GitState>,
+    window: Window,
     repo_path: String,
     branch_name: String,
 ) -> Result<crate::git::operations::SwitchResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
     log::debug!("切换分支: {} -> {}", repo_path, branch_name);
 
-    match crate::git::operations::switch_branch(&repo_path, &branch_name) {
+    let mut throttle = ProgressThrottle::default();
+    match crate::git::operations::switch_branch_with_progress(
+        &repo_path,
+        &branch_name,
+        |progress| {
+            // 开始时 0 == 0，不能视为最终进度
+            let is_final =
+                progress.total_steps > 0 && progress.completed_steps == progress.total_steps;
+            if throttle.should_emit(is_final) {
+                let _ = window.emit(CHECKOUT_PROGRESS_EVENT, &progress);
+            }
+        },
+    ) {
         Ok(result) => {
             log::debug!("分支切换结果: {:?}", result);
             Ok(result)
@@ -1470,13 +3707,37 @@ pub async fn switch_branch(
     }
 }
 
+/// 检出标签或任意提交（分离HEAD）
+#[command]
+pub async fn checkout_commit(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+    commit_ref: String,
+) -> Result<crate::git::operations::SwitchResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("检出提交: {} -> {}", repo_path, commit_ref);
+
+    match crate::git::operations::checkout_commit(&repo_path, &commit_ref) {
+        Ok(result) => {
+            log::debug!("提交检出结果: {:?}", result);
+            Ok(result)
+        }
+        Err(e) => {
+            log::error!("检出提交失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
 /// 删除分支
 #[command]
 pub async fn delete_branch(
+    git_state: State<'_, GitState>,
     repo_path: String,
     branch_name: String,
     force: Option<bool>,
 ) -> Result<crate::git::operations::SwitchResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
     log::debug!(
         "删除分支: {} (branch: {}, force: {:?})",
         repo_path,
@@ -1496,13 +3757,39 @@ pub async fn delete_branch(
     }
 }
 
+/// 设置或取消分支的上游分支
+#[command]
+pub async fn set_branch_upstream(
+    git_state: State<'_, GitState>,
+    repo_path: String,
+    local_branch: String,
+    remote_branch: Option<String>,
+) -> Result<(), String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!("设置上游分支: {} -> {:?}", local_branch, remote_branch);
+
+    match crate::git::operations::set_branch_upstream(
+        &repo_path,
+        &local_branch,
+        remote_branch.as_deref(),
+    ) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            log::error!("设置上游分支失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
 /// 检出远程分支
 #[command]
 pub async fn checkout_remote_branch(
+    git_state: State<'_, GitState>,
     repo_path: String,
     remote_branch_name: String,
     local_branch_name: Option<String>,
 ) -> Result<crate::git::operations::SwitchResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
     log::debug!(
         "检出远程分支: {} -> {:?}",
         remote_branch_name,
@@ -1525,12 +3812,109 @@ pub async fn checkout_remote_branch(
     }
 }
 
+/// 获取并检出 Pull Request（支持Token认证）
+#[command]
+pub async fn checkout_pull_request(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    remote_name: Option<String>,
+    pr_number: u64,
+    local_branch: Option<String>,
+) -> Result<crate::git::operations::SwitchResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!(
+        "检出 PR #{}: {} (remote: {:?})",
+        pr_number,
+        repo_path,
+        remote_name
+    );
+
+    let token_cache =
+        get_token_for_repository(&app_handle, &repo_path, remote_name.as_deref()).await;
+
+    tokio::task::spawn_blocking(move || {
+        crate::git::operations::checkout_pull_request(
+            &repo_path,
+            remote_name.as_deref(),
+            pr_number,
+            local_branch.as_deref(),
+            token_cache,
+        )
+    })
+    .await
+    .map_err(|e| {
+        log::error!("任务执行失败: {}", e);
+        format!("任务执行失败: {}", e)
+    })?
+    .map_err(|e| {
+        log::error!("检出 PR 失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 删除远程分支
+///
+/// `max_auth_attempts` 为最大认证尝试次数，未指定时使用默认值
+#[command]
+pub async fn delete_remote_branch(
+    git_state: State<'_, GitState>,
+    app_handle: AppHandle,
+    repo_path: String,
+    remote_name: Option<String>,
+    branch_name: String,
+    max_auth_attempts: Option<u32>,
+) -> Result<crate::git::operations::SwitchResult, String> {
+    let _repo_lock = git_state.repo_locks.lock(&repo_path).await;
+    log::debug!(
+        "删除远程分支: {} (remote: {:?}, branch: {})",
+        repo_path,
+        remote_name,
+        branch_name
+    );
+
+    let token_cache =
+        get_token_for_repository(&app_handle, &repo_path, remote_name.as_deref()).await;
+
+    // 凭据回调可能阻塞等待用户输入，在阻塞任务中执行
+    let deleted_branch = branch_name.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        crate::git::operations::delete_remote_branch(
+            &repo_path,
+            remote_name.as_deref(),
+            &deleted_branch,
+            token_cache,
+            crate::git::operations::AuthOptions {
+                interactive: true,
+                max_attempts: max_auth_attempts,
+                ..Default::default()
+            },
+        )
+    })
+    .await
+    .map_err(|e| {
+        log::error!("任务执行失败: {}", e);
+        format!("任务执行失败: {}", e)
+    })?;
+
+    match result {
+        Ok(result) => {
+            log::debug!("远程分支删除成功: {}", branch_name);
+            Ok(result)
+        }
+        Err(e) => {
+            log::error!("删除远程分支失败: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
 /// 检测并验证仓库的远程配置
 #[command]
 pub async fn detect_repository_remotes(repo_path: String) -> Result<Vec<String>, String> {
     log::debug!("检测仓库远程配置: {}", repo_path);
 
-    match git2::Repository::open(&repo_path) {
+    match crate::git::operations::open_repository(&repo_path) {
         Ok(repo) => match repo.remotes() {
             Ok(remotes) => {
                 let remote_list: Vec<String> =
@@ -1572,6 +3956,31 @@ pub async fn get_default_remote_name_command(repo_path: String) -> Result<String
     }
 }
 
+/// 获取用户为仓库指定的首选远程
+#[command]
+pub async fn get_preferred_remote(repo_path: String) -> Result<Option<String>, String> {
+    log::debug!("获取首选远程: {}", repo_path);
+
+    crate::git::operations::get_preferred_remote(&repo_path).map_err(|e| {
+        log::error!("获取首选远程失败: {}", e);
+        e.to_string()
+    })
+}
+
+/// 设置仓库的首选远程，remote_name 为空时清除设置
+#[command]
+pub async fn set_preferred_remote(
+    repo_path: String,
+    remote_name: Option<String>,
+) -> Result<(), String> {
+    log::debug!("设置首选远程: {:?} in {}", remote_name, repo_path);
+
+    crate::git::operations::set_preferred_remote(&repo_path, remote_name.as_deref()).map_err(|e| {
+        log::error!("设置首选远程失败: {}", e);
+        e.to_string()
+    })
+}
+
 // ==================== 远程名称检测辅助函数 ====================
 
 /// 检测仓库的默认远程名称
@@ -1579,7 +3988,7 @@ async fn detect_default_remote_name(repo_path: &str) -> Result<String, String> {
     log::debug!("检测仓库默认远程名称: {}", repo_path);
 
     // 使用git2库检测远程名称
-    match git2::Repository::open(repo_path) {
+    match crate::git::operations::open_repository(repo_path) {
         Ok(repo) => match crate::git::operations::get_default_remote_name(&repo) {
             Ok(remote_name) => {
                 log::debug!("检测到默认远程名称: {}", remote_name);
@@ -1599,84 +4008,62 @@ async fn detect_default_remote_name(repo_path: &str) -> Result<String, String> {
 
 // ==================== Token认证辅助函数 ====================
 
-/// 获取仓库的远程URL（内部辅助函数）
-async fn get_repository_remote_url(repo_path: String) -> Result<Option<String>, String> {
-    match crate::git::operations::get_remote_info(&repo_path) {
-        Ok(remote_info) => {
-            // 这里需要实际获取远程URL，暂时返回None
-            // 在实际实现中，应该从git2库获取远程URL
-            log::debug!("获取远程信息成功: {:?}", remote_info);
-
-            // 尝试使用git2获取远程URL
-            match git2::Repository::open(&repo_path) {
-                Ok(repo) => {
-                    if let Ok(remote) = repo.find_remote(&remote_info.remote_name) {
-                        if let Some(url) = remote.url() {
-                            return Ok(Some(url.to_string()));
-                        }
-                    }
-                    Ok(None)
-                }
-                Err(_) => Ok(None),
-            }
-        }
-        Err(e) => {
-            log::error!("获取远程信息失败: {}", e);
-            Err(e.to_string())
-        }
-    }
-}
-
 /// 为指定仓库获取Token
+///
+/// 按操作的目标远程（未指定时为默认远程）的URL域名选择Token，
+/// 使 origin 与 upstream 位于不同平台时各自使用对应的Token；
+/// 获取失败时记录警告并按未配置Token处理（使用默认认证）
 async fn get_token_for_repository(
     app_handle: &AppHandle,
     repo_path: &str,
-) -> Result<Option<String>, String> {
+    remote_name: Option<&str>,
+) -> Option<String> {
     // 获取远程URL
-    let remote_url = match get_repository_remote_url(repo_path.to_string()).await? {
-        Some(url) => url,
-        None => {
+    let remote_url = match crate::git::operations::get_remote_url(repo_path, remote_name) {
+        Ok(Some(url)) => url,
+        Ok(None) => {
             log::debug!("未找到远程URL");
-            return Ok(None);
+            return None;
+        }
+        Err(e) => {
+            log::warn!("获取远程URL失败，使用默认认证: {}", e);
+            return None;
         }
     };
 
     log::debug!("仓库远程URL: {}", remote_url);
 
-    // 检查是否是HTTPS协议
-    if !remote_url.starts_with("https://") && !remote_url.starts_with("http://") {
-        log::debug!("非HTTPS协议，不需要Token认证");
-        return Ok(None);
-    }
-
-    // 从URL提取域名
-    let domain = match extract_domain_from_url(remote_url).await {
-        Ok(domain) => domain,
-        Err(e) => {
-            log::warn!("提取域名失败: {}", e);
-            return Ok(None);
-        }
-    };
+    get_token_for_url(app_handle, &remote_url)
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("获取Token失败，使用默认认证: {}", e);
+            None
+        })
+}
 
-    log::debug!("提取到域名: {}", domain);
+/// 根据远程URL的域名获取已存储的Token（仅HTTP/HTTPS协议）
+async fn get_token_for_url(
+    app_handle: &AppHandle,
+    remote_url: &str,
+) -> Result<Option<String>, String> {
+    let tokens: std::collections::HashMap<String, TokenConfig> = get_all_tokens(app_handle.clone())
+        .await?
+        .into_iter()
+        .map(|token_config| (token_config.domain.clone(), token_config))
+        .collect();
 
-    // 获取对应的Token
-    match get_access_token(app_handle.clone(), domain.clone()).await {
-        Ok(Some(token_config)) => {
-            log::debug!("找到Token配置: {}", domain);
+    match crate::git::operations::select_token(remote_url, &tokens) {
+        Some(token_config) => {
+            log::debug!("找到Token配置: {}", token_config.domain);
 
             // 更新最后使用时间
-            let _ = update_token_last_used(app_handle.clone(), domain).await;
+            let _ = update_token_last_used(app_handle.clone(), token_config.domain.clone()).await;
 
-            Ok(Some(token_config.token))
+            Ok(Some(token_config.token.clone()))
         }
-        Ok(None) => {
-            log::debug!("未找到Token配置: {}", domain);
+        None => {
+            log::debug!("未找到适用于 {} 的Token配置", remote_url);
             Ok(None)
         }
-        Err(e) => {
-            log::error!("获取Token失败: {}", e);
-            Err(e)
-        }
     }
 }
@@ -0,0 +1,205 @@
+use crate::http_client::http_client;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+// 代码托管平台类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ForgeKind {
+    GitHub,
+    GitLab,
+}
+
+// Pull Request / Merge Request 创建结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PullRequest {
+    pub number: u64,
+    pub url: String,
+    pub state: String,
+}
+
+// 根据域名识别代码托管平台
+fn detect_forge(host: &str) -> Option<ForgeKind> {
+    let host = host.to_lowercase();
+    if host.contains("github") {
+        Some(ForgeKind::GitHub)
+    } else if host.contains("gitlab") {
+        Some(ForgeKind::GitLab)
+    } else {
+        None
+    }
+}
+
+// 获取平台 API 根地址（github.com 使用 api.github.com，自建实例使用 /api 路径）
+fn api_base(kind: ForgeKind, host: &str) -> String {
+    match kind {
+        ForgeKind::GitHub if host.eq_ignore_ascii_case("github.com") => {
+            "https://api.github.com".to_string()
+        }
+        ForgeKind::GitHub => format!("https://{}/api/v3", host),
+        ForgeKind::GitLab => format!("https://{}/api/v4", host),
+    }
+}
+
+// 创建 Pull Request（GitHub）或 Merge Request（GitLab）
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_pull_request(
+    host: String,
+    owner: String,
+    repo: String,
+    token: String,
+    base: String,
+    head: String,
+    title: String,
+    body: Option<String>,
+) -> Result<PullRequest, String> {
+    let kind = detect_forge(&host).ok_or_else(|| format!("无法识别的代码托管平台: {}", host))?;
+    let api_base = api_base(kind, &host);
+
+    let request = match kind {
+        ForgeKind::GitHub => http_client()
+            .post(format!("{}/repos/{}/{}/pulls", api_base, owner, repo))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Accept", "application/vnd.github+json")
+            .json(&serde_json::json!({
+                "title": title,
+                "head": head,
+                "base": base,
+                "body": body.unwrap_or_default(),
+            })),
+        ForgeKind::GitLab => {
+            // GitLab 项目路径需要整体 URL 编码（支持嵌套命名空间）
+            let project = format!("{}/{}", owner, repo).replace('/', "%2F");
+            http_client()
+                .post(format!("{}/projects/{}/merge_requests", api_base, project))
+                .header("PRIVATE-TOKEN", token)
+                .json(&serde_json::json!({
+                    "title": title,
+                    "source_branch": head,
+                    "target_branch": base,
+                    "description": body.unwrap_or_default(),
+                }))
+        }
+    };
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+
+    // 失败响应可能不是 JSON（如反向代理返回的 HTML 错误页），先检查状态码再解析
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "创建合并请求失败 ({}): {}",
+            status,
+            error_message(&body)
+        ));
+    }
+
+    let data: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    Ok(parse_pull_request(kind, &data))
+}
+
+// 从失败响应中提取错误信息：优先使用 JSON 中的 message 字段，否则使用原始响应内容
+fn error_message(body: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(data) => data
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| data.to_string()),
+        Err(_) => body.trim().to_string(),
+    }
+}
+
+// 解析创建结果：GitHub 返回 number/html_url，GitLab 返回 iid/web_url
+fn parse_pull_request(kind: ForgeKind, data: &serde_json::Value) -> PullRequest {
+    let (number_key, url_key) = match kind {
+        ForgeKind::GitHub => ("number", "html_url"),
+        ForgeKind::GitLab => ("iid", "web_url"),
+    };
+
+    PullRequest {
+        number: data.get(number_key).and_then(|v| v.as_u64()).unwrap_or(0),
+        url: data
+            .get(url_key)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        state: data
+            .get("state")
+            .and_then(|v| v.as_str())
+            .unwrap_or("open")
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_forge_by_host() {
+        assert_eq!(detect_forge("github.com"), Some(ForgeKind::GitHub));
+        assert_eq!(detect_forge("GitHub.example.com"), Some(ForgeKind::GitHub));
+        assert_eq!(detect_forge("gitlab.com"), Some(ForgeKind::GitLab));
+        assert_eq!(
+            detect_forge("gitlab.internal:8443"),
+            Some(ForgeKind::GitLab)
+        );
+        assert_eq!(detect_forge("bitbucket.org"), None);
+    }
+
+    #[test]
+    fn test_api_base_for_hosted_and_self_hosted() {
+        assert_eq!(
+            api_base(ForgeKind::GitHub, "GitHub.com"),
+            "https://api.github.com"
+        );
+        assert_eq!(
+            api_base(ForgeKind::GitHub, "github.example.com"),
+            "https://github.example.com/api/v3"
+        );
+        assert_eq!(
+            api_base(ForgeKind::GitLab, "gitlab.com"),
+            "https://gitlab.com/api/v4"
+        );
+    }
+
+    #[test]
+    fn test_error_message_from_json_or_plain_body() {
+        assert_eq!(
+            error_message(r#"{"message":"Validation Failed"}"#),
+            "Validation Failed"
+        );
+        assert_eq!(
+            error_message(r#"{"error":"forbidden"}"#),
+            r#"{"error":"forbidden"}"#
+        );
+        assert_eq!(
+            error_message("<html>502 Bad Gateway</html>\n"),
+            "<html>502 Bad Gateway</html>"
+        );
+    }
+
+    #[test]
+    fn test_parse_pull_request_for_each_forge() {
+        let github = serde_json::json!({
+            "number": 7,
+            "html_url": "https://github.com/o/r/pull/7",
+            "state": "open",
+        });
+        let pr = parse_pull_request(ForgeKind::GitHub, &github);
+        assert_eq!(pr.number, 7);
+        assert_eq!(pr.url, "https://github.com/o/r/pull/7");
+
+        let gitlab = serde_json::json!({
+            "iid": 3,
+            "web_url": "https://gitlab.com/o/r/-/merge_requests/3",
+            "state": "opened",
+        });
+        let mr = parse_pull_request(ForgeKind::GitLab, &gitlab);
+        assert_eq!(mr.number, 3);
+        assert_eq!(mr.url, "https://gitlab.com/o/r/-/merge_requests/3");
+        assert_eq!(mr.state, "opened");
+    }
+}
@@ -1,8 +1,10 @@
 use crate::git::auth::AuthManager;
+use crate::git::operations::classify_remote_error;
 use crate::git::types::{
     CloneOptions, CloneProgress, CloneResult, CloneStage, CloneStats, GitError, NetworkProgress,
     RepositoryInfo,
 };
+use crate::utils::progress::ProgressThrottle;
 use crate::utils::system_command::create_hidden_command;
 use git2::{FetchOptions, Progress, RemoteCallbacks, Repository};
 use std::path::Path;
@@ -11,6 +13,98 @@ use std::time::Instant;
 use tauri::{Emitter, Window};
 use uuid::Uuid;
 
+/// Windows 传统路径长度上限（MAX_PATH）
+const WINDOWS_MAX_PATH: usize = 260;
+
+/// 检查克隆目标路径并创建其父目录
+///
+/// 目标目录必须不存在或为空；父目录链上出现文件、没有写权限或路径过长时返回对应的具体错误
+pub fn prepare_clone_target(target_path: &Path) -> Result<(), GitError> {
+    let display = |path: &Path| path.to_string_lossy().to_string();
+
+    if cfg!(windows) && target_path.as_os_str().len() >= WINDOWS_MAX_PATH {
+        return Err(GitError::PathTooLong {
+            path: display(target_path),
+        });
+    }
+
+    if target_path.exists() {
+        if !target_path.is_dir() {
+            return Err(GitError::NotADirectory {
+                path: display(target_path),
+            });
+        }
+        if target_path
+            .read_dir()
+            .is_ok_and(|mut entries| entries.next().is_some())
+        {
+            return Err(GitError::DirectoryExists {
+                path: display(target_path),
+            });
+        }
+    }
+
+    let parent = match target_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => return Ok(()),
+    };
+
+    // 找到最近的已存在祖先，检查它是否为可写目录
+    if let Some(existing) = parent.ancestors().find(|ancestor| ancestor.exists()) {
+        if !existing.is_dir() {
+            return Err(GitError::NotADirectory {
+                path: display(existing),
+            });
+        }
+        if existing
+            .metadata()
+            .is_ok_and(|metadata| metadata.permissions().readonly())
+        {
+            return Err(GitError::PermissionDenied {
+                path: display(existing),
+            });
+        }
+    }
+
+    std::fs::create_dir_all(parent).map_err(|e| clone_target_io_error(e, parent))
+}
+
+/// 将创建克隆目录时的 IO 错误转换为具体的错误类型
+pub fn clone_target_io_error(error: std::io::Error, path: &Path) -> GitError {
+    let path = path.to_string_lossy().to_string();
+
+    // ERROR_FILENAME_EXCED_RANGE (Windows) / ENAMETOOLONG (Linux, macOS)
+    let code = error.raw_os_error();
+    let too_long = (cfg!(windows) && code == Some(206))
+        || (cfg!(target_os = "linux") && code == Some(36))
+        || (cfg!(target_os = "macos") && code == Some(63));
+
+    match error.kind() {
+        _ if too_long => GitError::PathTooLong { path },
+        std::io::ErrorKind::PermissionDenied => GitError::PermissionDenied { path },
+        _ => GitError::Io(error),
+    }
+}
+
+/// 获取路径所在磁盘的可用空间，路径不存在时使用最近的已存在祖先目录
+pub fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    fs2::available_space(existing).ok()
+}
+
+/// 检查磁盘剩余空间是否足以容纳预计的克隆大小，返回可用空间
+///
+/// 无法获取可用空间时不阻止克隆，返回 None
+pub fn check_disk_space(path: &Path, estimated_size: u64) -> Result<Option<u64>, GitError> {
+    match available_space(path) {
+        Some(available) if available < estimated_size => Err(GitError::InsufficientDiskSpace {
+            required: estimated_size,
+            available,
+        }),
+        available => Ok(available),
+    }
+}
+
 /// Git 克隆管理器
 pub struct CloneManager {
     window: Window,
@@ -45,21 +139,12 @@ impl CloneManager {
         // 发送初始化进度
         self.emit_progress(&clone_id, CloneStage::Initializing, 0, "准备克隆仓库...");
 
-        // 检查目标目录
+        // 检查并准备目标目录
         let target_path = Path::new(&options.directory);
-        if target_path.exists()
-            && target_path
-                .read_dir()
-                .map_or(false, |mut i| i.next().is_some())
-        {
-            return Err(GitError::DirectoryExists {
-                path: options.directory.clone(),
-            });
-        }
-
-        // 创建目标目录
-        if let Some(parent) = target_path.parent() {
-            std::fs::create_dir_all(parent).map_err(GitError::Io)?;
+        if let Err(error) = prepare_clone_target(target_path) {
+            log::error!("克隆目标目录不可用: {}", error);
+            self.emit_error(&clone_id, &error, &error.to_string());
+            return Err(error);
         }
 
         // 设置进度回调
@@ -114,6 +199,9 @@ impl CloneManager {
         callbacks.transfer_progress(move |progress| {
             let mut data = progress_clone.lock().unwrap();
             data.update_network_progress(progress);
+            if !data.should_emit() {
+                return true;
+            }
 
             let clone_progress = CloneProgress {
                 id: clone_id_clone.clone(),
@@ -122,6 +210,7 @@ impl CloneManager {
                 message: data.get_message(),
                 network_progress: Some(data.network_progress.clone()),
                 checkout_progress: None,
+                error_type: None,
             };
 
             // 发送进度更新
@@ -165,20 +254,28 @@ impl CloneManager {
 
                 // 检查是否是 SSH 相关错误
                 let error_str = e.to_string();
-                if error_str.contains("hostkey") || error_str.contains("SSH") {
+                let is_ssh_error = error_str.contains("hostkey") || error_str.contains("SSH");
+
+                // SSH 主机密钥错误保留原始错误，以便回退到系统Git
+                let error = if self.is_ssh_hostkey_error(&e) {
+                    GitError::Git(e)
+                } else {
+                    classify_remote_error(e, &options.url)
+                };
+
+                if is_ssh_error {
                     let ssh_help_msg = "SSH 错误提示：请检查 SSH 密钥配置或尝试使用 HTTPS URL";
                     log::error!("{}", ssh_help_msg);
-                    self.emit_progress(
+                    self.emit_error(
                         &clone_id,
-                        CloneStage::Error,
-                        0,
+                        &error,
                         &format!("{}\n{}", error_msg, ssh_help_msg),
                     );
                 } else {
-                    self.emit_progress(&clone_id, CloneStage::Error, 0, &error_msg);
+                    self.emit_error(&clone_id, &error, &error_msg);
                 }
 
-                return Err(GitError::Git(e));
+                return Err(error);
             }
         };
 
@@ -222,8 +319,8 @@ impl CloneManager {
         // 简单的 URL 格式验证
         if !options.url.starts_with("http://")
             && !options.url.starts_with("https://")
-            && !options.url.starts_with("git@")
             && !options.url.starts_with("ssh://")
+            && crate::git::operations::split_scp_url(&options.url).is_none()
         {
             return Err(GitError::InvalidUrl {
                 url: options.url.clone(),
@@ -347,6 +444,22 @@ impl CloneManager {
             message: message.to_string(),
             network_progress: None,
             checkout_progress: None,
+            error_type: None,
+        };
+
+        let _ = self.window.emit("clone-progress", &clone_progress);
+    }
+
+    /// 发送带错误类型的失败事件，便于前端给出针对性的提示
+    fn emit_error(&self, id: &str, error: &GitError, message: &str) {
+        let clone_progress = CloneProgress {
+            id: id.to_string(),
+            stage: CloneStage::Error,
+            progress: 0,
+            message: message.to_string(),
+            network_progress: None,
+            checkout_progress: None,
+            error_type: Some(error.error_type().to_string()),
         };
 
         let _ = self.window.emit("clone-progress", &clone_progress);
@@ -458,7 +571,10 @@ impl CloneManager {
 /// 进度数据
 struct ProgressData {
     network_progress: NetworkProgress,
-    last_update: Instant,
+    /// 增量对象是否已全部解析
+    deltas_resolved: bool,
+    /// 进度事件节流，记录上次发送的时间
+    last_update: ProgressThrottle,
 }
 
 impl ProgressData {
@@ -470,7 +586,8 @@ impl ProgressData {
                 total_objects: 0,
                 indexed_objects: 0,
             },
-            last_update: Instant::now(),
+            deltas_resolved: false,
+            last_update: ProgressThrottle::default(),
         }
     }
 
@@ -481,7 +598,17 @@ impl ProgressData {
             total_objects: progress.total_objects(),
             indexed_objects: progress.indexed_objects(),
         };
-        self.last_update = Instant::now();
+        self.deltas_resolved = progress.indexed_deltas() == progress.total_deltas();
+    }
+
+    /// 是否需要发送本次进度，下载、索引和增量解析全部完成时总是发送
+    fn should_emit(&mut self) -> bool {
+        let progress = &self.network_progress;
+        let is_final = progress.total_objects > 0
+            && progress.received_objects == progress.total_objects
+            && progress.indexed_objects == progress.total_objects
+            && self.deltas_resolved;
+        self.last_update.should_emit(is_final)
     }
 
     fn calculate_progress(&self) -> u32 {
@@ -512,3 +639,48 @@ impl ProgressData {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_prepare_clone_target_creates_parent() {
+        let dir = TempDir::new().unwrap();
+        let target = dir.path().join("a/b/repo");
+
+        prepare_clone_target(&target).unwrap();
+        assert!(dir.path().join("a/b").is_dir());
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_prepare_clone_target_rejects_file_parent() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("file.txt");
+        std::fs::write(&file, "content").unwrap();
+
+        let error = prepare_clone_target(&file.join("nested/repo")).unwrap_err();
+        assert!(
+            matches!(&error, GitError::NotADirectory { path } if path == &file.to_string_lossy()),
+            "{:?}",
+            error
+        );
+
+        let error = prepare_clone_target(&file).unwrap_err();
+        assert_eq!(error.error_type(), "not_a_directory");
+    }
+
+    #[test]
+    fn test_check_disk_space_rejects_oversized_estimate() {
+        let dir = TempDir::new().unwrap();
+        let target = dir.path().join("not/created/yet");
+
+        let available = check_disk_space(&target, 0).unwrap();
+        assert!(available.is_some());
+
+        let error = check_disk_space(&target, u64::MAX).unwrap_err();
+        assert_eq!(error.error_type(), "insufficient_disk_space");
+    }
+}
@@ -0,0 +1,183 @@
+use crate::git::types::{AuthConfig, CredentialRequest};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::Duration;
+
+/// 请求前端输入凭据的事件名称
+pub const CREDENTIAL_REQUEST_EVENT: &str = "credential-request";
+
+/// 等待用户输入凭据的默认超时时间
+pub const CREDENTIAL_PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+lazy_static::lazy_static! {
+    // 应用启动时安装的全局凭据提示器
+    static ref PROMPTER: RwLock<Option<Arc<CredentialPrompter>>> = RwLock::new(None);
+}
+
+/// 发送凭据请求事件的函数，返回是否发送成功
+type RequestEmitter = Box<dyn Fn(&CredentialRequest) -> bool + Send + Sync>;
+
+/// 交互式凭据提示器
+///
+/// Git 远程操作缺少可用凭据时，通过事件通知前端弹出输入框，
+/// 并阻塞等待 `provide` 送回凭据或超时
+pub struct CredentialPrompter {
+    emit: RequestEmitter,
+    timeout: Duration,
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<String, mpsc::Sender<Option<AuthConfig>>>>,
+}
+
+impl CredentialPrompter {
+    pub fn new<F>(timeout: Duration, emit: F) -> Self
+    where
+        F: Fn(&CredentialRequest) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            emit: Box::new(emit),
+            timeout,
+            next_id: AtomicU64::new(1),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 请求用户输入凭据，阻塞直到收到回复、用户取消或超时
+    pub fn request(
+        &self,
+        url: &str,
+        username: Option<&str>,
+        allowed_types: git2::CredentialType,
+    ) -> Option<AuthConfig> {
+        let request_id = format!(
+            "credential-{}",
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        );
+        let (sender, receiver) = mpsc::channel();
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(request_id.clone(), sender);
+
+        let request = CredentialRequest {
+            request_id: request_id.clone(),
+            url: url.to_string(),
+            username: username.map(|name| name.to_string()),
+            allowed_types: describe_credential_types(allowed_types),
+        };
+
+        let result = if (self.emit)(&request) {
+            log::debug!("等待用户输入凭据: {} ({})", url, request_id);
+            match receiver.recv_timeout(self.timeout) {
+                Ok(auth) => auth,
+                Err(_) => {
+                    log::warn!("等待用户输入凭据超时: {}", url);
+                    None
+                }
+            }
+        } else {
+            log::error!("发送凭据请求事件失败: {}", url);
+            None
+        };
+
+        self.pending.lock().unwrap().remove(&request_id);
+        result
+    }
+
+    /// 回复凭据请求，auth 为 None 表示用户取消；请求不存在或已超时时返回 false
+    pub fn provide(&self, request_id: &str, auth: Option<AuthConfig>) -> bool {
+        match self.pending.lock().unwrap().remove(request_id) {
+            Some(sender) => sender.send(auth).is_ok(),
+            None => false,
+        }
+    }
+}
+
+/// 安装全局凭据提示器
+pub fn install(prompter: CredentialPrompter) {
+    *PROMPTER.write().unwrap() = Some(Arc::new(prompter));
+}
+
+/// 获取已安装的全局凭据提示器
+pub fn current() -> Option<Arc<CredentialPrompter>> {
+    PROMPTER.read().unwrap().clone()
+}
+
+/// 将 libgit2 的凭据类型转换为前端可识别的名称
+fn describe_credential_types(allowed_types: git2::CredentialType) -> Vec<String> {
+    [
+        (git2::CredentialType::USER_PASS_PLAINTEXT, "userpass"),
+        (git2::CredentialType::SSH_KEY, "ssh_key"),
+        (git2::CredentialType::SSH_MEMORY, "ssh_memory"),
+        (git2::CredentialType::SSH_INTERACTIVE, "ssh_interactive"),
+        (git2::CredentialType::USERNAME, "username"),
+        (git2::CredentialType::DEFAULT, "default"),
+    ]
+    .into_iter()
+    .filter(|(kind, _)| allowed_types.contains(*kind))
+    .map(|(_, name)| name.to_string())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::types::AuthType;
+
+    fn token_auth(token: &str) -> AuthConfig {
+        AuthConfig {
+            auth_type: AuthType::Token,
+            username: None,
+            password: None,
+            token: Some(token.to_string()),
+            ssh_key_path: None,
+            ssh_key_passphrase: None,
+        }
+    }
+
+    #[test]
+    fn test_prompt_receives_provided_credentials() {
+        let (request_sender, request_receiver) = mpsc::channel::<CredentialRequest>();
+        let request_sender = Mutex::new(request_sender);
+        let prompter = Arc::new(CredentialPrompter::new(
+            Duration::from_secs(5),
+            move |request| request_sender.lock().unwrap().send(request.clone()).is_ok(),
+        ));
+
+        // 模拟前端：收到事件后调用 provide_credentials
+        let frontend = {
+            let prompter = prompter.clone();
+            std::thread::spawn(move || {
+                let request = request_receiver.recv().unwrap();
+                assert_eq!(request.url, "https://example.com/owner/repo.git");
+                assert_eq!(request.allowed_types, vec!["userpass".to_string()]);
+                assert!(prompter.provide(&request.request_id, Some(token_auth("secret"))));
+            })
+        };
+
+        let auth = prompter
+            .request(
+                "https://example.com/owner/repo.git",
+                None,
+                git2::CredentialType::USER_PASS_PLAINTEXT,
+            )
+            .unwrap();
+        frontend.join().unwrap();
+
+        assert_eq!(auth.token.as_deref(), Some("secret"));
+        assert!(prompter.pending.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_prompt_times_out_without_response() {
+        let prompter = CredentialPrompter::new(Duration::from_millis(50), |_| true);
+
+        let auth = prompter.request(
+            "git@example.com:owner/repo.git",
+            Some("git"),
+            git2::CredentialType::SSH_KEY,
+        );
+        assert!(auth.is_none());
+        assert!(!prompter.provide("credential-1", Some(token_auth("late"))));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod auth;
 pub mod clone;
+pub mod credential_prompt;
 pub mod operations;
 pub mod types;
 
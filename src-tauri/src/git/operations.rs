@@ -1,15 +1,65 @@
+use crate::git::auth::AuthManager;
+use crate::git::credential_prompt;
 use crate::git::types::{
-    CommitHistoryItem, CommitOptions, FileStatus, GitError, PullStrategy, RemoteBranchInfo,
-    RepositoryStatus, SyncResult,
+    AccessResult, ArchiveResult, AuthConfig, AuthType, BlameLine, ChangeCounts, CheckoutProgress,
+    CommitAndPushResult, CommitExportRow, CommitHistoryFilter, CommitHistoryItem, CommitLintRules,
+    CommitOptions, DiffHunk, DiffLine, DiffStat, FetchReport, FileDiff, FileDiffStat, FileStatus,
+    GitError, HistoryExportFormat, HistoryExportResult, LargeFile, LintIssue, LintSeverity,
+    ParsedRepoUrl, PathKind, PathStat, PathStatus, PullStrategy, PushPreview, PushProgress,
+    RebaseAction, RebaseStep, RecentRepo, RefInfo, RemoteBranchInfo, RemoteRef, RemoteRefKind,
+    RepairReport, RepoOperationState, RepoSize, RepoSummary, RepositoryStateKind, RepositoryStatus,
+    SignatureVerification, StashEntry, SyncResult, TrackingStatus, TreeEntry, WhitespaceOptions,
 };
 use git2::{
-    FetchOptions, PushOptions, RemoteCallbacks, Repository, Signature, Status, StatusOptions,
+    FetchOptions, FetchPrune, Oid, PushOptions, RemoteCallbacks, Repository, Signature, Status,
+    StatusOptions,
 };
 use std::path::Path;
 
+/// 打开仓库
+///
+/// 路径可以是工作区内的任意子目录，会向上查找所属仓库；
+/// 文件路径参数始终相对于仓库根目录（工作区根目录）解释
+pub fn open_repository(repo_path: &str) -> Result<Repository, git2::Error> {
+    Repository::discover(repo_path)
+}
+
+/// 获取路径所属仓库的根目录（工作区根目录，裸仓库为仓库目录），不在仓库内时返回 None
+pub fn get_repository_root(path: &str) -> Option<String> {
+    let repo = open_repository(path).ok()?;
+    let root = repo.workdir().unwrap_or_else(|| repo.path());
+    let root = root.to_string_lossy();
+    let trimmed = root.trim_end_matches(['/', '\\']);
+    Some(if trimmed.is_empty() {
+        root.to_string()
+    } else {
+        trimmed.to_string()
+    })
+}
+
+/// 读取仓库信息生成最近仓库记录，最后打开时间为当前时间
+pub fn recent_repository_info(repo_path: &str) -> Result<RecentRepo, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let path = get_repository_root(repo_path).unwrap_or_else(|| repo_path.to_string());
+    let name = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().trim_end_matches(".git").to_string())
+        .unwrap_or_else(|| path.clone());
+    let remote_url = get_default_remote_name(&repo)
+        .ok()
+        .and_then(|remote| repo.find_remote(&remote).ok()?.url().map(String::from));
+
+    Ok(RecentRepo {
+        path,
+        name,
+        last_opened: chrono::Utc::now().timestamp(),
+        remote_url,
+    })
+}
+
 /// 获取仓库状态
 pub fn get_repository_status(repo_path: &str) -> Result<RepositoryStatus, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
 
     // 获取状态选项
     let mut status_options = StatusOptions::new();
@@ -22,11 +72,26 @@ pub fn get_repository_status(repo_path: &str) -> Result<RepositoryStatus, GitErr
         .map_err(GitError::Git)?;
 
     let mut files = Vec::new();
+    let mut has_conflicts = false;
 
     for entry in statuses.iter() {
         let path = entry.path().unwrap_or("").to_string();
         let git_status = entry.status();
 
+        // 冲突文件单独标记，不再区分暂存与未暂存
+        if git_status.contains(Status::CONFLICTED) {
+            has_conflicts = true;
+            files.push(FileStatus {
+                path,
+                status: "conflicted".to_string(),
+                staged: false,
+                additions: 0,
+                deletions: 0,
+                ..Default::default()
+            });
+            continue;
+        }
+
         // 检查是否有暂存的变更
         let has_staged_changes = is_staged(git_status);
 
@@ -44,6 +109,7 @@ pub fn get_repository_status(repo_path: &str) -> Result<RepositoryStatus, GitErr
                 staged: true,
                 additions,
                 deletions,
+                ..Default::default()
             });
         }
 
@@ -58,6 +124,7 @@ pub fn get_repository_status(repo_path: &str) -> Result<RepositoryStatus, GitErr
                 staged: false,
                 additions,
                 deletions,
+                ..Default::default()
             });
         }
     }
@@ -70,23 +137,461 @@ pub fn get_repository_status(repo_path: &str) -> Result<RepositoryStatus, GitErr
 
     let is_clean = files.is_empty();
 
+    // 索引中的冲突条目（状态列表之外的兜底检查）
+    if !has_conflicts {
+        has_conflicts = repo
+            .index()
+            .map(|index| index.has_conflicts())
+            .unwrap_or(false);
+    }
+
     Ok(RepositoryStatus {
         current_branch,
         files,
         ahead,
         behind,
         is_clean,
+        has_conflicts,
+        operation_state: repository_operation_state(&repo),
+    })
+}
+
+/// 获取仓库当前正在进行中的操作
+fn repository_operation_state(repo: &Repository) -> Option<String> {
+    RepositoryStateKind::from(repo.state())
+        .operation_name()
+        .map(str::to_string)
+}
+
+/// 获取仓库概要状态
+///
+/// 只统计变更文件数量，不计算逐文件的增删行数；失败信息记录在 `error` 字段中
+pub fn get_repository_summary(repo_path: &str) -> RepoSummary {
+    let summarize = || -> Result<RepoSummary, GitError> {
+        let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+        let mut status_options = StatusOptions::new();
+        status_options.include_untracked(true);
+        status_options.include_ignored(false);
+        let changed_count = repo
+            .statuses(Some(&mut status_options))
+            .map_err(GitError::Git)?
+            .len();
+
+        let current_branch = get_current_branch(&repo)?;
+        let (ahead, behind) = get_ahead_behind_count(&repo)?;
+
+        Ok(RepoSummary {
+            path: repo_path.to_string(),
+            current_branch,
+            is_clean: changed_count == 0,
+            ahead,
+            behind,
+            changed_count,
+            error: None,
+        })
+    };
+
+    summarize().unwrap_or_else(|e| RepoSummary {
+        path: repo_path.to_string(),
+        current_branch: String::new(),
+        is_clean: false,
+        ahead: 0,
+        behind: 0,
+        changed_count: 0,
+        error: Some(e.to_string()),
+    })
+}
+
+/// 统计已暂存、未暂存、未跟踪和冲突的文件数量
+///
+/// 只遍历一次状态列表，不计算差异，适合频繁轮询；同时有已暂存和未暂存变更的文件两边都会计数
+pub fn get_change_counts(repo_path: &str) -> Result<ChangeCounts, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    let mut status_options = StatusOptions::new();
+    status_options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .exclude_submodules(true);
+    let statuses = repo
+        .statuses(Some(&mut status_options))
+        .map_err(GitError::Git)?;
+
+    let staged_flags = Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE;
+    let unstaged_flags =
+        Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE;
+
+    let mut counts = ChangeCounts::default();
+    for entry in statuses.iter() {
+        let status = entry.status();
+        if status.contains(Status::CONFLICTED) {
+            counts.conflicted += 1;
+            continue;
+        }
+        if status.intersects(staged_flags) {
+            counts.staged += 1;
+        }
+        if status.intersects(unstaged_flags) {
+            counts.unstaged += 1;
+        }
+        if status.contains(Status::WT_NEW) {
+            counts.untracked += 1;
+        }
+    }
+
+    Ok(counts)
+}
+
+/// 获取仓库正在进行中的操作（合并、变基、拣选等）及相关元数据
+pub fn get_repository_state(repo_path: &str) -> Result<RepoOperationState, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    let state = RepositoryStateKind::from(repo.state());
+
+    // 读取 .git 目录下的状态文件
+    let read_state_file = |relative: &str| {
+        std::fs::read_to_string(repo.path().join(relative))
+            .ok()
+            .map(|content| content.trim().to_string())
+            .filter(|content| !content.is_empty())
+    };
+
+    let (head_name, onto) = match state {
+        RepositoryStateKind::RebaseInteractive | RepositoryStateKind::RebaseMerge => (
+            read_state_file("rebase-merge/head-name"),
+            read_state_file("rebase-merge/onto"),
+        ),
+        RepositoryStateKind::Rebase | RepositoryStateKind::ApplyMailboxOrRebase => (
+            read_state_file("rebase-apply/head-name"),
+            read_state_file("rebase-apply/onto"),
+        ),
+        _ => (None, None),
+    };
+
+    let operation_head = match state {
+        RepositoryStateKind::Merge => read_state_file("MERGE_HEAD"),
+        RepositoryStateKind::CherryPick | RepositoryStateKind::CherryPickSequence => {
+            read_state_file("CHERRY_PICK_HEAD")
+        }
+        RepositoryStateKind::Revert | RepositoryStateKind::RevertSequence => {
+            read_state_file("REVERT_HEAD")
+        }
+        _ => None,
+    };
+
+    Ok(RepoOperationState {
+        state,
+        head_name,
+        onto,
+        operation_head,
+    })
+}
+
+/// 解析引用，支持 HEAD、ORIG_HEAD、MERGE_HEAD、FETCH_HEAD 等特殊引用、引用全名以及分支/标签短名
+///
+/// 引用不存在时（如未在合并中时的 MERGE_HEAD）返回 None
+pub fn resolve_ref(repo_path: &str, name: &str) -> Result<Option<RefInfo>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    // FETCH_HEAD 每行附带分支说明，不是合法的引用文件，取第一行的提交
+    if name == "FETCH_HEAD" {
+        let target_sha = std::fs::read_to_string(repo.path().join("FETCH_HEAD"))
+            .ok()
+            .and_then(|content| {
+                content
+                    .lines()
+                    .next()
+                    .and_then(|line| line.split_whitespace().next())
+                    .and_then(|sha| Oid::from_str(sha).ok())
+            })
+            .map(|oid| oid.to_string());
+        return Ok(target_sha.map(|sha| RefInfo {
+            name: name.to_string(),
+            target_sha: Some(sha),
+            is_symbolic: false,
+            symbolic_target: None,
+        }));
+    }
+
+    let reference = match repo.find_reference(name) {
+        Ok(reference) => reference,
+        Err(_) => match repo.resolve_reference_from_short_name(name) {
+            Ok(reference) => reference,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(e) if e.code() == git2::ErrorCode::InvalidSpec => return Ok(None),
+            Err(e) => return Err(GitError::Git(e)),
+        },
+    };
+
+    let is_symbolic = reference.kind() == Some(git2::ReferenceType::Symbolic);
+    let symbolic_target = reference.symbolic_target().map(String::from);
+    // 注释标签解析到其指向的提交
+    let target_sha = repo
+        .reference_to_annotated_commit(&reference)
+        .map(|commit| commit.id().to_string())
+        .ok()
+        .or_else(|| {
+            reference
+                .resolve()
+                .ok()?
+                .target()
+                .map(|oid| oid.to_string())
+        });
+
+    Ok(Some(RefInfo {
+        name: reference.name().unwrap_or(name).to_string(),
+        target_sha,
+        is_symbolic,
+        symbolic_target,
+    }))
+}
+
+/// 中止仓库中正在进行的操作并回到操作前的状态
+///
+/// 合并会硬重置到 ORIG_HEAD，变基通过 `open_rebase` 中止，拣选和还原会重置到 HEAD；
+/// 返回被中止的操作类型，仓库本身干净时返回 `Clean` 且不做任何修改
+pub fn abort_operation(repo_path: &str) -> Result<RepositoryStateKind, GitError> {
+    let state = get_repository_state(repo_path)?.state;
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    // 硬重置到指定引用并清理合并等状态文件
+    let reset_to = |refname: &str| -> Result<(), GitError> {
+        let target = repo
+            .revparse_single(refname)
+            .and_then(|object| object.peel(git2::ObjectType::Commit))
+            .map_err(GitError::Git)?;
+        repo.reset(
+            &target,
+            git2::ResetType::Hard,
+            Some(git2::build::CheckoutBuilder::new().force()),
+        )
+        .map_err(|e| index_error(&repo, e))?;
+        repo.cleanup_state().map_err(GitError::Git)
+    };
+
+    match state {
+        RepositoryStateKind::Clean => {}
+        RepositoryStateKind::Merge => {
+            let orig_head = if repo.path().join("ORIG_HEAD").exists() {
+                "ORIG_HEAD"
+            } else {
+                "HEAD"
+            };
+            reset_to(orig_head)?;
+        }
+        RepositoryStateKind::Rebase
+        | RepositoryStateKind::RebaseInteractive
+        | RepositoryStateKind::RebaseMerge
+        | RepositoryStateKind::ApplyMailboxOrRebase => {
+            let mut rebase = repo.open_rebase(None).map_err(GitError::Git)?;
+            rebase.abort().map_err(GitError::Git)?;
+            cleanup_rebase_work_dir(repo_path)?;
+        }
+        RepositoryStateKind::CherryPick
+        | RepositoryStateKind::CherryPickSequence
+        | RepositoryStateKind::Revert
+        | RepositoryStateKind::RevertSequence => reset_to("HEAD")?,
+        RepositoryStateKind::Bisect | RepositoryStateKind::ApplyMailbox => {
+            return Err(GitError::Unknown {
+                message: format!("不支持中止该操作: {:?}", state),
+            });
+        }
+    }
+
+    Ok(state)
+}
+
+/// 交互式变基临时文件（todo 列表和提交信息）所在的目录名，位于仓库的 Git 目录下
+const REBASE_WORK_DIR: &str = "github-heart-rebase";
+
+/// 交互式变基临时文件所在的目录
+pub fn rebase_work_dir(repo_path: &str) -> Result<std::path::PathBuf, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    Ok(repo.path().join(REBASE_WORK_DIR))
+}
+
+/// 变基结束（完成或中止）后删除交互式变基的临时目录
+///
+/// 变基仍在进行时保留目录，后续步骤继续执行时还需读取其中的提交信息文件
+pub fn cleanup_rebase_work_dir(repo_path: &str) -> Result<(), GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let rebase_in_progress = matches!(
+        repo.state(),
+        git2::RepositoryState::Rebase
+            | git2::RepositoryState::RebaseInteractive
+            | git2::RepositoryState::RebaseMerge
+            | git2::RepositoryState::ApplyMailboxOrRebase
+    );
+    let work_dir = repo.path().join(REBASE_WORK_DIR);
+    if !rebase_in_progress && work_dir.exists() {
+        std::fs::remove_dir_all(&work_dir)?;
+    }
+    Ok(())
+}
+
+/// 将路径转换为 sh 中可用的单引号字符串：统一为正斜杠，路径中的单引号转义为 `'\''`
+pub fn quote_shell_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    format!("'{}'", path.replace('\'', "'\\''"))
+}
+
+/// 在 `work_dir` 中生成交互式变基的 todo 列表和提交信息文件，返回 todo 文件路径
+pub fn write_rebase_todo(
+    work_dir: &Path,
+    todo: &[RebaseStep],
+) -> Result<std::path::PathBuf, GitError> {
+    let mut todo_list = String::new();
+    for (index, step) in todo.iter().enumerate() {
+        let sha = step.sha.trim();
+        if sha.is_empty() || !sha.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(GitError::Unknown {
+                message: format!("无效的提交 SHA: {}", step.sha),
+            });
+        }
+
+        let (command, message) = match step.action {
+            RebaseAction::Pick => ("pick", None),
+            RebaseAction::Drop => ("drop", None),
+            // 指定了新信息时先 fixup 再统一改写，否则沿用 git 拼接后的默认信息
+            RebaseAction::Squash => match &step.message {
+                Some(message) => ("fixup", Some(message)),
+                None => ("squash", None),
+            },
+            RebaseAction::Reword => match &step.message {
+                Some(message) => ("pick", Some(message)),
+                None => {
+                    return Err(GitError::Unknown {
+                        message: format!("改写提交 {} 需要提供新的提交信息", sha),
+                    })
+                }
+            },
+        };
+
+        todo_list.push_str(&format!("{} {}\n", command, sha));
+
+        if let Some(message) = message {
+            let message_path = work_dir.join(format!("message-{}.txt", index));
+            std::fs::write(&message_path, message)?;
+            todo_list.push_str(&format!(
+                "exec git commit --amend --allow-empty -F {}\n",
+                quote_shell_path(&message_path)
+            ));
+        }
+    }
+
+    let todo_path = work_dir.join("git-rebase-todo");
+    std::fs::write(&todo_path, &todo_list)?;
+    Ok(todo_path)
+}
+
+/// 清理进行中操作遗留的状态文件（MERGE_HEAD、CHERRY_PICK_HEAD、变基目录等）
+///
+/// 与 `abort_operation` 不同，这里不会重置工作区或索引，也不会修改任何提交；
+/// 返回清理前的操作状态
+pub fn cleanup_repository_state(repo_path: &str) -> Result<RepositoryStateKind, GitError> {
+    let state = get_repository_state(repo_path)?.state;
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    repo.cleanup_state().map_err(GitError::Git)?;
+    log::info!("已清理仓库操作状态: {} ({:?})", repo_path, state);
+
+    Ok(state)
+}
+
+/// 解决冲突并暂存后完成合并，创建以 HEAD 和 MERGE_HEAD 为父提交的合并提交
+///
+/// `message` 为空时使用 `.git/MERGE_MSG` 中的消息；索引中仍有未解决的冲突时拒绝执行
+pub fn continue_merge(repo_path: &str, message: Option<&str>) -> Result<SyncResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    let read_state_file = |name: &str| std::fs::read_to_string(repo.path().join(name)).ok();
+
+    let merge_heads = read_state_file("MERGE_HEAD").ok_or_else(|| GitError::Unknown {
+        message: "当前没有进行中的合并".to_string(),
+    })?;
+
+    let mut index = repo.index().map_err(GitError::Git)?;
+    if index.has_conflicts() {
+        let conflict_files = conflict_paths(&index)?;
+        return Err(GitError::Unknown {
+            message: format!("仍有未解决的冲突: {}", conflict_files.join(", ")),
+        });
+    }
+
+    let head_commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(GitError::Git)?;
+    let mut parents = vec![head_commit];
+    for line in merge_heads.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let oid = Oid::from_str(line).map_err(GitError::Git)?;
+        parents.push(repo.find_commit(oid).map_err(GitError::Git)?);
+    }
+    if parents.len() < 2 {
+        return Err(GitError::Unknown {
+            message: "MERGE_HEAD 中没有有效的提交".to_string(),
+        });
+    }
+
+    let message = match message.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(message) => message.to_string(),
+        None => read_state_file("MERGE_MSG")
+            .map(|msg| {
+                // 去掉 git 写入的注释行
+                msg.lines()
+                    .filter(|line| !line.starts_with('#'))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .trim()
+                    .to_string()
+            })
+            .filter(|msg| !msg.is_empty())
+            .unwrap_or_else(|| format!("Merge commit '{}'", parents[1].id())),
+    };
+
+    let signature = resolve_signature(&repo, None)?;
+    let tree_id = index.write_tree().map_err(GitError::Git)?;
+    let tree = repo.find_tree(tree_id).map_err(GitError::Git)?;
+    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &parent_refs,
+    )
+    .map_err(GitError::Git)?;
+    repo.cleanup_state().map_err(GitError::Git)?;
+
+    let (ahead, behind) = get_ahead_behind_count(&repo)?;
+    Ok(SyncResult {
+        success: true,
+        message: "合并成功".to_string(),
+        ahead,
+        behind,
+        ..Default::default()
     })
 }
 
 /// 暂存文件
 pub fn stage_files(repo_path: &str, file_paths: &[String]) -> Result<(), GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
     let mut index = repo.index().map_err(GitError::Git)?;
+    let workdir = repo.workdir().ok_or_else(|| GitError::Unknown {
+        message: "裸仓库没有工作区".to_string(),
+    })?;
 
     for file_path in file_paths {
-        // 检查文件是否存在
-        let full_path = Path::new(repo_path).join(file_path);
+        // 检查文件是否存在（相对于仓库根目录）
+        let full_path = workdir.join(file_path);
         if full_path.exists() {
             index
                 .add_path(Path::new(file_path))
@@ -99,13 +604,13 @@ pub fn stage_files(repo_path: &str, file_paths: &[String]) -> Result<(), GitErro
         }
     }
 
-    index.write().map_err(GitError::Git)?;
+    index.write().map_err(|e| index_error(&repo, e))?;
     Ok(())
 }
 
 /// 取消暂存文件
 pub fn unstage_files(repo_path: &str, file_paths: &[String]) -> Result<(), GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
 
     // 获取HEAD提交
     let head = repo.head().map_err(GitError::Git)?;
@@ -139,1134 +644,2299 @@ pub fn unstage_files(repo_path: &str, file_paths: &[String]) -> Result<(), GitEr
         }
     }
 
-    index.write().map_err(GitError::Git)?;
+    index.write().map_err(|e| index_error(&repo, e))?;
     Ok(())
 }
 
-/// 创建提交
-pub fn create_commit(repo_path: &str, options: &CommitOptions) -> Result<String, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
-
-    // 获取签名
-    let signature = if let (Some(name), Some(email)) = (&options.author_name, &options.author_email)
-    {
-        Signature::now(name, email).map_err(GitError::Git)?
+/// 将索引被锁定的错误转换为 `GitError::IndexLocked`，其他错误保持不变
+///
+/// 所有会写入索引的操作（暂存、提交、检出、储藏、重置等）都应使用此转换
+fn index_error(repo: &Repository, error: git2::Error) -> GitError {
+    if error.code() == git2::ErrorCode::Locked {
+        GitError::IndexLocked {
+            path: repo.path().join("index.lock").to_string_lossy().to_string(),
+        }
     } else {
-        repo.signature().map_err(GitError::Git)?
-    };
+        GitError::Git(error)
+    }
+}
 
-    // 获取索引并创建树
-    let mut index = repo.index().map_err(GitError::Git)?;
-    let tree_id = index.write_tree().map_err(GitError::Git)?;
-    let tree = repo.find_tree(tree_id).map_err(GitError::Git)?;
+/// 列出索引中冲突文件的路径
+///
+/// 按 our、their、ancestor 的顺序取第一个存在的条目，一侧删除、另一侧修改的冲突也能列出
+fn conflict_paths(index: &git2::Index) -> Result<Vec<String>, GitError> {
+    Ok(index
+        .conflicts()
+        .map_err(GitError::Git)?
+        .filter_map(|conflict| {
+            conflict.ok().and_then(|c| {
+                c.our
+                    .or(c.their)
+                    .or(c.ancestor)
+                    .and_then(|entry| std::str::from_utf8(&entry.path).ok().map(|s| s.to_string()))
+            })
+        })
+        .collect())
+}
 
-    // 构建提交消息
-    let mut message = options.message.clone();
-    if let Some(description) = &options.description {
-        if !description.is_empty() {
-            message.push_str("\n\n");
-            message.push_str(description);
+/// 默认认为超过该时长（秒）的 index.lock 已过期
+const STALE_LOCK_SECS: u64 = 300;
+
+/// 修复仓库：删除过期的 index.lock 并运行 `git fsck` 检查对象完整性
+///
+/// 无法可靠地跨平台判断锁文件是否仍被进程持有，因此以修改时间超过 `stale_after_secs`
+/// （默认 5 分钟）作为过期依据，较新的锁文件只会作为问题报告而不会被删除
+pub fn repair_repository(
+    repo_path: &str,
+    stale_after_secs: Option<u64>,
+) -> Result<RepairReport, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let lock_path = repo.path().join("index.lock");
+    let stale_after = std::time::Duration::from_secs(stale_after_secs.unwrap_or(STALE_LOCK_SECS));
+
+    let mut removed_lock = false;
+    let mut issues = Vec::new();
+
+    if let Ok(metadata) = std::fs::metadata(&lock_path) {
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .unwrap_or_default();
+
+        if age >= stale_after {
+            std::fs::remove_file(&lock_path).map_err(GitError::Io)?;
+            log::info!("已删除过期的索引锁文件: {}", lock_path.display());
+            removed_lock = true;
+        } else {
+            issues.push(format!(
+                "索引锁文件 {} 创建于 {} 秒前，可能仍有 Git 进程在运行",
+                lock_path.display(),
+                age.as_secs()
+            ));
         }
     }
 
-    if options.signoff {
-        message.push_str(&format!(
-            "\n\nSigned-off-by: {} <{}>",
-            signature.name().unwrap_or(""),
-            signature.email().unwrap_or("")
-        ));
+    // 检查索引是否可以正常读取
+    if let Err(e) = repo.index().and_then(|mut index| index.read(true)) {
+        issues.push(format!("读取索引失败: {}", e.message()));
     }
 
-    // 获取父提交
-    let parents = if options.amend {
-        // 修正提交：使用当前HEAD的父提交
-        let head = repo.head().map_err(GitError::Git)?;
-        let head_commit = head.peel_to_commit().map_err(GitError::Git)?;
-        head_commit.parents().collect::<Vec<_>>()
-    } else {
-        // 正常提交：使用HEAD作为父提交
-        match repo.head() {
-            Ok(head) => {
-                let head_commit = head.peel_to_commit().map_err(GitError::Git)?;
-                vec![head_commit]
-            }
-            Err(_) => {
-                // 首次提交，没有父提交
-                vec![]
+    // 使用系统Git检查对象完整性
+    let fsck_output = match crate::utils::system_command::create_hidden_command("git")
+        .arg("fsck")
+        .arg("--no-progress")
+        .current_dir(repo_path)
+        .output()
+    {
+        Ok(output) => {
+            let text = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            if !output.status.success() {
+                issues.push("git fsck 检测到仓库对象损坏".to_string());
             }
+            issues.extend(
+                text.lines()
+                    .filter(|line| line.starts_with("error") || line.starts_with("missing"))
+                    .map(|line| line.to_string()),
+            );
+            Some(text)
+        }
+        Err(e) => {
+            log::warn!("无法执行 git fsck: {}", e);
+            None
         }
     };
 
-    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+    Ok(RepairReport {
+        removed_lock,
+        issues,
+        fsck_output,
+    })
+}
 
-    // 创建提交
-    let commit_id = repo
-        .commit(
-            Some("HEAD"),
-            &signature,
-            &signature,
-            &message,
-            &tree,
-            &parent_refs,
-        )
-        .map_err(GitError::Git)?;
+/// 计算仓库占用的磁盘空间，并统计包文件与松散对象数量
+///
+/// 遍历时不跟随符号链接（只计算链接本身），避免链接成环导致死循环
+pub fn get_repository_size(repo_path: &str) -> Result<RepoSize, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    // 链接工作树的对象存放在主仓库的公共目录中
+    let git_dir = repo
+        .commondir()
+        .canonicalize()
+        .unwrap_or_else(|_| repo.commondir().to_path_buf());
+
+    let git_dir_bytes = directory_size(&git_dir, None);
+    let work_tree_bytes = match repo.workdir() {
+        Some(workdir) => {
+            let workdir = workdir
+                .canonicalize()
+                .unwrap_or_else(|_| workdir.to_path_buf());
+            directory_size(&workdir, Some(&git_dir))
+        }
+        None => 0,
+    };
 
-    Ok(commit_id.to_string())
+    let objects_dir = git_dir.join("objects");
+    let pack_count = std::fs::read_dir(objects_dir.join("pack"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "pack"))
+                .count() as u32
+        })
+        .unwrap_or(0);
+
+    // 松散对象位于 objects/xx/ 下，xx 为对象 ID 的前两位十六进制字符
+    let loose_object_count = std::fs::read_dir(&objects_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    name.len() == 2 && name.chars().all(|c| c.is_ascii_hexdigit())
+                })
+                .map(|entry| {
+                    std::fs::read_dir(entry.path())
+                        .map(|objects| objects.flatten().count() as u32)
+                        .unwrap_or(0)
+                })
+                .sum()
+        })
+        .unwrap_or(0);
+
+    Ok(RepoSize {
+        work_tree_bytes,
+        git_dir_bytes,
+        total_bytes: work_tree_bytes + git_dir_bytes,
+        pack_count,
+        loose_object_count,
+    })
 }
 
-/// 获取提交历史
-pub fn get_commit_history(
-    repo_path: &str,
-    limit: usize,
-    skip: usize,
-) -> Result<Vec<CommitHistoryItem>, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
-
-    let mut revwalk = repo.revwalk().map_err(GitError::Git)?;
-    revwalk.push_head().map_err(GitError::Git)?;
-    revwalk
-        .set_sorting(git2::Sort::TIME)
-        .map_err(GitError::Git)?;
+/// 递归统计目录大小，不跟随符号链接，可排除指定子目录
+fn directory_size(path: &Path, exclude: Option<&Path>) -> u64 {
+    let mut total = 0;
+    let mut pending = vec![path.to_path_buf()];
 
-    let mut commits = Vec::new();
+    while let Some(dir) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("无法读取目录 {}: {}", dir.display(), e);
+                continue;
+            }
+        };
 
-    for (index, oid_result) in revwalk.enumerate() {
-        if index < skip {
-            continue;
-        }
-        if commits.len() >= limit {
-            break;
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if exclude.is_some_and(|exclude| entry_path == exclude) {
+                continue;
+            }
+            // symlink_metadata 不跟随链接，符号链接只计算自身大小
+            let metadata = match std::fs::symlink_metadata(&entry_path) {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            if metadata.is_dir() {
+                pending.push(entry_path);
+            } else {
+                total += metadata.len();
+            }
         }
-
-        let oid = oid_result.map_err(GitError::Git)?;
-        let commit = repo.find_commit(oid).map_err(GitError::Git)?;
-
-        let author = commit.author();
-        let committer = commit.committer();
-
-        commits.push(CommitHistoryItem {
-            sha: oid.to_string(),
-            message: commit.message().unwrap_or("").to_string(),
-            author_name: author.name().unwrap_or("").to_string(),
-            author_email: author.email().unwrap_or("").to_string(),
-            author_date: author.when().seconds(),
-            committer_name: committer.name().unwrap_or("").to_string(),
-            committer_email: committer.email().unwrap_or("").to_string(),
-            committer_date: committer.when().seconds(),
-            parent_count: commit.parent_count(),
-        });
     }
 
-    Ok(commits)
+    total
 }
 
-/// 获取文件差异
-pub fn get_file_diff(repo_path: &str, file_path: &str, staged: bool) -> Result<String, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+/// 大文件检测的默认阈值（50MB，与 GitHub 的警告阈值一致）
+pub const DEFAULT_LARGE_FILE_THRESHOLD: u64 = 50 * 1024 * 1024;
 
-    let mut diff_options = git2::DiffOptions::new();
-    diff_options.pathspec(file_path);
+/// 查找超过大小阈值的变更文件
+///
+/// 检查工作区中新增或修改的文件；`include_staged` 为 true 时同时检查暂存区中的 blob
+pub fn find_large_files(
+    repo_path: &str,
+    threshold_bytes: Option<u64>,
+    include_staged: bool,
+) -> Result<Vec<LargeFile>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let threshold = threshold_bytes.unwrap_or(DEFAULT_LARGE_FILE_THRESHOLD);
+    let workdir = repo.workdir().ok_or_else(|| GitError::Unknown {
+        message: "裸仓库没有工作区".to_string(),
+    })?;
 
-    let diff = if staged {
-        // 暂存区与HEAD的差异
-        let head = repo.head().map_err(GitError::Git)?;
-        let head_tree = head.peel_to_tree().map_err(GitError::Git)?;
-        let index = repo.index().map_err(GitError::Git)?;
-        repo.diff_tree_to_index(Some(&head_tree), Some(&index), Some(&mut diff_options))
-            .map_err(GitError::Git)?
-    } else {
-        // 工作区与暂存区的差异
-        repo.diff_index_to_workdir(None, Some(&mut diff_options))
-            .map_err(GitError::Git)?
-    };
+    let mut status_options = StatusOptions::new();
+    status_options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut status_options))
+        .map_err(GitError::Git)?;
 
-    // 将差异转换为字符串
-    let mut diff_text = String::new();
-    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-        match line.origin() {
-            '+' | '-' | ' ' => {
-                diff_text.push(line.origin());
-                diff_text.push_str(std::str::from_utf8(line.content()).unwrap_or(""));
+    let odb = repo.odb().map_err(GitError::Git)?;
+    let index = repo.index().map_err(GitError::Git)?;
+    let mut large_files = Vec::new();
+
+    for entry in statuses.iter() {
+        let path = match entry.path() {
+            Some(path) => path.to_string(),
+            None => continue,
+        };
+        let status = entry.status();
+
+        if status.intersects(Status::WT_NEW | Status::WT_MODIFIED) {
+            if let Ok(metadata) = std::fs::metadata(workdir.join(&path)) {
+                if metadata.is_file() && metadata.len() > threshold {
+                    large_files.push(LargeFile {
+                        path: path.clone(),
+                        size: metadata.len(),
+                        staged: false,
+                    });
+                }
             }
-            _ => {}
         }
-        true
-    })
-    .map_err(GitError::Git)?;
-
-    Ok(diff_text)
-}
-
-/// 获取远程仓库信息
-pub fn get_remote_info(repo_path: &str) -> Result<RemoteBranchInfo, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
 
-    // 获取默认远程名称
-    let remote_name = get_default_remote_name(&repo)?;
-
-    // 获取当前分支
-    let head = repo.head().map_err(GitError::Git)?;
-    let branch_name = head.shorthand().unwrap_or("HEAD").to_string();
-
-    // 获取ahead/behind状态
-    let (ahead, behind) = get_ahead_behind_count(&repo)?;
+        if include_staged && status.intersects(Status::INDEX_NEW | Status::INDEX_MODIFIED) {
+            if let Some(index_entry) = index.get_path(Path::new(&path), 0) {
+                let (size, _) = odb.read_header(index_entry.id).map_err(GitError::Git)?;
+                let size = size as u64;
+                if size > threshold {
+                    large_files.push(LargeFile {
+                        path,
+                        size,
+                        staged: true,
+                    });
+                }
+            }
+        }
+    }
 
-    Ok(RemoteBranchInfo {
-        remote_name,
-        branch_name,
-        ahead,
-        behind,
-        last_sync: None, // 这里可以从配置或其他地方获取
-    })
+    large_files.sort_by(|a, b| b.size.cmp(&a.size));
+    Ok(large_files)
 }
 
-/// 获取远程变更（fetch操作）
-pub fn fetch_remote(repo_path: &str, remote_name: Option<&str>) -> Result<SyncResult, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+/// 将忽略规则追加到仓库根目录的 .gitignore（文件不存在时创建，已存在的规则不会重复添加）
+pub fn add_to_gitignore(repo_path: &str, patterns: &[String]) -> Result<(), GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let workdir = repo.workdir().ok_or_else(|| GitError::Unknown {
+        message: "裸仓库不支持 .gitignore".to_string(),
+    })?;
+    let gitignore_path = workdir.join(".gitignore");
 
-    // 获取远程仓库名称
-    let remote_name = if let Some(name) = remote_name {
-        name.to_string()
-    } else {
-        // 如果没有指定远程名称，尝试获取默认远程
-        get_default_remote_name(&repo)?
+    let mut content = match std::fs::read_to_string(&gitignore_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(GitError::Io(e)),
     };
 
-    let mut remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
-
-    // 设置回调函数
-    let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(|_url, _username_from_url, _allowed_types| {
-        // 这里可以集成现有的认证系统
-        // 暂时使用默认凭据
-        git2::Cred::default()
-    });
-
-    // 设置fetch选项
-    let mut fetch_options = FetchOptions::new();
-    fetch_options.remote_callbacks(callbacks);
+    let mut existing: Vec<String> = content
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect();
+    let mut appended = String::new();
 
-    // 执行fetch操作
-    let refspecs = remote.fetch_refspecs().map_err(GitError::Git)?;
-    let refspecs: Vec<&str> = refspecs.iter().flatten().collect();
+    for pattern in patterns {
+        let pattern = pattern.trim();
+        if pattern.is_empty() || existing.iter().any(|line| line == pattern) {
+            continue;
+        }
+        appended.push_str(pattern);
+        appended.push('\n');
+        existing.push(pattern.to_string());
+    }
 
-    match remote.fetch(&refspecs, Some(&mut fetch_options), None) {
-        Ok(()) => {
-            // 获取更新后的ahead/behind状态
-            let (ahead, behind) = get_ahead_behind_count(&repo)?;
+    if appended.is_empty() {
+        return Ok(());
+    }
 
-            Ok(SyncResult {
-                success: true,
-                message: "成功获取远程变更".to_string(),
-                has_conflicts: false,
-                conflict_files: vec![],
-                ahead,
-                behind,
-            })
-        }
-        Err(e) => Err(GitError::Git(e)),
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
     }
+    content.push_str(&appended);
+
+    std::fs::write(&gitignore_path, content).map_err(GitError::Io)?;
+    Ok(())
 }
 
-/// 拉取远程变更（pull操作）
-pub fn pull_remote(repo_path: &str, strategy: PullStrategy) -> Result<SyncResult, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+/// 判断路径是否被忽略规则匹配
+pub fn is_path_ignored(repo_path: &str, path: &str) -> Result<bool, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    repo.is_path_ignored(Path::new(path)).map_err(GitError::Git)
+}
 
-    // 首先执行fetch
-    let fetch_result = fetch_remote(repo_path, None)?;
-    if !fetch_result.success {
-        return Ok(fetch_result);
-    }
+/// 获取单个路径的 Git 状态，比获取完整状态列表开销更小
+///
+/// 路径可以是相对仓库根目录的路径，也可以是位于工作区内的绝对路径
+pub fn get_path_status(repo_path: &str, path: &str) -> Result<PathStatus, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let relative = repo_relative_path(&repo, path)?;
+
+    let status = repo.status_file(&relative).map_err(|e| match e.code() {
+        git2::ErrorCode::NotFound => GitError::Unknown {
+            message: format!("路径 '{}' 不存在", path),
+        },
+        _ => GitError::Git(e),
+    })?;
 
-    // 获取当前分支
-    let head = repo.head().map_err(GitError::Git)?;
-    let branch_name = head.shorthand().unwrap_or("HEAD");
+    Ok(if status.is_conflicted() {
+        PathStatus::Conflicted
+    } else if status.is_ignored() {
+        PathStatus::Ignored
+    } else if status.is_wt_new() {
+        PathStatus::Untracked
+    } else if status.intersects(
+        Status::INDEX_NEW
+            | Status::INDEX_MODIFIED
+            | Status::INDEX_DELETED
+            | Status::INDEX_RENAMED
+            | Status::INDEX_TYPECHANGE,
+    ) {
+        PathStatus::Staged
+    } else if status.intersects(
+        Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE,
+    ) {
+        PathStatus::Modified
+    } else {
+        PathStatus::Tracked
+    })
+}
 
-    // 获取远程跟踪分支
-    let upstream_name = repo
-        .branch_upstream_name(&format!("refs/heads/{}", branch_name))
-        .map_err(GitError::Git)?;
-    let upstream_ref = repo
-        .find_reference(upstream_name.as_str().unwrap())
-        .map_err(GitError::Git)?;
-    let upstream_commit = upstream_ref.peel_to_commit().map_err(GitError::Git)?;
+/// 将路径转换为相对仓库工作区根目录的路径，路径不在工作区内时返回错误
+fn repo_relative_path(repo: &Repository, path: &str) -> Result<std::path::PathBuf, GitError> {
+    let workdir = repo.workdir().ok_or_else(|| GitError::Unknown {
+        message: "裸仓库没有工作区".to_string(),
+    })?;
+    let outside = || GitError::Unknown {
+        message: format!("路径 '{}' 不在仓库 {} 中", path, workdir.display()),
+    };
 
-    // 获取当前提交
-    let local_commit = head.peel_to_commit().map_err(GitError::Git)?;
+    let candidate = Path::new(path);
+    let relative = if candidate.is_absolute() {
+        match candidate.strip_prefix(workdir) {
+            Ok(relative) => relative.to_path_buf(),
+            // 处理符号链接等导致的前缀不一致
+            Err(_) => {
+                let workdir = workdir.canonicalize().map_err(|_| outside())?;
+                let absolute = candidate.canonicalize().map_err(|_| outside())?;
+                absolute
+                    .strip_prefix(&workdir)
+                    .map_err(|_| outside())?
+                    .to_path_buf()
+            }
+        }
+    } else {
+        candidate.to_path_buf()
+    };
 
-    // 检查是否需要合并
-    if local_commit.id() == upstream_commit.id() {
-        return Ok(SyncResult {
-            success: true,
-            message: "已经是最新版本".to_string(),
-            has_conflicts: false,
-            conflict_files: vec![],
-            ahead: 0,
-            behind: 0,
-        });
+    let escapes = relative
+        .components()
+        .any(|component| !matches!(component, std::path::Component::Normal(_)));
+    if relative.as_os_str().is_empty() || escapes {
+        return Err(outside());
     }
+    Ok(relative)
+}
 
-    // 执行合并或变基
-    match strategy {
-        PullStrategy::Merge => perform_merge(&repo, &local_commit, &upstream_commit),
-        PullStrategy::Rebase => perform_rebase(&repo, &local_commit, &upstream_commit),
+/// 获取操作使用的签名：优先使用调用方提供的身份（name, email），否则使用仓库配置的身份
+fn resolve_signature(
+    repo: &Repository,
+    identity: Option<(&str, &str)>,
+) -> Result<Signature<'static>, GitError> {
+    match identity {
+        Some((name, email)) => Signature::now(name, email).map_err(GitError::Git),
+        None => repo
+            .signature()
+            .map_err(|_| GitError::IdentityNotConfigured),
     }
 }
 
-/// 推送本地变更（push操作）
-pub fn push_remote(
+/// 设置提交身份（user.name / user.email）
+///
+/// `global` 为 true 时写入全局配置（~/.gitconfig），否则写入仓库本地配置
+pub fn set_user_identity(
     repo_path: &str,
-    remote_name: Option<&str>,
-    force: bool,
-) -> Result<SyncResult, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+    name: &str,
+    email: &str,
+    global: bool,
+) -> Result<(), GitError> {
+    let (name, email) = (name.trim(), email.trim());
+    if name.is_empty() || email.is_empty() {
+        return Err(GitError::Unknown {
+            message: "姓名和邮箱不能为空".to_string(),
+        });
+    }
 
-    // 获取远程仓库名称
-    let remote_name = if let Some(name) = remote_name {
-        name.to_string()
+    let mut config = if global {
+        let path = git2::Config::find_global().or_else(|_| {
+            dirs::home_dir()
+                .map(|home| home.join(".gitconfig"))
+                .ok_or_else(|| git2::Error::from_str("无法确定用户主目录"))
+        });
+        git2::Config::open(&path.map_err(GitError::Git)?).map_err(GitError::Git)?
     } else {
-        // 如果没有指定远程名称，尝试获取默认远程
-        get_default_remote_name(&repo)?
+        let repo = open_repository(repo_path).map_err(GitError::Git)?;
+        repo.config()
+            .and_then(|config| config.open_level(git2::ConfigLevel::Local))
+            .map_err(GitError::Git)?
     };
 
-    let mut remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
-
-    // 获取当前分支
-    let head = repo.head().map_err(GitError::Git)?;
-    let branch_name = head.shorthand().unwrap_or("HEAD");
+    config.set_str("user.name", name).map_err(GitError::Git)?;
+    config.set_str("user.email", email).map_err(GitError::Git)?;
 
-    // 设置回调函数
-    let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(|_url, _username_from_url, _allowed_types| {
-        // 这里可以集成现有的认证系统
-        git2::Cred::default()
-    });
+    log::info!(
+        "已设置{}提交身份: {} <{}>",
+        if global { "全局" } else { "仓库" },
+        name,
+        email
+    );
+    Ok(())
+}
 
-    // 设置push选项
-    let mut push_options = PushOptions::new();
-    push_options.remote_callbacks(callbacks);
+/// 仅修改 HEAD 提交的消息（reword），返回新提交的SHA
+///
+/// 保留原提交的树、父提交、作者和作者时间，只更新提交者；
+/// HEAD 已包含在上游分支中（已推送）时拒绝执行，除非 `force` 为 true
+pub fn reword_head(repo_path: &str, new_message: &str, force: bool) -> Result<String, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
 
-    // 构建refspec
-    let refspec = if force {
-        format!("+refs/heads/{}:refs/heads/{}", branch_name, branch_name)
-    } else {
-        format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name)
-    };
+    if new_message.trim().is_empty() {
+        return Err(GitError::Unknown {
+            message: "提交消息不能为空".to_string(),
+        });
+    }
 
-    // 执行push操作
-    match remote.push(&[&refspec], Some(&mut push_options)) {
-        Ok(()) => {
-            // 获取更新后的ahead/behind状态
-            let (ahead, behind) = get_ahead_behind_count(&repo)?;
+    let head = repo.head().map_err(GitError::Git)?;
+    let head_commit = head.peel_to_commit().map_err(GitError::Git)?;
 
-            Ok(SyncResult {
-                success: true,
-                message: "成功推送到远程仓库".to_string(),
-                has_conflicts: false,
-                conflict_files: vec![],
-                ahead,
-                behind,
-            })
+    if !force && head.is_branch() {
+        if let Some(branch_name) = head.shorthand() {
+            let upstream_oid = repo
+                .find_branch(branch_name, git2::BranchType::Local)
+                .and_then(|branch| branch.upstream())
+                .ok()
+                .and_then(|upstream| upstream.get().target());
+            if let Some(upstream_oid) = upstream_oid {
+                let published = upstream_oid == head_commit.id()
+                    || repo
+                        .graph_descendant_of(upstream_oid, head_commit.id())
+                        .map_err(GitError::Git)?;
+                if published {
+                    return Err(GitError::PublishedCommit {
+                        sha: head_commit.id().to_string(),
+                    });
+                }
+            }
         }
-        Err(e) => Err(GitError::Git(e)),
     }
-}
 
-// 辅助函数
+    let committer = resolve_signature(&repo, None)?;
+    let new_id = head_commit
+        .amend(
+            Some("HEAD"),
+            None,
+            Some(&committer),
+            None,
+            Some(new_message),
+            None,
+        )
+        .map_err(GitError::Git)?;
 
-fn is_staged(status: Status) -> bool {
-    status.contains(Status::INDEX_NEW)
-        || status.contains(Status::INDEX_MODIFIED)
-        || status.contains(Status::INDEX_DELETED)
-        || status.contains(Status::INDEX_RENAMED)
-        || status.contains(Status::INDEX_TYPECHANGE)
+    Ok(new_id.to_string())
 }
 
-fn has_unstaged_changes(status: Status) -> bool {
-    status.contains(Status::WT_NEW)
-        || status.contains(Status::WT_MODIFIED)
-        || status.contains(Status::WT_DELETED)
-        || status.contains(Status::WT_RENAMED)
-        || status.contains(Status::WT_TYPECHANGE)
+/// 读取 `init.defaultBranch` 配置（包括全局配置），未配置时返回 None
+fn configured_default_branch(config: &git2::Config) -> Option<String> {
+    config
+        .get_string("init.defaultBranch")
+        .ok()
+        .map(|branch| branch.trim().to_string())
+        .filter(|branch| !branch.is_empty())
 }
 
-fn convert_git_status_staged(status: Status) -> String {
-    if status.contains(Status::INDEX_NEW) {
-        "added".to_string()
-    } else if status.contains(Status::INDEX_MODIFIED) {
-        "modified".to_string()
-    } else if status.contains(Status::INDEX_DELETED) {
-        "deleted".to_string()
-    } else if status.contains(Status::INDEX_RENAMED) {
-        "renamed".to_string()
-    } else {
-        "modified".to_string()
+/// 尚无提交的仓库中，将 HEAD 指向 `init.defaultBranch` 配置的分支
+fn apply_configured_default_branch(repo: &Repository) -> Result<(), GitError> {
+    let config = repo.config().map_err(GitError::Git)?;
+    if let Some(branch) = configured_default_branch(&config) {
+        let refname = format!("refs/heads/{}", branch);
+        let current = repo.find_reference("HEAD").map_err(GitError::Git)?;
+        if current.symbolic_target() != Some(refname.as_str()) {
+            log::debug!("首次提交使用默认分支: {}", branch);
+            repo.set_head(&refname).map_err(GitError::Git)?;
+        }
     }
+    Ok(())
 }
 
-fn convert_git_status_unstaged(status: Status) -> String {
-    if status.contains(Status::WT_NEW) {
-        "added".to_string()
-    } else if status.contains(Status::WT_MODIFIED) {
-        "modified".to_string()
-    } else if status.contains(Status::WT_DELETED) {
-        "deleted".to_string()
-    } else if status.contains(Status::WT_RENAMED) {
-        "renamed".to_string()
-    } else {
-        "modified".to_string()
+/// 初始化新仓库，返回初始分支名称
+///
+/// `default_branch` 未指定时使用全局配置的 `init.defaultBranch`，都没有时使用 libgit2 的默认值
+pub fn init_repository(path: &str, default_branch: Option<&str>) -> Result<String, GitError> {
+    let default_branch = match default_branch.map(str::trim).filter(|b| !b.is_empty()) {
+        Some(branch) => Some(branch.to_string()),
+        None => git2::Config::open_default()
+            .ok()
+            .and_then(|config| configured_default_branch(&config)),
+    };
+
+    let mut options = git2::RepositoryInitOptions::new();
+    options.mkpath(true);
+    if let Some(branch) = &default_branch {
+        options.initial_head(branch);
     }
-}
 
-fn calculate_file_stats(
-    repo: &Repository,
-    file_path: &str,
-    staged: bool,
-) -> Result<(u32, u32), GitError> {
-    let mut diff_options = git2::DiffOptions::new();
-    diff_options.pathspec(file_path);
+    let repo = Repository::init_opts(path, &options).map_err(GitError::Git)?;
+    let head = repo.find_reference("HEAD").map_err(GitError::Git)?;
+    let branch = head
+        .symbolic_target()
+        .and_then(|target| target.strip_prefix("refs/heads/"))
+        .unwrap_or("master")
+        .to_string();
 
-    let diff = if staged {
-        // 暂存区与HEAD的差异
-        let head = match repo.head() {
-            Ok(head) => head,
-            Err(_) => return Ok((0, 0)), // 如果没有HEAD，返回默认值
+    log::info!("仓库初始化成功: {} (分支: {})", path, branch);
+    Ok(branch)
+}
+
+/// 创建提交
+pub fn create_commit(repo_path: &str, options: &CommitOptions) -> Result<String, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    // 提交者使用仓库配置的身份；作者可以通过选项单独指定，未指定时与提交者相同
+    let configured = repo.signature().ok();
+    let author_identity = match (&options.author_name, &options.author_email) {
+        (Some(name), Some(email)) => Signature::now(name, email).map_err(GitError::Git)?,
+        _ => configured.clone().ok_or(GitError::IdentityNotConfigured)?,
+    };
+    let committer_identity = configured.unwrap_or_else(|| author_identity.clone());
+
+    // 指定了时间时使用该时间构建签名，否则使用当前时间
+    let offset = options.date_offset_minutes.unwrap_or(0);
+    let signature_at =
+        |identity: &Signature, date: Option<i64>| -> Result<Signature<'static>, GitError> {
+            match date {
+                Some(seconds) => Signature::new(
+                    identity.name().unwrap_or(""),
+                    identity.email().unwrap_or(""),
+                    &git2::Time::new(seconds, offset),
+                )
+                .map_err(GitError::Git),
+                None => Ok(identity.to_owned()),
+            }
         };
+    let author = signature_at(&author_identity, options.author_date)?;
+    let committer = signature_at(&committer_identity, options.committer_date)?;
 
-        let head_tree = head.peel_to_tree().map_err(|_| GitError::Unknown {
-            message: "无法获取HEAD树".to_string(),
-        })?;
+    // 获取索引并创建树
+    let mut index = repo.index().map_err(GitError::Git)?;
+    if options.stage_tracked {
+        // 只更新已在索引中的文件，未跟踪文件保持不变
+        index.update_all(["*"], None).map_err(GitError::Git)?;
+        index.write().map_err(|e| index_error(&repo, e))?;
+    }
+    let tree_id = match &options.only_paths {
+        Some(paths) => write_partial_tree(&repo, &index, paths)?,
+        None => index.write_tree().map_err(GitError::Git)?,
+    };
+    let tree = repo.find_tree(tree_id).map_err(GitError::Git)?;
 
-        let index = repo.index().map_err(GitError::Git)?;
+    // 构建提交消息
+    let mut message = options.message.clone();
+    if let Some(description) = &options.description {
+        if !description.is_empty() {
+            message.push_str("\n\n");
+            message.push_str(description);
+        }
+    }
 
-        repo.diff_tree_to_index(Some(&head_tree), Some(&index), Some(&mut diff_options))
-            .map_err(GitError::Git)?
+    let mut trailers = options.trailers.clone().unwrap_or_default();
+    // Signed-off-by 表示提交者的认可，因此使用提交者身份
+    if options.signoff {
+        trailers.push((
+            "Signed-off-by".to_string(),
+            format!(
+                "{} <{}>",
+                committer.name().unwrap_or(""),
+                committer.email().unwrap_or("")
+            ),
+        ));
+    }
+    let message = append_trailers(&message, &trailers);
+
+    // 获取父提交
+    let parents = if options.amend {
+        // 修正提交：使用当前HEAD的父提交
+        let head = repo.head().map_err(GitError::Git)?;
+        let head_commit = head.peel_to_commit().map_err(GitError::Git)?;
+        head_commit.parents().collect::<Vec<_>>()
     } else {
-        // 工作区与暂存区的差异
-        repo.diff_index_to_workdir(None, Some(&mut diff_options))
-            .map_err(GitError::Git)?
+        // 正常提交：使用HEAD作为父提交
+        match repo.head() {
+            Ok(head) => {
+                let head_commit = head.peel_to_commit().map_err(GitError::Git)?;
+                vec![head_commit]
+            }
+            Err(e) => {
+                // 首次提交，没有父提交；按 init.defaultBranch 配置确定创建的分支
+                if e.code() == git2::ErrorCode::UnbornBranch {
+                    apply_configured_default_branch(&repo)?;
+                }
+                vec![]
+            }
+        }
     };
 
-    // 计算添加和删除的行数
-    let mut additions = 0;
-    let mut deletions = 0;
+    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
 
-    diff.foreach(
-        &mut |_, _| true,
-        None,
-        None,
-        Some(&mut |_, _hunk, line| {
-            match line.origin() {
-                '+' => additions += 1,
-                '-' => deletions += 1,
-                _ => {}
-            }
-            true
-        }),
-    )
-    .map_err(GitError::Git)?;
+    // 创建提交
+    let commit_id = repo
+        .commit(
+            Some("HEAD"),
+            &author,
+            &committer,
+            &message,
+            &tree,
+            &parent_refs,
+        )
+        .map_err(GitError::Git)?;
 
-    Ok((additions, deletions))
+    Ok(commit_id.to_string())
 }
 
-fn get_current_branch(repo: &Repository) -> Result<String, GitError> {
-    match repo.head() {
-        Ok(head) => {
-            if let Some(branch_name) = head.shorthand() {
-                Ok(branch_name.to_string())
-            } else {
-                Ok("HEAD".to_string())
-            }
+/// 将 trailer 追加到提交消息末尾的 trailer 块中
+///
+/// 消息最后一段已经是 trailer 块时直接续写，否则以空行与正文分隔；已存在的相同 trailer 不会重复添加
+fn append_trailers(message: &str, trailers: &[(String, String)]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for (key, value) in trailers {
+        let (key, value) = (key.trim(), value.trim());
+        if key.is_empty() || value.is_empty() {
+            continue;
         }
-        Err(_) => Ok("main".to_string()),
+        let line = format!("{}: {}", key, value);
+        if !message.lines().any(|l| l.trim() == line) && !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+
+    if lines.is_empty() {
+        return message.to_string();
     }
+
+    let mut message = message.trim_end().to_string();
+    let last_paragraph = message.rsplit("\n\n").next().unwrap_or("");
+    let has_trailer_block = message.contains("\n\n")
+        && last_paragraph.lines().all(|line| {
+            line.split_once(": ").is_some_and(|(key, _)| {
+                !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+        });
+
+    message.push_str(if has_trailer_block { "\n" } else { "\n\n" });
+    message.push_str(&lines.join("\n"));
+    message
 }
 
-fn get_ahead_behind_count(repo: &Repository) -> Result<(u32, u32), GitError> {
-    // 获取当前分支
-    let head = match repo.head() {
-        Ok(head) => head,
-        Err(_) => return Ok((0, 0)), // 如果没有HEAD，返回默认值
-    };
+/// 未指定允许类型时使用的 Conventional Commits 类型
+const DEFAULT_CONVENTIONAL_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
 
-    // 获取当前分支名
-    let branch_name = match head.shorthand() {
-        Some(name) => name,
-        None => return Ok((0, 0)), // 如果无法获取分支名，返回默认值
-    };
+lazy_static::lazy_static! {
+    /// Conventional Commits 标题格式：`type(scope)!: subject`
+    static ref CONVENTIONAL_SUBJECT: regex::Regex =
+        regex::Regex::new(r"^([a-zA-Z]+)(\([^()\s][^()]*\))?!?: \S").unwrap();
+}
 
-    // 查找对应的远程跟踪分支
-    let upstream_name = match repo.branch_upstream_name(&format!("refs/heads/{}", branch_name)) {
-        Ok(name) => name,
-        Err(_) => return Ok((0, 0)), // 如果没有上游分支，返回默认值
+/// 按规则检查提交消息，返回发现的问题（不访问仓库）
+///
+/// 以 `#` 开头的注释行会被忽略，问题中的行号仍对应原消息中的行
+pub fn lint_commit_message(message: &str, rules: &CommitLintRules) -> Vec<LintIssue> {
+    let lines: Vec<(usize, &str)> = message
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.starts_with('#'))
+        .map(|(index, line)| (index + 1, line))
+        .collect();
+    let mut issues = Vec::new();
+    let mut issue = |rule: &str, severity: LintSeverity, message: String, line: usize| {
+        issues.push(LintIssue {
+            rule: rule.to_string(),
+            severity,
+            message,
+            line,
+        })
     };
 
-    // 获取本地和远程分支的OID
-    let local_oid = head.target().ok_or_else(|| GitError::Unknown {
-        message: "无法获取本地分支OID".to_string(),
-    })?;
+    let (subject_line, subject) = lines
+        .first()
+        .map(|(number, line)| (*number, line.trim_end()))
+        .unwrap_or((1, ""));
+    if subject.trim().is_empty() {
+        issue(
+            "subject_empty",
+            LintSeverity::Error,
+            "提交标题不能为空".to_string(),
+            subject_line,
+        );
+        return issues;
+    }
 
-    let upstream_ref = repo
-        .find_reference(upstream_name.as_str().unwrap())
-        .map_err(|_| GitError::Unknown {
-            message: "无法找到远程跟踪分支".to_string(),
-        })?;
+    let length = subject.chars().count();
+    if let Some(max) = rules.max_subject_length.filter(|max| length > *max) {
+        issue(
+            "subject_length",
+            LintSeverity::Error,
+            format!("标题长度为 {} 个字符，超过上限 {}", length, max),
+            subject_line,
+        );
+    } else if let Some(warn) = rules.warn_subject_length.filter(|warn| length > *warn) {
+        issue(
+            "subject_length",
+            LintSeverity::Warning,
+            format!("标题长度为 {} 个字符，建议不超过 {}", length, warn),
+            subject_line,
+        );
+    }
 
-    let upstream_oid = upstream_ref.target().ok_or_else(|| GitError::Unknown {
-        message: "无法获取远程分支OID".to_string(),
-    })?;
+    if let Some((body_line, _)) = lines
+        .get(1)
+        .filter(|(_, line)| rules.require_blank_line && !line.trim().is_empty())
+    {
+        issue(
+            "blank_line",
+            LintSeverity::Error,
+            "标题与正文之间需要空一行".to_string(),
+            *body_line,
+        );
+    }
 
-    // 计算领先/落后数量
-    let (ahead, behind) = repo
-        .graph_ahead_behind(local_oid, upstream_oid)
-        .map_err(GitError::Git)?;
+    if rules.conventional {
+        match CONVENTIONAL_SUBJECT.captures(subject) {
+            Some(captures) => {
+                let kind = &captures[1];
+                let allowed = match &rules.allowed_types {
+                    Some(types) => types.iter().any(|allowed| allowed == kind),
+                    None => DEFAULT_CONVENTIONAL_TYPES.contains(&kind),
+                };
+                if !allowed {
+                    issue(
+                        "conventional",
+                        LintSeverity::Error,
+                        format!("不允许的提交类型 '{}'", kind),
+                        subject_line,
+                    );
+                }
+            }
+            None => issue(
+                "conventional",
+                LintSeverity::Error,
+                "标题应符合 Conventional Commits 格式，如 \"feat(scope): 描述\"".to_string(),
+                subject_line,
+            ),
+        }
+    }
 
-    Ok((ahead as u32, behind as u32))
+    issues
 }
 
-/// 获取默认远程名称
-pub fn get_default_remote_name(repo: &Repository) -> Result<String, GitError> {
-    log::debug!("开始检测默认远程名称");
-
-    // 首先尝试获取当前分支的上游远程
-    if let Ok(head) = repo.head() {
-        if let Some(branch_name) = head.shorthand() {
-            log::debug!("当前分支: {}", branch_name);
+/// 验证提交签名（GPG 使用 `gpg --verify`，SSH 使用 `ssh-keygen -Y verify`）
+///
+/// 未签名的提交返回 `signed: false`；SSH 签名需要配置 `gpg.ssh.allowedSignersFile` 才能判定签名者是否受信任
+pub fn verify_commit_signature(
+    repo_path: &str,
+    commit_sha: &str,
+) -> Result<SignatureVerification, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let commit_id = resolve_commit_oid(&repo, commit_sha)?;
+
+    let (signature, signed_data) = match repo.extract_signature(&commit_id, None) {
+        Ok(extracted) => extracted,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {
+            return Ok(SignatureVerification {
+                signed: false,
+                valid: false,
+                format: None,
+                signer: None,
+                key_id: None,
+            })
+        }
+        Err(e) => return Err(GitError::Git(e)),
+    };
+    let signature = String::from_utf8_lossy(&signature).to_string();
 
-            if let Ok(upstream_name) =
-                repo.branch_upstream_name(&format!("refs/heads/{}", branch_name))
-            {
-                if let Some(upstream_str) = upstream_name.as_str() {
-                    log::debug!("找到上游分支: {}", upstream_str);
+    let format = if signature.contains("-----BEGIN SSH SIGNATURE-----") {
+        "ssh"
+    } else if signature.contains("-----BEGIN PGP SIGNATURE-----") {
+        "gpg"
+    } else {
+        "x509"
+    };
 
-                    // 解析远程名称，格式通常是 "refs/remotes/origin/main"
-                    if let Some(remote_part) = upstream_str.strip_prefix("refs/remotes/") {
-                        if let Some(slash_pos) = remote_part.find('/') {
-                            let remote_name = remote_part[..slash_pos].to_string();
-                            log::debug!("从上游分支解析到远程名称: {}", remote_name);
-                            return Ok(remote_name);
-                        }
-                    }
-                }
-            } else {
-                log::debug!("当前分支没有设置上游分支");
-            }
+    let work_dir =
+        std::env::temp_dir().join(format!("github-heart-verify-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&work_dir).map_err(GitError::Io)?;
+    let signature_path = work_dir.join("signature");
+    let data_path = work_dir.join("data");
+    let written = std::fs::write(&signature_path, &signature)
+        .and_then(|_| std::fs::write(&data_path, &*signed_data));
+
+    let config = repo.config().ok();
+    let result = written.map_err(GitError::Io).and_then(|_| match format {
+        "ssh" => {
+            let allowed_signers = config
+                .as_ref()
+                .and_then(|config| config.get_path("gpg.ssh.allowedSignersFile").ok());
+            verify_ssh_signature(&signature_path, &data_path, allowed_signers.as_deref())
         }
-    }
+        "gpg" => {
+            let program = config
+                .as_ref()
+                .and_then(|config| config.get_string("gpg.program").ok())
+                .unwrap_or_else(|| "gpg".to_string());
+            verify_gpg_signature(&program, &signature_path, &data_path)
+        }
+        _ => Ok((false, None, None)),
+    });
+    let _ = std::fs::remove_dir_all(&work_dir);
+
+    let (valid, signer, key_id) = result?;
+    Ok(SignatureVerification {
+        signed: true,
+        valid,
+        format: Some(format.to_string()),
+        signer,
+        key_id,
+    })
+}
 
-    // 如果无法从分支获取，尝试列出所有远程
-    let remotes = repo.remotes().map_err(GitError::Git)?;
-    let remote_names: Vec<String> = remotes.iter().flatten().map(|s| s.to_string()).collect();
-    log::debug!("仓库中的远程列表: {:?}", remote_names);
+/// 签名校验结果：(是否有效, 签名者, 密钥 ID)
+type SignatureCheck = (bool, Option<String>, Option<String>);
+
+fn verify_gpg_signature(
+    program: &str,
+    signature_path: &Path,
+    data_path: &Path,
+) -> Result<SignatureCheck, GitError> {
+    let output = crate::utils::system_command::create_hidden_command(program)
+        .args(["--status-fd=1", "--verify"])
+        .arg(signature_path)
+        .arg(data_path)
+        .output()
+        .map_err(|e| GitError::SystemGitFailed {
+            message: format!("无法执行 {}: {}", program, e),
+        })?;
 
-    if remotes.len() == 0 {
-        log::error!("仓库中没有配置任何远程仓库");
-        return Err(GitError::Unknown {
-            message: "仓库中没有配置任何远程仓库".to_string(),
-        });
-    }
+    Ok(parse_gpg_status(&String::from_utf8_lossy(&output.stdout)))
+}
 
-    // 优先选择常见的远程名称
-    let preferred_remotes = ["origin", "upstream", "github", "gitlab"];
-    for preferred in &preferred_remotes {
-        for i in 0..remotes.len() {
-            if let Some(remote_name) = remotes.get(i) {
-                if remote_name == *preferred {
-                    log::debug!("找到首选远程名称: {}", remote_name);
-                    return Ok(remote_name.to_string());
+/// 解析 `gpg --status-fd` 输出的状态行
+fn parse_gpg_status(status: &str) -> SignatureCheck {
+    let mut valid = false;
+    let mut trusted_key = true;
+    let mut signer = None;
+    let mut key_id = None;
+
+    for line in status.lines() {
+        let mut parts = line.strip_prefix("[GNUPG:] ").unwrap_or("").splitn(3, ' ');
+        let keyword = parts.next().unwrap_or("");
+        match keyword {
+            "GOODSIG" | "BADSIG" | "EXPSIG" | "EXPKEYSIG" | "REVKEYSIG" | "ERRSIG" => {
+                key_id = parts.next().map(|id| id.to_string());
+                if keyword != "ERRSIG" {
+                    signer = parts.next().map(|name| name.to_string());
                 }
+                valid = keyword == "GOODSIG";
+                trusted_key &= !matches!(keyword, "EXPKEYSIG" | "REVKEYSIG");
             }
+            _ => {}
         }
     }
 
-    // 如果没有找到首选的，使用第一个可用的远程
-    if let Some(first_remote) = remotes.get(0) {
-        log::debug!("使用第一个可用的远程: {}", first_remote);
-        return Ok(first_remote.to_string());
-    }
+    (valid && trusted_key, signer, key_id)
+}
 
-    // 这种情况理论上不应该发生，因为我们已经检查了remotes.len() == 0
-    Err(GitError::Unknown {
-        message: "无法确定默认远程名称".to_string(),
-    })
+fn verify_ssh_signature(
+    signature_path: &Path,
+    data_path: &Path,
+    allowed_signers: Option<&Path>,
+) -> Result<SignatureCheck, GitError> {
+    let run = |args: &[&str]| -> Result<std::process::Output, GitError> {
+        let data = std::fs::File::open(data_path).map_err(GitError::Io)?;
+        crate::utils::system_command::create_hidden_command("ssh-keygen")
+            .args(args)
+            .stdin(data)
+            .output()
+            .map_err(|_| GitError::SshKeygenNotFound)
+    };
+    let signature_path = signature_path.to_string_lossy();
+
+    let allowed_signers = match allowed_signers {
+        Some(path) => path.to_string_lossy(),
+        None => {
+            // 没有受信任签名者列表时只能校验签名本身，无法确认签名者
+            let output = run(&["-Y", "check-novalidate", "-n", "git", "-s", &signature_path])?;
+            let (_, _, key_id) = parse_ssh_verify_output(&output_text(&output));
+            return Ok((false, None, key_id));
+        }
+    };
+
+    let principals = run(&[
+        "-Y",
+        "find-principals",
+        "-f",
+        &allowed_signers,
+        "-s",
+        &signature_path,
+    ])?;
+    let principal = String::from_utf8_lossy(&principals.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty());
+    let Some(principal) = principal else {
+        return Ok((false, None, None));
+    };
+
+    let output = run(&[
+        "-Y",
+        "verify",
+        "-f",
+        &allowed_signers,
+        "-I",
+        &principal,
+        "-n",
+        "git",
+        "-s",
+        &signature_path,
+    ])?;
+    let (good, signer, key_id) = parse_ssh_verify_output(&output_text(&output));
+    Ok((
+        good && output.status.success(),
+        signer.or(Some(principal)),
+        key_id,
+    ))
 }
 
-/// 执行合并操作
-fn perform_merge(
-    repo: &Repository,
-    local_commit: &git2::Commit,
-    upstream_commit: &git2::Commit,
-) -> Result<SyncResult, GitError> {
-    // 获取合并基础
-    let merge_base = repo
-        .merge_base(local_commit.id(), upstream_commit.id())
-        .map_err(GitError::Git)?;
-    let merge_base_commit = repo.find_commit(merge_base).map_err(GitError::Git)?;
+fn output_text(output: &std::process::Output) -> String {
+    format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
 
-    // 创建AnnotatedCommit用于合并分析
-    let upstream_annotated = repo
-        .find_annotated_commit(upstream_commit.id())
-        .map_err(GitError::Git)?;
-    let analysis = repo
-        .merge_analysis(&[&upstream_annotated])
-        .map_err(GitError::Git)?;
+/// 解析 `ssh-keygen -Y verify` 的输出，如
+/// `Good "git" signature for alice@example.com with ED25519 key SHA256:...`
+fn parse_ssh_verify_output(output: &str) -> SignatureCheck {
+    let Some(line) = output.lines().find(|line| line.starts_with("Good ")) else {
+        return (false, None, None);
+    };
 
-    if analysis.0.is_fast_forward() {
-        // 快进合并
-        let refname = format!(
-            "refs/heads/{}",
-            repo.head()
-                .map_err(GitError::Git)?
-                .shorthand()
-                .unwrap_or("HEAD")
-        );
-        let mut reference = repo.find_reference(&refname).map_err(GitError::Git)?;
-        reference
-            .set_target(upstream_commit.id(), "Fast-forward merge")
-            .map_err(GitError::Git)?;
+    let signer = line
+        .split_once(" signature for ")
+        .and_then(|(_, rest)| rest.split_once(" with "))
+        .map(|(signer, _)| signer.to_string());
+    let key_id = line
+        .split_once(" key ")
+        .map(|(_, key)| key.trim().to_string());
 
-        // 更新工作目录
-        repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
-            .map_err(GitError::Git)?;
+    (true, signer, key_id)
+}
 
-        Ok(SyncResult {
-            success: true,
-            message: "快进合并成功".to_string(),
-            has_conflicts: false,
-            conflict_files: vec![],
-            ahead: 0,
-            behind: 0,
-        })
-    } else if analysis.0.is_normal() {
-        // 正常合并
-        let local_tree = local_commit.tree().map_err(GitError::Git)?;
-        let upstream_tree = upstream_commit.tree().map_err(GitError::Git)?;
-        let ancestor_tree = merge_base_commit.tree().map_err(GitError::Git)?;
+/// 获取提交消息模板
+///
+/// 优先读取 `commit.template` 配置指向的文件，未配置时读取仓库根目录的 `.gitmessage`
+pub fn get_commit_template(repo_path: &str) -> Result<Option<String>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let workdir = repo.workdir().unwrap_or_else(|| repo.path());
+
+    let configured = repo
+        .config()
+        .map_err(GitError::Git)?
+        .get_path("commit.template")
+        .ok();
+    let template_path = match configured {
+        Some(path) if path.is_absolute() => path,
+        Some(path) => workdir.join(path),
+        None => workdir.join(".gitmessage"),
+    };
 
-        let mut index = repo
-            .merge_trees(&ancestor_tree, &local_tree, &upstream_tree, None)
-            .map_err(GitError::Git)?;
+    match std::fs::read_to_string(&template_path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(GitError::Io(e)),
+    }
+}
 
-        if index.has_conflicts() {
-            // 有冲突，需要用户解决
-            let conflict_files: Vec<String> = index
-                .conflicts()
-                .map_err(GitError::Git)?
-                .filter_map(|conflict| {
-                    conflict.ok().and_then(|c| {
-                        c.our.as_ref().and_then(|entry| {
-                            std::str::from_utf8(&entry.path).ok().map(|s| s.to_string())
-                        })
-                    })
-                })
-                .collect();
+/// 以 HEAD 的树为基础，仅叠加指定路径在索引中的条目，生成提交用的树
+///
+/// 路径可以是文件或目录；索引中已删除的路径会从结果树中移除，其他暂存内容保持不变
+fn write_partial_tree(
+    repo: &Repository,
+    index: &git2::Index,
+    paths: &[String],
+) -> Result<git2::Oid, GitError> {
+    let mut partial = git2::Index::new().map_err(GitError::Git)?;
+    if let Ok(head) = repo.head() {
+        let head_tree = head.peel_to_tree().map_err(GitError::Git)?;
+        partial.read_tree(&head_tree).map_err(GitError::Git)?;
+    }
 
-            return Ok(SyncResult {
-                success: false,
-                message: "合并时发现冲突，请手动解决".to_string(),
-                has_conflicts: true,
-                conflict_files,
-                ahead: 0,
-                behind: 0,
-            });
+    for path in paths {
+        let path = path.trim_end_matches('/');
+        let dir_prefix = format!("{}/", path);
+        let matches = |entry: &git2::IndexEntry| {
+            entry.path == path.as_bytes() || entry.path.starts_with(dir_prefix.as_bytes())
+        };
+
+        // 先移除 HEAD 中匹配的条目，再加入索引中的条目，以便反映删除操作
+        let stale: Vec<Vec<u8>> = partial
+            .iter()
+            .filter(|entry| matches(entry))
+            .map(|entry| entry.path)
+            .collect();
+        for entry_path in stale {
+            let entry_path = String::from_utf8_lossy(&entry_path).to_string();
+            partial
+                .remove_path(Path::new(&entry_path))
+                .map_err(GitError::Git)?;
         }
 
-        // 无冲突，创建合并提交
-        let signature = repo.signature().map_err(GitError::Git)?;
-        let tree_id = index.write_tree_to(repo).map_err(GitError::Git)?;
-        let tree = repo.find_tree(tree_id).map_err(GitError::Git)?;
+        for entry in index.iter().filter(|entry| matches(entry)) {
+            partial.add(&entry).map_err(GitError::Git)?;
+        }
+    }
 
-        let message = format!(
-            "Merge branch '{}'",
-            upstream_commit.summary().unwrap_or("unknown")
-        );
+    partial.write_tree_to(repo).map_err(GitError::Git)
+}
 
-        repo.commit(
-            Some("HEAD"),
-            &signature,
-            &signature,
-            &message,
-            &tree,
-            &[local_commit, upstream_commit],
-        )
+/// 获取提交历史
+pub fn get_commit_history(
+    repo_path: &str,
+    limit: usize,
+    skip: usize,
+) -> Result<Vec<CommitHistoryItem>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    let mut revwalk = repo.revwalk().map_err(GitError::Git)?;
+    revwalk.push_head().map_err(GitError::Git)?;
+    revwalk
+        .set_sorting(git2::Sort::TIME)
         .map_err(GitError::Git)?;
 
-        Ok(SyncResult {
-            success: true,
-            message: "合并成功".to_string(),
-            has_conflicts: false,
-            conflict_files: vec![],
-            ahead: 0,
-            behind: 0,
-        })
-    } else {
-        Ok(SyncResult {
-            success: true,
-            message: "无需合并".to_string(),
-            has_conflicts: false,
-            conflict_files: vec![],
-            ahead: 0,
-            behind: 0,
-        })
-    }
-}
+    let mut commits = Vec::new();
 
-/// 执行变基操作
-fn perform_rebase(
-    repo: &Repository,
-    local_commit: &git2::Commit,
-    upstream_commit: &git2::Commit,
-) -> Result<SyncResult, GitError> {
-    // 变基操作比较复杂，这里提供一个简化的实现
-    // 在实际项目中，可能需要更复杂的冲突处理逻辑
+    for (index, oid_result) in revwalk.enumerate() {
+        if index < skip {
+            continue;
+        }
+        if commits.len() >= limit {
+            break;
+        }
 
-    let signature = repo.signature().map_err(GitError::Git)?;
+        let oid = oid_result.map_err(GitError::Git)?;
+        let commit = repo.find_commit(oid).map_err(GitError::Git)?;
 
-    // 创建AnnotatedCommit用于变基
-    let local_annotated = repo
-        .find_annotated_commit(local_commit.id())
-        .map_err(GitError::Git)?;
-    let upstream_annotated = repo
-        .find_annotated_commit(upstream_commit.id())
-        .map_err(GitError::Git)?;
+        commits.push(commit_to_history_item(&commit));
+    }
 
-    // 创建变基操作
-    let mut rebase = repo
-        .rebase(
-            Some(&local_annotated),
-            Some(&upstream_annotated),
-            None,
-            None,
-        )
+    Ok(commits)
+}
+
+/// 按过滤条件获取提交历史（从 HEAD 开始，按时间倒序），最多返回 `limit` 条
+pub fn get_filtered_commit_history(
+    repo_path: &str,
+    limit: usize,
+    filter: &CommitHistoryFilter,
+) -> Result<Vec<CommitHistoryItem>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    let mut revwalk = repo.revwalk().map_err(GitError::Git)?;
+    revwalk.push_head().map_err(GitError::Git)?;
+    revwalk
+        .set_sorting(git2::Sort::TIME)
         .map_err(GitError::Git)?;
 
-    // 执行变基步骤
-    while let Some(operation) = rebase.next() {
-        match operation {
-            Ok(_op) => {
-                // 检查是否有冲突
-                let index = repo.index().map_err(GitError::Git)?;
-                if index.has_conflicts() {
-                    // 有冲突，中止变基
-                    rebase.abort().map_err(GitError::Git)?;
+    let author = filter.author.as_ref().map(|author| author.to_lowercase());
+    let message = filter
+        .message
+        .as_ref()
+        .map(|message| message.to_lowercase());
 
-                    let conflict_files: Vec<String> = index
-                        .conflicts()
-                        .map_err(GitError::Git)?
-                        .filter_map(|conflict| {
-                            conflict.ok().and_then(|c| {
-                                c.our.as_ref().and_then(|entry| {
-                                    std::str::from_utf8(&entry.path).ok().map(|s| s.to_string())
-                                })
-                            })
-                        })
-                        .collect();
+    let mut commits = Vec::new();
+    for oid_result in revwalk {
+        if commits.len() >= limit {
+            break;
+        }
 
-                    return Ok(SyncResult {
-                        success: false,
-                        message: "变基时发现冲突，请手动解决".to_string(),
-                        has_conflicts: true,
-                        conflict_files,
-                        ahead: 0,
-                        behind: 0,
-                    });
-                }
+        let oid = oid_result.map_err(GitError::Git)?;
+        let commit = repo.find_commit(oid).map_err(GitError::Git)?;
+        let item = commit_to_history_item(&commit);
 
-                // 提交当前步骤
-                rebase
-                    .commit(None, &signature, None)
-                    .map_err(GitError::Git)?;
+        if filter.since.is_some_and(|since| item.author_date < since)
+            || filter.until.is_some_and(|until| item.author_date > until)
+        {
+            continue;
+        }
+        if let Some(author) = &author {
+            if !item.author_name.to_lowercase().contains(author)
+                && !item.author_email.to_lowercase().contains(author)
+            {
+                continue;
             }
-            Err(e) => {
-                rebase.abort().map_err(GitError::Git)?;
-                return Err(GitError::Git(e));
+        }
+        if let Some(message) = &message {
+            if !item.message.to_lowercase().contains(message) {
+                continue;
+            }
+        }
+        if let Some(path) = &filter.path {
+            let mut diff_options = git2::DiffOptions::new();
+            diff_options.pathspec(path);
+            if commit_diff(&repo, &commit, Some(&mut diff_options))?
+                .deltas()
+                .len()
+                == 0
+            {
+                continue;
             }
         }
+
+        commits.push(item);
     }
 
-    // 完成变基
-    rebase.finish(None).map_err(GitError::Git)?;
+    Ok(commits)
+}
 
-    Ok(SyncResult {
-        success: true,
-        message: "变基成功".to_string(),
-        has_conflicts: false,
-        conflict_files: vec![],
-        ahead: 0,
-        behind: 0,
-    })
+/// 提交相对第一个父提交（根提交为空树）的差异
+fn commit_diff<'r>(
+    repo: &'r Repository,
+    commit: &git2::Commit,
+    diff_options: Option<&mut git2::DiffOptions>,
+) -> Result<git2::Diff<'r>, GitError> {
+    let tree = commit.tree().map_err(GitError::Git)?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(GitError::Git)?),
+        Err(_) => None,
+    };
+    repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), diff_options)
+        .map_err(GitError::Git)
 }
 
-// ==================== Token认证系统 ====================
+/// 按过滤条件导出提交历史到 JSON 或 CSV 文件
+///
+/// 每条记录包含相对第一个父提交的增删行数；CSV 字段按 RFC 4180 转义
+pub fn export_commit_history(
+    repo_path: &str,
+    output_path: &str,
+    format: HistoryExportFormat,
+    limit: usize,
+    filter: &CommitHistoryFilter,
+) -> Result<HistoryExportResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let history = get_filtered_commit_history(repo_path, limit, filter)?;
+
+    let mut rows = Vec::with_capacity(history.len());
+    for item in history {
+        let oid = Oid::from_str(&item.sha).map_err(GitError::Git)?;
+        let commit = repo.find_commit(oid).map_err(GitError::Git)?;
+        let stats = commit_diff(&repo, &commit, None)?
+            .stats()
+            .map_err(GitError::Git)?;
+        let date = chrono::DateTime::from_timestamp(item.author_date, 0)
+            .map(|date| date.to_rfc3339())
+            .unwrap_or_default();
+
+        rows.push(CommitExportRow {
+            sha: item.sha,
+            author: item.author_name,
+            email: item.author_email,
+            date,
+            subject: item.message.lines().next().unwrap_or("").to_string(),
+            insertions: stats.insertions() as u32,
+            deletions: stats.deletions() as u32,
+        });
+    }
 
-/// Token配置结构
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub struct TokenConfig {
-    pub domain: String,
-    pub token: String,
-    pub username: Option<String>,
-    pub created_at: i64,
-    pub last_used: Option<i64>,
+    let content = match format {
+        HistoryExportFormat::Json => {
+            serde_json::to_string_pretty(&rows).map_err(|e| GitError::Unknown {
+                message: format!("序列化提交历史失败: {}", e),
+            })?
+        }
+        HistoryExportFormat::Csv => {
+            let mut csv = String::from("sha,author,email,date,subject,insertions,deletions\n");
+            for row in &rows {
+                let fields = [
+                    csv_field(&row.sha),
+                    csv_field(&row.author),
+                    csv_field(&row.email),
+                    csv_field(&row.date),
+                    csv_field(&row.subject),
+                    row.insertions.to_string(),
+                    row.deletions.to_string(),
+                ];
+                csv.push_str(&fields.join(","));
+                csv.push('\n');
+            }
+            csv
+        }
+    };
+    std::fs::write(output_path, content).map_err(GitError::Io)?;
+
+    Ok(HistoryExportResult {
+        output_path: output_path.to_string(),
+        rows: rows.len(),
+    })
 }
 
-/// 创建支持Token认证的回调函数
-pub fn create_authenticated_callbacks(
-    repo_url: &str,
-    token_cache: Option<String>,
-) -> RemoteCallbacks {
-    let mut callbacks = RemoteCallbacks::new();
-    let _url = repo_url.to_string();
+/// CSV 字段转义：包含逗号、引号或换行时用双引号包裹，内部引号加倍
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
 
-    // 添加认证尝试计数器，防止无限重试
-    let auth_attempts = std::cell::RefCell::new(0);
+/// 将提交对象转换为提交历史项
+fn commit_to_history_item(commit: &git2::Commit) -> CommitHistoryItem {
+    let author = commit.author();
+    let committer = commit.committer();
+
+    CommitHistoryItem {
+        sha: commit.id().to_string(),
+        message: commit.message().unwrap_or("").to_string(),
+        author_name: author.name().unwrap_or("").to_string(),
+        author_email: author.email().unwrap_or("").to_string(),
+        author_date: author.when().seconds(),
+        committer_name: committer.name().unwrap_or("").to_string(),
+        committer_email: committer.email().unwrap_or("").to_string(),
+        committer_date: committer.when().seconds(),
+        parent_count: commit.parent_count(),
+    }
+}
 
-    callbacks.credentials(move |url, username_from_url, allowed_types| {
-        let mut attempts = auth_attempts.borrow_mut();
-        *attempts += 1;
+/// 将空白字符处理选项应用到差异选项
+fn apply_whitespace_options(diff_options: &mut git2::DiffOptions, whitespace: &WhitespaceOptions) {
+    diff_options
+        .ignore_whitespace(whitespace.ignore_whitespace)
+        .ignore_whitespace_change(whitespace.ignore_whitespace_change)
+        .ignore_whitespace_eol(whitespace.ignore_whitespace_eol)
+        .ignore_blank_lines(whitespace.ignore_blank_lines);
+}
 
-        log::debug!(
-            "Git认证回调被调用: url={}, username={:?}, allowed_types={:?}, 尝试次数={}",
-            url,
-            username_from_url,
-            allowed_types,
-            *attempts
-        );
+/// 获取文件差异
+pub fn get_file_diff(
+    repo_path: &str,
+    file_path: &str,
+    staged: bool,
+    whitespace: &WhitespaceOptions,
+) -> Result<String, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
 
-        // 防止无限重试，最多尝试3次
-        if *attempts > 3 {
-            log::error!("认证尝试次数过多，停止重试");
-            return Err(git2::Error::from_str("认证失败：尝试次数过多"));
-        }
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options.pathspec(file_path);
+    apply_whitespace_options(&mut diff_options, whitespace);
 
-        // 检查是否是HTTPS协议
-        if url.starts_with("https://") || url.starts_with("http://") {
-            // 尝试使用Token认证
-            if let Some(ref token) = token_cache {
-                log::debug!("使用Token进行HTTPS认证 (尝试 {})", *attempts);
+    let diff = if staged {
+        // 暂存区与HEAD的差异
+        let head = repo.head().map_err(GitError::Git)?;
+        let head_tree = head.peel_to_tree().map_err(GitError::Git)?;
+        let index = repo.index().map_err(GitError::Git)?;
+        repo.diff_tree_to_index(Some(&head_tree), Some(&index), Some(&mut diff_options))
+            .map_err(GitError::Git)?
+    } else {
+        // 工作区与暂存区的差异
+        repo.diff_index_to_workdir(None, Some(&mut diff_options))
+            .map_err(GitError::Git)?
+    };
 
-                // 对于AtomGit等服务，Token应该作为用户名，密码为空或token
-                // 第一次尝试：token作为用户名，密码为空
-                if *attempts == 1 {
-                    return git2::Cred::userpass_plaintext(token, "");
-                }
-                // 第二次尝试：使用用户名和token作为密码
-                else if *attempts == 2 {
-                    let username = username_from_url.unwrap_or("git");
-                    return git2::Cred::userpass_plaintext(username, token);
-                }
-            } else {
-                log::debug!("未找到Token，尝试默认凭据");
-                // 只在第一次尝试时使用默认凭据
-                if *attempts == 1 {
-                    return git2::Cred::default();
-                }
+    // 将差异转换为字符串
+    let mut diff_text = String::new();
+    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+        match line.origin() {
+            '+' | '-' | ' ' => {
+                diff_text.push(line.origin());
+                diff_text.push_str(std::str::from_utf8(line.content()).unwrap_or(""));
             }
+            _ => {}
         }
+        true
+    })
+    .map_err(GitError::Git)?;
 
-        // 对于SSH或其他情况，使用默认凭据
-        if *attempts == 1 {
-            log::debug!("使用默认凭据");
-            git2::Cred::default()
-        } else {
-            log::error!("认证失败，无更多认证方式可尝试");
-            Err(git2::Error::from_str("认证失败"))
-        }
-    });
+    Ok(diff_text)
+}
 
-    // 添加证书检查回调
-    callbacks.certificate_check(|_cert, _valid| {
-        log::debug!("证书检查回调");
-        // 对于开发环境，可以选择接受所有证书
-        // 生产环境应该进行适当的证书验证
-        Ok(git2::CertificateCheckStatus::CertificateOk)
-    });
+/// 获取所有已暂存变更的结构化差异（HEAD 与索引之间）
+///
+/// 尚无提交时与空树比较
+pub fn get_staged_diff(
+    repo_path: &str,
+    whitespace: &WhitespaceOptions,
+) -> Result<Vec<FileDiff>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
 
-    callbacks
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree().map_err(GitError::Git)?),
+        Err(_) => None,
+    };
+
+    let mut diff_options = git2::DiffOptions::new();
+    apply_whitespace_options(&mut diff_options, whitespace);
+
+    let mut diff = repo
+        .diff_tree_to_index(head_tree.as_ref(), None, Some(&mut diff_options))
+        .map_err(GitError::Git)?;
+    diff.find_similar(None).map_err(GitError::Git)?;
+
+    collect_file_diffs(&diff)
 }
 
-// ==================== 分支管理功能 ====================
+/// 获取所有未暂存变更的结构化差异（索引与工作区之间，包含未跟踪文件）
+pub fn get_unstaged_diff(
+    repo_path: &str,
+    whitespace: &WhitespaceOptions,
+) -> Result<Vec<FileDiff>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
 
-/// 分支信息结构
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub struct BranchInfo {
-    /// 分支名称
-    pub name: String,
-    /// 是否为当前分支
-    pub is_current: bool,
-    /// 是否为远程分支
-    pub is_remote: bool,
-    /// 上游分支名称
-    pub upstream: Option<String>,
-    /// 领先提交数
-    pub ahead: u32,
-    /// 落后提交数
-    pub behind: u32,
-    /// 最后提交信息
-    pub last_commit: Option<CommitInfo>,
-}
-
-/// 简化的提交信息结构
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub struct CommitInfo {
-    /// 提交SHA
-    pub sha: String,
-    /// 提交消息
-    pub message: String,
-    /// 作者姓名
-    pub author_name: String,
-    /// 作者邮箱
-    pub author_email: String,
-    /// 提交时间戳
-    pub timestamp: i64,
-}
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    apply_whitespace_options(&mut diff_options, whitespace);
+
+    let diff = repo
+        .diff_index_to_workdir(None, Some(&mut diff_options))
+        .map_err(GitError::Git)?;
 
-/// 分支切换结果
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub struct SwitchResult {
-    /// 操作是否成功
-    pub success: bool,
-    /// 操作消息
-    pub message: String,
-    /// 是否有未提交的变更
-    pub has_uncommitted_changes: bool,
-    /// 未提交的文件列表
-    pub uncommitted_files: Vec<String>,
+    collect_file_diffs(&diff)
 }
 
-/// 获取分支列表
-pub fn list_branches(repo_path: &str) -> Result<Vec<BranchInfo>, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
-    let mut branches = Vec::new();
+/// 获取两个版本之间的差异统计（启用重命名检测），不生成完整差异
+///
+/// `from` 默认为 HEAD（尚无提交时为空树），`to` 为 None 时与工作区（含暂存内容）比较
+pub fn get_diff_stat(
+    repo_path: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<DiffStat, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let tree_of = |reference: &str| -> Result<git2::Tree, GitError> {
+        let commit_id = resolve_commit_oid(&repo, reference)?;
+        repo.find_commit(commit_id)
+            .and_then(|commit| commit.tree())
+            .map_err(GitError::Git)
+    };
 
-    log::debug!("开始获取分支列表: {}", repo_path);
+    let from_tree = match from {
+        Some(reference) => Some(tree_of(reference)?),
+        None => match repo.head() {
+            Ok(head) => Some(head.peel_to_tree().map_err(GitError::Git)?),
+            Err(_) => None,
+        },
+    };
 
-    // 获取当前分支名称
-    let current_branch_name = get_current_branch_name(&repo)?;
-    log::debug!("当前分支: {:?}", current_branch_name);
+    let mut diff = match to {
+        Some(reference) => {
+            let to_tree = tree_of(reference)?;
+            repo.diff_tree_to_tree(from_tree.as_ref(), Some(&to_tree), None)
+        }
+        None => repo.diff_tree_to_workdir_with_index(from_tree.as_ref(), None),
+    }
+    .map_err(GitError::Git)?;
 
-    // 获取本地分支
-    let local_branches = repo
-        .branches(Some(git2::BranchType::Local))
+    let mut find_options = git2::DiffFindOptions::new();
+    find_options.renames(true);
+    diff.find_similar(Some(&mut find_options))
         .map_err(GitError::Git)?;
-    for branch_result in local_branches {
-        if let Ok((branch, _)) = branch_result {
-            if let Some(name) = branch.name().map_err(GitError::Git)? {
-                let is_current = current_branch_name.as_ref() == Some(&name.to_string());
 
-                log::debug!("处理本地分支: {} (当前: {})", name, is_current);
-
-                let branch_info =
-                    create_branch_info(&repo, name.to_string(), is_current, false, &branch)?;
+    let stats = diff.stats().map_err(GitError::Git)?;
+    let mut files = Vec::new();
+    for (index, delta) in diff.deltas().enumerate() {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let old_path = match delta.status() {
+            git2::Delta::Renamed | git2::Delta::Copied => delta
+                .old_file()
+                .path()
+                .map(|p| p.to_string_lossy().to_string()),
+            _ => None,
+        };
 
-                branches.push(branch_info);
-            }
+        let mut file = FileDiffStat {
+            path,
+            old_path,
+            status: convert_delta_status(delta.status()),
+            is_binary: delta.flags().is_binary(),
+            additions: 0,
+            deletions: 0,
+        };
+        if let Some(patch) = git2::Patch::from_diff(&diff, index).map_err(GitError::Git)? {
+            let (_, additions, deletions) = patch.line_stats().map_err(GitError::Git)?;
+            file.additions = additions as u32;
+            file.deletions = deletions as u32;
+            file.is_binary = file.is_binary || patch.delta().flags().is_binary();
         }
+        files.push(file);
     }
 
-    // 获取远程分支
-    let remote_branches = repo
-        .branches(Some(git2::BranchType::Remote))
+    Ok(DiffStat {
+        files_changed: stats.files_changed() as u32,
+        insertions: stats.insertions() as u32,
+        deletions: stats.deletions() as u32,
+        files,
+    })
+}
+
+/// 获取工作区文件与任意提交之间的结构化差异
+///
+/// 直接比较提交中的 blob 与工作区文件（不经过索引）；文件在提交之后新增或被删除时，
+/// 状态分别为 added / deleted；两边内容相同时返回不含差异块的 unmodified 结果
+pub fn get_file_diff_against(
+    repo_path: &str,
+    file_path: &str,
+    commit_ref: &str,
+    whitespace: &WhitespaceOptions,
+) -> Result<FileDiff, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let commit_oid = resolve_commit_oid(&repo, commit_ref)?;
+    let tree = repo
+        .find_commit(commit_oid)
+        .and_then(|commit| commit.tree())
         .map_err(GitError::Git)?;
-    for branch_result in remote_branches {
-        if let Ok((branch, _)) = branch_result {
-            if let Some(name) = branch.name().map_err(GitError::Git)? {
-                // 过滤掉远程HEAD分支
-                if is_remote_head_branch(name) {
-                    log::debug!("跳过远程HEAD分支: {}", name);
-                    continue;
-                }
 
-                log::debug!("处理远程分支: {}", name);
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options
+        .pathspec(file_path)
+        .disable_pathspec_match(true)
+        .include_untracked(true)
+        .show_untracked_content(true);
+    apply_whitespace_options(&mut diff_options, whitespace);
+
+    let diff = repo
+        .diff_tree_to_workdir(Some(&tree), Some(&mut diff_options))
+        .map_err(GitError::Git)?;
 
-                let branch_info =
-                    create_branch_info(&repo, name.to_string(), false, true, &branch)?;
+    if let Some(file_diff) = collect_file_diffs(&diff)?.into_iter().next() {
+        return Ok(file_diff);
+    }
 
-                branches.push(branch_info);
-            }
-        }
+    let in_commit = tree.get_path(Path::new(file_path)).is_ok();
+    let in_workdir = repo
+        .workdir()
+        .is_some_and(|workdir| workdir.join(file_path).exists());
+    if !in_commit && !in_workdir {
+        return Err(GitError::Unknown {
+            message: format!("文件 '{}' 在工作区和 {} 中均不存在", file_path, commit_ref),
+        });
     }
 
-    log::debug!("获取到 {} 个分支", branches.len());
-    Ok(branches)
+    Ok(FileDiff {
+        path: file_path.to_string(),
+        old_path: None,
+        status: "unmodified".to_string(),
+        is_binary: false,
+        additions: 0,
+        deletions: 0,
+        hunks: Vec::new(),
+    })
 }
 
-/// 创建分支信息
-fn create_branch_info(
-    repo: &Repository,
-    name: String,
-    is_current: bool,
-    is_remote: bool,
-    branch: &git2::Branch,
-) -> Result<BranchInfo, GitError> {
-    // 获取上游分支
-    let upstream = if !is_remote {
-        branch.upstream().ok().and_then(|upstream_branch| {
-            upstream_branch.name().ok().flatten().map(|s| s.to_string())
-        })
-    } else {
-        None
+/// 默认的 blame 忽略提交列表文件（与 `git blame --ignore-revs-file` 约定一致）
+pub const BLAME_IGNORE_REVS_FILE: &str = ".git-blame-ignore-revs";
+
+/// 跳过被忽略提交时最多向前追溯的层数
+const MAX_BLAME_IGNORE_DEPTH: usize = 16;
+
+/// 读取 blame 忽略的提交列表
+///
+/// 优先使用 `blame.ignoreRevsFile` 配置（相对于仓库根目录），否则读取根目录下的
+/// `.git-blame-ignore-revs`；文件中 `#` 之后为注释，无法解析的行会被跳过
+fn load_blame_ignore_revs(repo: &Repository) -> std::collections::HashSet<Oid> {
+    let workdir = match repo.workdir() {
+        Some(workdir) => workdir,
+        None => return std::collections::HashSet::new(),
+    };
+    let file = repo
+        .config()
+        .and_then(|config| config.get_path("blame.ignoreRevsFile"))
+        .map(|path| workdir.join(path))
+        .unwrap_or_else(|_| workdir.join(BLAME_IGNORE_REVS_FILE));
+
+    let content = match std::fs::read_to_string(&file) {
+        Ok(content) => content,
+        Err(_) => return std::collections::HashSet::new(),
     };
 
-    // 获取分支的最后提交
-    let last_commit = {
-        let reference = branch.get();
-        if let Some(target_oid) = reference.target() {
-            if let Ok(commit) = repo.find_commit(target_oid) {
-                Some(CommitInfo {
-                    sha: commit.id().to_string(),
-                    message: commit.message().unwrap_or("").to_string(),
-                    author_name: commit.author().name().unwrap_or("").to_string(),
-                    author_email: commit.author().email().unwrap_or("").to_string(),
-                    timestamp: commit.time().seconds(),
-                })
-            } else {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .filter_map(|rev| match resolve_commit_oid(repo, rev) {
+            Ok(oid) => Some(oid),
+            Err(_) => {
+                log::warn!("忽略无法解析的 blame 忽略提交: {}", rev);
                 None
             }
-        } else {
-            None
-        }
-    };
+        })
+        .collect()
+}
 
-    // 计算ahead/behind状态（仅对有上游的本地分支）
-    let (ahead, behind) = if !is_remote && upstream.is_some() {
-        calculate_ahead_behind_for_branch(repo, &name).unwrap_or((0, 0))
-    } else {
-        (0, 0)
+/// 差异块在新旧版本中的行范围（起始行号和行数）
+#[derive(Debug, Clone, Copy)]
+struct LineHunk {
+    old_start: usize,
+    old_lines: usize,
+    new_start: usize,
+    new_lines: usize,
+}
+
+/// 获取文件在提交与父提交之间的差异块（不含上下文行），文件在父提交中不存在时返回 None
+fn diff_hunks_against_parent(
+    repo: &Repository,
+    parent_tree: &git2::Tree,
+    commit_tree: &git2::Tree,
+    path: &str,
+) -> Result<Option<Vec<LineHunk>>, GitError> {
+    if parent_tree.get_path(Path::new(path)).is_err() {
+        return Ok(None);
+    }
+
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options.pathspec(path).context_lines(0);
+    let diff = repo
+        .diff_tree_to_tree(
+            Some(parent_tree),
+            Some(commit_tree),
+            Some(&mut diff_options),
+        )
+        .map_err(GitError::Git)?;
+    let patch = match git2::Patch::from_diff(&diff, 0).map_err(GitError::Git)? {
+        Some(patch) => patch,
+        None => return Ok(Some(Vec::new())),
     };
 
-    Ok(BranchInfo {
-        name,
-        is_current,
-        is_remote,
-        upstream,
-        ahead,
-        behind,
-        last_commit,
-    })
+    (0..patch.num_hunks())
+        .map(|index| {
+            let (hunk, _) = patch.hunk(index).map_err(GitError::Git)?;
+            Ok(LineHunk {
+                old_start: hunk.old_start() as usize,
+                old_lines: hunk.old_lines() as usize,
+                new_start: hunk.new_start() as usize,
+                new_lines: hunk.new_lines() as usize,
+            })
+        })
+        .collect::<Result<Vec<_>, GitError>>()
+        .map(Some)
 }
 
-/// 获取当前分支名称
-fn get_current_branch_name(repo: &Repository) -> Result<Option<String>, GitError> {
-    match repo.head() {
-        Ok(head) => {
-            if let Some(shorthand) = head.shorthand() {
-                Ok(Some(shorthand.to_string()))
-            } else {
-                Ok(None)
+/// 按差异块将提交版本文件中的行号映射到父提交版本中的对应行号
+///
+/// 行位于修改块内时映射到旧块中相同偏移的行（超出时取旧块最后一行）；该行为新增行时返回 None
+fn map_line_to_parent(hunks: &[LineHunk], line: usize) -> Option<usize> {
+    let mut offset: i64 = 0;
+    for hunk in hunks {
+        let LineHunk {
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+        } = *hunk;
+
+        // 行数为 0 的块，起始行号表示插入/删除位置的前一行
+        if (new_lines == 0 && line <= new_start) || (new_lines > 0 && line < new_start) {
+            break;
+        }
+        if new_lines > 0 && line < new_start + new_lines {
+            if old_lines == 0 {
+                return None;
             }
+            return Some(old_start + (line - new_start).min(old_lines - 1));
         }
-        Err(_) => Ok(None), // 可能是新仓库或detached HEAD
+
+        let old_end = old_start + old_lines.max(1);
+        let new_end = new_start + new_lines.max(1);
+        offset = old_end as i64 - new_end as i64;
     }
-}
 
-/// 检查是否是远程HEAD分支
-fn is_remote_head_branch(branch_name: &str) -> bool {
-    // 远程HEAD分支通常以 "/HEAD" 结尾
-    // 例如: "origin/HEAD", "upstream/HEAD" 等
-    branch_name.ends_with("/HEAD")
+    Some((line as i64 + offset) as usize)
 }
 
-/// 计算特定分支的ahead/behind状态
-fn calculate_ahead_behind_for_branch(
-    repo: &Repository,
-    branch_name: &str,
-) -> Result<(u32, u32), GitError> {
-    // 尝试获取分支的上游
-    let upstream_name = match repo.branch_upstream_name(&format!("refs/heads/{}", branch_name)) {
-        Ok(name) => name,
-        Err(_) => return Ok((0, 0)), // 没有上游分支
+/// 逐行追溯文件（默认 HEAD 版本），遵循 `.git-blame-ignore-revs` / `blame.ignoreRevsFile`
+///
+/// 最后修改某行的提交在忽略列表中时，按该提交的差异将行映射到父提交并继续追溯，
+/// 结果中的 `ignored_commit` 记录被跳过的提交
+pub fn blame_file(
+    repo_path: &str,
+    file_path: &str,
+    revision: Option<&str>,
+) -> Result<Vec<BlameLine>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let newest = resolve_commit_oid(&repo, revision.unwrap_or("HEAD"))?;
+    let ignored = load_blame_ignore_revs(&repo);
+
+    let blame_at = |commit: Oid, path: &str| {
+        let mut options = git2::BlameOptions::new();
+        options.newest_commit(commit);
+        repo.blame_file(Path::new(path), Some(&mut options))
+            .map_err(GitError::Git)
     };
 
-    if let Some(upstream_str) = upstream_name.as_str() {
-        // 获取本地分支和上游分支的提交
-        let local_ref = format!("refs/heads/{}", branch_name);
-        let upstream_ref = upstream_str;
+    let blame = blame_at(newest, file_path)?;
+    let blob = repo
+        .find_commit(newest)
+        .and_then(|commit| commit.tree())
+        .and_then(|tree| tree.get_path(Path::new(file_path)))
+        .and_then(|entry| repo.find_blob(entry.id()))
+        .map_err(GitError::Git)?;
+    let content = String::from_utf8_lossy(blob.content()).to_string();
+
+    // 同一提交和路径的差异块及父提交 blame 在多行之间复用
+    let mut parent_hunks: std::collections::HashMap<(Oid, String), Option<Vec<LineHunk>>> =
+        std::collections::HashMap::new();
+    let mut parent_blames: std::collections::HashMap<(Oid, String), git2::Blame> =
+        std::collections::HashMap::new();
+    let mut lines = Vec::new();
+
+    for (index, text) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let hunk = match blame.get_line(line_number) {
+            Some(hunk) => hunk,
+            None => continue,
+        };
+        let mut commit_id = hunk.final_commit_id();
+        let mut line = hunk.orig_start_line() + (line_number - hunk.final_start_line());
+        let mut path = hunk
+            .path()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| file_path.to_string());
+        let mut ignored_commit = None;
+
+        for _ in 0..MAX_BLAME_IGNORE_DEPTH {
+            if !ignored.contains(&commit_id) {
+                break;
+            }
+            let commit = repo.find_commit(commit_id).map_err(GitError::Git)?;
+            let parent = match commit.parent(0) {
+                Ok(parent) => parent,
+                Err(_) => break,
+            };
+            let hunks_key = (commit_id, path.clone());
+            if !parent_hunks.contains_key(&hunks_key) {
+                let hunks = diff_hunks_against_parent(
+                    &repo,
+                    &parent.tree().map_err(GitError::Git)?,
+                    &commit.tree().map_err(GitError::Git)?,
+                    &path,
+                )?;
+                parent_hunks.insert(hunks_key.clone(), hunks);
+            }
+            let parent_line = parent_hunks[&hunks_key]
+                .as_deref()
+                .and_then(|hunks| map_line_to_parent(hunks, line));
+            let parent_line = match parent_line {
+                Some(parent_line) => parent_line,
+                None => break,
+            };
 
-        if let (Ok(local_oid), Ok(upstream_oid)) = (
-            repo.refname_to_id(&local_ref),
-            repo.refname_to_id(upstream_ref),
-        ) {
-            return calculate_ahead_behind_from_oids(repo, local_oid, upstream_oid);
+            let key = (parent.id(), path.clone());
+            if !parent_blames.contains_key(&key) {
+                parent_blames.insert(key.clone(), blame_at(parent.id(), &path)?);
+            }
+            let parent_hunk = match parent_blames[&key].get_line(parent_line) {
+                Some(parent_hunk) => parent_hunk,
+                None => break,
+            };
+
+            ignored_commit.get_or_insert_with(|| commit_id.to_string());
+            commit_id = parent_hunk.final_commit_id();
+            line = parent_hunk.orig_start_line() + (parent_line - parent_hunk.final_start_line());
+            if let Some(parent_path) = parent_hunk.path() {
+                path = parent_path.to_string_lossy().to_string();
+            }
         }
+
+        let commit = repo.find_commit(commit_id).map_err(GitError::Git)?;
+        let author = commit.author();
+        lines.push(BlameLine {
+            line_number,
+            commit_sha: commit_id.to_string(),
+            author_name: author.name().unwrap_or("").to_string(),
+            author_email: author.email().unwrap_or("").to_string(),
+            author_date: author.when().seconds(),
+            summary: commit.summary().unwrap_or("").to_string(),
+            content: text.to_string(),
+            ignored_commit,
+        });
     }
 
-    Ok((0, 0))
+    Ok(lines)
 }
 
-/// 从OID计算ahead/behind
-fn calculate_ahead_behind_from_oids(
-    repo: &Repository,
-    local_oid: git2::Oid,
-    upstream_oid: git2::Oid,
-) -> Result<(u32, u32), GitError> {
-    match repo.graph_ahead_behind(local_oid, upstream_oid) {
-        Ok((ahead, behind)) => Ok((ahead as u32, behind as u32)),
-        Err(_) => Ok((0, 0)),
+/// 将 git2 的差异转换为结构化的逐文件差异
+fn collect_file_diffs(diff: &git2::Diff) -> Result<Vec<FileDiff>, GitError> {
+    let mut files = Vec::new();
+
+    for (index, delta) in diff.deltas().enumerate() {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let old_path = match delta.status() {
+            git2::Delta::Renamed | git2::Delta::Copied => delta
+                .old_file()
+                .path()
+                .map(|p| p.to_string_lossy().to_string()),
+            _ => None,
+        };
+
+        let mut file_diff = FileDiff {
+            path,
+            old_path,
+            status: convert_delta_status(delta.status()),
+            is_binary: delta.flags().is_binary(),
+            additions: 0,
+            deletions: 0,
+            hunks: Vec::new(),
+        };
+
+        // 二进制文件没有文本补丁
+        if let Some(patch) = git2::Patch::from_diff(diff, index).map_err(GitError::Git)? {
+            let (_, additions, deletions) = patch.line_stats().map_err(GitError::Git)?;
+            file_diff.additions = additions as u32;
+            file_diff.deletions = deletions as u32;
+            file_diff.is_binary = file_diff.is_binary || patch.delta().flags().is_binary();
+
+            for hunk_index in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(hunk_index).map_err(GitError::Git)?;
+                let mut lines = Vec::with_capacity(line_count);
+
+                for line_index in 0..line_count {
+                    let line = patch
+                        .line_in_hunk(hunk_index, line_index)
+                        .map_err(GitError::Git)?;
+                    lines.push(DiffLine {
+                        origin: line.origin(),
+                        content: String::from_utf8_lossy(line.content())
+                            .trim_end_matches(['\r', '\n'])
+                            .to_string(),
+                        old_lineno: line.old_lineno(),
+                        new_lineno: line.new_lineno(),
+                    });
+                }
+
+                file_diff.hunks.push(DiffHunk {
+                    header: String::from_utf8_lossy(hunk.header())
+                        .trim_end()
+                        .to_string(),
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                    lines,
+                });
+            }
+        }
+
+        files.push(file_diff);
     }
+
+    Ok(files)
 }
 
-/// 支持Token认证的fetch操作
-pub fn fetch_remote_with_token(
+/// 储藏当前变更
+///
+/// `include_untracked` 同时储藏未跟踪文件，`keep_index` 保留已暂存的变更；返回储藏提交的SHA
+pub fn stash_save(
     repo_path: &str,
-    remote_name: Option<&str>,
-    token_cache: Option<String>,
-) -> Result<SyncResult, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+    message: Option<&str>,
+    include_untracked: bool,
+    keep_index: bool,
+) -> Result<String, GitError> {
+    let mut repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let signature = resolve_signature(&repo, None)?;
+
+    let mut flags = git2::StashFlags::DEFAULT;
+    if include_untracked {
+        flags |= git2::StashFlags::INCLUDE_UNTRACKED;
+    }
+    if keep_index {
+        flags |= git2::StashFlags::KEEP_INDEX;
+    }
 
-    // 获取远程仓库名称
-    let remote_name = if let Some(name) = remote_name {
-        name.to_string()
-    } else {
-        // 如果没有指定远程名称，尝试获取默认远程
-        get_default_remote_name(&repo)?
-    };
+    match repo.stash_save2(&signature, message, Some(flags)) {
+        Ok(oid) => Ok(oid.to_string()),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Err(GitError::Unknown {
+            message: "没有可储藏的变更".to_string(),
+        }),
+        Err(e) => Err(index_error(&repo, e)),
+    }
+}
 
-    let mut remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
+/// 获取储藏列表
+pub fn stash_list(repo_path: &str) -> Result<Vec<StashEntry>, GitError> {
+    let mut repo = open_repository(repo_path).map_err(GitError::Git)?;
 
-    // 获取远程URL
-    let remote_url = remote.url().unwrap_or("").to_string();
-    log::debug!("Fetch操作使用远程URL: {}", remote_url);
+    let mut stashes = Vec::new();
+    repo.stash_foreach(|index, message, oid| {
+        stashes.push((index, message.to_string(), *oid));
+        true
+    })
+    .map_err(GitError::Git)?;
 
-    // 创建支持Token认证的回调
-    let callbacks = create_authenticated_callbacks(&remote_url, token_cache);
+    stashes
+        .into_iter()
+        .map(|(index, message, oid)| {
+            let commit = repo.find_commit(oid).map_err(GitError::Git)?;
+            Ok(StashEntry {
+                index,
+                branch: stash_branch_name(&message),
+                message,
+                sha: oid.to_string(),
+                timestamp: commit.time().seconds(),
+            })
+        })
+        .collect()
+}
 
-    // 设置fetch选项
-    let mut fetch_options = FetchOptions::new();
-    fetch_options.remote_callbacks(callbacks);
+/// 从储藏消息中解析创建时的分支名称
+///
+/// libgit2 生成的消息格式为 `WIP on <branch>: ...`（无自定义消息）或 `On <branch>: <message>`
+fn stash_branch_name(message: &str) -> Option<String> {
+    let rest = message
+        .strip_prefix("WIP on ")
+        .or_else(|| message.strip_prefix("On "))?;
+    let (branch, _) = rest.split_once(':')?;
+    Some(branch.to_string())
+}
 
-    // 执行fetch操作
-    let refspecs = remote.fetch_refspecs().map_err(GitError::Git)?;
-    let refspecs: Vec<&str> = refspecs.iter().flatten().collect();
+/// 查看储藏内容：与创建储藏时的基础提交比较，包含储藏的未跟踪文件
+pub fn stash_show(repo_path: &str, index: usize) -> Result<Vec<FileStatus>, GitError> {
+    let mut repo = open_repository(repo_path).map_err(GitError::Git)?;
 
-    match remote.fetch(&refspecs, Some(&mut fetch_options), None) {
-        Ok(()) => {
-            // 获取更新后的ahead/behind状态
-            let (ahead, behind) = get_ahead_behind_count(&repo)?;
+    let mut stash_oid = None;
+    repo.stash_foreach(|i, _, oid| {
+        if i == index {
+            stash_oid = Some(*oid);
+            return false;
+        }
+        true
+    })
+    .map_err(GitError::Git)?;
+    let stash_oid = stash_oid.ok_or_else(|| GitError::Unknown {
+        message: format!("储藏 stash@{{{}}} 不存在", index),
+    })?;
 
-            Ok(SyncResult {
-                success: true,
-                message: "成功获取远程变更".to_string(),
-                has_conflicts: false,
-                conflict_files: vec![],
-                ahead,
-                behind,
-            })
+    let stash_commit = repo.find_commit(stash_oid).map_err(GitError::Git)?;
+    let base_tree = stash_commit
+        .parent(0)
+        .and_then(|parent| parent.tree())
+        .map_err(GitError::Git)?;
+    let stash_tree = stash_commit.tree().map_err(GitError::Git)?;
+
+    let mut diff = repo
+        .diff_tree_to_tree(Some(&base_tree), Some(&stash_tree), None)
+        .map_err(GitError::Git)?;
+
+    // 第三个父提交保存了储藏的未跟踪文件
+    if let Ok(untracked) = stash_commit.parent(2) {
+        let untracked_tree = untracked.tree().map_err(GitError::Git)?;
+        let untracked_diff = repo
+            .diff_tree_to_tree(None, Some(&untracked_tree), None)
+            .map_err(GitError::Git)?;
+        diff.merge(&untracked_diff).map_err(GitError::Git)?;
+    }
+
+    let files = collect_file_diffs(&diff)?
+        .into_iter()
+        .map(|file| FileStatus {
+            path: file.path,
+            status: file.status,
+            staged: false,
+            additions: file.additions,
+            deletions: file.deletions,
+            ..Default::default()
+        })
+        .collect();
+
+    Ok(files)
+}
+
+/// 将差异类型转换为状态字符串
+fn convert_delta_status(status: git2::Delta) -> String {
+    match status {
+        git2::Delta::Added | git2::Delta::Untracked => "added",
+        git2::Delta::Deleted => "deleted",
+        git2::Delta::Renamed => "renamed",
+        git2::Delta::Copied => "copied",
+        git2::Delta::Typechange => "typechange",
+        git2::Delta::Conflicted => "conflicted",
+        _ => "modified",
+    }
+    .to_string()
+}
+
+/// 获取远程仓库信息
+pub fn get_remote_info(repo_path: &str) -> Result<RemoteBranchInfo, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    // 获取默认远程名称
+    let remote_name = get_default_remote_name(&repo)?;
+
+    // 获取当前分支
+    let head = repo.head().map_err(GitError::Git)?;
+    let branch_name = head.shorthand().unwrap_or("HEAD").to_string();
+
+    // 获取ahead/behind状态
+    let (ahead, behind) = get_ahead_behind_count(&repo)?;
+
+    // 获取远程默认分支
+    let default_branch = resolve_remote_default_branch(&repo, &remote_name);
+
+    Ok(RemoteBranchInfo {
+        remote_name,
+        branch_name,
+        ahead,
+        behind,
+        default_branch,
+        last_sync: read_last_sync(&repo),
+    })
+}
+
+/// 从仓库URL中提取域名（小写），支持 `scheme://` 和 SCP 风格的 `[user@]host:path` 形式
+///
+/// 本地路径（包括 Windows 盘符路径）返回 None
+pub fn extract_domain(url: &str) -> Option<String> {
+    let url = url.trim();
+    if url.contains("://") {
+        return url::Url::parse(url)
+            .ok()
+            .and_then(|parsed_url| parsed_url.host_str().map(|host| host.to_lowercase()))
+            .filter(|host| !host.is_empty());
+    }
+
+    split_scp_url(url).map(|(_, host, _)| host.to_lowercase())
+}
+
+/// 拆分 SCP 风格的地址 `[user@]host:path`，返回 (用户名, 主机, 路径)
+///
+/// 用户名可以是任意值（如 `git`、`org-1`）；`scheme://` 形式和本地路径（包括 Windows 盘符路径）返回 None
+pub fn split_scp_url(url: &str) -> Option<(Option<&str>, &str, &str)> {
+    if url.contains("://") {
+        return None;
+    }
+
+    let (authority, path) = url.split_once(':')?;
+    let (user, host) = match authority.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, authority),
+    };
+    let is_drive_letter = host.len() == 1 && user.is_none();
+    if host.is_empty() || host.contains(['/', '\\']) || is_drive_letter {
+        return None;
+    }
+    Some((user.filter(|user| !user.is_empty()), host, path))
+}
+
+/// 按远程URL的域名从已存储的Token中选择（`tokens` 以域名为键）
+///
+/// 只有 HTTP/HTTPS 远程使用Token，SSH 等其他协议返回 None
+pub fn select_token<'a, T>(
+    remote_url: &str,
+    tokens: &'a std::collections::HashMap<String, T>,
+) -> Option<&'a T> {
+    if !remote_url.starts_with("https://") && !remote_url.starts_with("http://") {
+        return None;
+    }
+    tokens.get(&extract_domain(remote_url)?)
+}
+
+/// 解析并校验 `ssh-keyscan` 的目标主机，支持 `host`、`host:port` 和 `[IPv6]:port`
+///
+/// 主机名只允许字母、数字、`.`、`-`、`_` 且不能以 `-` 开头（IPv6 地址需放在方括号中），
+/// 避免被当作命令行选项
+pub fn parse_host_key_target(host: &str) -> Result<(String, Option<u16>), GitError> {
+    let invalid = || GitError::Unknown {
+        message: format!("无效的主机名: {}", host),
+    };
+    let host = host.trim();
+
+    let (hostname, port) = if let Some(rest) = host.strip_prefix('[') {
+        let (address, rest) = rest.split_once(']').ok_or_else(invalid)?;
+        let port = match rest {
+            "" => None,
+            _ => Some(rest.strip_prefix(':').ok_or_else(invalid)?),
+        };
+        if !address.contains(':') || !address.chars().all(|c| c.is_ascii_hexdigit() || c == ':') {
+            return Err(invalid());
+        }
+        (address, port)
+    } else {
+        let (hostname, port) = match host.split_once(':') {
+            Some((name, port)) => (name, Some(port)),
+            None => (host, None),
+        };
+        let valid_hostname = !hostname.is_empty()
+            && !hostname.starts_with('-')
+            && hostname
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+        if !valid_hostname {
+            return Err(invalid());
+        }
+        (hostname, port)
+    };
+
+    let port = match port {
+        Some(port) => Some(port.parse::<u16>().map_err(|_| invalid())?),
+        None => None,
+    };
+
+    Ok((hostname.to_string(), port))
+}
+
+/// 解析仓库URL，识别协议、主机以及所有者和仓库名
+///
+/// 所有者可以包含多级命名空间（GitLab 子群组）；无法识别的URL返回 `valid: false`
+pub fn parse_repository_url(url: &str) -> ParsedRepoUrl {
+    let url = url.trim();
+    let (protocol, path) =
+        if url.starts_with("https://") || url.starts_with("http://") || url.starts_with("ssh://") {
+            match url::Url::parse(url) {
+                Ok(parsed_url) => (
+                    Some(parsed_url.scheme().to_string()),
+                    Some(parsed_url.path().to_string()),
+                ),
+                Err(_) => (None, None),
+            }
+        } else if let Some((_, _, path)) = split_scp_url(url) {
+            (Some("ssh".to_string()), Some(path.to_string()))
+        } else {
+            (None, None)
+        };
+
+    let host = protocol
+        .as_ref()
+        .and_then(|_| extract_domain(url))
+        .filter(|host| !host.is_empty());
+
+    let (owner, repo) = match path {
+        Some(path) => {
+            let path = path.trim_matches('/');
+            let path = path
+                .strip_suffix(".git")
+                .unwrap_or(path)
+                .trim_end_matches('/');
+            match path.rsplit_once('/') {
+                Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() => {
+                    (Some(owner.to_string()), Some(repo.to_string()))
+                }
+                _ => (None, None),
+            }
         }
+        None => (None, None),
+    };
+
+    ParsedRepoUrl {
+        valid: host.is_some() && repo.is_some(),
+        protocol,
+        host,
+        owner,
+        suggested_directory: repo.clone(),
+        repo,
+    }
+}
+
+/// 获取指定远程的URL，未指定远程时使用默认远程；远程没有URL时返回 None
+pub fn get_remote_url(
+    repo_path: &str,
+    remote_name: Option<&str>,
+) -> Result<Option<String>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let remote_name = match remote_name {
+        Some(name) => name.to_string(),
+        None => get_default_remote_name(&repo)?,
+    };
+    let remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
+    Ok(remote.url().map(String::from))
+}
+
+/// 记录最后同步时间的文件名（位于 .git 目录下）
+const LAST_SYNC_FILE: &str = "github-heart-last-sync";
+
+/// 串行化最后同步时间的写入，避免并发操作互相覆盖
+static LAST_SYNC_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// 读取仓库最后一次成功同步（fetch/push）的时间戳（秒）
+fn read_last_sync(repo: &Repository) -> Option<i64> {
+    std::fs::read_to_string(repo.path().join(LAST_SYNC_FILE))
+        .ok()
+        .and_then(|content| content.trim().parse::<i64>().ok())
+}
+
+/// 记录仓库最后一次成功同步的时间
+///
+/// 先写入临时文件再原子重命名，保证读取方不会看到写了一半的内容；
+/// 记录失败只输出警告，不影响同步操作本身的结果
+pub fn record_last_sync(repo_path: &str) {
+    let repo = match open_repository(repo_path) {
+        Ok(repo) => repo,
         Err(e) => {
-            log::error!("Fetch操作失败: {}", e);
-            Err(GitError::Git(e))
+            log::warn!("记录最后同步时间失败: {}", e);
+            return;
         }
+    };
+
+    let _guard = LAST_SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    // 时间戳只前进不后退
+    let now = chrono::Utc::now().timestamp();
+    let timestamp = read_last_sync(&repo).map_or(now, |previous| previous.max(now));
+
+    let target = repo.path().join(LAST_SYNC_FILE);
+    let temp = repo
+        .path()
+        .join(format!("{}.{}.tmp", LAST_SYNC_FILE, uuid::Uuid::new_v4()));
+
+    let result =
+        std::fs::write(&temp, timestamp.to_string()).and_then(|_| std::fs::rename(&temp, &target));
+
+    if let Err(e) = result {
+        log::warn!("记录最后同步时间失败: {}", e);
+        let _ = std::fs::remove_file(&temp);
     }
 }
 
-/// 支持Token认证的push操作
-pub fn push_remote_with_token(
+/// 解析远程默认分支名称
+///
+/// 只读取本地的 `refs/remotes/<remote>/HEAD` 符号引用，不访问网络；
+/// 该引用不存在时返回 None，可通过 `refresh_remote_default_branch` 查询远程并写入
+fn resolve_remote_default_branch(repo: &Repository, remote_name: &str) -> Option<String> {
+    let prefix = format!("refs/remotes/{}/", remote_name);
+    let reference = repo.find_reference(&format!("{}HEAD", prefix)).ok()?;
+    reference
+        .symbolic_target()
+        .and_then(|target| target.strip_prefix(&prefix))
+        .map(|name| name.to_string())
+}
+
+/// 连接远程查询其默认分支（相当于 `git ls-remote --symref`），并写入本地的
+/// `refs/remotes/<remote>/HEAD` 供之后离线读取；远程没有默认分支时返回 None
+pub fn refresh_remote_default_branch(
     repo_path: &str,
     remote_name: Option<&str>,
-    force: bool,
     token_cache: Option<String>,
+) -> Result<Option<String>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let remote_name = match remote_name {
+        Some(name) => name.to_string(),
+        None => get_default_remote_name(&repo)?,
+    };
+
+    let mut remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
+    let remote_url = remote.url().unwrap_or("").to_string();
+    let attempts = AuthAttempts::new(DEFAULT_MAX_AUTH_ATTEMPTS);
+    let callbacks = create_authenticated_callbacks(&remote_url, token_cache, None, &attempts);
+    let default_branch = {
+        let connection = remote
+            .connect_auth(git2::Direction::Fetch, Some(callbacks), None)
+            .map_err(|e| attempts.to_error(e))?;
+        connection.default_branch().ok().and_then(|name| {
+            name.as_str()
+                .and_then(|name| name.strip_prefix("refs/heads/"))
+                .map(|name| name.to_string())
+        })
+    };
+
+    if let Some(branch) = &default_branch {
+        repo.reference_symbolic(
+            &format!("refs/remotes/{}/HEAD", remote_name),
+            &format!("refs/remotes/{}/{}", remote_name, branch),
+            true,
+            "refresh remote default branch",
+        )
+        .map_err(GitError::Git)?;
+    }
+
+    Ok(default_branch)
+}
+
+/// 获取当前分支的上游跟踪状态
+///
+/// 当前分支未设置上游时返回 `GitError::NoUpstream`
+pub fn get_tracking_status(repo_path: &str) -> Result<TrackingStatus, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    let head = repo.head().map_err(GitError::Git)?;
+    if !head.is_branch() {
+        return Err(GitError::Unknown {
+            message: "当前处于分离 HEAD 状态，没有跟踪分支".to_string(),
+        });
+    }
+    let branch_name = head.shorthand().unwrap_or("HEAD").to_string();
+
+    let upstream_name = repo
+        .branch_upstream_name(&format!("refs/heads/{}", branch_name))
+        .map_err(|e| upstream_error(e, &branch_name))?;
+    let upstream_name = upstream_name.as_str().unwrap_or_default().to_string();
+
+    let local_oid = head.target().ok_or_else(|| GitError::Unknown {
+        message: "无法获取本地分支OID".to_string(),
+    })?;
+    let upstream_oid = repo
+        .refname_to_id(&upstream_name)
+        .map_err(|e| upstream_error(e, &branch_name))?;
+
+    let (ahead, behind) = repo
+        .graph_ahead_behind(local_oid, upstream_oid)
+        .map_err(GitError::Git)?;
+
+    Ok(TrackingStatus {
+        upstream: upstream_name
+            .strip_prefix("refs/remotes/")
+            .unwrap_or(&upstream_name)
+            .to_string(),
+        branch: branch_name,
+        ahead: ahead as u32,
+        behind: behind as u32,
+    })
+}
+
+/// 将查找上游分支时的“未找到”错误转换为 `GitError::NoUpstream`
+fn upstream_error(error: git2::Error, branch: &str) -> GitError {
+    if error.code() == git2::ErrorCode::NotFound {
+        GitError::NoUpstream {
+            branch: branch.to_string(),
+        }
+    } else {
+        GitError::Git(error)
+    }
+}
+
+/// 获取远程变更（fetch操作）
+///
+/// `prune` 为 true 时会同时删除远程已不存在的远程跟踪分支
+pub fn fetch_remote(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    prune: bool,
+    refspecs: Option<&[String]>,
 ) -> Result<SyncResult, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
 
     // 获取远程仓库名称
     let remote_name = if let Some(name) = remote_name {
@@ -1278,384 +2948,6775 @@ pub fn push_remote_with_token(
 
     let mut remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
 
-    // 获取当前分支
-    let head = repo.head().map_err(GitError::Git)?;
-    let branch_name = head.shorthand().unwrap_or("HEAD");
-
-    // 获取远程URL
-    let remote_url = remote.url().unwrap_or("").to_string();
-    log::debug!("Push操作使用远程URL: {}", remote_url);
-
-    // 创建支持Token认证的回调
-    let callbacks = create_authenticated_callbacks(&remote_url, token_cache);
+    // 设置回调函数
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(|_url, _username_from_url, _allowed_types| {
+        // 这里可以集成现有的认证系统
+        // 暂时使用默认凭据
+        git2::Cred::default()
+    });
 
-    // 设置push选项
-    let mut push_options = PushOptions::new();
-    push_options.remote_callbacks(callbacks);
+    // 设置fetch选项
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+    if prune {
+        fetch_options.prune(FetchPrune::On);
+    }
 
-    // 构建refspec
-    let refspec = if force {
-        format!("+refs/heads/{}:refs/heads/{}", branch_name, branch_name)
-    } else {
-        format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name)
-    };
+    // 执行fetch操作
+    let refspecs = resolve_fetch_refspecs(&remote, refspecs)?;
 
-    // 执行push操作
-    match remote.push(&[&refspec], Some(&mut push_options)) {
+    match remote.fetch(&refspecs, Some(&mut fetch_options), None) {
         Ok(()) => {
+            record_last_sync(repo_path);
+
             // 获取更新后的ahead/behind状态
-            let (ahead, behind) = get_ahead_behind_count(&repo)?;
+            let (ahead, behind) = fetched_ahead_behind_count(&repo, &remote_name)?;
 
             Ok(SyncResult {
                 success: true,
-                message: "成功推送到远程仓库".to_string(),
-                has_conflicts: false,
-                conflict_files: vec![],
+                message: "成功获取远程变更".to_string(),
                 ahead,
                 behind,
+                ..Default::default()
             })
         }
-        Err(e) => {
-            log::error!("Push操作失败: {}", e);
-            Err(GitError::Git(e))
-        }
+        Err(e) => Err(GitError::Git(e)),
     }
 }
 
-/// 创建新分支
-pub fn create_branch(
-    repo_path: &str,
-    branch_name: &str,
-    from_commit: Option<&str>,
-    checkout: bool,
-) -> Result<SwitchResult, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+/// 确定 fetch 使用的 refspec：指定了非空的自定义 refspec 时覆盖远程配置的默认值
+fn resolve_fetch_refspecs(
+    remote: &git2::Remote,
+    custom: Option<&[String]>,
+) -> Result<Vec<String>, GitError> {
+    match custom {
+        Some(refspecs) if !refspecs.is_empty() => Ok(refspecs.to_vec()),
+        _ => {
+            let refspecs = remote.fetch_refspecs().map_err(GitError::Git)?;
+            Ok(refspecs.iter().flatten().map(|s| s.to_string()).collect())
+        }
+    }
+}
 
-    log::debug!(
-        "创建分支: {} (from: {:?}, checkout: {})",
-        branch_name,
-        from_commit,
-        checkout
-    );
+/// 清理远程已删除分支对应的远程跟踪分支
+pub fn prune_remote(repo_path: &str, remote_name: Option<&str>) -> Result<SyncResult, GitError> {
+    let mut result = fetch_remote(repo_path, remote_name, true, None)?;
+    result.message = "成功清理过期的远程跟踪分支".to_string();
+    Ok(result)
+}
 
-    // 检查分支名称是否已存在
-    if let Ok(_) = repo.find_branch(branch_name, git2::BranchType::Local) {
-        return Err(GitError::Unknown {
-            message: format!("分支 '{}' 已存在", branch_name),
-        });
-    }
+/// 补全浅克隆历史的超时时间
+pub const UNSHALLOW_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
 
-    // 获取目标提交
-    let target_commit = if let Some(commit_ref) = from_commit {
-        log::debug!("从指定引用创建分支: {}", commit_ref);
+/// 将浅克隆仓库补全为完整历史（`git fetch --unshallow`）
+///
+/// libgit2 不支持取消浅克隆，因此通过系统Git执行；`ssh_command` 会作为 GIT_SSH_COMMAND 传入。
+/// 子进程登记在 `processes` 中，提供 `operation_id` 时可中途取消，超过 `timeout` 时终止。
+/// 仓库不是浅克隆时不执行任何操作
+pub async fn unshallow(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    ssh_command: Option<&str>,
+    processes: &crate::utils::git_process::GitProcesses,
+    operation_id: Option<&str>,
+    timeout: std::time::Duration,
+) -> Result<SyncResult, GitError> {
+    // Repository 不能跨 await 持有，运行子进程前释放
+    let remote_name = {
+        let repo = open_repository(repo_path).map_err(GitError::Git)?;
 
-        // 首先尝试作为分支名称解析
-        if let Ok(branch) = repo.find_branch(commit_ref, git2::BranchType::Local) {
-            let branch_ref = branch.get();
-            let oid = branch_ref.target().ok_or_else(|| GitError::Unknown {
-                message: format!("无法获取分支 '{}' 的目标", commit_ref),
-            })?;
-            repo.find_commit(oid).map_err(GitError::Git)?
+        if !repo.path().join("shallow").exists() {
+            let (ahead, behind) = get_ahead_behind_count(&repo)?;
+            return Ok(SyncResult {
+                success: true,
+                message: "仓库已包含完整历史，无需补全".to_string(),
+                ahead,
+                behind,
+                ..Default::default()
+            });
         }
-        // 然后尝试作为提交SHA解析
-        else if let Ok(oid) = git2::Oid::from_str(commit_ref) {
-            repo.find_commit(oid).map_err(GitError::Git)?
+
+        match remote_name {
+            Some(name) => name.to_string(),
+            None => get_default_remote_name(&repo)?,
         }
-        // 最后尝试作为引用名称解析
-        else if let Ok(reference) = repo.find_reference(commit_ref) {
-            let oid = reference.target().ok_or_else(|| GitError::Unknown {
-                message: format!("无法获取引用 '{}' 的目标", commit_ref),
-            })?;
-            repo.find_commit(oid).map_err(GitError::Git)?
+    };
+
+    let mut cmd = crate::utils::system_command::create_hidden_command_async("git");
+    cmd.arg("fetch")
+        .arg("--unshallow")
+        .arg(&remote_name)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .current_dir(repo_path);
+    if let Some(ssh_command) = ssh_command {
+        cmd.env("GIT_SSH_COMMAND", ssh_command);
+    }
+
+    let output = match processes.run(operation_id, cmd, timeout).await {
+        Ok(crate::utils::git_process::GitProcessOutcome::Finished(output)) => output,
+        Ok(crate::utils::git_process::GitProcessOutcome::Cancelled) => {
+            log::info!("补全浅克隆历史已取消");
+            return Err(GitError::OperationCancelled);
         }
-        // 尝试作为完整的引用路径解析
-        else if let Ok(reference) = repo.find_reference(&format!("refs/heads/{}", commit_ref)) {
-            let oid = reference.target().ok_or_else(|| GitError::Unknown {
-                message: format!("无法获取引用 'refs/heads/{}' 的目标", commit_ref),
-            })?;
-            repo.find_commit(oid).map_err(GitError::Git)?
-        } else {
-            return Err(GitError::Unknown {
-                message: format!("无法找到分支、提交或引用: '{}'", commit_ref),
+        Ok(crate::utils::git_process::GitProcessOutcome::TimedOut) => {
+            log::error!("补全浅克隆历史超时 ({}秒)", timeout.as_secs());
+            return Err(GitError::SystemGitFailed {
+                message: "补全浅克隆历史超时，可能是网络问题或仓库过大".to_string(),
             });
         }
-    } else {
-        // 从当前HEAD创建
-        log::debug!("从当前HEAD创建分支");
-        let head = repo.head().map_err(GitError::Git)?;
-        let oid = head.target().ok_or_else(|| GitError::Unknown {
-            message: "无法获取HEAD目标".to_string(),
-        })?;
-        repo.find_commit(oid).map_err(GitError::Git)?
+        Err(e) => {
+            log::error!("执行git命令失败: {}", e);
+            return Err(e);
+        }
     };
 
-    // 创建分支
-    let _branch = repo
-        .branch(branch_name, &target_commit, false)
-        .map_err(GitError::Git)?;
-    log::debug!("分支创建成功: {}", branch_name);
-
-    // 如果需要切换到新分支
-    if checkout {
-        return switch_branch(repo_path, branch_name);
+    if !output.status.success() {
+        return Err(GitError::SystemGitFailed {
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
     }
 
-    Ok(SwitchResult {
+    record_last_sync(repo_path);
+
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let (ahead, behind) = get_ahead_behind_count(&repo)?;
+    Ok(SyncResult {
         success: true,
-        message: format!("成功创建分支 '{}'", branch_name),
-        has_uncommitted_changes: false,
-        uncommitted_files: vec![],
+        message: "成功获取完整历史".to_string(),
+        ahead,
+        behind,
+        ..Default::default()
     })
 }
 
-/// 切换分支
-pub fn switch_branch(repo_path: &str, branch_name: &str) -> Result<SwitchResult, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+/// 拉取远程变更（pull操作）
+///
+/// `identity` 为合并提交或变基时使用的身份（name, email），None 时使用仓库配置的身份
+pub fn pull_remote(
+    repo_path: &str,
+    strategy: PullStrategy,
+    identity: Option<(&str, &str)>,
+) -> Result<SyncResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
 
-    log::debug!("切换分支: {}", branch_name);
+    // 首先执行fetch
+    let fetch_result = fetch_remote(repo_path, None, false, None)?;
+    if !fetch_result.success {
+        return Ok(fetch_result);
+    }
 
-    // 检查是否有未提交的变更
-    let status = get_repository_status(repo_path)?;
-    let has_uncommitted = !status.files.is_empty();
-    let uncommitted_files: Vec<String> = status.files.iter().map(|f| f.path.clone()).collect();
+    // 获取当前分支
+    let head = repo.head().map_err(GitError::Git)?;
+    let branch_name = head.shorthand().unwrap_or("HEAD");
 
-    if has_uncommitted {
-        return Ok(SwitchResult {
-            success: false,
-            message: "存在未提交的变更，请先提交或暂存变更".to_string(),
-            has_uncommitted_changes: true,
-            uncommitted_files,
-        });
-    }
+    // 获取远程跟踪分支
+    let upstream_name = repo
+        .branch_upstream_name(&format!("refs/heads/{}", branch_name))
+        .map_err(|e| upstream_error(e, branch_name))?;
+    let upstream_ref = repo
+        .find_reference(upstream_name.as_str().unwrap())
+        .map_err(GitError::Git)?;
+    let upstream_commit = upstream_ref.peel_to_commit().map_err(GitError::Git)?;
 
-    // 查找目标分支
-    let branch = repo
-        .find_branch(branch_name, git2::BranchType::Local)
-        .map_err(|_| GitError::Unknown {
-            message: format!("分支 '{}' 不存在", branch_name),
-        })?;
+    // 获取当前提交
+    let local_commit = head.peel_to_commit().map_err(GitError::Git)?;
 
-    // 获取分支的引用
-    let branch_ref = branch.get();
-    let branch_oid = branch_ref.target().ok_or_else(|| GitError::Unknown {
-        message: "无法获取分支目标".to_string(),
-    })?;
+    // 检查是否需要合并
+    if local_commit.id() == upstream_commit.id() {
+        return Ok(SyncResult {
+            success: true,
+            message: "已经是最新版本".to_string(),
+            ..Default::default()
+        });
+    }
 
-    // 设置HEAD到目标分支
-    let refname = format!("refs/heads/{}", branch_name);
-    repo.set_head(&refname).map_err(GitError::Git)?;
+    // 执行合并或变基
+    let mut result = match strategy {
+        PullStrategy::Merge => {
+            let merge_message = format!(
+                "Merge remote-tracking branch '{}'",
+                upstream_ref.shorthand().unwrap_or("upstream")
+            );
+            perform_merge(
+                &repo,
+                &local_commit,
+                &upstream_commit,
+                &merge_message,
+                identity,
+            )?
+        }
+        PullStrategy::Rebase => perform_rebase(&repo, &local_commit, &upstream_commit, identity)?,
+    };
 
-    // 检出工作目录
-    let commit = repo.find_commit(branch_oid).map_err(GitError::Git)?;
-    let tree = commit.tree().map_err(GitError::Git)?;
+    if result.success {
+        apply_pull_diff_stats(&repo, local_commit.id(), &mut result);
+    }
 
-    repo.checkout_tree(
-        tree.as_object(),
-        Some(
-            git2::build::CheckoutBuilder::new()
-                .safe()
-                .recreate_missing(true),
-        ),
-    )
-    .map_err(GitError::Git)?;
+    Ok(result)
+}
 
-    log::debug!("分支切换成功: {}", branch_name);
+/// 将拉取前后HEAD之间的差异统计写入同步结果
+///
+/// HEAD 未发生变化或统计失败时保持字段为 None
+pub fn apply_pull_diff_stats(repo: &Repository, old_head: Oid, result: &mut SyncResult) {
+    let new_head = match repo.head().ok().and_then(|head| head.target()) {
+        Some(oid) if oid != old_head => oid,
+        _ => return,
+    };
 
-    Ok(SwitchResult {
-        success: true,
-        message: format!("成功切换到分支 '{}'", branch_name),
-        has_uncommitted_changes: false,
-        uncommitted_files: vec![],
-    })
+    let stats = (|| -> Result<git2::DiffStats, git2::Error> {
+        let old_tree = repo.find_commit(old_head)?.tree()?;
+        let new_tree = repo.find_commit(new_head)?.tree()?;
+        repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?
+            .stats()
+    })();
+
+    match stats {
+        Ok(stats) => {
+            result.files_changed = Some(stats.files_changed() as u32);
+            result.insertions = Some(stats.insertions() as u32);
+            result.deletions = Some(stats.deletions() as u32);
+        }
+        Err(e) => log::warn!("统计拉取变更失败: {}", e),
+    }
 }
 
-/// 删除分支
-pub fn delete_branch(
-    repo_path: &str,
-    branch_name: &str,
-    force: bool,
-) -> Result<SwitchResult, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
+/// 系统 git pull 的结果类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemPullOutcome {
+    /// 已是最新
+    UpToDate,
+    /// 快进更新
+    FastForward,
+    /// 生成了合并提交
+    Merged,
+    /// 变基完成
+    Rebased,
+    /// 出现冲突
+    Conflict,
+    /// 本地与远程已分叉，需要选择合并或变基
+    Diverged,
+    /// 其他失败
+    Failed,
+}
 
-    log::debug!("删除分支: {} (force: {})", branch_name, force);
+/// 从 git pull 输出中解析出的摘要
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemPullSummary {
+    pub outcome: SystemPullOutcome,
+    pub files_changed: Option<u32>,
+    pub insertions: Option<u32>,
+    pub deletions: Option<u32>,
+}
 
-    // 检查是否是当前分支
-    if let Ok(current_name) = get_current_branch_name(&repo) {
-        if current_name.as_ref() == Some(&branch_name.to_string()) {
-            return Err(GitError::Unknown {
-                message: "不能删除当前分支".to_string(),
-            });
+impl SystemPullSummary {
+    /// 面向用户的结果描述
+    pub fn message(&self) -> String {
+        match self.outcome {
+            SystemPullOutcome::UpToDate => "已是最新，没有需要拉取的变更".to_string(),
+            SystemPullOutcome::FastForward => match self.files_changed {
+                Some(files) => format!("已快进到远程最新提交，{} 个文件变更", files),
+                None => "已快进到远程最新提交".to_string(),
+            },
+            SystemPullOutcome::Merged => match self.files_changed {
+                Some(files) => format!("已合并远程变更，{} 个文件变更", files),
+                None => "已合并远程变更".to_string(),
+            },
+            SystemPullOutcome::Rebased => "已将本地提交变基到远程变更之上".to_string(),
+            SystemPullOutcome::Conflict => "拉取时发现冲突".to_string(),
+            SystemPullOutcome::Diverged => {
+                "本地与远程分支已分叉，请选择合并或变基方式拉取".to_string()
+            }
+            SystemPullOutcome::Failed => "拉取失败".to_string(),
         }
     }
+}
 
-    // 查找分支
-    let mut branch = repo
-        .find_branch(branch_name, git2::BranchType::Local)
-        .map_err(|_| GitError::Unknown {
-            message: format!("分支 '{}' 不存在", branch_name),
-        })?;
+/// 运行系统 git 时固定的语言环境变量
+///
+/// `parse_system_pull_output` 按英文输出匹配，本地化（如 zh_CN）输出会被误判
+pub const SYSTEM_GIT_LOCALE_ENV: [(&str, &str); 2] = [("LC_ALL", "C"), ("LANGUAGE", "C")];
+
+/// 解析系统 git pull 的标准输出和标准错误
+///
+/// 调用方需通过 `SYSTEM_GIT_LOCALE_ENV` 让 git 输出英文
+pub fn parse_system_pull_output(success: bool, stdout: &str, stderr: &str) -> SystemPullSummary {
+    let combined = format!("{}\n{}", stdout, stderr);
+
+    let outcome = if combined.contains("CONFLICT") || combined.contains("Merge conflict") {
+        SystemPullOutcome::Conflict
+    } else if combined.contains("divergent branches")
+        || combined.contains("Not possible to fast-forward")
+    {
+        SystemPullOutcome::Diverged
+    } else if !success {
+        SystemPullOutcome::Failed
+    } else if combined.contains("Already up to date") || combined.contains("Already up-to-date") {
+        SystemPullOutcome::UpToDate
+    } else if combined.contains("Fast-forward") {
+        SystemPullOutcome::FastForward
+    } else if combined.contains("Successfully rebased") {
+        SystemPullOutcome::Rebased
+    } else {
+        SystemPullOutcome::Merged
+    };
 
-    // 如果不是强制删除，检查分支是否已合并
-    if !force {
-        // 检查分支是否有未合并的提交
-        if let Ok(upstream) = branch.upstream() {
-            let branch_oid = branch.get().target().unwrap();
-            let upstream_oid = upstream.get().target().unwrap();
+    let mut summary = SystemPullSummary {
+        outcome,
+        files_changed: None,
+        insertions: None,
+        deletions: None,
+    };
 
-            if let Ok((ahead, _)) = repo.graph_ahead_behind(branch_oid, upstream_oid) {
-                if ahead > 0 {
-                    return Err(GitError::Unknown {
-                        message: format!(
-                            "分支 '{}' 包含未合并的提交，使用强制删除或先合并分支",
-                            branch_name
-                        ),
-                    });
+    // 统计行形如 " 2 files changed, 11 insertions(+), 1 deletion(-)"
+    if let Some(line) = stdout
+        .lines()
+        .rev()
+        .find(|line| line.contains(" changed") && line.contains("file"))
+    {
+        for part in line.split(',') {
+            let mut words = part.split_whitespace();
+            let count = words.next().and_then(|count| count.parse::<u32>().ok());
+            match (count, words.next()) {
+                (Some(count), Some(word)) if word.starts_with("file") => {
+                    summary.files_changed = Some(count)
+                }
+                (Some(count), Some(word)) if word.starts_with("insertion") => {
+                    summary.insertions = Some(count)
                 }
+                (Some(count), Some(word)) if word.starts_with("deletion") => {
+                    summary.deletions = Some(count)
+                }
+                _ => {}
             }
         }
+        if summary.files_changed.is_some() {
+            summary.insertions.get_or_insert(0);
+            summary.deletions.get_or_insert(0);
+        }
     }
 
-    // 删除分支
-    branch.delete().map_err(GitError::Git)?;
-    log::debug!("分支删除成功: {}", branch_name);
-
-    Ok(SwitchResult {
-        success: true,
-        message: format!("成功删除分支 '{}'", branch_name),
-        has_uncommitted_changes: false,
-        uncommitted_files: vec![],
-    })
+    summary
 }
 
-/// 从远程分支检出本地分支
-pub fn checkout_remote_branch(
+/// 推送本地变更（push操作）
+pub fn push_remote(
     repo_path: &str,
-    remote_branch_name: &str,
-    local_branch_name: Option<&str>,
-) -> Result<SwitchResult, GitError> {
-    let repo = Repository::open(repo_path).map_err(GitError::Git)?;
-
-    log::debug!(
-        "检出远程分支: {} -> {:?}",
-        remote_branch_name,
-        local_branch_name
-    );
+    remote_name: Option<&str>,
+    force: bool,
+) -> Result<SyncResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
 
-    // 提取本地分支名称
-    let local_name = if let Some(name) = local_branch_name {
+    // 获取远程仓库名称
+    let remote_name = if let Some(name) = remote_name {
         name.to_string()
     } else {
-        extract_local_branch_name(remote_branch_name)
+        // 如果没有指定远程名称，尝试获取默认远程
+        get_default_remote_name(&repo)?
     };
 
-    log::debug!("本地分支名称: {}", local_name);
+    let mut remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
 
-    // 检查本地分支是否已存在
-    if let Ok(_) = repo.find_branch(&local_name, git2::BranchType::Local) {
-        return Err(GitError::Unknown {
-            message: format!("本地分支 '{}' 已存在", local_name),
-        });
-    }
+    // 获取当前分支
+    let head = repo.head().map_err(GitError::Git)?;
+    let branch_name = head.shorthand().unwrap_or("HEAD");
 
-    // 查找远程分支
-    let remote_branch = repo
-        .find_branch(remote_branch_name, git2::BranchType::Remote)
-        .map_err(|_| GitError::Unknown {
-            message: format!("远程分支 '{}' 不存在", remote_branch_name),
-        })?;
+    // 记录服务器对引用更新的拒绝信息
+    let rejection: std::cell::RefCell<Option<String>> = std::cell::RefCell::new(None);
 
-    // 获取远程分支的提交
-    let remote_ref = remote_branch.get();
-    let remote_oid = remote_ref.target().ok_or_else(|| GitError::Unknown {
-        message: format!("无法获取远程分支 '{}' 的目标", remote_branch_name),
-    })?;
-    let remote_commit = repo.find_commit(remote_oid).map_err(GitError::Git)?;
+    // 设置回调函数
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(|_url, _username_from_url, _allowed_types| {
+        // 这里可以集成现有的认证系统
+        git2::Cred::default()
+    });
+    callbacks.push_update_reference(|refname, status| {
+        record_push_rejection(&rejection, refname, status)
+    });
 
-    // 创建本地分支
-    let local_branch = repo
-        .branch(&local_name, &remote_commit, false)
-        .map_err(GitError::Git)?;
-    log::debug!("本地分支创建成功: {}", local_name);
+    // 设置push选项
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(callbacks);
 
-    // 设置上游跟踪分支
-    if let Err(e) = set_upstream_branch(&repo, &local_name, remote_branch_name) {
-        log::warn!("设置上游分支失败: {}", e);
-        // 不影响主要功能，继续执行
-    }
+    // 构建refspec
+    let refspec = if force {
+        format!("+refs/heads/{}:refs/heads/{}", branch_name, branch_name)
+    } else {
+        format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name)
+    };
 
-    // 切换到新创建的本地分支
-    let switch_result = switch_branch(repo_path, &local_name)?;
+    // 执行push操作
+    let pushed = remote.push(&[&refspec], Some(&mut push_options));
+    let rejection = rejection.borrow_mut().take();
+    match (pushed, rejection) {
+        (Ok(()), None) => {
+            record_last_sync(repo_path);
 
-    if switch_result.success {
-        Ok(SwitchResult {
-            success: true,
-            message: format!(
-                "成功检出远程分支 '{}' 到本地分支 '{}'",
-                remote_branch_name, local_name
-            ),
-            has_uncommitted_changes: false,
-            uncommitted_files: vec![],
-        })
-    } else {
-        // 如果切换失败，删除刚创建的分支
-        let mut branch_to_delete = local_branch;
-        let _ = branch_to_delete.delete();
-        Err(GitError::Unknown {
-            message: format!("检出成功但切换失败: {}", switch_result.message),
-        })
+            // 获取更新后的ahead/behind状态
+            let (ahead, behind) = get_ahead_behind_count(&repo)?;
+
+            Ok(SyncResult {
+                success: true,
+                message: "成功推送到远程仓库".to_string(),
+                ahead,
+                behind,
+                ..Default::default()
+            })
+        }
+        (Ok(()), Some(message)) => Err(push_rejection_error(branch_name, &message)),
+        (Err(e), _) if is_non_fast_forward_error(&e) => Err(GitError::NonFastForward {
+            branch: branch_name.to_string(),
+        }),
+        (Err(e), _) => Err(GitError::Git(e)),
     }
 }
 
-/// 从远程分支名称提取本地分支名称
-fn extract_local_branch_name(remote_branch_name: &str) -> String {
-    // "origin/feature/new-ui" -> "feature/new-ui"
-    if let Some(pos) = remote_branch_name.find('/') {
-        remote_branch_name[pos + 1..].to_string()
-    } else {
-        remote_branch_name.to_string()
+/// 记录服务器拒绝的引用更新（push_update_reference 回调）
+fn record_push_rejection(
+    rejection: &std::cell::RefCell<Option<String>>,
+    refname: &str,
+    status: Option<&str>,
+) -> Result<(), git2::Error> {
+    if let Some(message) = status {
+        log::error!("远程拒绝更新引用 {}: {}", refname, message);
+        *rejection.borrow_mut() = Some(message.to_string());
     }
+    Ok(())
 }
 
-/// 设置上游跟踪分支
-fn set_upstream_branch(
-    repo: &Repository,
-    local_branch: &str,
-    remote_branch: &str,
-) -> Result<(), GitError> {
-    log::debug!("设置上游分支: {} -> {}", local_branch, remote_branch);
+/// 判断拒绝原因是否为非快进更新（远程分支包含本地没有的提交）
+fn is_non_fast_forward_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "non-fast-forward",
+        "non-fastforward",
+        "not fast-forward",
+        "fetch first",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
 
-    // 解析远程名称和分支名称
-    let (remote_name, branch_name) = if let Some(pos) = remote_branch.find('/') {
-        let remote = &remote_branch[..pos];
-        let branch = &remote_branch[pos + 1..];
-        (remote, branch)
-    } else {
-        return Err(GitError::Unknown {
-            message: "无效的远程分支名称格式".to_string(),
-        });
+fn is_non_fast_forward_error(error: &git2::Error) -> bool {
+    error.code() == git2::ErrorCode::NotFastForward || is_non_fast_forward_message(error.message())
+}
+
+/// 将服务器拒绝引用更新的原因转换为错误
+fn push_rejection_error(branch_name: &str, message: &str) -> GitError {
+    if is_non_fast_forward_message(message) {
+        GitError::NonFastForward {
+            branch: branch_name.to_string(),
+        }
+    } else {
+        GitError::Unknown {
+            message: format!("远程拒绝更新分支 '{}': {}", branch_name, message),
+        }
+    }
+}
+
+// 辅助函数
+
+fn is_staged(status: Status) -> bool {
+    status.contains(Status::INDEX_NEW)
+        || status.contains(Status::INDEX_MODIFIED)
+        || status.contains(Status::INDEX_DELETED)
+        || status.contains(Status::INDEX_RENAMED)
+        || status.contains(Status::INDEX_TYPECHANGE)
+}
+
+fn has_unstaged_changes(status: Status) -> bool {
+    status.contains(Status::WT_NEW)
+        || status.contains(Status::WT_MODIFIED)
+        || status.contains(Status::WT_DELETED)
+        || status.contains(Status::WT_RENAMED)
+        || status.contains(Status::WT_TYPECHANGE)
+}
+
+fn convert_git_status_staged(status: Status) -> String {
+    if status.contains(Status::INDEX_NEW) {
+        "added".to_string()
+    } else if status.contains(Status::INDEX_MODIFIED) {
+        "modified".to_string()
+    } else if status.contains(Status::INDEX_DELETED) {
+        "deleted".to_string()
+    } else if status.contains(Status::INDEX_RENAMED) {
+        "renamed".to_string()
+    } else {
+        "modified".to_string()
+    }
+}
+
+fn convert_git_status_unstaged(status: Status) -> String {
+    if status.contains(Status::WT_NEW) {
+        "added".to_string()
+    } else if status.contains(Status::WT_MODIFIED) {
+        "modified".to_string()
+    } else if status.contains(Status::WT_DELETED) {
+        "deleted".to_string()
+    } else if status.contains(Status::WT_RENAMED) {
+        "renamed".to_string()
+    } else {
+        "modified".to_string()
+    }
+}
+
+fn calculate_file_stats(
+    repo: &Repository,
+    file_path: &str,
+    staged: bool,
+) -> Result<(u32, u32), GitError> {
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options.pathspec(file_path);
+
+    let diff = if staged {
+        // 暂存区与HEAD的差异
+        let head = match repo.head() {
+            Ok(head) => head,
+            Err(_) => return Ok((0, 0)), // 如果没有HEAD，返回默认值
+        };
+
+        let head_tree = head.peel_to_tree().map_err(|_| GitError::Unknown {
+            message: "无法获取HEAD树".to_string(),
+        })?;
+
+        let index = repo.index().map_err(GitError::Git)?;
+
+        repo.diff_tree_to_index(Some(&head_tree), Some(&index), Some(&mut diff_options))
+            .map_err(GitError::Git)?
+    } else {
+        // 工作区与暂存区的差异
+        repo.diff_index_to_workdir(None, Some(&mut diff_options))
+            .map_err(GitError::Git)?
     };
 
-    // 设置配置
-    let mut config = repo.config().map_err(GitError::Git)?;
+    // 计算添加和删除的行数
+    let mut additions = 0;
+    let mut deletions = 0;
 
-    // 设置远程名称
-    config
-        .set_str(&format!("branch.{}.remote", local_branch), remote_name)
+    diff.foreach(
+        &mut |_, _| true,
+        None,
+        None,
+        Some(&mut |_, _hunk, line| {
+            match line.origin() {
+                '+' => additions += 1,
+                '-' => deletions += 1,
+                _ => {}
+            }
+            true
+        }),
+    )
+    .map_err(GitError::Git)?;
+
+    Ok((additions, deletions))
+}
+
+fn get_current_branch(repo: &Repository) -> Result<String, GitError> {
+    match repo.head() {
+        Ok(head) => {
+            if let Some(branch_name) = head.shorthand() {
+                Ok(branch_name.to_string())
+            } else {
+                Ok("HEAD".to_string())
+            }
+        }
+        Err(_) => Ok("main".to_string()),
+    }
+}
+
+fn get_ahead_behind_count(repo: &Repository) -> Result<(u32, u32), GitError> {
+    // 获取当前分支
+    let head = match repo.head() {
+        Ok(head) => head,
+        Err(_) => return Ok((0, 0)), // 如果没有HEAD，返回默认值
+    };
+
+    // 获取当前分支名
+    let branch_name = match head.shorthand() {
+        Some(name) => name,
+        None => return Ok((0, 0)), // 如果无法获取分支名，返回默认值
+    };
+
+    // 查找对应的远程跟踪分支
+    let upstream_name = match repo.branch_upstream_name(&format!("refs/heads/{}", branch_name)) {
+        Ok(name) => name,
+        Err(_) => return Ok((0, 0)), // 如果没有上游分支，返回默认值
+    };
+
+    // 获取本地和远程分支的OID
+    let local_oid = head.target().ok_or_else(|| GitError::Unknown {
+        message: "无法获取本地分支OID".to_string(),
+    })?;
+
+    let upstream_ref = repo
+        .find_reference(upstream_name.as_str().unwrap())
+        .map_err(|_| GitError::Unknown {
+            message: "无法找到远程跟踪分支".to_string(),
+        })?;
+
+    let upstream_oid = upstream_ref.target().ok_or_else(|| GitError::Unknown {
+        message: "无法获取远程分支OID".to_string(),
+    })?;
+
+    // 计算领先/落后数量
+    let (ahead, behind) = repo
+        .graph_ahead_behind(local_oid, upstream_oid)
         .map_err(GitError::Git)?;
 
-    // 设置合并引用
-    config
-        .set_str(
-            &format!("branch.{}.merge", local_branch),
-            &format!("refs/heads/{}", branch_name),
-        )
+    Ok((ahead as u32, behind as u32))
+}
+
+/// 获取远程变更后计算当前分支的领先/落后数量
+///
+/// 优先与指向该远程的上游分支比较；未配置上游（或上游属于其他远程）时与刚获取的
+/// `refs/remotes/<remote>/<当前分支>` 比较，未配置上游的分支也能反映远程更新；
+/// 两者都不存在时回退到上游配置
+fn fetched_ahead_behind_count(
+    repo: &Repository,
+    remote_name: &str,
+) -> Result<(u32, u32), GitError> {
+    let head = match repo.head() {
+        Ok(head) if head.is_branch() => head,
+        _ => return Ok((0, 0)),
+    };
+    let (Some(branch_name), Some(local_oid)) = (head.shorthand(), head.target()) else {
+        return Ok((0, 0));
+    };
+
+    let remote_prefix = format!("refs/remotes/{}/", remote_name);
+    let tracking_ref = repo
+        .branch_upstream_name(&format!("refs/heads/{}", branch_name))
+        .ok()
+        .and_then(|name| name.as_str().map(|name| name.to_string()))
+        .filter(|name| name.starts_with(&remote_prefix))
+        .unwrap_or_else(|| format!("{}{}", remote_prefix, branch_name));
+    match repo.refname_to_id(&tracking_ref) {
+        Ok(remote_oid) => {
+            let (ahead, behind) = repo
+                .graph_ahead_behind(local_oid, remote_oid)
+                .map_err(GitError::Git)?;
+            Ok((ahead as u32, behind as u32))
+        }
+        Err(_) => get_ahead_behind_count(repo),
+    }
+}
+
+/// 获取远程变更后当前分支相对该远程的领先/落后数量（规则同 fetch 结果）
+pub fn get_fetched_ahead_behind(
+    repo_path: &str,
+    remote_name: &str,
+) -> Result<(u32, u32), GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    fetched_ahead_behind_count(&repo, remote_name)
+}
+
+/// 首选远程的仓库配置键
+pub const PREFERRED_REMOTE_CONFIG_KEY: &str = "github-heart.preferredRemote";
+
+/// 获取用户为仓库指定的首选远程（需仍然存在于仓库中）
+pub fn get_preferred_remote(repo_path: &str) -> Result<Option<String>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    Ok(preferred_remote(&repo))
+}
+
+/// 设置仓库的首选远程，传入 None 时清除设置
+pub fn set_preferred_remote(repo_path: &str, remote_name: Option<&str>) -> Result<(), GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let mut config = repo
+        .config()
+        .and_then(|config| config.open_level(git2::ConfigLevel::Local))
         .map_err(GitError::Git)?;
 
-    log::debug!(
-        "上游分支设置成功: {} -> {}/{}",
-        local_branch,
-        remote_name,
-        branch_name
-    );
+    match remote_name.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => {
+            repo.find_remote(name).map_err(|_| GitError::Unknown {
+                message: format!("远程仓库 '{}' 不存在", name),
+            })?;
+            config
+                .set_str(PREFERRED_REMOTE_CONFIG_KEY, name)
+                .map_err(GitError::Git)?;
+            log::info!("已设置首选远程: {}", name);
+        }
+        None => match config.remove(PREFERRED_REMOTE_CONFIG_KEY) {
+            Ok(()) => log::info!("已清除首选远程"),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => {}
+            Err(e) => return Err(GitError::Git(e)),
+        },
+    }
+
     Ok(())
 }
+
+fn preferred_remote(repo: &Repository) -> Option<String> {
+    let name = repo
+        .config()
+        .ok()?
+        .get_string(PREFERRED_REMOTE_CONFIG_KEY)
+        .ok()?;
+    if repo.find_remote(&name).is_ok() {
+        Some(name)
+    } else {
+        log::warn!("首选远程 '{}' 已不存在，忽略该设置", name);
+        None
+    }
+}
+
+/// 获取默认远程名称
+pub fn get_default_remote_name(repo: &Repository) -> Result<String, GitError> {
+    log::debug!("开始检测默认远程名称");
+
+    // 用户指定的首选远程优先于自动检测
+    if let Some(remote_name) = preferred_remote(repo) {
+        log::debug!("使用首选远程: {}", remote_name);
+        return Ok(remote_name);
+    }
+
+    // 首先尝试获取当前分支的上游远程
+    if let Ok(head) = repo.head() {
+        if let Some(branch_name) = head.shorthand() {
+            log::debug!("当前分支: {}", branch_name);
+
+            if let Ok(upstream_name) =
+                repo.branch_upstream_name(&format!("refs/heads/{}", branch_name))
+            {
+                if let Some(upstream_str) = upstream_name.as_str() {
+                    log::debug!("找到上游分支: {}", upstream_str);
+
+                    // 解析远程名称，格式通常是 "refs/remotes/origin/main"
+                    if let Some(remote_part) = upstream_str.strip_prefix("refs/remotes/") {
+                        if let Some(slash_pos) = remote_part.find('/') {
+                            let remote_name = remote_part[..slash_pos].to_string();
+                            log::debug!("从上游分支解析到远程名称: {}", remote_name);
+                            return Ok(remote_name);
+                        }
+                    }
+                }
+            } else {
+                log::debug!("当前分支没有设置上游分支");
+            }
+        }
+    }
+
+    // 如果无法从分支获取，尝试列出所有远程
+    let remotes = repo.remotes().map_err(GitError::Git)?;
+    let remote_names: Vec<String> = remotes.iter().flatten().map(|s| s.to_string()).collect();
+    log::debug!("仓库中的远程列表: {:?}", remote_names);
+
+    if remotes.len() == 0 {
+        log::error!("仓库中没有配置任何远程仓库");
+        return Err(GitError::Unknown {
+            message: "仓库中没有配置任何远程仓库".to_string(),
+        });
+    }
+
+    // 优先选择常见的远程名称
+    let preferred_remotes = ["origin", "upstream", "github", "gitlab"];
+    for preferred in &preferred_remotes {
+        for i in 0..remotes.len() {
+            if let Some(remote_name) = remotes.get(i) {
+                if remote_name == *preferred {
+                    log::debug!("找到首选远程名称: {}", remote_name);
+                    return Ok(remote_name.to_string());
+                }
+            }
+        }
+    }
+
+    // 如果没有找到首选的，使用第一个可用的远程
+    if let Some(first_remote) = remotes.get(0) {
+        log::debug!("使用第一个可用的远程: {}", first_remote);
+        return Ok(first_remote.to_string());
+    }
+
+    // 这种情况理论上不应该发生，因为我们已经检查了remotes.len() == 0
+    Err(GitError::Unknown {
+        message: "无法确定默认远程名称".to_string(),
+    })
+}
+
+/// 将指定分支、标签或提交合并到当前分支
+///
+/// 发生冲突时在工作区写入冲突标记并记录合并状态，解决冲突并暂存后调用 `continue_merge` 完成合并
+pub fn merge_branch(
+    repo_path: &str,
+    branch_name: &str,
+    identity: Option<(&str, &str)>,
+) -> Result<SyncResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    if repo.state() != git2::RepositoryState::Clean {
+        return Err(GitError::Unknown {
+            message: "仓库有进行中的操作，请先完成或中止".to_string(),
+        });
+    }
+
+    let local_commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(GitError::Git)?;
+    let their_commit =
+        resolve_commit_input(&repo, branch_name).ok_or_else(|| GitError::Unknown {
+            message: format!("无法找到分支、提交或引用: '{}'", branch_name),
+        })?;
+
+    log::debug!("合并 {} ({}) 到当前分支", branch_name, their_commit.id());
+    let merge_message = format!("Merge branch '{}'", branch_name);
+    let mut result = perform_merge(
+        &repo,
+        &local_commit,
+        &their_commit,
+        &merge_message,
+        identity,
+    )?;
+    if result.success {
+        apply_pull_diff_stats(&repo, local_commit.id(), &mut result);
+    }
+
+    Ok(result)
+}
+
+/// 执行合并操作，`merge_message` 为创建合并提交时使用的提交信息
+fn perform_merge(
+    repo: &Repository,
+    local_commit: &git2::Commit,
+    upstream_commit: &git2::Commit,
+    merge_message: &str,
+    identity: Option<(&str, &str)>,
+) -> Result<SyncResult, GitError> {
+    // 获取合并基础
+    let merge_base = repo
+        .merge_base(local_commit.id(), upstream_commit.id())
+        .map_err(GitError::Git)?;
+    let merge_base_commit = repo.find_commit(merge_base).map_err(GitError::Git)?;
+
+    // 创建AnnotatedCommit用于合并分析
+    let upstream_annotated = repo
+        .find_annotated_commit(upstream_commit.id())
+        .map_err(GitError::Git)?;
+    let analysis = repo
+        .merge_analysis(&[&upstream_annotated])
+        .map_err(GitError::Git)?;
+
+    if analysis.0.is_fast_forward() {
+        // 快进合并：先安全检出目标树，绝不覆盖未提交的本地变更
+        let upstream_tree = upstream_commit.tree().map_err(GitError::Git)?;
+        let conflict_files = checkout_tree_safely(repo, &upstream_tree)?;
+        if !conflict_files.is_empty() {
+            return Ok(SyncResult::conflicts(
+                "快进合并会覆盖本地未提交的变更，请先提交或暂存变更",
+                conflict_files,
+            ));
+        }
+
+        let refname = format!(
+            "refs/heads/{}",
+            repo.head()
+                .map_err(GitError::Git)?
+                .shorthand()
+                .unwrap_or("HEAD")
+        );
+        let mut reference = repo.find_reference(&refname).map_err(GitError::Git)?;
+        reference
+            .set_target(upstream_commit.id(), "Fast-forward merge")
+            .map_err(GitError::Git)?;
+
+        Ok(SyncResult {
+            success: true,
+            message: "快进合并成功".to_string(),
+            ..Default::default()
+        })
+    } else if analysis.0.is_normal() {
+        // 正常合并
+        let local_tree = local_commit.tree().map_err(GitError::Git)?;
+        let upstream_tree = upstream_commit.tree().map_err(GitError::Git)?;
+        let ancestor_tree = merge_base_commit.tree().map_err(GitError::Git)?;
+
+        let mut index = repo
+            .merge_trees(&ancestor_tree, &local_tree, &upstream_tree, None)
+            .map_err(GitError::Git)?;
+
+        if index.has_conflicts() {
+            // 有冲突，需要用户解决
+            let conflict_files = conflict_paths(&index)?;
+
+            // 将冲突写入工作区和索引，并记录 MERGE_HEAD / MERGE_MSG，
+            // 解决冲突并暂存后可通过 continue_merge 创建双父提交
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.allow_conflicts(true).conflict_style_merge(true);
+            repo.merge(&[&upstream_annotated], None, Some(&mut checkout))
+                .map_err(|e| {
+                    log::error!("写入合并冲突状态失败: {}", e);
+                    index_error(repo, e)
+                })?;
+
+            return Ok(SyncResult::conflicts(
+                "合并时发现冲突，请手动解决",
+                conflict_files,
+            ));
+        }
+
+        // 无冲突，先安全检出合并结果（不覆盖未提交的本地变更），再创建合并提交
+        let signature = resolve_signature(repo, identity)?;
+        let tree_id = index.write_tree_to(repo).map_err(GitError::Git)?;
+        let tree = repo.find_tree(tree_id).map_err(GitError::Git)?;
+        let conflict_files = checkout_tree_safely(repo, &tree)?;
+        if !conflict_files.is_empty() {
+            return Ok(SyncResult::conflicts(
+                "合并会覆盖本地未提交的变更，请先提交或暂存变更",
+                conflict_files,
+            ));
+        }
+
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            merge_message,
+            &tree,
+            &[local_commit, upstream_commit],
+        )
+        .map_err(GitError::Git)?;
+
+        Ok(SyncResult {
+            success: true,
+            message: "合并成功".to_string(),
+            ..Default::default()
+        })
+    } else {
+        Ok(SyncResult {
+            success: true,
+            message: "无需合并".to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+/// 执行变基操作
+fn perform_rebase(
+    repo: &Repository,
+    local_commit: &git2::Commit,
+    upstream_commit: &git2::Commit,
+    identity: Option<(&str, &str)>,
+) -> Result<SyncResult, GitError> {
+    // 变基操作比较复杂，这里提供一个简化的实现
+    // 在实际项目中，可能需要更复杂的冲突处理逻辑
+
+    let signature = resolve_signature(repo, identity)?;
+
+    // 创建AnnotatedCommit用于变基
+    let local_annotated = repo
+        .find_annotated_commit(local_commit.id())
+        .map_err(GitError::Git)?;
+    let upstream_annotated = repo
+        .find_annotated_commit(upstream_commit.id())
+        .map_err(GitError::Git)?;
+
+    // 创建变基操作
+    let mut rebase = repo
+        .rebase(
+            Some(&local_annotated),
+            Some(&upstream_annotated),
+            None,
+            None,
+        )
+        .map_err(GitError::Git)?;
+
+    // 执行变基步骤
+    while let Some(operation) = rebase.next() {
+        match operation {
+            Ok(_op) => {
+                // 检查是否有冲突
+                let index = repo.index().map_err(GitError::Git)?;
+                if index.has_conflicts() {
+                    // 有冲突，中止变基
+                    rebase.abort().map_err(GitError::Git)?;
+
+                    let conflict_files = conflict_paths(&index)?;
+
+                    return Ok(SyncResult::conflicts(
+                        "变基时发现冲突，请手动解决",
+                        conflict_files,
+                    ));
+                }
+
+                // 提交当前步骤
+                rebase
+                    .commit(None, &signature, None)
+                    .map_err(GitError::Git)?;
+            }
+            Err(e) => {
+                rebase.abort().map_err(GitError::Git)?;
+                return Err(index_error(repo, e));
+            }
+        }
+    }
+
+    // 完成变基
+    rebase.finish(None).map_err(GitError::Git)?;
+
+    Ok(SyncResult {
+        success: true,
+        message: "变基成功".to_string(),
+        ..Default::default()
+    })
+}
+
+// ==================== Token认证系统 ====================
+
+/// Token配置结构
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct TokenConfig {
+    pub domain: String,
+    pub token: String,
+    pub username: Option<String>,
+    pub created_at: i64,
+    pub last_used: Option<i64>,
+}
+
+/// 默认的最大认证尝试次数
+pub const DEFAULT_MAX_AUTH_ATTEMPTS: u32 = 3;
+
+/// 远程操作的认证设置
+#[derive(Debug, Clone, Default)]
+pub struct AuthOptions {
+    /// 用户为SSH远程指定的私钥
+    pub ssh_key: Option<AuthConfig>,
+    /// 缺少可用凭据时是否请求用户在界面中输入
+    ///
+    /// 仅用于用户主动发起的操作，后台刷新等操作不应弹出输入框
+    pub interactive: bool,
+    /// 最大认证尝试次数，未设置时使用 `DEFAULT_MAX_AUTH_ATTEMPTS`
+    pub max_attempts: Option<u32>,
+}
+
+impl AuthOptions {
+    /// 按本设置创建认证尝试记录
+    fn attempts(&self) -> AuthAttempts {
+        AuthAttempts::new(self.max_attempts.unwrap_or(DEFAULT_MAX_AUTH_ATTEMPTS))
+            .interactive(self.interactive)
+    }
+}
+
+/// 将远程操作（克隆、获取等）的 git2 错误归类为具体的 GitError
+///
+/// 依据错误码、错误类别和消息区分认证失败、仓库不存在和网络错误，无法归类时保留原始错误
+pub fn classify_remote_error(error: git2::Error, url: &str) -> GitError {
+    use git2::{ErrorClass, ErrorCode};
+
+    let message = error.message().to_lowercase();
+    let contains_any = |patterns: &[&str]| patterns.iter().any(|p| message.contains(p));
+
+    if error.code() == ErrorCode::Auth
+        || contains_any(&[
+            "authentication",
+            "status code: 401",
+            "status code: 403",
+            "permission denied",
+            "invalid credentials",
+        ])
+    {
+        return GitError::AuthenticationFailed {
+            message: "远程仓库拒绝了提供的凭据".to_string(),
+            methods_tried: vec![],
+            last_error: Some(error.message().to_string()),
+        };
+    }
+
+    let remote_class = matches!(
+        error.class(),
+        ErrorClass::Http | ErrorClass::Net | ErrorClass::Ssh | ErrorClass::Repository
+    );
+    if (error.code() == ErrorCode::NotFound && remote_class)
+        || contains_any(&[
+            "status code: 404",
+            "repository not found",
+            "does not appear to be a git repository",
+        ])
+    {
+        return GitError::RepositoryNotFound {
+            url: url.to_string(),
+        };
+    }
+
+    if matches!(
+        error.class(),
+        ErrorClass::Net | ErrorClass::Http | ErrorClass::Ssl
+    ) || error.code() == ErrorCode::Certificate
+        || contains_any(&[
+            "failed to resolve",
+            "could not resolve",
+            "timed out",
+            "failed to connect",
+            "connection refused",
+            "connection reset",
+        ])
+    {
+        return GitError::NetworkError {
+            message: error.message().to_string(),
+        };
+    }
+
+    GitError::Git(error)
+}
+
+/// 认证尝试记录
+///
+/// 在凭据回调中记录尝试过的认证方式，远程操作失败时据此生成结构化的认证错误
+#[derive(Debug)]
+pub struct AuthAttempts {
+    max_attempts: u32,
+    interactive: bool,
+    count: std::cell::Cell<u32>,
+    methods: std::cell::RefCell<Vec<String>>,
+}
+
+impl AuthAttempts {
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            interactive: false,
+            count: std::cell::Cell::new(0),
+            methods: std::cell::RefCell::new(Vec::new()),
+        }
+    }
+
+    /// 设置缺少凭据时是否请求用户输入（默认不请求）
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// 开始新一次尝试，返回尝试序号（从 1 开始）
+    fn next_attempt(&self) -> u32 {
+        let attempt = self.count.get() + 1;
+        self.count.set(attempt);
+        attempt
+    }
+
+    fn record(&self, method: &str) {
+        self.methods.borrow_mut().push(method.to_string());
+    }
+
+    /// 已尝试过的认证方式
+    pub fn methods_tried(&self) -> Vec<String> {
+        self.methods.borrow().clone()
+    }
+
+    /// 将远程操作的错误转换为 `GitError`，认证类错误转换为 `AuthenticationFailed`
+    pub fn to_error(&self, error: git2::Error) -> GitError {
+        if error.code() != git2::ErrorCode::Auth {
+            return GitError::Git(error);
+        }
+
+        let methods_tried = self.methods_tried();
+        let message = if methods_tried.is_empty() {
+            "未配置可用的凭据".to_string()
+        } else {
+            format!("已尝试 {}，均被拒绝", methods_tried.join("、"))
+        };
+
+        GitError::AuthenticationFailed {
+            message,
+            methods_tried,
+            last_error: Some(error.message().to_string()),
+        }
+    }
+}
+
+/// 凭据回调每次尝试选择的认证方式
+#[derive(Debug, PartialEq)]
+enum CredentialChoice {
+    /// Token 作为用户名，密码为空
+    TokenAsUsername,
+    /// 用户名 + Token 作为密码
+    TokenAsPassword,
+    /// SSH 远程的 URL 未包含用户名时，先提供用户名（URL 中的用户名或 `git`）
+    SshUsername,
+    /// 用户为本次操作选择的SSH私钥
+    SshKey,
+    /// 系统默认凭据（NTLM/Negotiate）
+    Default,
+    /// 停止尝试
+    Fail(&'static str),
+}
+
+/// 根据尝试次数和可用信息选择本次使用的凭据
+///
+/// 提供了 Token 的 HTTPS 请求只尝试两种 Token 形式，被拒绝后直接停止，
+/// 不再回退到默认凭据（那只会产生另一次失败）；SSH 请求先提供用户名，在指定了私钥时只尝试该私钥，
+/// HTTPS 请求忽略指定的私钥
+fn choose_credential(
+    attempt: u32,
+    max_attempts: u32,
+    is_http: bool,
+    has_token: bool,
+    has_ssh_key: bool,
+    allowed_types: git2::CredentialType,
+) -> CredentialChoice {
+    if attempt > max_attempts {
+        return CredentialChoice::Fail("认证失败：尝试次数过多");
+    }
+
+    if !is_http && allowed_types.contains(git2::CredentialType::USERNAME) {
+        return CredentialChoice::SshUsername;
+    }
+
+    if !is_http && has_ssh_key && allowed_types.contains(git2::CredentialType::SSH_KEY) {
+        return match attempt {
+            1 => CredentialChoice::SshKey,
+            _ => CredentialChoice::Fail("SSH 密钥被服务器拒绝"),
+        };
+    }
+
+    if is_http && has_token {
+        return match attempt {
+            1 => CredentialChoice::TokenAsUsername,
+            2 => CredentialChoice::TokenAsPassword,
+            _ => CredentialChoice::Fail("Token 被服务器拒绝"),
+        };
+    }
+
+    if attempt == 1 && allowed_types.contains(git2::CredentialType::DEFAULT) {
+        CredentialChoice::Default
+    } else if attempt == 1 {
+        CredentialChoice::Fail("未配置可用的凭据")
+    } else {
+        CredentialChoice::Fail("认证失败，无更多认证方式可尝试")
+    }
+}
+
+/// 根据用户选择的私钥生成SSH认证配置，未选择私钥时返回 None
+pub fn ssh_key_auth(
+    ssh_key_path: Option<String>,
+    passphrase: Option<String>,
+) -> Option<AuthConfig> {
+    let ssh_key_path = ssh_key_path.filter(|path| !path.trim().is_empty())?;
+    Some(AuthConfig {
+        auth_type: AuthType::Ssh,
+        username: None,
+        password: None,
+        token: None,
+        ssh_key_path: Some(ssh_key_path),
+        ssh_key_passphrase: passphrase.filter(|passphrase| !passphrase.is_empty()),
+    })
+}
+
+/// 凭据回调的状态：可用的 Token、指定的SSH私钥、尝试记录以及是否已询问过用户
+struct CredentialResolver<'a> {
+    token_cache: Option<String>,
+    ssh_auth: Option<AuthConfig>,
+    /// 以 Token 作为密码时使用的用户名，未设置时使用 URL 中的用户名
+    username: Option<String>,
+    attempts: &'a AuthAttempts,
+    prompted: bool,
+}
+
+impl<'a> CredentialResolver<'a> {
+    fn new(
+        token_cache: Option<String>,
+        ssh_auth: Option<AuthConfig>,
+        attempts: &'a AuthAttempts,
+    ) -> Self {
+        Self {
+            token_cache,
+            ssh_auth,
+            username: None,
+            attempts,
+            prompted: false,
+        }
+    }
+
+    /// 设置以 Token 作为密码时使用的用户名
+    fn with_username(mut self, username: Option<String>) -> Self {
+        self.username = username.filter(|username| !username.is_empty());
+        self
+    }
+
+    /// 响应 libgit2 的一次凭据请求
+    fn resolve(
+        &mut self,
+        url: &str,
+        username_from_url: Option<&str>,
+        allowed_types: git2::CredentialType,
+    ) -> Result<git2::Cred, git2::Error> {
+        let is_http = url.starts_with("https://") || url.starts_with("http://");
+        let attempt = self.attempts.count.get() + 1;
+        let choice = choose_credential(
+            attempt,
+            self.attempts.max_attempts,
+            is_http,
+            self.token_cache.is_some(),
+            self.ssh_auth.is_some(),
+            allowed_types,
+        );
+
+        // 单独请求用户名不是一次认证，不计入尝试次数
+        if choice == CredentialChoice::SshUsername {
+            let username = username_from_url.unwrap_or("git");
+            log::debug!("提供SSH用户名: {}", username);
+            return git2::Cred::username(username);
+        }
+        self.attempts.next_attempt();
+
+        log::debug!(
+            "Git认证回调被调用: url={}, username={:?}, allowed_types={:?}, 尝试次数={}",
+            url,
+            username_from_url,
+            allowed_types,
+            attempt
+        );
+
+        // 没有缓存的凭据可用时，请求用户在界面中输入（仅限用户主动发起的操作，每次操作只询问一次）
+        if matches!(choice, CredentialChoice::Fail(_))
+            && self.attempts.interactive
+            && self.token_cache.is_none()
+            && !self.prompted
+        {
+            if let Some(prompter) = credential_prompt::current() {
+                self.prompted = true;
+                self.attempts.record("interactive");
+                return match prompter.request(url, username_from_url, allowed_types) {
+                    Some(auth) => AuthManager::create_credentials(
+                        &auth,
+                        url,
+                        username_from_url,
+                        allowed_types,
+                    )
+                    .map_err(|e| {
+                        git2::Error::new(
+                            git2::ErrorCode::Auth,
+                            git2::ErrorClass::Callback,
+                            &e.to_string(),
+                        )
+                    }),
+                    None => Err(git2::Error::new(
+                        git2::ErrorCode::Auth,
+                        git2::ErrorClass::Callback,
+                        "未收到用户输入的凭据（已取消或等待超时）",
+                    )),
+                };
+            }
+        }
+
+        match (choice, self.token_cache.as_deref()) {
+            // 对于AtomGit等服务，Token应该作为用户名，密码为空或token
+            (CredentialChoice::TokenAsUsername, Some(token)) => {
+                log::debug!("使用Token进行HTTPS认证 (尝试 {})", attempt);
+                self.attempts.record("token_as_username");
+                git2::Cred::userpass_plaintext(token, "")
+            }
+            (CredentialChoice::TokenAsPassword, Some(token)) => {
+                log::debug!("使用Token进行HTTPS认证 (尝试 {})", attempt);
+                self.attempts.record("token_as_password");
+                let username = self
+                    .username
+                    .as_deref()
+                    .or(username_from_url)
+                    .unwrap_or("git");
+                git2::Cred::userpass_plaintext(username, token)
+            }
+            (CredentialChoice::SshKey, _) => match &self.ssh_auth {
+                Some(auth) => {
+                    log::debug!("使用指定的SSH私钥 (尝试 {})", attempt);
+                    self.attempts.record("ssh_key");
+                    AuthManager::create_credentials(auth, url, username_from_url, allowed_types)
+                        .map_err(|e| {
+                            git2::Error::new(
+                                git2::ErrorCode::Auth,
+                                git2::ErrorClass::Callback,
+                                &e.to_string(),
+                            )
+                        })
+                }
+                None => Err(git2::Error::new(
+                    git2::ErrorCode::Auth,
+                    git2::ErrorClass::Callback,
+                    "未配置可用的凭据",
+                )),
+            },
+            (CredentialChoice::Default, _) => {
+                log::debug!("使用默认凭据");
+                self.attempts.record("default");
+                git2::Cred::default()
+            }
+            (CredentialChoice::Fail(reason), _) => {
+                log::error!("{}", reason);
+                Err(git2::Error::new(
+                    git2::ErrorCode::Auth,
+                    git2::ErrorClass::Callback,
+                    reason,
+                ))
+            }
+            (_, None) => Err(git2::Error::new(
+                git2::ErrorCode::Auth,
+                git2::ErrorClass::Callback,
+                "未配置可用的凭据",
+            )),
+        }
+    }
+}
+
+/// 创建支持Token认证的回调函数
+///
+/// `ssh_auth` 为用户指定的SSH私钥，仅用于SSH远程；
+/// 尝试过的认证方式记录在 `attempts` 中，最大尝试次数也由其决定
+pub fn create_authenticated_callbacks<'a>(
+    repo_url: &str,
+    token_cache: Option<String>,
+    ssh_auth: Option<AuthConfig>,
+    attempts: &'a AuthAttempts,
+) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+    let _url = repo_url.to_string();
+    let mut resolver = CredentialResolver::new(token_cache, ssh_auth, attempts);
+    callbacks.credentials(move |url, username_from_url, allowed_types| {
+        resolver.resolve(url, username_from_url, allowed_types)
+    });
+
+    // 添加证书检查回调
+    callbacks.certificate_check(|_cert, _valid| {
+        log::debug!("证书检查回调");
+        // 对于开发环境，可以选择接受所有证书
+        // 生产环境应该进行适当的证书验证
+        Ok(git2::CertificateCheckStatus::CertificateOk)
+    });
+
+    callbacks
+}
+
+/// 为未关联本地仓库的远程操作创建认证回调
+///
+/// 用户提供的凭据按类型交给 `CredentialResolver`：Token 和密码按 Token 的方式尝试，SSH 私钥仅用于SSH远程；
+/// `auth_requested` 用于记录服务器是否请求过凭据
+fn create_detached_callbacks<'a>(
+    auth: Option<&AuthConfig>,
+    attempts: &'a AuthAttempts,
+    auth_requested: &'a std::cell::Cell<bool>,
+) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+
+    let (token_cache, ssh_auth) = match auth {
+        Some(auth) => match auth.auth_type {
+            AuthType::Token => (auth.token.clone(), None),
+            AuthType::Password => (auth.password.clone(), None),
+            AuthType::Ssh => (None, Some(auth.clone())),
+            AuthType::None => (None, None),
+        },
+        None => (None, None),
+    };
+    let username = auth.and_then(|auth| auth.username.clone());
+
+    let mut resolver =
+        CredentialResolver::new(token_cache, ssh_auth, attempts).with_username(username);
+    callbacks.credentials(move |url, username_from_url, allowed_types| {
+        auth_requested.set(true);
+        resolver.resolve(url, username_from_url, allowed_types)
+    });
+
+    // 交由 libgit2 按系统信任链和 known_hosts 校验证书与主机密钥
+    callbacks.certificate_check(|_cert, _valid| {
+        Ok(git2::CertificateCheckStatus::CertificatePassthrough)
+    });
+
+    callbacks
+}
+
+/// 测试远程仓库的连通性和认证（类似 `git ls-remote`）
+pub fn test_repository_access(url: &str, auth: Option<&AuthConfig>) -> AccessResult {
+    let auth_requested = std::cell::Cell::new(false);
+    let attempts = AuthAttempts::new(DEFAULT_MAX_AUTH_ATTEMPTS);
+
+    let mut remote = match git2::Remote::create_detached(url) {
+        Ok(remote) => remote,
+        Err(e) => {
+            return AccessResult {
+                reachable: false,
+                requires_auth: false,
+                default_branch: None,
+                error: Some(e.message().to_string()),
+            }
+        }
+    };
+
+    let callbacks = create_detached_callbacks(auth, &attempts, &auth_requested);
+    let connection = remote.connect_auth(git2::Direction::Fetch, Some(callbacks), None);
+
+    match connection {
+        Ok(connection) => {
+            let default_branch = connection
+                .default_branch()
+                .ok()
+                .and_then(|buf| buf.as_str().map(|s| s.to_string()))
+                .map(|name| {
+                    name.strip_prefix("refs/heads/")
+                        .unwrap_or(&name)
+                        .to_string()
+                });
+
+            AccessResult {
+                reachable: true,
+                requires_auth: auth_requested.get(),
+                default_branch,
+                error: None,
+            }
+        }
+        Err(e) => {
+            log::warn!("连接远程仓库失败: {} ({})", url, e);
+            AccessResult {
+                reachable: false,
+                requires_auth: auth_requested.get() || e.code() == git2::ErrorCode::Auth,
+                default_branch: None,
+                error: Some(e.message().to_string()),
+            }
+        }
+    }
+}
+
+/// 在不克隆的情况下列出远程仓库的分支和标签
+pub fn list_remote_refs(url: &str, auth: Option<&AuthConfig>) -> Result<Vec<RemoteRef>, GitError> {
+    let auth_requested = std::cell::Cell::new(false);
+    let attempts = AuthAttempts::new(DEFAULT_MAX_AUTH_ATTEMPTS);
+    let mut remote = git2::Remote::create_detached(url).map_err(GitError::Git)?;
+
+    let callbacks = create_detached_callbacks(auth, &attempts, &auth_requested);
+    let connection = remote
+        .connect_auth(git2::Direction::Fetch, Some(callbacks), None)
+        .map_err(|e| {
+            log::error!("连接远程仓库失败: {}", e);
+            if e.code() == git2::ErrorCode::Auth {
+                attempts.to_error(e)
+            } else {
+                classify_remote_error(e, url)
+            }
+        })?;
+
+    let heads = connection.list().map_err(GitError::Git)?;
+
+    // 远程HEAD指向的默认分支
+    let default_ref = heads
+        .iter()
+        .find(|head| head.name() == "HEAD")
+        .and_then(|head| head.symref_target().map(|s| s.to_string()));
+
+    let mut refs = Vec::new();
+    for head in heads {
+        let full_name = head.name().to_string();
+
+        // 跳过附注标签的解引用条目（refs/tags/v1^{}）
+        if full_name.ends_with("^{}") {
+            continue;
+        }
+
+        let (kind, name) = if full_name == "HEAD" {
+            (RemoteRefKind::Head, full_name.clone())
+        } else if let Some(name) = full_name.strip_prefix("refs/heads/") {
+            (RemoteRefKind::Branch, name.to_string())
+        } else if let Some(name) = full_name.strip_prefix("refs/tags/") {
+            (RemoteRefKind::Tag, name.to_string())
+        } else {
+            continue;
+        };
+
+        refs.push(RemoteRef {
+            is_default: default_ref.as_deref() == Some(full_name.as_str()),
+            symref_target: head.symref_target().map(|s| s.to_string()),
+            oid: head.oid().to_string(),
+            name,
+            full_name,
+            kind,
+        });
+    }
+
+    log::debug!("获取到 {} 个远程引用", refs.len());
+    Ok(refs)
+}
+
+// ==================== 归档导出 ====================
+
+/// 规范化归档内的路径前缀，非空时保证以 `/` 结尾
+pub fn normalize_archive_prefix(prefix: Option<&str>) -> String {
+    let prefix = prefix.unwrap_or("").trim().trim_matches('/');
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", prefix)
+    }
+}
+
+/// 将分支名、标签、SHA 等引用解析为提交 ID，解析顺序同 [`resolve_commit_input`]
+fn resolve_commit_oid(repo: &Repository, reference: &str) -> Result<git2::Oid, GitError> {
+    resolve_commit_input(repo, reference)
+        .map(|commit| commit.id())
+        .ok_or_else(|| GitError::Unknown {
+            message: format!("无法找到标签、提交或引用: '{}'", reference),
+        })
+}
+
+/// 列出指定版本中某个目录下的条目（只展开一层）
+///
+/// `revision` 默认为 HEAD，`path` 默认为仓库根目录；目录排在文件之前，各自按名称排序
+pub fn list_tree(
+    repo_path: &str,
+    revision: Option<&str>,
+    path: Option<&str>,
+) -> Result<Vec<TreeEntry>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let revision = revision.unwrap_or("HEAD");
+    let commit_id = resolve_commit_oid(&repo, revision)?;
+    let root = repo
+        .find_commit(commit_id)
+        .and_then(|commit| commit.tree())
+        .map_err(GitError::Git)?;
+
+    let dir = path.unwrap_or("").trim_matches('/');
+    let tree = if dir.is_empty() {
+        root
+    } else {
+        root.get_path(Path::new(dir))
+            .and_then(|entry| entry.to_object(&repo))
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|_| GitError::Unknown {
+                message: format!("目录 '{}' 在 {} 中不存在", dir, revision),
+            })?
+    };
+
+    let mut entries = Vec::with_capacity(tree.len());
+    for entry in tree.iter() {
+        let name = String::from_utf8_lossy(entry.name_bytes()).to_string();
+        let is_dir = entry.kind() == Some(git2::ObjectType::Tree);
+        let size = match entry.kind() {
+            Some(git2::ObjectType::Blob) => repo
+                .odb()
+                .and_then(|odb| odb.read_header(entry.id()))
+                .map(|(size, _)| Some(size as u64))
+                .map_err(GitError::Git)?,
+            _ => None,
+        };
+
+        entries.push(TreeEntry {
+            path: if dir.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", dir, name)
+            },
+            name,
+            is_dir,
+            size,
+            mode: entry.filemode(),
+        });
+    }
+
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+/// 从指定提交恢复文件到工作区并暂存（如恢复被误删的文件）
+pub fn restore_file_from_commit(
+    repo_path: &str,
+    commit_ref: &str,
+    file_path: &str,
+) -> Result<(), GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let relative = repo_relative_path(&repo, file_path)?;
+    let commit_id = resolve_commit_oid(&repo, commit_ref)?;
+    let tree = repo
+        .find_commit(commit_id)
+        .and_then(|commit| commit.tree())
+        .map_err(GitError::Git)?;
+
+    let entry = tree
+        .get_path(&relative)
+        .ok()
+        .filter(|entry| entry.kind() == Some(git2::ObjectType::Blob))
+        .ok_or_else(|| GitError::Unknown {
+            message: format!("文件 '{}' 在 {} 中不存在", file_path, commit_ref),
+        })?;
+    let blob = repo.find_blob(entry.id()).map_err(GitError::Git)?;
+
+    let workdir = repo.workdir().ok_or_else(|| GitError::Unknown {
+        message: "裸仓库没有工作区".to_string(),
+    })?;
+    let target = workdir.join(&relative);
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(GitError::Io)?;
+    }
+    std::fs::write(&target, blob.content()).map_err(GitError::Io)?;
+
+    #[cfg(unix)]
+    if entry.filemode() == 0o100755 {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755))
+            .map_err(GitError::Io)?;
+    }
+
+    let mut index = repo.index().map_err(GitError::Git)?;
+    index.add_path(&relative).map_err(GitError::Git)?;
+    index.write().map_err(|e| index_error(&repo, e))?;
+
+    log::info!("已从 {} 恢复文件: {}", commit_ref, file_path);
+    Ok(())
+}
+
+/// 获取指定版本中某个路径的类型、大小等元数据，不加载文件内容
+pub fn stat_path(
+    repo_path: &str,
+    revision: Option<&str>,
+    path: &str,
+) -> Result<PathStat, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let revision = revision.unwrap_or("HEAD");
+    let commit_id = resolve_commit_oid(&repo, revision)?;
+    let root = repo
+        .find_commit(commit_id)
+        .and_then(|commit| commit.tree())
+        .map_err(GitError::Git)?;
+
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        return Ok(PathStat {
+            kind: PathKind::Tree,
+            size: None,
+            entry_count: Some(root.len()),
+            mode: 0o040000,
+            sha: root.id().to_string(),
+        });
+    }
+
+    let entry = root
+        .get_path(Path::new(path))
+        .map_err(|_| GitError::Unknown {
+            message: format!("路径 '{}' 在 {} 中不存在", path, revision),
+        })?;
+
+    let (kind, size, entry_count) = match entry.kind() {
+        Some(git2::ObjectType::Blob) => {
+            let (size, _) = repo
+                .odb()
+                .and_then(|odb| odb.read_header(entry.id()))
+                .map_err(GitError::Git)?;
+            (PathKind::Blob, Some(size as u64), None)
+        }
+        Some(git2::ObjectType::Tree) => {
+            let tree = repo.find_tree(entry.id()).map_err(GitError::Git)?;
+            (PathKind::Tree, None, Some(tree.len()))
+        }
+        Some(git2::ObjectType::Commit) => (PathKind::Submodule, None, None),
+        _ => {
+            return Err(GitError::Unknown {
+                message: format!("无法识别路径 '{}' 的对象类型", path),
+            })
+        }
+    };
+
+    Ok(PathStat {
+        kind,
+        size,
+        entry_count,
+        mode: entry.filemode(),
+        sha: entry.id().to_string(),
+    })
+}
+
+/// 获取两个引用的合并基准（最近共同祖先）
+pub fn get_merge_base(repo_path: &str, ref_a: &str, ref_b: &str) -> Result<String, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let a = resolve_commit_oid(&repo, ref_a)?;
+    let b = resolve_commit_oid(&repo, ref_b)?;
+
+    match repo.merge_base(a, b) {
+        Ok(oid) => Ok(oid.to_string()),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Err(GitError::NoMergeBase {
+            refs: format!("'{}' 和 '{}'", ref_a, ref_b),
+        }),
+        Err(e) => Err(GitError::Git(e)),
+    }
+}
+
+/// 获取多个引用的所有合并基准
+///
+/// 两个引用时返回所有最佳共同祖先（交叉合并时可能有多个）；多于两个引用时按章鱼合并计算
+pub fn get_merge_bases(repo_path: &str, refs: &[String]) -> Result<Vec<String>, GitError> {
+    if refs.len() < 2 {
+        return Err(GitError::Unknown {
+            message: "计算合并基准至少需要两个引用".to_string(),
+        });
+    }
+
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let oids = refs
+        .iter()
+        .map(|reference| resolve_commit_oid(&repo, reference))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // 多个引用时与 `git merge-base --octopus` 一致，返回所有引用共同的最佳祖先
+    let bases = if oids.len() == 2 {
+        repo.merge_bases(oids[0], oids[1])
+            .map(|bases| bases.iter().map(|oid| oid.to_string()).collect())
+    } else {
+        repo.merge_base_octopus(&oids)
+            .map(|oid| vec![oid.to_string()])
+    };
+
+    match bases {
+        Ok(bases) => Ok(bases),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Err(GitError::NoMergeBase {
+            refs: refs
+                .iter()
+                .map(|r| format!("'{}'", r))
+                .collect::<Vec<_>>()
+                .join("、"),
+        }),
+        Err(e) => Err(GitError::Git(e)),
+    }
+}
+
+/// 使用 git2 遍历提交树生成 tar 归档（系统Git不可用时的回退方案）
+pub fn archive_repository_tar(
+    repo_path: &str,
+    revision: &str,
+    output_path: &str,
+    prefix: Option<&str>,
+) -> Result<ArchiveResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let commit = repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(GitError::Git)?;
+    let tree = commit.tree().map_err(GitError::Git)?;
+    let mtime = commit.time().seconds().max(0) as u64;
+
+    let file = std::fs::File::create(output_path).map_err(GitError::Io)?;
+    let mut builder = tar::Builder::new(file);
+    append_tree_to_tar(
+        &repo,
+        &mut builder,
+        &tree,
+        &normalize_archive_prefix(prefix),
+        mtime,
+    )?;
+    builder.finish().map_err(GitError::Io)?;
+
+    let size = std::fs::metadata(output_path).map_err(GitError::Io)?.len();
+    Ok(ArchiveResult {
+        output_path: output_path.to_string(),
+        size,
+    })
+}
+
+/// 递归地将树对象中的文件写入 tar 归档
+fn append_tree_to_tar<W: std::io::Write>(
+    repo: &Repository,
+    builder: &mut tar::Builder<W>,
+    tree: &git2::Tree,
+    base_path: &str,
+    mtime: u64,
+) -> Result<(), GitError> {
+    for entry in tree.iter() {
+        let name = entry.name().unwrap_or_default();
+        let path = format!("{}{}", base_path, name);
+
+        match entry.kind() {
+            Some(git2::ObjectType::Tree) => {
+                let subtree = repo.find_tree(entry.id()).map_err(GitError::Git)?;
+                append_tree_to_tar(repo, builder, &subtree, &format!("{}/", path), mtime)?;
+            }
+            Some(git2::ObjectType::Blob) => {
+                let blob = repo.find_blob(entry.id()).map_err(GitError::Git)?;
+                let mut header = tar::Header::new_gnu();
+                header.set_mtime(mtime);
+
+                if entry.filemode() == 0o120000 {
+                    // 符号链接：blob 内容即链接目标
+                    let target = String::from_utf8_lossy(blob.content()).to_string();
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_mode(0o777);
+                    header.set_size(0);
+                    builder
+                        .append_link(&mut header, &path, &target)
+                        .map_err(GitError::Io)?;
+                } else {
+                    let mode = if entry.filemode() == 0o100755 {
+                        0o755
+                    } else {
+                        0o644
+                    };
+                    header.set_entry_type(tar::EntryType::Regular);
+                    header.set_mode(mode);
+                    header.set_size(blob.content().len() as u64);
+                    builder
+                        .append_data(&mut header, &path, blob.content())
+                        .map_err(GitError::Io)?;
+                }
+            }
+            // 子模块等其他条目不包含在归档中
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+// ==================== 分支管理功能 ====================
+
+/// 分支信息结构
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct BranchInfo {
+    /// 分支名称
+    pub name: String,
+    /// 是否为当前分支
+    pub is_current: bool,
+    /// 是否为远程分支
+    pub is_remote: bool,
+    /// 上游分支名称
+    pub upstream: Option<String>,
+    /// 领先提交数
+    pub ahead: u32,
+    /// 落后提交数
+    pub behind: u32,
+    /// 最后提交信息
+    pub last_commit: Option<CommitInfo>,
+}
+
+/// 轻量分支信息结构（不含提交信息和 ahead/behind）
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct BranchLite {
+    /// 分支名称
+    pub name: String,
+    /// 是否为当前分支
+    pub is_current: bool,
+    /// 是否为远程分支
+    pub is_remote: bool,
+    /// 上游分支名称
+    pub upstream: Option<String>,
+}
+
+/// 简化的提交信息结构
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct CommitInfo {
+    /// 提交SHA
+    pub sha: String,
+    /// 提交消息
+    pub message: String,
+    /// 作者姓名
+    pub author_name: String,
+    /// 作者邮箱
+    pub author_email: String,
+    /// 提交时间戳
+    pub timestamp: i64,
+}
+
+/// 分支切换结果
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct SwitchResult {
+    /// 操作是否成功
+    pub success: bool,
+    /// 操作消息
+    pub message: String,
+    /// 是否有未提交的变更
+    pub has_uncommitted_changes: bool,
+    /// 未提交的文件列表
+    pub uncommitted_files: Vec<String>,
+    /// 是否处于分离HEAD状态
+    pub is_detached: bool,
+    /// 目标分支已在其他工作树中检出时，该工作树的路径
+    pub worktree_path: Option<String>,
+}
+
+/// 获取分支列表
+pub fn list_branches(
+    repo_path: &str,
+    name_filter: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<BranchInfo>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    log::debug!("开始获取分支列表: {}", repo_path);
+
+    let branches = collect_branches(&repo, name_filter, limit, create_branch_info)?;
+
+    log::debug!("获取到 {} 个分支", branches.len());
+    Ok(branches)
+}
+
+/// 获取轻量分支列表
+///
+/// 不解析最后提交，也不计算 ahead/behind，用于大量分支时的快速首屏渲染
+pub fn list_branches_lite(
+    repo_path: &str,
+    name_filter: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<BranchLite>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    collect_branches(
+        &repo,
+        name_filter,
+        limit,
+        |_, name, is_current, is_remote, branch| {
+            let upstream = if is_remote {
+                None
+            } else {
+                branch
+                    .upstream()
+                    .ok()
+                    .and_then(|upstream| upstream.name().ok().flatten().map(|s| s.to_string()))
+            };
+
+            Ok(BranchLite {
+                name,
+                is_current,
+                is_remote,
+                upstream,
+            })
+        },
+    )
+}
+
+/// 列出包含指定提交的分支（类似 `git branch --contains`）
+///
+/// 本地分支在前；`include_remote` 为 true 时同时检查远程跟踪分支（不含 `origin/HEAD`）
+pub fn branches_containing(
+    repo_path: &str,
+    commit_sha: &str,
+    include_remote: bool,
+) -> Result<Vec<String>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let commit_id = resolve_commit_oid(&repo, commit_sha)?;
+
+    let filter = if include_remote {
+        None
+    } else {
+        Some(git2::BranchType::Local)
+    };
+
+    let mut local = Vec::new();
+    let mut remote = Vec::new();
+    for branch in repo.branches(filter).map_err(GitError::Git)? {
+        let (branch, branch_type) = branch.map_err(GitError::Git)?;
+        let name = match branch.name() {
+            Ok(Some(name)) => name.to_string(),
+            _ => continue,
+        };
+        if branch_type == git2::BranchType::Remote && name.ends_with("/HEAD") {
+            continue;
+        }
+        let Some(tip) = branch.get().target() else {
+            continue;
+        };
+
+        let contains = tip == commit_id
+            || repo
+                .graph_descendant_of(tip, commit_id)
+                .map_err(GitError::Git)?;
+        if contains {
+            match branch_type {
+                git2::BranchType::Local => local.push(name),
+                git2::BranchType::Remote => remote.push(name),
+            }
+        }
+    }
+
+    local.sort();
+    remote.sort();
+    local.extend(remote);
+    Ok(local)
+}
+
+/// 遍历本地和远程分支（本地分支在前），按名称过滤并限制数量
+///
+/// 名称过滤不区分大小写；过滤在构建分支信息之前进行，避免为不需要的分支做额外计算
+fn collect_branches<T, F>(
+    repo: &Repository,
+    name_filter: Option<&str>,
+    limit: Option<usize>,
+    mut build: F,
+) -> Result<Vec<T>, GitError>
+where
+    F: FnMut(&Repository, String, bool, bool, &git2::Branch) -> Result<T, GitError>,
+{
+    let current_branch_name = get_current_branch_name(repo)?;
+    log::debug!("当前分支: {:?}", current_branch_name);
+
+    let filter = name_filter
+        .map(|f| f.trim().to_lowercase())
+        .filter(|f| !f.is_empty());
+    let limit = limit.unwrap_or(usize::MAX);
+    let mut branches = Vec::new();
+
+    for (branch_type, is_remote) in [
+        (git2::BranchType::Local, false),
+        (git2::BranchType::Remote, true),
+    ] {
+        for branch_result in repo.branches(Some(branch_type)).map_err(GitError::Git)? {
+            if branches.len() >= limit {
+                return Ok(branches);
+            }
+
+            let Ok((branch, _)) = branch_result else {
+                continue;
+            };
+            let Some(name) = branch.name().map_err(GitError::Git)? else {
+                continue;
+            };
+
+            // 过滤掉远程HEAD分支
+            if is_remote && is_remote_head_branch(name) {
+                log::debug!("跳过远程HEAD分支: {}", name);
+                continue;
+            }
+
+            if let Some(filter) = &filter {
+                if !name.to_lowercase().contains(filter.as_str()) {
+                    continue;
+                }
+            }
+
+            let is_current = !is_remote && current_branch_name.as_deref() == Some(name);
+            branches.push(build(
+                repo,
+                name.to_string(),
+                is_current,
+                is_remote,
+                &branch,
+            )?);
+        }
+    }
+
+    Ok(branches)
+}
+
+/// 创建分支信息
+fn create_branch_info(
+    repo: &Repository,
+    name: String,
+    is_current: bool,
+    is_remote: bool,
+    branch: &git2::Branch,
+) -> Result<BranchInfo, GitError> {
+    // 获取上游分支
+    let upstream = if !is_remote {
+        branch.upstream().ok().and_then(|upstream_branch| {
+            upstream_branch.name().ok().flatten().map(|s| s.to_string())
+        })
+    } else {
+        None
+    };
+
+    // 获取分支的最后提交
+    let last_commit = {
+        let reference = branch.get();
+        if let Some(target_oid) = reference.target() {
+            if let Ok(commit) = repo.find_commit(target_oid) {
+                Some(CommitInfo {
+                    sha: commit.id().to_string(),
+                    message: commit.message().unwrap_or("").to_string(),
+                    author_name: commit.author().name().unwrap_or("").to_string(),
+                    author_email: commit.author().email().unwrap_or("").to_string(),
+                    timestamp: commit.time().seconds(),
+                })
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    };
+
+    // 计算ahead/behind状态（仅对有上游的本地分支）
+    let (ahead, behind) = if !is_remote && upstream.is_some() {
+        calculate_ahead_behind_for_branch(repo, &name).unwrap_or((0, 0))
+    } else {
+        (0, 0)
+    };
+
+    Ok(BranchInfo {
+        name,
+        is_current,
+        is_remote,
+        upstream,
+        ahead,
+        behind,
+        last_commit,
+    })
+}
+
+/// 获取当前分支名称
+fn get_current_branch_name(repo: &Repository) -> Result<Option<String>, GitError> {
+    match repo.head() {
+        Ok(head) => {
+            if let Some(shorthand) = head.shorthand() {
+                Ok(Some(shorthand.to_string()))
+            } else {
+                Ok(None)
+            }
+        }
+        Err(_) => Ok(None), // 可能是新仓库或detached HEAD
+    }
+}
+
+/// 检查是否是远程HEAD分支
+fn is_remote_head_branch(branch_name: &str) -> bool {
+    // 远程HEAD分支通常以 "/HEAD" 结尾
+    // 例如: "origin/HEAD", "upstream/HEAD" 等
+    branch_name.ends_with("/HEAD")
+}
+
+/// 计算特定分支的ahead/behind状态
+fn calculate_ahead_behind_for_branch(
+    repo: &Repository,
+    branch_name: &str,
+) -> Result<(u32, u32), GitError> {
+    // 尝试获取分支的上游
+    let upstream_name = match repo.branch_upstream_name(&format!("refs/heads/{}", branch_name)) {
+        Ok(name) => name,
+        Err(_) => return Ok((0, 0)), // 没有上游分支
+    };
+
+    if let Some(upstream_str) = upstream_name.as_str() {
+        // 获取本地分支和上游分支的提交
+        let local_ref = format!("refs/heads/{}", branch_name);
+        let upstream_ref = upstream_str;
+
+        if let (Ok(local_oid), Ok(upstream_oid)) = (
+            repo.refname_to_id(&local_ref),
+            repo.refname_to_id(upstream_ref),
+        ) {
+            return calculate_ahead_behind_from_oids(repo, local_oid, upstream_oid);
+        }
+    }
+
+    Ok((0, 0))
+}
+
+/// 从OID计算ahead/behind
+fn calculate_ahead_behind_from_oids(
+    repo: &Repository,
+    local_oid: git2::Oid,
+    upstream_oid: git2::Oid,
+) -> Result<(u32, u32), GitError> {
+    match repo.graph_ahead_behind(local_oid, upstream_oid) {
+        Ok((ahead, behind)) => Ok((ahead as u32, behind as u32)),
+        Err(_) => Ok((0, 0)),
+    }
+}
+
+/// 支持Token认证的fetch操作
+pub fn fetch_remote_with_token(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    token_cache: Option<String>,
+    prune: bool,
+    refspecs: Option<&[String]>,
+) -> Result<SyncResult, GitError> {
+    fetch_remote_with_auth(
+        repo_path,
+        remote_name,
+        token_cache,
+        prune,
+        refspecs,
+        AuthOptions::default(),
+    )
+}
+
+/// 支持Token认证、指定SSH私钥和交互式凭据输入的fetch操作
+pub fn fetch_remote_with_auth(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    token_cache: Option<String>,
+    prune: bool,
+    refspecs: Option<&[String]>,
+    auth: AuthOptions,
+) -> Result<SyncResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    // 获取远程仓库名称
+    let remote_name = if let Some(name) = remote_name {
+        name.to_string()
+    } else {
+        // 如果没有指定远程名称，尝试获取默认远程
+        get_default_remote_name(&repo)?
+    };
+
+    let mut remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
+
+    // 获取远程URL
+    let remote_url = remote.url().unwrap_or("").to_string();
+    log::debug!("Fetch操作使用远程URL: {}", remote_url);
+
+    // 创建支持Token认证的回调
+    let attempts = auth.attempts();
+    let callbacks =
+        create_authenticated_callbacks(&remote_url, token_cache, auth.ssh_key, &attempts);
+
+    // 设置fetch选项
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+    if prune {
+        fetch_options.prune(FetchPrune::On);
+    }
+
+    // 执行fetch操作
+    let refspecs = resolve_fetch_refspecs(&remote, refspecs)?;
+
+    match remote.fetch(&refspecs, Some(&mut fetch_options), None) {
+        Ok(()) => {
+            record_last_sync(repo_path);
+
+            // 获取更新后的ahead/behind状态
+            let (ahead, behind) = fetched_ahead_behind_count(&repo, &remote_name)?;
+
+            Ok(SyncResult {
+                success: true,
+                message: "成功获取远程变更".to_string(),
+                ahead,
+                behind,
+                ..Default::default()
+            })
+        }
+        Err(e) => {
+            log::error!("Fetch操作失败: {}", e);
+            Err(attempts.to_error(e))
+        }
+    }
+}
+
+/// 获取远程变更并报告上游新增的提交，不合并也不修改本地分支
+///
+/// 提交列表为 `HEAD..@{upstream}` 范围内的提交
+pub fn fetch_and_report(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    token_cache: Option<String>,
+) -> Result<FetchReport, GitError> {
+    fetch_remote_with_token(repo_path, remote_name, token_cache, false, None)?;
+
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let head = repo.head().map_err(GitError::Git)?;
+    let branch_name = head.shorthand().unwrap_or("HEAD").to_string();
+    let head_oid = head.target().ok_or_else(|| GitError::Unknown {
+        message: "无法获取HEAD目标".to_string(),
+    })?;
+
+    let upstream_name = repo
+        .branch_upstream_name(&format!("refs/heads/{}", branch_name))
+        .map_err(|e| upstream_error(e, &branch_name))?;
+    let upstream_oid = repo
+        .refname_to_id(upstream_name.as_str().unwrap_or_default())
+        .map_err(GitError::Git)?;
+
+    let mut revwalk = repo.revwalk().map_err(GitError::Git)?;
+    revwalk
+        .set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)
+        .map_err(GitError::Git)?;
+    revwalk.push(upstream_oid).map_err(GitError::Git)?;
+    revwalk.hide(head_oid).map_err(GitError::Git)?;
+
+    let new_commits = revwalk
+        .map(|oid| {
+            let oid = oid.map_err(GitError::Git)?;
+            let commit = repo.find_commit(oid).map_err(GitError::Git)?;
+            Ok(commit_to_history_item(&commit))
+        })
+        .collect::<Result<Vec<_>, GitError>>()?;
+
+    Ok(FetchReport {
+        behind: new_commits.len() as u32,
+        new_commits,
+    })
+}
+
+/// 预览拉取会带来的文件变更（不执行合并）
+///
+/// `fetch` 为 true 时先获取远程，否则使用已获取的远程跟踪分支；比较当前分支与上游的
+/// 合并基础到上游之间的变更（即上游新增提交修改的文件），同时有未提交本地变更的文件
+/// 标记为 `potential_conflict`
+pub fn preview_pull_changes(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    token_cache: Option<String>,
+    fetch: bool,
+) -> Result<Vec<FileStatus>, GitError> {
+    if fetch {
+        fetch_remote_with_token(repo_path, remote_name, token_cache, false, None)?;
+    }
+
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    let head = repo.head().map_err(GitError::Git)?;
+    let branch_name = head.shorthand().unwrap_or("HEAD").to_string();
+    let head_oid = head.target().ok_or_else(|| GitError::Unknown {
+        message: "无法获取HEAD目标".to_string(),
+    })?;
+
+    let upstream_name = repo
+        .branch_upstream_name(&format!("refs/heads/{}", branch_name))
+        .map_err(|e| upstream_error(e, &branch_name))?;
+    let upstream_oid = repo
+        .refname_to_id(upstream_name.as_str().unwrap_or_default())
+        .map_err(GitError::Git)?;
+
+    let base_tree = match repo.merge_base(head_oid, upstream_oid) {
+        Ok(base) => Some(
+            repo.find_commit(base)
+                .and_then(|commit| commit.tree())
+                .map_err(GitError::Git)?,
+        ),
+        Err(_) => None,
+    };
+    let upstream_tree = repo
+        .find_commit(upstream_oid)
+        .and_then(|commit| commit.tree())
+        .map_err(GitError::Git)?;
+
+    let mut diff = repo
+        .diff_tree_to_tree(base_tree.as_ref(), Some(&upstream_tree), None)
+        .map_err(GitError::Git)?;
+    let mut find_options = git2::DiffFindOptions::new();
+    find_options.renames(true);
+    diff.find_similar(Some(&mut find_options))
+        .map_err(GitError::Git)?;
+
+    // 收集有未提交变更的路径（含未跟踪文件）
+    let mut status_options = StatusOptions::new();
+    status_options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true);
+    let dirty: std::collections::HashSet<String> = repo
+        .statuses(Some(&mut status_options))
+        .map_err(GitError::Git)?
+        .iter()
+        .filter(|entry| entry.status() != Status::CURRENT)
+        .filter_map(|entry| entry.path().map(String::from))
+        .collect();
+
+    let files = collect_file_diffs(&diff)?
+        .into_iter()
+        .map(|file| {
+            let potential_conflict = dirty.contains(&file.path)
+                || file
+                    .old_path
+                    .as_ref()
+                    .is_some_and(|old| dirty.contains(old));
+            FileStatus {
+                path: file.path,
+                status: file.status,
+                staged: false,
+                additions: file.additions,
+                deletions: file.deletions,
+                potential_conflict,
+            }
+        })
+        .collect();
+
+    Ok(files)
+}
+
+/// 支持Token认证的push操作
+pub fn push_remote_with_token(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    force: bool,
+    token_cache: Option<String>,
+) -> Result<SyncResult, GitError> {
+    push_remote_with_progress(
+        repo_path,
+        remote_name,
+        force,
+        token_cache,
+        AuthOptions::default(),
+        None,
+        |_| {},
+    )
+}
+
+/// 推送本地变更并报告打包、上传进度（支持Token认证）
+///
+/// `auth` 为SSH私钥和交互式凭据输入等认证设置；
+/// `packbuilder_parallelism` 为打包对象时使用的线程数，None 时由 libgit2 按 CPU 核心数决定
+pub fn push_remote_with_progress<F>(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    force: bool,
+    token_cache: Option<String>,
+    auth: AuthOptions,
+    packbuilder_parallelism: Option<u32>,
+    on_progress: F,
+) -> Result<SyncResult, GitError>
+where
+    F: FnMut(PushProgress),
+{
+    let on_progress = std::cell::RefCell::new(on_progress);
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    // 获取远程仓库名称
+    let remote_name = if let Some(name) = remote_name {
+        name.to_string()
+    } else {
+        // 如果没有指定远程名称，尝试获取默认远程
+        get_default_remote_name(&repo)?
+    };
+
+    let mut remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
+
+    // 获取当前分支
+    let head = repo.head().map_err(GitError::Git)?;
+    let branch_name = head.shorthand().unwrap_or("HEAD");
+
+    // 获取远程URL
+    let remote_url = remote.url().unwrap_or("").to_string();
+    log::debug!("Push操作使用远程URL: {}", remote_url);
+
+    // 创建支持Token认证的回调
+    let attempts = auth.attempts();
+    // 记录服务器对引用更新的拒绝信息
+    let rejection: std::cell::RefCell<Option<String>> = std::cell::RefCell::new(None);
+    // 打包和上传进度回调
+    let report = |stage: &str, current: usize, total: usize, bytes: usize| {
+        (*on_progress.borrow_mut())(PushProgress {
+            repo_path: repo_path.to_string(),
+            stage: stage.to_string(),
+            current,
+            total,
+            bytes,
+        });
+    };
+    let mut callbacks =
+        create_authenticated_callbacks(&remote_url, token_cache, auth.ssh_key, &attempts);
+    callbacks.push_update_reference(|refname, status| {
+        record_push_rejection(&rejection, refname, status)
+    });
+    callbacks.pack_progress(|_stage, current, total| report("packing", current, total, 0));
+    callbacks
+        .push_transfer_progress(|current, total, bytes| report("uploading", current, total, bytes));
+
+    // 设置push选项
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+    if let Some(threads) = packbuilder_parallelism {
+        push_options.packbuilder_parallelism(threads);
+    }
+
+    // 构建refspec
+    let refspec = if force {
+        format!("+refs/heads/{}:refs/heads/{}", branch_name, branch_name)
+    } else {
+        format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name)
+    };
+
+    // 执行push操作
+    let pushed = remote.push(&[&refspec], Some(&mut push_options));
+    let rejection = rejection.borrow_mut().take();
+    match (pushed, rejection) {
+        (Ok(()), None) => {
+            record_last_sync(repo_path);
+
+            // 获取更新后的ahead/behind状态
+            let (ahead, behind) = get_ahead_behind_count(&repo)?;
+
+            Ok(SyncResult {
+                success: true,
+                message: "成功推送到远程仓库".to_string(),
+                ahead,
+                behind,
+                ..Default::default()
+            })
+        }
+        (Ok(()), Some(message)) => Err(push_rejection_error(branch_name, &message)),
+        (Err(e), _) if is_non_fast_forward_error(&e) => {
+            log::error!("Push被拒绝（非快进）: {}", e);
+            Err(GitError::NonFastForward {
+                branch: branch_name.to_string(),
+            })
+        }
+        (Err(e), _) => {
+            log::error!("Push操作失败: {}", e);
+            Err(attempts.to_error(e))
+        }
+    }
+}
+
+/// 创建提交并推送到远程仓库
+///
+/// 推送失败时不会回滚提交，而是在结果中标明“已在本地提交但推送失败”
+pub fn commit_and_push(
+    repo_path: &str,
+    options: &CommitOptions,
+    remote_name: Option<&str>,
+    force: bool,
+    token_cache: Option<String>,
+) -> Result<CommitAndPushResult, GitError> {
+    let commit_sha = create_commit(repo_path, options)?;
+
+    match push_remote_with_token(repo_path, remote_name, force, token_cache) {
+        Ok(result) => Ok(CommitAndPushResult {
+            commit_sha,
+            pushed: true,
+            push_result: Some(result),
+            push_error: None,
+        }),
+        Err(e) => {
+            log::warn!("提交 {} 已在本地创建，但推送失败: {}", commit_sha, e);
+            Ok(CommitAndPushResult {
+                commit_sha,
+                pushed: false,
+                push_result: None,
+                push_error: Some(e.to_string()),
+            })
+        }
+    }
+}
+
+/// 预览推送结果（不实际推送）
+///
+/// 基于本地的远程跟踪分支计算将要推送的提交，以及是否为快进推送；
+/// 结果反映的是上次 fetch 时的远程状态
+pub fn push_preview(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    branch: Option<&str>,
+) -> Result<PushPreview, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    let remote_name = match remote_name {
+        Some(name) => name.to_string(),
+        None => get_default_remote_name(&repo)?,
+    };
+    let branch = match branch {
+        Some(name) => name.to_string(),
+        None => get_current_branch(&repo)?,
+    };
+
+    let local_oid = repo
+        .refname_to_id(&format!("refs/heads/{}", branch))
+        .map_err(|_| GitError::Unknown {
+            message: format!("本地分支 '{}' 不存在", branch),
+        })?;
+
+    let remote_ref = format!("refs/remotes/{}/{}", remote_name, branch);
+    let remote_oid = repo.refname_to_id(&remote_ref).ok();
+
+    // 将要推送的提交：本地可达但远程跟踪分支不可达的提交
+    let mut revwalk = repo.revwalk().map_err(GitError::Git)?;
+    revwalk.push(local_oid).map_err(GitError::Git)?;
+    match remote_oid {
+        Some(oid) => revwalk.hide(oid).map_err(GitError::Git)?,
+        // 远程尚无该分支时，排除远程上已有的所有提交
+        None => revwalk
+            .hide_glob(&format!("refs/remotes/{}/*", remote_name))
+            .map_err(GitError::Git)?,
+    }
+    revwalk
+        .set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)
+        .map_err(GitError::Git)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let oid = oid.map_err(GitError::Git)?;
+        let commit = repo.find_commit(oid).map_err(GitError::Git)?;
+        commits.push(commit_to_history_item(&commit));
+    }
+
+    let (ahead, behind) = match remote_oid {
+        Some(oid) => {
+            let (ahead, behind) = repo
+                .graph_ahead_behind(local_oid, oid)
+                .map_err(GitError::Git)?;
+            (ahead as u32, behind as u32)
+        }
+        None => (commits.len() as u32, 0),
+    };
+
+    Ok(PushPreview {
+        remote_ref: remote_oid.map(|_| remote_ref),
+        remote_name,
+        branch,
+        ahead,
+        behind,
+        is_fast_forward: behind == 0,
+        requires_force: behind > 0,
+        commits,
+    })
+}
+
+/// 删除远程分支
+///
+/// 通过推送删除refspec（`:refs/heads/<branch>`）在服务器上删除分支，
+/// 成功后同时清理本地对应的远程跟踪分支
+pub fn delete_remote_branch(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    branch_name: &str,
+    token_cache: Option<String>,
+    auth: AuthOptions,
+) -> Result<SwitchResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    // 获取远程仓库名称
+    let remote_name = if let Some(name) = remote_name {
+        name.to_string()
+    } else {
+        get_default_remote_name(&repo)?
+    };
+
+    log::debug!("删除远程分支: {}/{}", remote_name, branch_name);
+
+    let mut remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
+    let remote_url = remote.url().unwrap_or("").to_string();
+
+    // 记录服务器对引用更新的拒绝信息（例如受保护分支）
+    let rejection: std::cell::RefCell<Option<String>> = std::cell::RefCell::new(None);
+
+    let attempts = auth.attempts();
+    let mut callbacks =
+        create_authenticated_callbacks(&remote_url, token_cache, auth.ssh_key, &attempts);
+    callbacks.push_update_reference(|refname, status| {
+        record_push_rejection(&rejection, refname, status)
+    });
+
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+
+    let refspec = format!(":refs/heads/{}", branch_name);
+    if let Err(e) = remote.push(&[&refspec], Some(&mut push_options)) {
+        log::error!("删除远程分支失败: {}", e);
+        if e.code() == git2::ErrorCode::Auth {
+            return Err(attempts.to_error(e));
+        }
+        return Err(classify_remote_error(e, &remote_url));
+    }
+    drop(push_options);
+
+    if let Some(message) = rejection.into_inner() {
+        return Err(GitError::Unknown {
+            message: format!("远程拒绝删除分支 '{}': {}", branch_name, message),
+        });
+    }
+
+    // 确保本地的远程跟踪分支也被删除
+    let tracking_ref = format!("refs/remotes/{}/{}", remote_name, branch_name);
+    if let Ok(mut reference) = repo.find_reference(&tracking_ref) {
+        reference.delete().map_err(GitError::Git)?;
+        log::debug!("已删除远程跟踪分支: {}", tracking_ref);
+    }
+
+    Ok(SwitchResult {
+        success: true,
+        message: format!("成功删除远程分支 '{}/{}'", remote_name, branch_name),
+        has_uncommitted_changes: false,
+        uncommitted_files: vec![],
+        is_detached: false,
+        ..Default::default()
+    })
+}
+
+/// 校验分支名称是否符合 Git 引用命名规则，不符合时返回说明具体原因的错误
+pub fn validate_branch_name(name: &str) -> Result<(), GitError> {
+    let invalid = |reason: &str| {
+        Err(GitError::InvalidBranchName {
+            name: name.to_string(),
+            reason: reason.to_string(),
+        })
+    };
+
+    if name.trim().is_empty() {
+        return invalid("分支名称不能为空");
+    }
+    if name.chars().any(char::is_whitespace) {
+        return invalid("不能包含空格");
+    }
+    if name.contains("..") {
+        return invalid("不能包含 '..'");
+    }
+    if name.starts_with('/') || name.ends_with('/') || name.contains("//") {
+        return invalid("不能以 '/' 开头或结尾，也不能包含连续的 '/'");
+    }
+    if name == "@" || name.contains("@{") {
+        return invalid("不能为 '@' 或包含 '@{'");
+    }
+    if name.starts_with('-') {
+        return invalid("不能以 '-' 开头");
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| c.is_control() || "~^:?*[\\".contains(*c))
+    {
+        return invalid(&format!("不能包含字符 '{}'", c.escape_default()));
+    }
+    if name.ends_with('.') || name.ends_with(".lock") {
+        return invalid("不能以 '.' 或 '.lock' 结尾");
+    }
+    if name
+        .split('/')
+        .any(|component| component.starts_with('.') || component.ends_with(".lock"))
+    {
+        return invalid("路径中的每一级都不能以 '.' 开头或以 '.lock' 结尾");
+    }
+    if !git2::Reference::is_valid_name(&format!("refs/heads/{}", name)) {
+        return invalid("不符合 Git 引用命名规则");
+    }
+
+    Ok(())
+}
+
+/// 创建新分支
+pub fn create_branch(
+    repo_path: &str,
+    branch_name: &str,
+    from_commit: Option<&str>,
+    checkout: bool,
+) -> Result<SwitchResult, GitError> {
+    validate_branch_name(branch_name)?;
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    log::debug!(
+        "创建分支: {} (from: {:?}, checkout: {})",
+        branch_name,
+        from_commit,
+        checkout
+    );
+
+    // 检查分支名称是否已存在
+    if let Ok(_) = repo.find_branch(branch_name, git2::BranchType::Local) {
+        return Err(GitError::Unknown {
+            message: format!("分支 '{}' 已存在", branch_name),
+        });
+    }
+
+    // 获取目标提交
+    let target_commit = if let Some(commit_ref) = from_commit {
+        log::debug!("从指定引用创建分支: {}", commit_ref);
+
+        resolve_commit_input(&repo, commit_ref).ok_or_else(|| GitError::Unknown {
+            message: format!("无法找到分支、提交或引用: '{}'", commit_ref),
+        })?
+    } else {
+        // 从当前HEAD创建
+        log::debug!("从当前HEAD创建分支");
+        let head = repo.head().map_err(GitError::Git)?;
+        let oid = head.target().ok_or_else(|| GitError::Unknown {
+            message: "无法获取HEAD目标".to_string(),
+        })?;
+        repo.find_commit(oid).map_err(GitError::Git)?
+    };
+
+    // 创建分支
+    let _branch = repo
+        .branch(branch_name, &target_commit, false)
+        .map_err(GitError::Git)?;
+    log::debug!("分支创建成功: {}", branch_name);
+
+    // 如果需要切换到新分支
+    if checkout {
+        return switch_branch(repo_path, branch_name);
+    }
+
+    Ok(SwitchResult {
+        success: true,
+        message: format!("成功创建分支 '{}'", branch_name),
+        has_uncommitted_changes: false,
+        uncommitted_files: vec![],
+        is_detached: false,
+        ..Default::default()
+    })
+}
+
+/// 切换分支
+pub fn switch_branch(repo_path: &str, branch_name: &str) -> Result<SwitchResult, GitError> {
+    switch_branch_with_progress(repo_path, branch_name, |_| {})
+}
+
+/// 切换分支，检出工作区时通过 `on_progress` 报告进度
+pub fn switch_branch_with_progress<F>(
+    repo_path: &str,
+    branch_name: &str,
+    on_progress: F,
+) -> Result<SwitchResult, GitError>
+where
+    F: FnMut(CheckoutProgress),
+{
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    log::debug!("切换分支: {}", branch_name);
+
+    // 检查是否有未提交的变更
+    let status = get_repository_status(repo_path)?;
+    let has_uncommitted = !status.files.is_empty();
+    let uncommitted_files: Vec<String> = status.files.iter().map(|f| f.path.clone()).collect();
+
+    if has_uncommitted {
+        return Ok(SwitchResult {
+            success: false,
+            message: "存在未提交的变更，请先提交或暂存变更".to_string(),
+            has_uncommitted_changes: true,
+            uncommitted_files,
+            is_detached: false,
+            ..Default::default()
+        });
+    }
+
+    // 查找目标分支
+    let branch = repo
+        .find_branch(branch_name, git2::BranchType::Local)
+        .map_err(|_| GitError::Unknown {
+            message: format!("分支 '{}' 不存在", branch_name),
+        })?;
+
+    // 目标分支已在其他工作树中检出时无法切换
+    let refname = format!("refs/heads/{}", branch_name);
+    if let Some(worktree_path) = find_worktree_with_branch(&repo, &refname) {
+        return Ok(SwitchResult {
+            success: false,
+            message: format!(
+                "分支 '{}' 已在其他工作树中检出: {}",
+                branch_name, worktree_path
+            ),
+            has_uncommitted_changes: false,
+            uncommitted_files: vec![],
+            is_detached: false,
+            worktree_path: Some(worktree_path),
+        });
+    }
+
+    // 获取分支的引用
+    let branch_ref = branch.get();
+    let branch_oid = branch_ref.target().ok_or_else(|| GitError::Unknown {
+        message: "无法获取分支目标".to_string(),
+    })?;
+
+    // 检出工作目录（先检出再移动HEAD，检出冲突时HEAD保持不变）
+    let commit = repo.find_commit(branch_oid).map_err(GitError::Git)?;
+    let tree = commit.tree().map_err(GitError::Git)?;
+
+    let conflicts = checkout_tree_with_progress(&repo, &tree, on_progress)?;
+    if !conflicts.is_empty() {
+        log::warn!("切换分支时发现冲突文件: {:?}", conflicts);
+        return Ok(SwitchResult {
+            success: false,
+            message: format!(
+                "切换到分支 '{}' 会覆盖以下文件的本地变更，请先提交或暂存变更",
+                branch_name
+            ),
+            has_uncommitted_changes: true,
+            uncommitted_files: conflicts,
+            is_detached: false,
+            ..Default::default()
+        });
+    }
+
+    // 设置HEAD到目标分支
+    repo.set_head(&refname).map_err(GitError::Git)?;
+
+    log::debug!("分支切换成功: {}", branch_name);
+
+    Ok(SwitchResult {
+        success: true,
+        message: format!("成功切换到分支 '{}'", branch_name),
+        has_uncommitted_changes: false,
+        uncommitted_files: vec![],
+        is_detached: false,
+        ..Default::default()
+    })
+}
+
+/// 查找已检出指定分支的其他工作树（包括主工作树），返回其路径
+fn find_worktree_with_branch(repo: &Repository, refname: &str) -> Option<String> {
+    let current = repo.workdir().and_then(|path| path.canonicalize().ok());
+    let main = Repository::open(repo.commondir()).ok()?;
+
+    let mut checkouts = vec![main];
+    if let Ok(names) = checkouts[0].worktrees() {
+        for name in names.iter().flatten() {
+            let linked = checkouts[0]
+                .find_worktree(name)
+                .and_then(|worktree| Repository::open_from_worktree(&worktree));
+            if let Ok(linked) = linked {
+                checkouts.push(linked);
+            }
+        }
+    }
+
+    checkouts.iter().find_map(|checkout| {
+        let workdir = checkout.workdir()?;
+        if workdir.canonicalize().ok() == current {
+            return None;
+        }
+        let head = checkout.find_reference("HEAD").ok()?;
+        if head.symbolic_target() != Some(refname) {
+            return None;
+        }
+        let path = workdir.to_string_lossy();
+        Some(path.trim_end_matches(['/', '\\']).to_string())
+    })
+}
+
+/// 安全地检出树对象，不覆盖本地未提交的变更
+///
+/// 检出成功返回空列表；若本地变更与检出冲突，返回冲突文件列表且不修改工作目录
+fn checkout_tree_safely(repo: &Repository, tree: &git2::Tree) -> Result<Vec<String>, GitError> {
+    checkout_tree_with_progress(repo, tree, |_| {})
+}
+
+/// 同 [`checkout_tree_safely`]，检出过程中通过 `on_progress` 报告进度
+fn checkout_tree_with_progress<F>(
+    repo: &Repository,
+    tree: &git2::Tree,
+    mut on_progress: F,
+) -> Result<Vec<String>, GitError>
+where
+    F: FnMut(CheckoutProgress),
+{
+    let mut conflicts = Vec::new();
+
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout
+        .safe()
+        .recreate_missing(true)
+        .notify_on(git2::CheckoutNotificationType::CONFLICT)
+        .notify(|_why, path, _baseline, _target, _workdir| {
+            if let Some(path) = path.and_then(|p| p.to_str()) {
+                conflicts.push(path.to_string());
+            }
+            true
+        })
+        .progress(|path, completed_steps, total_steps| {
+            on_progress(CheckoutProgress {
+                completed_steps,
+                total_steps,
+                current_path: path.map(|p| p.to_string_lossy().to_string()),
+            })
+        });
+
+    let result = repo.checkout_tree(tree.as_object(), Some(&mut checkout));
+    drop(checkout);
+
+    match result {
+        Ok(()) => Ok(vec![]),
+        Err(e) if e.code() == git2::ErrorCode::Conflict && !conflicts.is_empty() => Ok(conflicts),
+        Err(e) => Err(index_error(repo, e)),
+    }
+}
+
+/// 以分离HEAD方式检出标签或任意提交
+pub fn checkout_commit(repo_path: &str, commit_ref: &str) -> Result<SwitchResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    log::debug!("检出提交: {}", commit_ref);
+
+    // 检查是否有未提交的变更
+    let status = get_repository_status(repo_path)?;
+    if !status.files.is_empty() {
+        return Ok(SwitchResult {
+            success: false,
+            message: "存在未提交的变更，请先提交或暂存变更".to_string(),
+            has_uncommitted_changes: true,
+            uncommitted_files: status.files.iter().map(|f| f.path.clone()).collect(),
+            is_detached: false,
+            ..Default::default()
+        });
+    }
+
+    // 解析标签、提交SHA或引用名称
+    let commit = repo
+        .revparse_single(commit_ref)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| GitError::Unknown {
+            message: format!("无法找到标签、提交或引用: '{}'", commit_ref),
+        })?;
+
+    // 先检出工作目录，再移动HEAD，避免检出失败时HEAD已被修改
+    let tree = commit.tree().map_err(GitError::Git)?;
+    let conflicts = checkout_tree_safely(&repo, &tree)?;
+    if !conflicts.is_empty() {
+        return Ok(SwitchResult {
+            success: false,
+            message: format!("检出 '{}' 会覆盖以下文件的本地变更", commit_ref),
+            has_uncommitted_changes: true,
+            uncommitted_files: conflicts,
+            is_detached: false,
+            ..Default::default()
+        });
+    }
+    repo.set_head_detached(commit.id()).map_err(GitError::Git)?;
+
+    let short_sha = &commit.id().to_string()[..7];
+    log::debug!("已检出提交: {} ({})", commit_ref, short_sha);
+
+    Ok(SwitchResult {
+        success: true,
+        message: format!(
+            "已检出 '{}' ({})，当前处于分离HEAD状态",
+            commit_ref, short_sha
+        ),
+        has_uncommitted_changes: false,
+        uncommitted_files: vec![],
+        is_detached: true,
+        ..Default::default()
+    })
+}
+
+/// 按分支名、完整SHA、引用名、标签名、缩写SHA（及其他修订表达式）的顺序解析用户输入
+///
+/// 标签和符号引用会解析到其指向的提交，无法解析时返回 None
+fn resolve_commit_input<'r>(repo: &'r Repository, input: &str) -> Option<git2::Commit<'r>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+
+    // 首先尝试作为分支名称解析
+    if let Ok(branch) = repo.find_branch(input, git2::BranchType::Local) {
+        if let Ok(commit) = branch.get().peel_to_commit() {
+            return Some(commit);
+        }
+    }
+
+    // 然后尝试作为完整提交SHA解析
+    if input.len() == 40 {
+        if let Ok(commit) = Oid::from_str(input).and_then(|oid| repo.find_commit(oid)) {
+            return Some(commit);
+        }
+    }
+
+    // 再尝试作为引用名称或完整的引用路径解析
+    for name in [
+        input.to_string(),
+        format!("refs/heads/{}", input),
+        format!("refs/tags/{}", input),
+    ] {
+        if let Ok(commit) = repo
+            .find_reference(&name)
+            .and_then(|reference| reference.peel_to_commit())
+        {
+            return Some(commit);
+        }
+    }
+
+    // 最后交给 revparse 处理缩写SHA、远程分支以及 HEAD~1 等表达式
+    repo.revparse_single(input)
+        .and_then(|object| object.peel_to_commit())
+        .ok()
+}
+
+/// 检查分支、标签、引用名或（缩写）SHA 是否能解析到提交，返回完整的提交SHA
+pub fn ref_exists(repo_path: &str, ref_or_sha: &str) -> Result<Option<String>, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+    Ok(resolve_commit_input(&repo, ref_or_sha).map(|commit| commit.id().to_string()))
+}
+
+/// 删除分支
+pub fn delete_branch(
+    repo_path: &str,
+    branch_name: &str,
+    force: bool,
+) -> Result<SwitchResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    log::debug!("删除分支: {} (force: {})", branch_name, force);
+
+    // 检查是否是当前分支
+    if let Ok(current_name) = get_current_branch_name(&repo) {
+        if current_name.as_ref() == Some(&branch_name.to_string()) {
+            return Err(GitError::Unknown {
+                message: "不能删除当前分支".to_string(),
+            });
+        }
+    }
+
+    // 查找分支
+    let mut branch = repo
+        .find_branch(branch_name, git2::BranchType::Local)
+        .map_err(|_| GitError::Unknown {
+            message: format!("分支 '{}' 不存在", branch_name),
+        })?;
+
+    // 如果不是强制删除，检查分支是否已合并
+    if !force {
+        // 检查分支是否有未合并的提交
+        if let Ok(upstream) = branch.upstream() {
+            let branch_oid = branch.get().target().unwrap();
+            let upstream_oid = upstream.get().target().unwrap();
+
+            if let Ok((ahead, _)) = repo.graph_ahead_behind(branch_oid, upstream_oid) {
+                if ahead > 0 {
+                    return Err(GitError::Unknown {
+                        message: format!(
+                            "分支 '{}' 包含未合并的提交，使用强制删除或先合并分支",
+                            branch_name
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    // 删除分支
+    branch.delete().map_err(GitError::Git)?;
+    log::debug!("分支删除成功: {}", branch_name);
+
+    Ok(SwitchResult {
+        success: true,
+        message: format!("成功删除分支 '{}'", branch_name),
+        has_uncommitted_changes: false,
+        uncommitted_files: vec![],
+        is_detached: false,
+        ..Default::default()
+    })
+}
+
+/// 从远程分支检出本地分支
+pub fn checkout_remote_branch(
+    repo_path: &str,
+    remote_branch_name: &str,
+    local_branch_name: Option<&str>,
+) -> Result<SwitchResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    log::debug!(
+        "检出远程分支: {} -> {:?}",
+        remote_branch_name,
+        local_branch_name
+    );
+
+    // 提取本地分支名称
+    let local_name = if let Some(name) = local_branch_name {
+        name.to_string()
+    } else {
+        extract_local_branch_name(remote_branch_name)
+    };
+
+    log::debug!("本地分支名称: {}", local_name);
+
+    // 检查本地分支是否已存在
+    if let Ok(_) = repo.find_branch(&local_name, git2::BranchType::Local) {
+        return Err(GitError::Unknown {
+            message: format!("本地分支 '{}' 已存在", local_name),
+        });
+    }
+
+    // 查找远程分支
+    let remote_branch = repo
+        .find_branch(remote_branch_name, git2::BranchType::Remote)
+        .map_err(|_| GitError::Unknown {
+            message: format!("远程分支 '{}' 不存在", remote_branch_name),
+        })?;
+
+    // 获取远程分支的提交
+    let remote_ref = remote_branch.get();
+    let remote_oid = remote_ref.target().ok_or_else(|| GitError::Unknown {
+        message: format!("无法获取远程分支 '{}' 的目标", remote_branch_name),
+    })?;
+    let remote_commit = repo.find_commit(remote_oid).map_err(GitError::Git)?;
+
+    // 创建本地分支
+    let local_branch = repo
+        .branch(&local_name, &remote_commit, false)
+        .map_err(GitError::Git)?;
+    log::debug!("本地分支创建成功: {}", local_name);
+
+    // 设置上游跟踪分支
+    if let Err(e) = set_upstream_branch(&repo, &local_name, remote_branch_name) {
+        log::warn!("设置上游分支失败: {}", e);
+        // 不影响主要功能，继续执行
+    }
+
+    // 切换到新创建的本地分支
+    let switch_result = switch_branch(repo_path, &local_name)?;
+
+    if switch_result.success {
+        Ok(SwitchResult {
+            success: true,
+            message: format!(
+                "成功检出远程分支 '{}' 到本地分支 '{}'",
+                remote_branch_name, local_name
+            ),
+            has_uncommitted_changes: false,
+            uncommitted_files: vec![],
+            is_detached: false,
+            ..Default::default()
+        })
+    } else {
+        // 如果切换失败，删除刚创建的分支
+        let mut branch_to_delete = local_branch;
+        let _ = branch_to_delete.delete();
+        Err(GitError::Unknown {
+            message: format!("检出成功但切换失败: {}", switch_result.message),
+        })
+    }
+}
+
+/// 根据远程 URL 确定 Pull Request 在远程仓库中的引用
+///
+/// GitLab 使用 `refs/merge-requests/<n>/head`，其他平台按 GitHub 的 `refs/pull/<n>/head` 处理
+pub fn pull_request_ref(remote_url: &str, pr_number: u64) -> String {
+    let host = extract_domain(remote_url).unwrap_or_default();
+    if host.contains("gitlab") {
+        format!("refs/merge-requests/{}/head", pr_number)
+    } else {
+        format!("refs/pull/{}/head", pr_number)
+    }
+}
+
+/// 获取并检出 Pull Request（GitLab 为 Merge Request）
+///
+/// PR 引用会被获取到 `refs/remotes/<remote>/pr/<n>`，然后基于它创建本地分支
+/// （默认为 `pr-<n>`）并切换；本地分支已存在时快进到 PR 的最新提交再切换，
+/// 无法快进（本地分支有额外提交或 PR 被强制推送）时不做修改并返回失败结果
+pub fn checkout_pull_request(
+    repo_path: &str,
+    remote_name: Option<&str>,
+    pr_number: u64,
+    local_branch: Option<&str>,
+    token_cache: Option<String>,
+) -> Result<SwitchResult, GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    let remote_name = match remote_name {
+        Some(name) => name.to_string(),
+        None => get_default_remote_name(&repo)?,
+    };
+    let remote = repo.find_remote(&remote_name).map_err(GitError::Git)?;
+    let source_ref = pull_request_ref(remote.url().unwrap_or(""), pr_number);
+    let tracking_ref = format!("refs/remotes/{}/pr/{}", remote_name, pr_number);
+
+    log::debug!("检出 PR #{}: {} -> {}", pr_number, source_ref, tracking_ref);
+
+    // 先删除旧的跟踪引用，以便判断 PR 引用是否真实存在
+    if let Ok(mut reference) = repo.find_reference(&tracking_ref) {
+        reference.delete().map_err(GitError::Git)?;
+    }
+
+    let refspecs = [format!("+{}:{}", source_ref, tracking_ref)];
+    fetch_remote_with_token(
+        repo_path,
+        Some(&remote_name),
+        token_cache,
+        false,
+        Some(&refspecs[..]),
+    )?;
+
+    let pr_oid = repo
+        .refname_to_id(&tracking_ref)
+        .map_err(|_| GitError::Unknown {
+            message: format!(
+                "远程仓库 '{}' 中不存在 PR #{}（{}）",
+                remote_name, pr_number, source_ref
+            ),
+        })?;
+
+    let local_name = local_branch
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("pr-{}", pr_number));
+
+    let pr_commit = repo.find_commit(pr_oid).map_err(GitError::Git)?;
+
+    if let Ok(branch) = repo.find_branch(&local_name, git2::BranchType::Local) {
+        return update_pull_request_branch(repo_path, &repo, branch, &pr_commit, pr_number);
+    }
+
+    let mut new_branch = repo
+        .branch(&local_name, &pr_commit, false)
+        .map_err(GitError::Git)?;
+
+    let switch_result = switch_branch(repo_path, &local_name)?;
+    if switch_result.success {
+        Ok(SwitchResult {
+            success: true,
+            message: format!("成功检出 PR #{} 到本地分支 '{}'", pr_number, local_name),
+            has_uncommitted_changes: false,
+            uncommitted_files: vec![],
+            is_detached: false,
+            ..Default::default()
+        })
+    } else {
+        // 切换失败时删除刚创建的分支
+        let _ = new_branch.delete();
+        Ok(switch_result)
+    }
+}
+
+/// 将已存在的 PR 本地分支快进到 PR 的最新提交并切换到该分支
+fn update_pull_request_branch(
+    repo_path: &str,
+    repo: &Repository,
+    mut branch: git2::Branch,
+    pr_commit: &git2::Commit,
+    pr_number: u64,
+) -> Result<SwitchResult, GitError> {
+    let local_name = branch
+        .name()
+        .map_err(GitError::Git)?
+        .unwrap_or_default()
+        .to_string();
+    let local_oid = branch.get().target().ok_or_else(|| GitError::Unknown {
+        message: format!("无法获取本地分支 '{}' 的目标", local_name),
+    })?;
+
+    if local_oid == pr_commit.id() {
+        log::debug!(
+            "本地分支 '{}' 已是 PR #{} 的最新提交",
+            local_name,
+            pr_number
+        );
+        return switch_branch(repo_path, &local_name);
+    }
+
+    if !repo
+        .graph_descendant_of(pr_commit.id(), local_oid)
+        .map_err(GitError::Git)?
+    {
+        return Ok(SwitchResult {
+            success: false,
+            message: format!(
+                "本地分支 '{}' 与 PR #{} 的最新提交已分叉，无法快进，请删除或重命名该分支后重试",
+                local_name, pr_number
+            ),
+            ..Default::default()
+        });
+    }
+
+    log::debug!(
+        "快进本地分支 '{}' 到 PR #{}: {}",
+        local_name,
+        pr_number,
+        pr_commit.id()
+    );
+    let reflog = format!("checkout pr #{}: fast-forward", pr_number);
+
+    if branch.is_head() {
+        // 当前分支需要同时更新工作区，存在未提交的变更时不快进
+        let status = get_repository_status(repo_path)?;
+        if !status.files.is_empty() {
+            return Ok(SwitchResult {
+                success: false,
+                message: "存在未提交的变更，请先提交或暂存变更".to_string(),
+                has_uncommitted_changes: true,
+                uncommitted_files: status.files.iter().map(|f| f.path.clone()).collect(),
+                ..Default::default()
+            });
+        }
+        let tree = pr_commit.tree().map_err(GitError::Git)?;
+        let conflicts = checkout_tree_safely(repo, &tree)?;
+        if !conflicts.is_empty() {
+            return Ok(SwitchResult {
+                success: false,
+                message: "快进会覆盖工作区中的文件，请先处理这些文件".to_string(),
+                uncommitted_files: conflicts,
+                ..Default::default()
+            });
+        }
+        branch
+            .get_mut()
+            .set_target(pr_commit.id(), &reflog)
+            .map_err(GitError::Git)?;
+    } else {
+        branch
+            .get_mut()
+            .set_target(pr_commit.id(), &reflog)
+            .map_err(GitError::Git)?;
+        let switch_result = switch_branch(repo_path, &local_name)?;
+        if !switch_result.success {
+            return Ok(switch_result);
+        }
+    }
+
+    Ok(SwitchResult {
+        success: true,
+        message: format!(
+            "已将本地分支 '{}' 快进到 PR #{} 的最新提交",
+            local_name, pr_number
+        ),
+        ..Default::default()
+    })
+}
+
+/// 从远程分支名称提取本地分支名称
+fn extract_local_branch_name(remote_branch_name: &str) -> String {
+    // "origin/feature/new-ui" -> "feature/new-ui"
+    if let Some(pos) = remote_branch_name.find('/') {
+        remote_branch_name[pos + 1..].to_string()
+    } else {
+        remote_branch_name.to_string()
+    }
+}
+
+/// 设置上游跟踪分支
+fn set_upstream_branch(
+    repo: &Repository,
+    local_branch: &str,
+    remote_branch: &str,
+) -> Result<(), GitError> {
+    log::debug!("设置上游分支: {} -> {}", local_branch, remote_branch);
+
+    // 解析远程名称和分支名称
+    let (remote_name, branch_name) = if let Some(pos) = remote_branch.find('/') {
+        let remote = &remote_branch[..pos];
+        let branch = &remote_branch[pos + 1..];
+        (remote, branch)
+    } else {
+        return Err(GitError::Unknown {
+            message: "无效的远程分支名称格式".to_string(),
+        });
+    };
+
+    // 设置配置
+    let mut config = repo.config().map_err(GitError::Git)?;
+
+    // 设置远程名称
+    config
+        .set_str(&format!("branch.{}.remote", local_branch), remote_name)
+        .map_err(GitError::Git)?;
+
+    // 设置合并引用
+    config
+        .set_str(
+            &format!("branch.{}.merge", local_branch),
+            &format!("refs/heads/{}", branch_name),
+        )
+        .map_err(GitError::Git)?;
+
+    log::debug!(
+        "上游分支设置成功: {} -> {}/{}",
+        local_branch,
+        remote_name,
+        branch_name
+    );
+    Ok(())
+}
+
+/// 设置或取消本地分支的上游分支
+///
+/// `remote_branch` 为远程跟踪分支的短名称（如 origin/main），None 表示取消上游
+pub fn set_branch_upstream(
+    repo_path: &str,
+    local_branch: &str,
+    remote_branch: Option<&str>,
+) -> Result<(), GitError> {
+    let repo = open_repository(repo_path).map_err(GitError::Git)?;
+
+    let mut branch = repo
+        .find_branch(local_branch, git2::BranchType::Local)
+        .map_err(|_| GitError::Unknown {
+            message: format!("本地分支 '{}' 不存在", local_branch),
+        })?;
+
+    if let Some(remote_branch) = remote_branch {
+        repo.find_branch(remote_branch, git2::BranchType::Remote)
+            .map_err(|_| GitError::Unknown {
+                message: format!("远程分支 '{}' 不存在", remote_branch),
+            })?;
+    }
+
+    branch.set_upstream(remote_branch).map_err(GitError::Git)?;
+
+    log::debug!("设置上游分支: {} -> {:?}", local_branch, remote_branch);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 初始化带有身份配置的测试仓库
+    fn init_repo(path: &Path) -> Repository {
+        let repo = Repository::init(path).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        repo
+    }
+
+    /// 写入文件并在当前分支上提交
+    fn commit_file(repo: &Repository, name: &str, content: &str, message: &str) -> git2::Oid {
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join(name), content).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+        let tree_id = index.write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let signature = repo.signature().unwrap();
+
+        let parents = match repo.head() {
+            Ok(head) => vec![head.peel_to_commit().unwrap()],
+            Err(_) => vec![],
+        };
+        let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parent_refs,
+        )
+        .unwrap()
+    }
+
+    /// 创建一个带初始提交的"远程"仓库，并克隆出本地仓库
+    fn setup_remote_and_clone(dir: &TempDir) -> (Repository, Repository) {
+        let remote_path = dir.path().join("remote");
+        let local_path = dir.path().join("local");
+
+        let remote = init_repo(&remote_path);
+        commit_file(&remote, "README.md", "hello\n", "initial commit");
+
+        let local = Repository::clone(remote_path.to_str().unwrap(), &local_path).unwrap();
+        let mut config = local.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+
+        (remote, local)
+    }
+
+    /// 创建一个裸远程仓库（带初始提交），并克隆出本地仓库
+    fn setup_bare_remote_and_clone(dir: &TempDir) -> (Repository, Repository) {
+        let seed_path = dir.path().join("seed");
+        let bare_path = dir.path().join("remote.git");
+        let local_path = dir.path().join("local");
+
+        let seed = init_repo(&seed_path);
+        commit_file(&seed, "README.md", "hello\n", "initial commit");
+
+        let bare = git2::build::RepoBuilder::new()
+            .bare(true)
+            .clone(seed_path.to_str().unwrap(), &bare_path)
+            .unwrap();
+
+        let local = Repository::clone(bare_path.to_str().unwrap(), &local_path).unwrap();
+        let mut config = local.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+
+        (bare, local)
+    }
+
+    #[test]
+    fn test_fetch_with_prune_removes_deleted_remote_branch() {
+        let dir = TempDir::new().unwrap();
+        let (remote, _) = setup_remote_and_clone(&dir);
+
+        // 在远程创建分支后再由本地获取
+        let head_commit = remote.head().unwrap().peel_to_commit().unwrap();
+        remote.branch("feature-x", &head_commit, false).unwrap();
+        let local_path = dir.path().join("local");
+        let local_path = local_path.to_str().unwrap();
+        fetch_remote(local_path, None, false, None).unwrap();
+
+        let has_feature = |path: &str| {
+            list_branches(path, None, None)
+                .unwrap()
+                .iter()
+                .any(|b| b.is_remote && b.name == "origin/feature-x")
+        };
+        assert!(has_feature(local_path));
+
+        // 远程删除分支，普通fetch不会清理
+        remote
+            .find_branch("feature-x", git2::BranchType::Local)
+            .unwrap()
+            .delete()
+            .unwrap();
+        fetch_remote(local_path, None, false, None).unwrap();
+        assert!(has_feature(local_path));
+
+        // 带prune的fetch会清理过期的远程跟踪分支
+        prune_remote(local_path, None).unwrap();
+        assert!(!has_feature(local_path));
+    }
+
+    #[test]
+    fn test_delete_remote_branch_removes_tracking_ref() {
+        let dir = TempDir::new().unwrap();
+        let (bare, local) = setup_bare_remote_and_clone(&dir);
+
+        // 创建本地分支并推送到远程
+        let head_commit = local.head().unwrap().peel_to_commit().unwrap();
+        local.branch("feature-x", &head_commit, false).unwrap();
+        local
+            .find_remote("origin")
+            .unwrap()
+            .push(&["refs/heads/feature-x:refs/heads/feature-x"], None)
+            .unwrap();
+        assert!(bare
+            .find_branch("feature-x", git2::BranchType::Local)
+            .is_ok());
+
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+        let result = delete_remote_branch(
+            &local_path,
+            Some("origin"),
+            "feature-x",
+            None,
+            AuthOptions::default(),
+        )
+        .unwrap();
+
+        assert!(result.success);
+        assert!(bare
+            .find_branch("feature-x", git2::BranchType::Local)
+            .is_err());
+        assert!(local
+            .find_reference("refs/remotes/origin/feature-x")
+            .is_err());
+    }
+
+    #[test]
+    fn test_checkout_commit_detaches_head() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let first = commit_file(&repo, "a.txt", "v1\n", "first");
+        commit_file(&repo, "a.txt", "v2\n", "second");
+
+        let first_commit = repo.find_commit(first).unwrap();
+        repo.tag_lightweight("v1.0", first_commit.as_object(), false)
+            .unwrap();
+
+        let result = checkout_commit(repo_path.to_str().unwrap(), "v1.0").unwrap();
+
+        assert!(result.success);
+        assert!(result.is_detached);
+        assert!(repo.head_detached().unwrap());
+        assert_eq!(repo.head().unwrap().target(), Some(first));
+        assert_eq!(
+            std::fs::read_to_string(repo_path.join("a.txt")).unwrap(),
+            "v1\n"
+        );
+    }
+
+    #[test]
+    fn test_checkout_reports_conflicting_local_edit() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let first = commit_file(&repo, "a.txt", "v1\n", "first");
+        commit_file(&repo, "a.txt", "v2\n", "second");
+
+        // 本地未提交的修改与目标版本冲突
+        std::fs::write(repo_path.join("a.txt"), "local edit\n").unwrap();
+
+        let old_tree = repo.find_commit(first).unwrap().tree().unwrap();
+        let conflicts = checkout_tree_safely(&repo, &old_tree).unwrap();
+
+        assert_eq!(conflicts, vec!["a.txt".to_string()]);
+        assert_eq!(
+            std::fs::read_to_string(repo_path.join("a.txt")).unwrap(),
+            "local edit\n"
+        );
+    }
+
+    #[test]
+    fn test_repository_access_reports_default_branch() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "a.txt", "v1\n", "first");
+        let head_branch = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        let result = test_repository_access(repo_path.to_str().unwrap(), None);
+
+        assert!(result.reachable);
+        assert!(result.error.is_none());
+        assert_eq!(result.default_branch, Some(head_branch));
+
+        let missing = test_repository_access(dir.path().join("missing").to_str().unwrap(), None);
+        assert!(!missing.reachable);
+        assert!(missing.error.is_some());
+    }
+
+    #[test]
+    fn test_list_remote_refs_returns_branches_tags_and_head() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let oid = commit_file(&repo, "a.txt", "v1\n", "first");
+        let commit = repo.find_commit(oid).unwrap();
+        repo.branch("develop", &commit, false).unwrap();
+        repo.tag_lightweight("v1.0", commit.as_object(), false)
+            .unwrap();
+        let head_branch = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        let refs = list_remote_refs(repo_path.to_str().unwrap(), None).unwrap();
+
+        assert!(refs
+            .iter()
+            .any(|r| r.kind == RemoteRefKind::Branch && r.name == "develop"));
+        assert!(refs
+            .iter()
+            .any(|r| r.kind == RemoteRefKind::Tag && r.name == "v1.0"));
+        let default = refs.iter().find(|r| r.is_default).unwrap();
+        assert_eq!(default.name, head_branch);
+        assert_eq!(default.oid, oid.to_string());
+    }
+
+    #[test]
+    fn test_remote_info_reports_non_main_default_branch() {
+        let dir = TempDir::new().unwrap();
+        let remote_path = dir.path().join("remote");
+        let local_path = dir.path().join("local");
+
+        // 远程仓库的默认分支为 develop
+        let remote = init_repo(&remote_path);
+        let oid = commit_file(&remote, "README.md", "hello\n", "initial commit");
+        let commit = remote.find_commit(oid).unwrap();
+        remote.branch("develop", &commit, false).unwrap();
+        remote.set_head("refs/heads/develop").unwrap();
+
+        let local = Repository::clone(remote_path.to_str().unwrap(), &local_path).unwrap();
+        let local_path_str = local_path.to_str().unwrap();
+
+        let info = get_remote_info(local_path_str).unwrap();
+        assert_eq!(info.remote_name, "origin");
+        assert_eq!(info.default_branch.as_deref(), Some("develop"));
+
+        // 删除本地的 origin/HEAD 后不访问网络，需显式刷新
+        if let Ok(mut reference) = local.find_reference("refs/remotes/origin/HEAD") {
+            reference.delete().unwrap();
+        }
+        let info = get_remote_info(local_path_str).unwrap();
+        assert_eq!(info.default_branch, None);
+
+        let refreshed = refresh_remote_default_branch(local_path_str, None, None).unwrap();
+        assert_eq!(refreshed.as_deref(), Some("develop"));
+        let info = get_remote_info(local_path_str).unwrap();
+        assert_eq!(info.default_branch.as_deref(), Some("develop"));
+    }
+
+    #[test]
+    fn test_fetch_records_last_sync() {
+        let dir = TempDir::new().unwrap();
+        let (_remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        assert_eq!(get_remote_info(&local_path).unwrap().last_sync, None);
+
+        fetch_remote(&local_path, Some("origin"), false, None).unwrap();
+
+        let last_sync = get_remote_info(&local_path).unwrap().last_sync;
+        assert!(last_sync.is_some());
+        assert!(last_sync.unwrap() <= chrono::Utc::now().timestamp());
+    }
+
+    #[test]
+    fn test_tracking_status_reports_no_upstream() {
+        let dir = TempDir::new().unwrap();
+        let (_remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        let status = get_tracking_status(&local_path).unwrap();
+        assert_eq!(status.upstream, format!("origin/{}", status.branch));
+        assert_eq!((status.ahead, status.behind), (0, 0));
+
+        // 新建一个没有上游的本地分支
+        let head_commit = local.head().unwrap().peel_to_commit().unwrap();
+        local.branch("feature", &head_commit, false).unwrap();
+        local.set_head("refs/heads/feature").unwrap();
+
+        match get_tracking_status(&local_path) {
+            Err(GitError::NoUpstream { branch }) => assert_eq!(branch, "feature"),
+            other => panic!("expected NoUpstream, got {:?}", other),
+        }
+        assert!(matches!(
+            pull_remote(&local_path, PullStrategy::Merge, None),
+            Err(GitError::NoUpstream { .. })
+        ));
+    }
+
+    #[test]
+    fn test_add_to_gitignore_hides_untracked_files() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        std::fs::write(repo_path.join("debug.log"), "log\n").unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+
+        let status = get_repository_status(repo_path).unwrap();
+        assert!(status.files.iter().any(|f| f.path == "debug.log"));
+        assert!(!is_path_ignored(repo_path, "debug.log").unwrap());
+
+        let patterns = vec!["*.log".to_string(), "*.log".to_string()];
+        add_to_gitignore(repo_path, &patterns).unwrap();
+        add_to_gitignore(repo_path, &patterns).unwrap();
+
+        let gitignore = std::fs::read_to_string(Path::new(repo_path).join(".gitignore")).unwrap();
+        assert_eq!(gitignore, "*.log\n");
+        assert!(is_path_ignored(repo_path, "debug.log").unwrap());
+
+        let status = get_repository_status(repo_path).unwrap();
+        assert!(!status.files.iter().any(|f| f.path == "debug.log"));
+    }
+
+    #[test]
+    fn test_repository_summary_captures_errors() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        std::fs::write(repo_path.join("new.txt"), "new\n").unwrap();
+
+        let summary = get_repository_summary(repo_path.to_str().unwrap());
+        assert!(summary.error.is_none());
+        assert!(!summary.is_clean);
+        assert_eq!(summary.changed_count, 1);
+
+        let missing = dir.path().join("missing");
+        let summary = get_repository_summary(missing.to_str().unwrap());
+        assert!(summary.error.is_some());
+    }
+
+    #[test]
+    fn test_archive_repository_tar_with_prefix() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        std::fs::create_dir_all(repo_path.join("src")).unwrap();
+        commit_file(&repo, "src/main.rs", "fn main() {}\n", "initial commit");
+
+        let output = dir.path().join("snapshot.tar");
+        let result = archive_repository_tar(
+            repo_path.to_str().unwrap(),
+            "HEAD",
+            output.to_str().unwrap(),
+            Some("project"),
+        )
+        .unwrap();
+        assert!(result.size > 0);
+
+        let mut archive = tar::Archive::new(std::fs::File::open(&output).unwrap());
+        let paths: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(paths, vec!["project/src/main.rs".to_string()]);
+    }
+
+    #[test]
+    fn test_repository_status_reports_merge_conflicts() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let base = commit_file(&repo, "file.txt", "base\n", "initial commit");
+        let base_commit = repo.find_commit(base).unwrap();
+        let main_ref = repo.head().unwrap().name().unwrap().to_string();
+
+        // 在 feature 分支和主分支上修改同一行
+        repo.branch("feature", &base_commit, false).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let feature = commit_file(&repo, "file.txt", "feature\n", "feature change");
+
+        repo.set_head(&main_ref).unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        commit_file(&repo, "file.txt", "main\n", "main change");
+
+        let annotated = repo.find_annotated_commit(feature).unwrap();
+        repo.merge(&[&annotated], None, None).unwrap();
+
+        let status = get_repository_status(repo_path.to_str().unwrap()).unwrap();
+        assert!(status.has_conflicts);
+        assert_eq!(status.operation_state.as_deref(), Some("merge"));
+        assert!(status
+            .files
+            .iter()
+            .any(|f| f.path == "file.txt" && f.status == "conflicted"));
+    }
+
+    #[test]
+    fn test_commit_only_selected_paths() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+
+        std::fs::write(repo_path.join("a.txt"), "a\n").unwrap();
+        std::fs::write(repo_path.join("b.txt"), "b\n").unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+        stage_files(repo_path, &["a.txt".to_string(), "b.txt".to_string()]).unwrap();
+
+        let options = CommitOptions {
+            message: "add a".to_string(),
+            only_paths: Some(vec!["a.txt".to_string()]),
+            ..Default::default()
+        };
+        create_commit(repo_path, &options).unwrap();
+
+        let head_tree = repo.head().unwrap().peel_to_tree().unwrap();
+        assert!(head_tree.get_path(Path::new("a.txt")).is_ok());
+        assert!(head_tree.get_path(Path::new("b.txt")).is_err());
+
+        // b.txt 仍然保持暂存状态
+        let status = get_repository_status(repo_path).unwrap();
+        assert!(status
+            .files
+            .iter()
+            .any(|f| f.path == "b.txt" && f.staged && f.status == "added"));
+        assert!(!status.files.iter().any(|f| f.path == "a.txt"));
+    }
+
+    #[test]
+    fn test_commit_with_specified_dates() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        std::fs::write(repo_path.join("a.txt"), "a\n").unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+        stage_files(repo_path, &["a.txt".to_string()]).unwrap();
+
+        let options = CommitOptions {
+            message: "backdated".to_string(),
+            author_date: Some(1_000_000_000),
+            committer_date: Some(1_100_000_000),
+            date_offset_minutes: Some(480),
+            ..Default::default()
+        };
+        let sha = create_commit(repo_path, &options).unwrap();
+
+        let commit = repo
+            .find_commit(git2::Oid::from_str(&sha).unwrap())
+            .unwrap();
+        assert_eq!(commit.author().when().seconds(), 1_000_000_000);
+        assert_eq!(commit.author().when().offset_minutes(), 480);
+        assert_eq!(commit.committer().when().seconds(), 1_100_000_000);
+        assert_eq!(commit.author().name(), Some("Test User"));
+    }
+
+    #[test]
+    fn test_repair_repository_removes_stale_index_lock() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        std::fs::write(repo_path.join("a.txt"), "a\n").unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+
+        // 模拟异常退出后残留的锁文件
+        let lock_path = repo.path().join("index.lock");
+        let lock = std::fs::File::create(&lock_path).unwrap();
+        lock.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(3600))
+            .unwrap();
+        drop(lock);
+
+        assert!(matches!(
+            stage_files(repo_path, &["a.txt".to_string()]),
+            Err(GitError::IndexLocked { .. })
+        ));
+
+        // 较新的锁文件不会被删除
+        let report = repair_repository(repo_path, Some(7200)).unwrap();
+        assert!(!report.removed_lock);
+        assert!(lock_path.exists());
+
+        let report = repair_repository(repo_path, None).unwrap();
+        assert!(report.removed_lock);
+        assert!(!lock_path.exists());
+
+        stage_files(repo_path, &["a.txt".to_string()]).unwrap();
+    }
+
+    #[test]
+    fn test_checkout_and_stash_report_index_locked() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let first = commit_file(&repo, "a.txt", "one\n", "first");
+        commit_file(&repo, "a.txt", "two\n", "second");
+        let repo_path_str = repo_path.to_str().unwrap();
+
+        let lock_path = repo.path().join("index.lock");
+        std::fs::File::create(&lock_path).unwrap();
+
+        assert!(matches!(
+            checkout_commit(repo_path_str, &first.to_string()),
+            Err(GitError::IndexLocked { .. })
+        ));
+
+        std::fs::write(repo_path.join("a.txt"), "dirty\n").unwrap();
+        assert!(matches!(
+            stash_save(repo_path_str, None, false, false),
+            Err(GitError::IndexLocked { .. })
+        ));
+
+        std::fs::remove_file(&lock_path).unwrap();
+        stash_save(repo_path_str, None, false, false).unwrap();
+    }
+
+    #[test]
+    fn test_rejected_token_stops_without_default_fallback() {
+        let allowed = git2::CredentialType::USER_PASS_PLAINTEXT | git2::CredentialType::DEFAULT;
+
+        assert_eq!(
+            choose_credential(1, 5, true, true, false, allowed),
+            CredentialChoice::TokenAsUsername
+        );
+        assert_eq!(
+            choose_credential(2, 5, true, true, false, allowed),
+            CredentialChoice::TokenAsPassword
+        );
+        assert!(matches!(
+            choose_credential(3, 5, true, true, false, allowed),
+            CredentialChoice::Fail(_)
+        ));
+
+        // 尝试次数限制可配置
+        assert!(matches!(
+            choose_credential(2, 1, true, true, false, allowed),
+            CredentialChoice::Fail(_)
+        ));
+
+        let attempts = AuthAttempts::new(DEFAULT_MAX_AUTH_ATTEMPTS);
+        attempts.record("token_as_username");
+        attempts.record("token_as_password");
+        let error = git2::Error::new(
+            git2::ErrorCode::Auth,
+            git2::ErrorClass::Callback,
+            "Token 被服务器拒绝",
+        );
+        match attempts.to_error(error) {
+            GitError::AuthenticationFailed {
+                methods_tried,
+                last_error,
+                ..
+            } => {
+                assert_eq!(
+                    methods_tried,
+                    vec!["token_as_username", "token_as_password"]
+                );
+                assert_eq!(last_error.as_deref(), Some("Token 被服务器拒绝"));
+            }
+            other => panic!("expected AuthenticationFailed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_no_credentials_reports_authentication_failure() {
+        let choice = choose_credential(
+            1,
+            DEFAULT_MAX_AUTH_ATTEMPTS,
+            true,
+            false,
+            false,
+            git2::CredentialType::USER_PASS_PLAINTEXT,
+        );
+        assert!(matches!(choice, CredentialChoice::Fail(_)));
+
+        let attempts = AuthAttempts::new(DEFAULT_MAX_AUTH_ATTEMPTS);
+        let error = git2::Error::new(
+            git2::ErrorCode::Auth,
+            git2::ErrorClass::Callback,
+            "未配置可用的凭据",
+        );
+        match attempts.to_error(error) {
+            GitError::AuthenticationFailed { methods_tried, .. } => {
+                assert!(methods_tried.is_empty());
+            }
+            other => panic!("expected AuthenticationFailed, got {:?}", other),
+        }
+
+        // 非认证错误保持原样
+        let error = git2::Error::from_str("network error");
+        assert!(matches!(attempts.to_error(error), GitError::Git(_)));
+    }
+
+    #[test]
+    fn test_staged_and_unstaged_diff() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        std::fs::write(repo_path.join("a.txt"), "one\n").unwrap();
+        let repo_path_str = repo_path.to_str().unwrap();
+
+        // 首次提交前与空树比较
+        stage_files(repo_path_str, &["a.txt".to_string()]).unwrap();
+        let staged = get_staged_diff(repo_path_str, &WhitespaceOptions::default()).unwrap();
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].status, "added");
+        assert_eq!(staged[0].additions, 1);
+
+        commit_file(&repo, "a.txt", "one\n", "initial commit");
+        std::fs::write(repo_path.join("a.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(repo_path.join("b.txt"), "b\n").unwrap();
+        stage_files(repo_path_str, &["a.txt".to_string()]).unwrap();
+
+        let staged = get_staged_diff(repo_path_str, &WhitespaceOptions::default()).unwrap();
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].path, "a.txt");
+        assert_eq!(staged[0].status, "modified");
+        assert_eq!((staged[0].additions, staged[0].deletions), (1, 0));
+        let added: Vec<&DiffLine> = staged[0].hunks[0]
+            .lines
+            .iter()
+            .filter(|line| line.origin == '+')
+            .collect();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].content, "two");
+        assert_eq!(added[0].new_lineno, Some(2));
+
+        let unstaged = get_unstaged_diff(repo_path_str, &WhitespaceOptions::default()).unwrap();
+        assert_eq!(unstaged.len(), 1);
+        assert_eq!(unstaged[0].path, "b.txt");
+        assert_eq!(unstaged[0].status, "added");
+    }
+
+    #[test]
+    fn test_repository_state_reports_interactive_rebase() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let oid = commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let repo_path = repo_path.to_str().unwrap();
+
+        let state = get_repository_state(repo_path).unwrap();
+        assert_eq!(state.state, RepositoryStateKind::Clean);
+        assert!(state.head_name.is_none());
+
+        // 模拟中断的交互式变基
+        let rebase_dir = repo.path().join("rebase-merge");
+        std::fs::create_dir_all(&rebase_dir).unwrap();
+        std::fs::write(rebase_dir.join("interactive"), "").unwrap();
+        std::fs::write(rebase_dir.join("head-name"), "refs/heads/feature\n").unwrap();
+        std::fs::write(rebase_dir.join("onto"), format!("{}\n", oid)).unwrap();
+
+        let state = get_repository_state(repo_path).unwrap();
+        assert_eq!(state.state, RepositoryStateKind::RebaseInteractive);
+        assert_eq!(state.head_name.as_deref(), Some("refs/heads/feature"));
+        assert_eq!(state.onto, Some(oid.to_string()));
+    }
+
+    #[test]
+    fn test_set_branch_upstream() {
+        let dir = TempDir::new().unwrap();
+        let (_remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        let head_commit = local.head().unwrap().peel_to_commit().unwrap();
+        let default_branch = local.head().unwrap().shorthand().unwrap().to_string();
+        local.branch("feature", &head_commit, false).unwrap();
+        assert!(local.branch_upstream_name("refs/heads/feature").is_err());
+
+        let upstream = format!("origin/{}", default_branch);
+        set_branch_upstream(&local_path, "feature", Some(&upstream)).unwrap();
+        let upstream_name = local.branch_upstream_name("refs/heads/feature").unwrap();
+        assert_eq!(
+            upstream_name.as_str(),
+            Some(format!("refs/remotes/origin/{}", default_branch).as_str())
+        );
+
+        // 设置后 ahead/behind 可以正常计算
+        local.set_head("refs/heads/feature").unwrap();
+        let status = get_tracking_status(&local_path).unwrap();
+        assert_eq!(status.upstream, upstream);
+        assert_eq!((status.ahead, status.behind), (0, 0));
+
+        set_branch_upstream(&local_path, "feature", None).unwrap();
+        assert!(local.branch_upstream_name("refs/heads/feature").is_err());
+    }
+
+    #[test]
+    fn test_commit_with_distinct_author_and_committer() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        std::fs::write(repo_path.join("a.txt"), "a\n").unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+        stage_files(repo_path, &["a.txt".to_string()]).unwrap();
+
+        let options = CommitOptions {
+            message: "patch from contributor".to_string(),
+            author_name: Some("Contributor".to_string()),
+            author_email: Some("contributor@example.com".to_string()),
+            signoff: true,
+            ..Default::default()
+        };
+        let sha = create_commit(repo_path, &options).unwrap();
+
+        let commit = repo
+            .find_commit(git2::Oid::from_str(&sha).unwrap())
+            .unwrap();
+        assert_eq!(commit.author().name(), Some("Contributor"));
+        assert_eq!(commit.author().email(), Some("contributor@example.com"));
+        assert_eq!(commit.committer().name(), Some("Test User"));
+        assert_eq!(commit.committer().email(), Some("test@example.com"));
+        assert!(commit
+            .message()
+            .unwrap()
+            .ends_with("Signed-off-by: Test User <test@example.com>"));
+    }
+
+    #[test]
+    fn test_push_preview_reports_outgoing_and_force() {
+        let dir = TempDir::new().unwrap();
+        let (_remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        commit_file(&local, "a.txt", "a\n", "first local");
+        commit_file(&local, "b.txt", "b\n", "second local");
+
+        let preview = push_preview(&local_path, None, None).unwrap();
+        assert_eq!(preview.remote_name, "origin");
+        assert_eq!(preview.ahead, 2);
+        assert_eq!(preview.commits.len(), 2);
+        assert_eq!(preview.commits[0].message, "second local");
+        assert!(preview.is_fast_forward);
+        assert!(!preview.requires_force);
+
+        // 改写历史后需要强制推送
+        let head = local.head().unwrap().peel_to_commit().unwrap();
+        let remote_ref = preview.remote_ref.clone().unwrap();
+        let remote_commit = local
+            .find_reference(&remote_ref)
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        local
+            .reset(remote_commit.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+        commit_file(&local, "c.txt", "c\n", "rewritten");
+        local
+            .reference(&remote_ref, head.id(), true, "simulate remote")
+            .unwrap();
+
+        let preview = push_preview(&local_path, None, None).unwrap();
+        assert_eq!(preview.ahead, 1);
+        assert_eq!(preview.behind, 2);
+        assert!(!preview.is_fast_forward);
+        assert!(preview.requires_force);
+    }
+
+    #[test]
+    fn test_find_large_files_respects_threshold() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+
+        std::fs::write(repo_path.join("video.mp4"), vec![0u8; 2048]).unwrap();
+        std::fs::write(repo_path.join("small.txt"), "small\n").unwrap();
+        std::fs::write(repo_path.join("staged.bin"), vec![1u8; 4096]).unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+        stage_files(repo_path, &["staged.bin".to_string()]).unwrap();
+
+        let working = find_large_files(repo_path, Some(1024), false).unwrap();
+        assert_eq!(working.len(), 1);
+        assert_eq!(working[0].path, "video.mp4");
+        assert_eq!(working[0].size, 2048);
+        assert!(!working[0].staged);
+
+        let all = find_large_files(repo_path, Some(1024), true).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].path, "staged.bin");
+        assert!(all[0].staged);
+
+        assert!(find_large_files(repo_path, None, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_commit_and_push_keeps_local_commit_when_push_fails() {
+        let dir = TempDir::new().unwrap();
+        let (bare, local) = setup_bare_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+        let options = |message: &str| CommitOptions {
+            message: message.to_string(),
+            ..Default::default()
+        };
+
+        std::fs::write(local.workdir().unwrap().join("a.txt"), "a\n").unwrap();
+        stage_files(&local_path, &["a.txt".to_string()]).unwrap();
+        let result = commit_and_push(&local_path, &options("add a"), None, false, None).unwrap();
+        assert!(result.pushed);
+        assert!(result.push_error.is_none());
+        let remote_head = bare.head().unwrap().target().unwrap();
+        assert_eq!(remote_head.to_string(), result.commit_sha);
+
+        // 远程不可达时，提交仍保留在本地
+        local
+            .remote_set_url("origin", dir.path().join("missing.git").to_str().unwrap())
+            .unwrap();
+        std::fs::write(local.workdir().unwrap().join("b.txt"), "b\n").unwrap();
+        stage_files(&local_path, &["b.txt".to_string()]).unwrap();
+        let result = commit_and_push(&local_path, &options("add b"), None, false, None).unwrap();
+        assert!(!result.pushed);
+        assert!(result.push_error.is_some());
+        let local_head = local.refname_to_id("HEAD").unwrap();
+        assert_eq!(local_head.to_string(), result.commit_sha);
+    }
+
+    #[test]
+    fn test_list_branches_lite_with_filter_and_limit() {
+        let dir = TempDir::new().unwrap();
+        let (_remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        let head_commit = local.head().unwrap().peel_to_commit().unwrap();
+        for name in ["feature/login", "feature/Logout", "bugfix/crash"] {
+            local.branch(name, &head_commit, false).unwrap();
+        }
+
+        let all = list_branches_lite(&local_path, None, None).unwrap();
+        assert_eq!(all.iter().filter(|b| !b.is_remote).count(), 4);
+        assert!(all.iter().any(|b| b.is_remote));
+        assert!(!all.iter().any(|b| b.name.ends_with("/HEAD")));
+        let current = all.iter().find(|b| b.is_current).unwrap();
+        assert!(current.upstream.is_some());
+
+        let filtered = list_branches_lite(&local_path, Some("LOG"), None).unwrap();
+        let mut names: Vec<&str> = filtered.iter().map(|b| b.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["feature/login", "feature/Logout"]);
+
+        let limited = list_branches(&local_path, Some("feature"), Some(1)).unwrap();
+        assert_eq!(limited.len(), 1);
+    }
+
+    #[test]
+    fn test_classify_remote_error() {
+        use git2::{Error, ErrorClass, ErrorCode};
+
+        let url = "https://github.com/example/repo.git";
+        let classify = |code, class, message: &str| {
+            classify_remote_error(Error::new(code, class, message), url).error_type()
+        };
+
+        assert_eq!(
+            classify(ErrorCode::Auth, ErrorClass::Http, "authentication required"),
+            "authentication"
+        );
+        assert_eq!(
+            classify(
+                ErrorCode::GenericError,
+                ErrorClass::Http,
+                "too many redirects or authentication replays"
+            ),
+            "authentication"
+        );
+        assert_eq!(
+            classify(
+                ErrorCode::GenericError,
+                ErrorClass::Http,
+                "unexpected http status code: 404"
+            ),
+            "repository_not_found"
+        );
+        assert_eq!(
+            classify(
+                ErrorCode::GenericError,
+                ErrorClass::Ssh,
+                "ERROR: Repository not found."
+            ),
+            "repository_not_found"
+        );
+        assert_eq!(
+            classify(
+                ErrorCode::GenericError,
+                ErrorClass::Net,
+                "failed to resolve address for github.com"
+            ),
+            "network"
+        );
+        assert_eq!(
+            classify(
+                ErrorCode::Certificate,
+                ErrorClass::Ssl,
+                "certificate expired"
+            ),
+            "network"
+        );
+        assert_eq!(
+            classify(
+                ErrorCode::GenericError,
+                ErrorClass::Reference,
+                "remote branch 'dev' not found"
+            ),
+            "git"
+        );
+    }
+
+    #[test]
+    fn test_fetch_with_custom_refspec() {
+        let dir = TempDir::new().unwrap();
+        let (remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        // 模拟 GitHub 的 PR 引用
+        let head = remote.head().unwrap().peel_to_commit().unwrap();
+        let pr_commit = commit_file(&remote, "pr.txt", "pr\n", "pr change");
+        remote
+            .reference("refs/pull/123/head", pr_commit, true, "pr ref")
+            .unwrap();
+        remote
+            .reset(head.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+
+        // 默认 refspec 不会获取 PR 引用
+        fetch_remote(&local_path, None, false, None).unwrap();
+        assert!(local.refname_to_id("refs/pull/123").is_err());
+
+        let refspecs = vec!["refs/pull/123/head:refs/pull/123".to_string()];
+        fetch_remote_with_token(&local_path, None, None, false, Some(refspecs.as_slice())).unwrap();
+        assert_eq!(local.refname_to_id("refs/pull/123").unwrap(), pr_commit);
+    }
+
+    #[test]
+    fn test_pull_request_ref_detects_forge() {
+        assert_eq!(
+            pull_request_ref("https://github.com/owner/repo.git", 12),
+            "refs/pull/12/head"
+        );
+        assert_eq!(
+            pull_request_ref("git@gitlab.com:group/sub/repo.git", 5),
+            "refs/merge-requests/5/head"
+        );
+        assert_eq!(
+            pull_request_ref("ssh://git@GitLab.example.com:2222/group/repo.git", 7),
+            "refs/merge-requests/7/head"
+        );
+        assert_eq!(pull_request_ref("/srv/git/repo.git", 3), "refs/pull/3/head");
+    }
+
+    #[test]
+    fn test_checkout_pull_request() {
+        let dir = TempDir::new().unwrap();
+        let (remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        let head = remote.head().unwrap().peel_to_commit().unwrap();
+        let pr_commit = commit_file(&remote, "pr.txt", "pr\n", "pr change");
+        remote
+            .reference("refs/pull/42/head", pr_commit, true, "pr ref")
+            .unwrap();
+        remote
+            .reset(head.as_object(), git2::ResetType::Hard, None)
+            .unwrap();
+
+        let result = checkout_pull_request(&local_path, None, 42, None, None).unwrap();
+        assert!(result.success);
+        let local_head = local.head().unwrap();
+        assert_eq!(local_head.shorthand(), Some("pr-42"));
+        assert_eq!(local_head.target(), Some(pr_commit));
+        assert!(local.workdir().unwrap().join("pr.txt").exists());
+
+        assert!(checkout_pull_request(&local_path, None, 43, None, None).is_err());
+
+        // PR 有新提交时，已存在的本地分支被快进
+        let remote_head = remote.head().unwrap().peel_to_commit().unwrap();
+        remote.set_head_detached(pr_commit).unwrap();
+        let pr_update = commit_file(&remote, "pr.txt", "pr v2\n", "pr update");
+        remote
+            .reference("refs/pull/42/head", pr_update, true, "pr ref")
+            .unwrap();
+        remote.set_head_detached(remote_head.id()).unwrap();
+
+        let result = checkout_pull_request(&local_path, None, 42, None, None).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(local.head().unwrap().target(), Some(pr_update));
+        assert_eq!(
+            std::fs::read_to_string(local.workdir().unwrap().join("pr.txt")).unwrap(),
+            "pr v2\n"
+        );
+
+        // PR 被强制推送后本地分支已分叉，保持不变并报告
+        let rewritten = remote
+            .commit(
+                None,
+                &remote_head.author(),
+                &remote_head.committer(),
+                "rewritten pr",
+                &remote_head.tree().unwrap(),
+                &[&remote_head],
+            )
+            .unwrap();
+        remote
+            .reference("refs/pull/42/head", rewritten, true, "force push")
+            .unwrap();
+
+        let result = checkout_pull_request(&local_path, None, 42, None, None).unwrap();
+        assert!(!result.success);
+        assert!(result.message.contains("分叉"));
+        assert_eq!(local.head().unwrap().target(), Some(pr_update));
+    }
+
+    #[test]
+    fn test_extract_domain_handles_all_remote_forms() {
+        assert_eq!(
+            extract_domain("https://GitHub.com/owner/repo.git").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(
+            extract_domain("ssh://git@gitlab.example.com:2222/g/r.git").as_deref(),
+            Some("gitlab.example.com")
+        );
+        assert_eq!(
+            extract_domain("git@github.com:owner/repo.git").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(
+            extract_domain("github.com:owner/repo.git").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(extract_domain("/srv/git/repo.git"), None);
+        assert_eq!(extract_domain("C:\\repos\\repo"), None);
+        assert_eq!(extract_domain("C:/repos/repo"), None);
+        assert_eq!(extract_domain("file:///srv/git/repo.git"), None);
+    }
+
+    #[test]
+    fn test_stash_untracked_and_show() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let branch = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        std::fs::write(repo_path.join("README.md"), "hello\nworld\n").unwrap();
+        std::fs::write(repo_path.join("notes.txt"), "draft\n").unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+
+        let sha = stash_save(repo_path, Some("wip notes"), true, false).unwrap();
+        assert!(get_repository_status(repo_path).unwrap().files.is_empty());
+
+        let stashes = stash_list(repo_path).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert_eq!(stashes[0].index, 0);
+        assert_eq!(stashes[0].sha, sha);
+        assert_eq!(stashes[0].branch.as_deref(), Some(branch.as_str()));
+        assert!(stashes[0].message.contains("wip notes"));
+
+        let files = stash_show(repo_path, 0).unwrap();
+        let readme = files.iter().find(|f| f.path == "README.md").unwrap();
+        assert_eq!(readme.status, "modified");
+        assert_eq!(readme.additions, 1);
+        let notes = files.iter().find(|f| f.path == "notes.txt").unwrap();
+        assert_eq!(notes.status, "added");
+
+        assert!(stash_show(repo_path, 1).is_err());
+        assert!(stash_save(repo_path, None, true, false).is_err());
+    }
+
+    #[test]
+    fn test_commit_trailers_grouped_with_signoff() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        std::fs::write(repo_path.join("a.txt"), "a\n").unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+        stage_files(repo_path, &["a.txt".to_string()]).unwrap();
+
+        let options = CommitOptions {
+            message: "Fix login".to_string(),
+            description: Some("Handle expired sessions.\n".to_string()),
+            signoff: true,
+            trailers: Some(vec![
+                (
+                    "Reviewed-by".to_string(),
+                    "Alice <alice@example.com>".to_string(),
+                ),
+                ("Refs".to_string(), "#42".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let sha = create_commit(repo_path, &options).unwrap();
+        let commit = repo
+            .find_commit(git2::Oid::from_str(&sha).unwrap())
+            .unwrap();
+        assert_eq!(
+            commit.message().unwrap(),
+            "Fix login\n\nHandle expired sessions.\n\n\
+             Reviewed-by: Alice <alice@example.com>\n\
+             Refs: #42\n\
+             Signed-off-by: Test User <test@example.com>"
+        );
+
+        // 已有 trailer 块时续写，不插入额外空行，也不重复添加
+        assert_eq!(
+            append_trailers(
+                "Subject\n\nCo-authored-by: Bob <bob@example.com>",
+                &[
+                    ("Refs".to_string(), "#1".to_string()),
+                    (
+                        "Co-authored-by".to_string(),
+                        "Bob <bob@example.com>".to_string()
+                    ),
+                ],
+            ),
+            "Subject\n\nCo-authored-by: Bob <bob@example.com>\nRefs: #1"
+        );
+        // 只有标题时以空行分隔
+        assert_eq!(
+            append_trailers("Subject", &[("Refs".to_string(), "#1".to_string())]),
+            "Subject\n\nRefs: #1"
+        );
+    }
+
+    #[test]
+    fn test_get_commit_template() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let repo_path_str = repo_path.to_str().unwrap();
+
+        assert!(get_commit_template(repo_path_str).unwrap().is_none());
+
+        std::fs::write(repo_path.join(".gitmessage"), "feat: \n").unwrap();
+        assert_eq!(
+            get_commit_template(repo_path_str).unwrap().as_deref(),
+            Some("feat: \n")
+        );
+
+        let template = dir.path().join("team-template.txt");
+        std::fs::write(&template, "[TEAM-000] \n").unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("commit.template", template.to_str().unwrap())
+            .unwrap();
+        assert_eq!(
+            get_commit_template(repo_path_str).unwrap().as_deref(),
+            Some("[TEAM-000] \n")
+        );
+    }
+
+    #[test]
+    fn test_merge_base_and_unrelated_histories() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let base = commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let main_branch = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        let base_commit = repo.find_commit(base).unwrap();
+        repo.branch("feature", &base_commit, false).unwrap();
+        repo.branch("other", &base_commit, false).unwrap();
+        commit_file(&repo, "main.txt", "main\n", "main change");
+        repo.set_head("refs/heads/feature").unwrap();
+        commit_file(&repo, "feature.txt", "feature\n", "feature change");
+
+        // 构造一个没有共同祖先的孤立分支
+        let tree = repo.find_commit(base).unwrap().tree().unwrap();
+        let signature = repo.signature().unwrap();
+        repo.commit(
+            Some("refs/heads/orphan"),
+            &signature,
+            &signature,
+            "orphan",
+            &tree,
+            &[],
+        )
+        .unwrap();
+
+        let repo_path = repo_path.to_str().unwrap();
+        assert_eq!(
+            get_merge_base(repo_path, &main_branch, "feature").unwrap(),
+            base.to_string()
+        );
+        let refs = vec![
+            main_branch.clone(),
+            "feature".to_string(),
+            "other".to_string(),
+        ];
+        assert_eq!(
+            get_merge_bases(repo_path, &refs).unwrap(),
+            vec![base.to_string()]
+        );
+
+        assert!(matches!(
+            get_merge_base(repo_path, &main_branch, "orphan"),
+            Err(GitError::NoMergeBase { .. })
+        ));
+        assert!(get_merge_base(repo_path, &main_branch, "missing").is_err());
+    }
+
+    #[test]
+    fn test_merge_bases_of_three_refs_uses_octopus_base() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let signature = repo.signature().unwrap();
+        let tree_id = repo.treebuilder(None).unwrap().write().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let commit = |branch: &str, message: &str, parent: Option<git2::Oid>| {
+            let parents: Vec<git2::Commit> = parent
+                .map(|oid| repo.find_commit(oid).unwrap())
+                .into_iter()
+                .collect();
+            let parents: Vec<&git2::Commit> = parents.iter().collect();
+            repo.commit(
+                Some(&format!("refs/heads/{}", branch)),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap()
+        };
+
+        //         o---C
+        //        /
+        //       /   o---B
+        //      /   /
+        //  ---2---1---o---A
+        let two = commit("a", "2", None);
+        let one = commit("a", "1", Some(two));
+        let a = commit("a", "A", Some(one));
+        let b_base = commit("b", "b", Some(one));
+        commit("b", "B", Some(b_base));
+        let c_base = commit("c", "c", Some(two));
+        commit("c", "C", Some(c_base));
+
+        // 按 A 与 B、C 假想合并计算会得到 1，而三者共同的祖先只有 2
+        let oids = [
+            a,
+            repo.refname_to_id("refs/heads/b").unwrap(),
+            repo.refname_to_id("refs/heads/c").unwrap(),
+        ];
+        assert_eq!(
+            repo.merge_bases_many(&oids)
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![&one]
+        );
+
+        let refs = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(
+            get_merge_bases(dir.path().to_str().unwrap(), &refs).unwrap(),
+            vec![two.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_pull_merge_with_explicit_identity() {
+        let dir = TempDir::new().unwrap();
+        let (remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        commit_file(&remote, "remote.txt", "remote\n", "remote change");
+        commit_file(&local, "local.txt", "local\n", "local change");
+
+        // 清除仓库身份配置，由调用方显式提供
+        let mut config = local.config().unwrap();
+        config.remove("user.name").unwrap();
+        config.remove("user.email").unwrap();
+
+        let result = pull_remote(
+            &local_path,
+            PullStrategy::Merge,
+            Some(("Sync Bot", "bot@example.com")),
+        )
+        .unwrap();
+        assert!(result.success, "{}", result.message);
+
+        let merge_commit = local.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(merge_commit.parent_count(), 2);
+        assert_eq!(merge_commit.committer().name(), Some("Sync Bot"));
+        assert_eq!(merge_commit.author().email(), Some("bot@example.com"));
+    }
+
+    #[test]
+    fn test_list_tree_one_level() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        std::fs::create_dir_all(repo_path.join("src/nested")).unwrap();
+        std::fs::write(repo_path.join("src/nested/deep.rs"), "deep\n").unwrap();
+        std::fs::write(repo_path.join("src/main.rs"), "fn main() {}\n").unwrap();
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("src/main.rs")).unwrap();
+        index.add_path(Path::new("src/nested/deep.rs")).unwrap();
+        index.write().unwrap();
+        commit_file(&repo, "README.md", "hello again\n", "add sources");
+        let repo_path = repo_path.to_str().unwrap();
+
+        let root = list_tree(repo_path, None, None).unwrap();
+        let names: Vec<&str> = root.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["src", "README.md"]);
+        assert!(root[0].is_dir);
+        assert_eq!(root[0].size, None);
+        assert_eq!(root[1].size, Some(12));
+        assert_eq!(root[1].mode, 0o100644);
+
+        let src = list_tree(repo_path, Some("HEAD"), Some("src/")).unwrap();
+        let paths: Vec<&str> = src.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/nested", "src/main.rs"]);
+
+        // 初始提交中还没有 src 目录
+        assert!(list_tree(repo_path, Some("HEAD~1"), Some("src")).is_err());
+    }
+
+    #[test]
+    fn test_first_commit_uses_configured_default_branch() {
+        let dir = TempDir::new().unwrap();
+
+        let trunk_path = dir.path().join("trunk");
+        let branch = init_repository(trunk_path.to_str().unwrap(), Some("trunk")).unwrap();
+        assert_eq!(branch, "trunk");
+        let trunk = Repository::open(&trunk_path).unwrap();
+        assert_eq!(
+            trunk.find_reference("HEAD").unwrap().symbolic_target(),
+            Some("refs/heads/trunk")
+        );
+
+        // 仓库以其他默认分支创建，但配置了 init.defaultBranch=main
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        repo.set_head("refs/heads/master").unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("init.defaultBranch", "main")
+            .unwrap();
+        std::fs::write(repo_path.join("a.txt"), "a\n").unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+        stage_files(repo_path, &["a.txt".to_string()]).unwrap();
+
+        let options = CommitOptions {
+            message: "initial commit".to_string(),
+            ..Default::default()
+        };
+        create_commit(repo_path, &options).unwrap();
+
+        let head = repo.head().unwrap();
+        assert_eq!(head.shorthand(), Some("main"));
+        assert!(repo.find_branch("master", git2::BranchType::Local).is_err());
+    }
+
+    #[test]
+    fn test_reword_head_keeps_tree_and_author() {
+        let dir = TempDir::new().unwrap();
+        let (_remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        // 已推送的提交默认不允许修改
+        assert!(matches!(
+            reword_head(&local_path, "rewritten", false),
+            Err(GitError::PublishedCommit { .. })
+        ));
+
+        let original_id = commit_file(&local, "a.txt", "a\n", "tpyo in message");
+        let original = local.find_commit(original_id).unwrap();
+
+        let new_sha = reword_head(&local_path, "fix typo in message", false).unwrap();
+        let reworded = local
+            .find_commit(git2::Oid::from_str(&new_sha).unwrap())
+            .unwrap();
+
+        assert_ne!(reworded.id(), original.id());
+        assert_eq!(reworded.tree_id(), original.tree_id());
+        assert_eq!(reworded.message(), Some("fix typo in message"));
+        assert_eq!(
+            reworded.parent_id(0).unwrap(),
+            original.parent_id(0).unwrap()
+        );
+        assert_eq!(reworded.author().name(), original.author().name());
+        assert_eq!(reworded.author().when(), original.author().when());
+        assert_eq!(local.head().unwrap().target(), Some(reworded.id()));
+    }
+
+    #[test]
+    fn test_pull_fast_forward_reports_diff_stats() {
+        let dir = TempDir::new().unwrap();
+        let (remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        // 已是最新时不统计
+        let result = pull_remote(&local_path, PullStrategy::Merge, None).unwrap();
+        assert!(result.success);
+        assert_eq!(result.files_changed, None);
+
+        commit_file(&remote, "remote.txt", "one\ntwo\n", "remote change");
+
+        let result = pull_remote(&local_path, PullStrategy::Merge, None).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(result.files_changed, Some(1));
+        assert_eq!(result.insertions, Some(2));
+        assert_eq!(result.deletions, Some(0));
+    }
+
+    #[test]
+    fn test_abort_conflicted_merge_restores_head() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let base = commit_file(&repo, "file.txt", "base\n", "initial commit");
+        let base_commit = repo.find_commit(base).unwrap();
+        let main_ref = repo.head().unwrap().name().unwrap().to_string();
+
+        repo.branch("feature", &base_commit, false).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let feature = commit_file(&repo, "file.txt", "feature\n", "feature change");
+
+        repo.set_head(&main_ref).unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let main_head = commit_file(&repo, "file.txt", "main\n", "main change");
+
+        let annotated = repo.find_annotated_commit(feature).unwrap();
+        repo.merge(&[&annotated], None, None).unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+        assert!(get_repository_status(repo_path).unwrap().has_conflicts);
+
+        let aborted = abort_operation(repo_path).unwrap();
+        assert_eq!(aborted, RepositoryStateKind::Merge);
+
+        let status = get_repository_status(repo_path).unwrap();
+        assert!(status.is_clean, "{:?}", status.files);
+        assert_eq!(status.operation_state, None);
+        assert_eq!(repo.head().unwrap().target(), Some(main_head));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("repo/file.txt")).unwrap(),
+            "main\n"
+        );
+
+        // 干净的仓库无需中止
+        assert_eq!(
+            abort_operation(repo_path).unwrap(),
+            RepositoryStateKind::Clean
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unshallow_fetches_full_history() {
+        let dir = TempDir::new().unwrap();
+        let remote_path = dir.path().join("remote");
+        let local_path = dir.path().join("local");
+        let remote = init_repo(&remote_path);
+        commit_file(&remote, "file.txt", "one\n", "first");
+        commit_file(&remote, "file.txt", "two\n", "second");
+        commit_file(&remote, "file.txt", "three\n", "third");
+
+        let remote_url = format!("file://{}", remote_path.to_str().unwrap());
+        let output = crate::utils::system_command::create_hidden_command("git")
+            .args(["clone", "--depth", "1", &remote_url])
+            .arg(&local_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let count_commits = || {
+            let repo = Repository::open(&local_path).unwrap();
+            let mut revwalk = repo.revwalk().unwrap();
+            revwalk.push_head().unwrap();
+            revwalk.count()
+        };
+        assert_eq!(count_commits(), 1);
+
+        let local_path_str = local_path.to_str().unwrap();
+        let processes = crate::utils::git_process::GitProcesses::default();
+        let result = unshallow(
+            local_path_str,
+            None,
+            None,
+            &processes,
+            None,
+            UNSHALLOW_TIMEOUT,
+        )
+        .await
+        .unwrap();
+        assert!(result.success);
+        assert_eq!(count_commits(), 3);
+        assert!(!local_path.join(".git/shallow").exists());
+
+        // 已是完整历史时不执行任何操作
+        let result = unshallow(
+            local_path_str,
+            None,
+            None,
+            &processes,
+            None,
+            UNSHALLOW_TIMEOUT,
+        )
+        .await
+        .unwrap();
+        assert!(result.message.contains("无需补全"));
+    }
+
+    #[test]
+    fn test_commit_without_identity_and_set_user_identity() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let repo_path = repo_path.to_str().unwrap();
+
+        // 空的本地配置会覆盖全局身份，模拟未配置身份的环境
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "").unwrap();
+        config.set_str("user.email", "").unwrap();
+
+        let options = |author: Option<(&str, &str)>| CommitOptions {
+            message: "change".to_string(),
+            author_name: author.map(|(name, _)| name.to_string()),
+            author_email: author.map(|(_, email)| email.to_string()),
+            ..Default::default()
+        };
+
+        std::fs::write(dir.path().join("repo/README.md"), "one\n").unwrap();
+        stage_files(repo_path, &["README.md".to_string()]).unwrap();
+        let error = create_commit(repo_path, &options(None)).unwrap_err();
+        assert!(matches!(error, GitError::IdentityNotConfigured));
+
+        // 直接提供身份时无需配置
+        let sha =
+            create_commit(repo_path, &options(Some(("Inline", "inline@example.com")))).unwrap();
+        let commit = repo.find_commit(Oid::from_str(&sha).unwrap()).unwrap();
+        assert_eq!(commit.author().name(), Some("Inline"));
+        assert_eq!(commit.committer().email(), Some("inline@example.com"));
+
+        // 设置仓库身份后可以正常提交
+        set_user_identity(repo_path, "Configured", "configured@example.com", false).unwrap();
+        std::fs::write(dir.path().join("repo/README.md"), "two\n").unwrap();
+        stage_files(repo_path, &["README.md".to_string()]).unwrap();
+        let sha = create_commit(repo_path, &options(None)).unwrap();
+        let commit = repo.find_commit(Oid::from_str(&sha).unwrap()).unwrap();
+        assert_eq!(commit.author().name(), Some("Configured"));
+
+        assert!(set_user_identity(repo_path, " ", "x@example.com", false).is_err());
+    }
+
+    #[test]
+    fn test_file_diff_against_commit() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "same.txt", "same\n", "add same");
+        commit_file(&repo, "file.txt", "one\n", "first");
+        commit_file(&repo, "file.txt", "one\ntwo\n", "second");
+        commit_file(&repo, "old.txt", "old\n", "add old");
+        std::fs::write(repo_path.join("file.txt"), "one\ntwo\nthree\n").unwrap();
+        std::fs::remove_file(repo_path.join("old.txt")).unwrap();
+        std::fs::write(repo_path.join("new.txt"), "new\n").unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+
+        // 与两个提交之前的版本比较，包含已提交和未提交的变更
+        let diff = get_file_diff_against(
+            repo_path,
+            "file.txt",
+            "HEAD~2",
+            &WhitespaceOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(diff.status, "modified");
+        assert_eq!((diff.additions, diff.deletions), (2, 0));
+
+        let diff =
+            get_file_diff_against(repo_path, "old.txt", "HEAD", &WhitespaceOptions::default())
+                .unwrap();
+        assert_eq!(diff.status, "deleted");
+
+        let diff =
+            get_file_diff_against(repo_path, "new.txt", "HEAD", &WhitespaceOptions::default())
+                .unwrap();
+        assert_eq!(diff.status, "added");
+
+        let diff = get_file_diff_against(
+            repo_path,
+            "same.txt",
+            "HEAD~1",
+            &WhitespaceOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(diff.status, "unmodified");
+        assert!(diff.hunks.is_empty());
+
+        assert!(get_file_diff_against(
+            repo_path,
+            "missing.txt",
+            "HEAD",
+            &WhitespaceOptions::default()
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "integration-tests")]
+    #[test]
+    fn test_push_reports_progress_to_bare_remote() {
+        let dir = TempDir::new().unwrap();
+        let (bare, local) = setup_bare_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        let content: String = (0..20_000).map(|i| format!("line {}\n", i)).collect();
+        let head = commit_file(&local, "large.txt", &content, "add large file");
+
+        let mut events = Vec::new();
+        let result = push_remote_with_progress(
+            &local_path,
+            None,
+            false,
+            None,
+            AuthOptions::default(),
+            Some(2),
+            |p| events.push(p),
+        )
+        .unwrap();
+
+        assert!(result.success);
+        assert!(!events.is_empty());
+        assert!(events.iter().all(|p| p.repo_path == local_path));
+        assert!(events.iter().any(|p| p.stage == "uploading"));
+        let branch = local.head().unwrap().name().unwrap().to_string();
+        assert_eq!(bare.refname_to_id(&branch).unwrap(), head);
+    }
+
+    #[test]
+    fn test_open_repository_from_subdirectory() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        std::fs::create_dir_all(repo_path.join("src/nested")).unwrap();
+        std::fs::write(repo_path.join("src/main.rs"), "fn main() {}\n").unwrap();
+
+        let subdir = repo_path.join("src/nested");
+        let subdir = subdir.to_str().unwrap();
+        let root = repo_path.canonicalize().unwrap();
+        let found = get_repository_root(subdir).unwrap();
+        assert_eq!(Path::new(&found).canonicalize().unwrap(), root);
+        assert_eq!(get_repository_root(dir.path().to_str().unwrap()), None);
+
+        // 文件路径相对于仓库根目录解释
+        stage_files(subdir, &["src/main.rs".to_string()]).unwrap();
+        let status = get_repository_status(subdir).unwrap();
+        assert!(status
+            .files
+            .iter()
+            .any(|f| f.path == "src/main.rs" && f.staged));
+    }
+
+    #[test]
+    fn test_change_counts() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "a.txt", "a\n", "add a");
+        commit_file(&repo, "b.txt", "b\n", "add b");
+        commit_file(&repo, "c.txt", "c\n", "add c");
+
+        // a.txt 同时有已暂存和未暂存的修改，b.txt 只有未暂存修改，c.txt 已暂存删除
+        std::fs::write(repo_path.join("a.txt"), "a staged\n").unwrap();
+        std::fs::write(repo_path.join("new.txt"), "new\n").unwrap();
+        let repo_path_str = repo_path.to_str().unwrap();
+        stage_files(repo_path_str, &["a.txt".to_string(), "new.txt".to_string()]).unwrap();
+        std::fs::write(repo_path.join("a.txt"), "a unstaged\n").unwrap();
+        std::fs::write(repo_path.join("b.txt"), "b changed\n").unwrap();
+        std::fs::remove_file(repo_path.join("c.txt")).unwrap();
+        stage_files(repo_path_str, &["c.txt".to_string()]).unwrap();
+        std::fs::write(repo_path.join("untracked1.txt"), "u\n").unwrap();
+        std::fs::write(repo_path.join("untracked2.txt"), "u\n").unwrap();
+
+        let counts = get_change_counts(repo_path_str).unwrap();
+        assert_eq!(
+            counts,
+            ChangeCounts {
+                staged: 3,
+                unstaged: 2,
+                untracked: 2,
+                conflicted: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_continue_merge_after_resolving_conflicts() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let base = commit_file(&repo, "file.txt", "base\n", "initial commit");
+        let base_commit = repo.find_commit(base).unwrap();
+        let main_ref = repo.head().unwrap().name().unwrap().to_string();
+
+        repo.branch("feature", &base_commit, false).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let feature = commit_file(&repo, "file.txt", "feature\n", "feature change");
+
+        repo.set_head(&main_ref).unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let main_head = commit_file(&repo, "file.txt", "main\n", "main change");
+
+        let annotated = repo.find_annotated_commit(feature).unwrap();
+        repo.merge(&[&annotated], None, None).unwrap();
+        let repo_path_str = repo_path.to_str().unwrap();
+
+        // 冲突未解决时拒绝继续
+        assert!(continue_merge(repo_path_str, None).is_err());
+
+        std::fs::write(repo_path.join("file.txt"), "resolved\n").unwrap();
+        stage_files(repo_path_str, &["file.txt".to_string()]).unwrap();
+
+        let result = continue_merge(repo_path_str, Some("Merge feature")).unwrap();
+        assert!(result.success);
+
+        let merge_commit = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(merge_commit.message(), Some("Merge feature"));
+        let parents: Vec<Oid> = merge_commit.parent_ids().collect();
+        assert_eq!(parents, vec![main_head, feature]);
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+        assert!(!repo.path().join("MERGE_HEAD").exists());
+    }
+
+    #[test]
+    fn test_conflict_paths_include_deleted_on_our_side() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let base = commit_file(&repo, "file.txt", "base\n", "add file");
+        let base_commit = repo.find_commit(base).unwrap();
+        let main_ref = repo.head().unwrap().name().unwrap().to_string();
+
+        repo.branch("feature", &base_commit, false).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let feature = commit_file(&repo, "file.txt", "feature\n", "feature change");
+
+        repo.set_head(&main_ref).unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+        std::fs::remove_file(repo_path.join("file.txt")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = repo.signature().unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "delete file",
+            &tree,
+            &[&base_commit],
+        )
+        .unwrap();
+
+        let annotated = repo.find_annotated_commit(feature).unwrap();
+        repo.merge(&[&annotated], None, None).unwrap();
+
+        let index = repo.index().unwrap();
+        assert!(index.has_conflicts());
+        assert_eq!(
+            conflict_paths(&index).unwrap(),
+            vec!["file.txt".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_cleanup_repository_state_removes_merge_head() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let head = commit_file(&repo, "README.md", "hello\n", "initial commit");
+        std::fs::write(repo.path().join("MERGE_HEAD"), format!("{}\n", head)).unwrap();
+        std::fs::write(repo.path().join("MERGE_MSG"), "Merge\n").unwrap();
+        let repo_path = repo_path.to_str().unwrap();
+        assert_eq!(repo.state(), git2::RepositoryState::Merge);
+
+        let cleared = cleanup_repository_state(repo_path).unwrap();
+        assert_eq!(cleared, RepositoryStateKind::Merge);
+        assert!(!repo.path().join("MERGE_HEAD").exists());
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+        assert_eq!(repo.head().unwrap().target(), Some(head));
+    }
+
+    #[test]
+    fn test_validate_branch_name() {
+        for name in ["feature/login", "fix-123", "release/v1.2", "用户/分支"] {
+            assert!(validate_branch_name(name).is_ok(), "{}", name);
+        }
+
+        for name in [
+            "",
+            "my branch",
+            "feature..x",
+            "/leading",
+            "trailing/",
+            "a//b",
+            "@",
+            "topic@{1}",
+            "-dash",
+            "what?",
+            "a~1",
+            "colon:name",
+            "ends.",
+            "name.lock",
+            ".hidden",
+            "feature/.hidden",
+        ] {
+            let error = validate_branch_name(name).unwrap_err();
+            assert_eq!(error.error_type(), "invalid_branch_name", "{}", name);
+        }
+
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let error =
+            create_branch(repo_path.to_str().unwrap(), "bad name", None, false).unwrap_err();
+        assert!(matches!(error, GitError::InvalidBranchName { .. }));
+    }
+
+    #[test]
+    fn test_switch_branch_reports_branch_checked_out_in_other_worktree() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        let head = commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let main_branch = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        let feature = repo
+            .branch("feature", &repo.find_commit(head).unwrap(), false)
+            .unwrap();
+        let worktree_path = dir.path().join("feature-wt");
+        let mut options = git2::WorktreeAddOptions::new();
+        options.reference(Some(feature.get()));
+        repo.worktree("feature-wt", &worktree_path, Some(&options))
+            .unwrap();
+        let expected = worktree_path.canonicalize().unwrap();
+
+        // 主工作树切换到已在链接工作树中检出的分支
+        let result = switch_branch(repo_path.to_str().unwrap(), "feature").unwrap();
+        assert!(!result.success);
+        let reported = result.worktree_path.unwrap();
+        assert_eq!(Path::new(&reported).canonicalize().unwrap(), expected);
+        assert!(result.message.contains(&reported));
+        assert_eq!(repo.head().unwrap().shorthand(), Some(main_branch.as_str()));
+
+        // 链接工作树切换到主工作树中的分支
+        let result = switch_branch(worktree_path.to_str().unwrap(), &main_branch).unwrap();
+        assert!(!result.success);
+        assert_eq!(
+            Path::new(&result.worktree_path.unwrap())
+                .canonicalize()
+                .unwrap(),
+            repo_path.canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fetch_and_report_lists_new_upstream_commits() {
+        let dir = TempDir::new().unwrap();
+        let (remote, local) = setup_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+        let local_head = local.head().unwrap().target();
+
+        commit_file(&remote, "a.txt", "a\n", "first upstream");
+        commit_file(&remote, "b.txt", "b\n", "second upstream");
+
+        let report = fetch_and_report(&local_path, None, None).unwrap();
+        assert_eq!(report.behind, 2);
+        let messages: Vec<&str> = report
+            .new_commits
+            .iter()
+            .map(|c| c.message.trim())
+            .collect();
+        assert_eq!(messages, vec!["second upstream", "first upstream"]);
+
+        // 本地分支保持不变
+        assert_eq!(local.head().unwrap().target(), local_head);
+    }
+
+    #[test]
+    fn test_commit_stage_tracked_skips_untracked_files() {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        let repo = init_repo(&repo_path);
+        commit_file(&repo, "tracked.txt", "one\n", "add tracked");
+        commit_file(&repo, "removed.txt", "gone\n", "add removed");
+
+        std::fs::write(repo_path.join("tracked.txt"), "two\n").unwrap();
+        std::fs::remove_file(repo_path.join("removed.txt")).unwrap();
+        std::fs::write(repo_path.join("untracked.txt"), "new\n").unwrap();
+
+        let options = CommitOptions {
+            message: "commit -a".to_string(),
+            stage_tracked: true,
+            ..Default::default()
+        };
+        let repo_path_str = repo_path.to_str().unwrap();
+        let sha = create_commit(repo_path_str, &options).unwrap();
+
+        let tree = repo
+            .find_commit(Oid::from_str(&sha).unwrap())
+            .unwrap()
+            .tree()
+            .unwrap();
+        let blob = tree
+            .get_path(Path::new("tracked.txt"))
+            .unwrap()
+            .to_object(&repo)
+            .unwrap()
+            .peel_to_blob()
+            .unwrap();
+        assert_eq!(blob.content(), b"two\n");
+        assert!(tree.get_path(Path::new("removed.txt")).is_err());
+        assert!(tree.get_path(Path::new("untracked.txt")).is_err());
+
+        let status = get_repository_status(repo_path_str).unwrap();
+        let paths: Vec<&str> = status.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["untracked.txt"]);
+    }
+
+    #[test]
+    fn test_preferred_remote_wins_over_heuristic() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        repo.remote("origin", "https://github.com/me/fork.git")
+            .unwrap();
+        repo.remote("upstream", "https://github.com/owner/project.git")
+            .unwrap();
+        let repo_path = dir.path().to_str().unwrap();
+
+        assert_eq!(get_default_remote_name(&repo).unwrap(), "origin");
+        assert_eq!(get_preferred_remote(repo_path).unwrap(), None);
+
+        set_preferred_remote(repo_path, Some("upstream")).unwrap();
+        let repo = Repository::open(dir.path()).unwrap();
+        assert_eq!(get_default_remote_name(&repo).unwrap(), "upstream");
+        assert_eq!(
+            get_preferred_remote(repo_path).unwrap().as_deref(),
+            Some("upstream")
+        );
+
+        assert!(set_preferred_remote(repo_path, Some("missing")).is_err());
+
+        set_preferred_remote(repo_path, None).unwrap();
+        let repo = Repository::open(dir.path()).unwrap();
+        assert_eq!(get_default_remote_name(&repo).unwrap(), "origin");
+    }
+
+    #[test]
+    fn test_parse_system_pull_output_up_to_date() {
+        let summary = parse_system_pull_output(true, "Already up to date.\n", "");
+        assert_eq!(summary.outcome, SystemPullOutcome::UpToDate);
+        assert_eq!(summary.files_changed, None);
+
+        let summary = parse_system_pull_output(true, "Already up-to-date.\n", "");
+        assert_eq!(summary.outcome, SystemPullOutcome::UpToDate);
+    }
+
+    #[test]
+    fn test_parse_system_pull_output_fast_forward() {
+        let stdout = concat!(
+            "Updating 1a2b3c4..5d6e7f8\n",
+            "Fast-forward\n",
+            " README.md   |  2 +-\n",
+            " src/main.rs | 10 ++++++++++\n",
+            " 2 files changed, 11 insertions(+), 1 deletion(-)\n",
+        );
+        let stderr =
+            "From github.com:owner/project\n   1a2b3c4..5d6e7f8  main       -> origin/main\n";
+
+        let summary = parse_system_pull_output(true, stdout, stderr);
+        assert_eq!(summary.outcome, SystemPullOutcome::FastForward);
+        assert_eq!(summary.files_changed, Some(2));
+        assert_eq!(summary.insertions, Some(11));
+        assert_eq!(summary.deletions, Some(1));
+
+        let summary = parse_system_pull_output(
+            true,
+            "Updating 1a2b3c4..5d6e7f8\nFast-forward\n notes.txt | 1 +\n 1 file changed, 1 insertion(+)\n",
+            "",
+        );
+        assert_eq!(summary.files_changed, Some(1));
+        assert_eq!(summary.insertions, Some(1));
+        assert_eq!(summary.deletions, Some(0));
+    }
+
+    #[test]
+    fn test_system_pull_output_is_parsed_under_localized_environment() {
+        let dir = TempDir::new().unwrap();
+        let (bare, local) = setup_bare_remote_and_clone(&dir);
+
+        // 另一个克隆推送新提交，使本地可以快进
+        let other_path = dir.path().join("other");
+        let other = Repository::clone(bare.path().to_str().unwrap(), &other_path).unwrap();
+        let mut config = other.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        commit_file(&other, "notes.txt", "note\n", "add notes");
+        let branch = other.head().unwrap().name().unwrap().to_string();
+        other
+            .find_remote("origin")
+            .unwrap()
+            .push(&[format!("{}:{}", branch, branch)], None)
+            .unwrap();
+
+        let output = match std::process::Command::new("git")
+            .env("LANG", "zh_CN.UTF-8")
+            .env("LANGUAGE", "zh_CN:zh")
+            .env("LC_MESSAGES", "zh_CN.UTF-8")
+            .envs(SYSTEM_GIT_LOCALE_ENV)
+            .arg("pull")
+            .current_dir(local.workdir().unwrap())
+            .output()
+        {
+            Ok(output) => output,
+            // 系统未安装Git
+            Err(_) => return,
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let summary = parse_system_pull_output(output.status.success(), &stdout, &stderr);
+        assert_eq!(
+            summary.outcome,
+            SystemPullOutcome::FastForward,
+            "{}",
+            stderr
+        );
+        assert_eq!(summary.files_changed, Some(1));
+        assert_eq!(summary.insertions, Some(1));
+    }
+
+    #[test]
+    fn test_parse_system_pull_output_localized_is_not_recognized() {
+        // 未固定语言环境时的 zh_CN 输出无法识别，快进会被当作合并
+        let stdout = concat!(
+            "更新 1a2b3c4..5d6e7f8\n",
+            "快进\n",
+            " notes.txt | 1 +\n",
+            " 1 个文件被修改，插入 1 行(+)\n",
+        );
+        let summary = parse_system_pull_output(true, stdout, "");
+        assert_eq!(summary.outcome, SystemPullOutcome::Merged);
+        assert_eq!(summary.files_changed, None);
+    }
+
+    #[test]
+    fn test_parse_system_pull_output_conflict_and_diverged() {
+        let stdout = concat!(
+            "Auto-merging a.txt\n",
+            "CONFLICT (content): Merge conflict in a.txt\n",
+            "Automatic merge failed; fix conflicts and then commit the result.\n",
+        );
+        let summary = parse_system_pull_output(false, stdout, "");
+        assert_eq!(summary.outcome, SystemPullOutcome::Conflict);
+
+        let stderr = concat!(
+            "hint: You have divergent branches and need to specify how to reconcile them.\n",
+            "fatal: Need to specify how to reconcile divergent branches.\n",
+        );
+        let summary = parse_system_pull_output(false, "", stderr);
+        assert_eq!(summary.outcome, SystemPullOutcome::Diverged);
+
+        let summary = parse_system_pull_output(false, "", "fatal: couldn't find remote ref main\n");
+        assert_eq!(summary.outcome, SystemPullOutcome::Failed);
+    }
+
+    #[test]
+    fn test_stat_path_reports_blob_and_tree_metadata() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("src/main.rs")).unwrap();
+        index.add_path(Path::new("src/lib.rs")).unwrap();
+        index.write().unwrap();
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let repo_path = dir.path().to_str().unwrap();
+
+        let file = stat_path(repo_path, None, "src/main.rs").unwrap();
+        assert_eq!(file.kind, PathKind::Blob);
+        assert_eq!(file.size, Some(13));
+        assert_eq!(file.entry_count, None);
+        assert_eq!(file.mode, 0o100644);
+
+        let src = stat_path(repo_path, Some("HEAD"), "src/").unwrap();
+        assert_eq!(src.kind, PathKind::Tree);
+        assert_eq!(src.size, None);
+        assert_eq!(src.entry_count, Some(2));
+
+        let root = stat_path(repo_path, None, "").unwrap();
+        assert_eq!(root.entry_count, Some(2));
+
+        assert!(stat_path(repo_path, None, "missing.txt").is_err());
+    }
+
+    #[test]
+    fn test_lint_commit_message_rules() {
+        let rules = CommitLintRules {
+            max_subject_length: Some(72),
+            warn_subject_length: Some(50),
+            require_blank_line: true,
+            conventional: true,
+            allowed_types: None,
+        };
+
+        let issues = lint_commit_message("feat(auth): add token refresh\n\nDetails.\n", &rules);
+        assert!(issues.is_empty(), "{:?}", issues);
+
+        let issues = lint_commit_message("update stuff\nno blank line", &rules);
+        let found: Vec<(&str, LintSeverity)> = issues
+            .iter()
+            .map(|issue| (issue.rule.as_str(), issue.severity))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("blank_line", LintSeverity::Error),
+                ("conventional", LintSeverity::Error),
+            ]
+        );
+
+        let long = format!("fix: {}", "x".repeat(50));
+        let issues = lint_commit_message(&long, &rules);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, LintSeverity::Warning);
+
+        let too_long = format!("fix: {}", "x".repeat(80));
+        let issues = lint_commit_message(&too_long, &rules);
+        assert_eq!(issues[0].rule, "subject_length");
+        assert_eq!(issues[0].severity, LintSeverity::Error);
+
+        let issues = lint_commit_message("wip: testing", &rules);
+        assert_eq!(issues[0].message, "不允许的提交类型 'wip'");
+
+        let issues = lint_commit_message("# comment only\n", &CommitLintRules::default());
+        assert_eq!(issues[0].rule, "subject_empty");
+
+        // 行号对应原消息，不受被忽略的注释行影响
+        let issues = lint_commit_message("# template\nupdate stuff\n# note\nbody", &rules);
+        let found: Vec<(&str, usize)> = issues
+            .iter()
+            .map(|issue| (issue.rule.as_str(), issue.line))
+            .collect();
+        assert_eq!(found, vec![("blank_line", 4), ("conventional", 2)]);
+    }
+
+    #[test]
+    fn test_get_path_status_for_single_paths() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "tracked.txt", "one\n", "add tracked");
+        commit_file(&repo, "modified.txt", "one\n", "add modified");
+        commit_file(&repo, "staged.txt", "one\n", "add staged");
+        std::fs::write(dir.path().join("modified.txt"), "two\n").unwrap();
+        std::fs::write(dir.path().join("staged.txt"), "two\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("staged.txt")).unwrap();
+        index.write().unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(dir.path().join("debug.log"), "log\n").unwrap();
+        let repo_path = dir.path().to_str().unwrap();
+
+        let status = |path: &str| get_path_status(repo_path, path).unwrap();
+        assert_eq!(status("tracked.txt"), PathStatus::Tracked);
+        assert_eq!(status("modified.txt"), PathStatus::Modified);
+        assert_eq!(status("staged.txt"), PathStatus::Staged);
+        assert_eq!(status(".gitignore"), PathStatus::Untracked);
+        assert_eq!(status("debug.log"), PathStatus::Ignored);
+
+        let absolute = dir.path().join("tracked.txt");
+        assert_eq!(status(absolute.to_str().unwrap()), PathStatus::Tracked);
+
+        let outside = TempDir::new().unwrap();
+        let error = get_path_status(repo_path, outside.path().to_str().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("不在仓库"), "{}", error);
+        assert!(get_path_status(repo_path, "../escape.txt").is_err());
+    }
+
+    #[test]
+    fn test_restore_deleted_file_from_head() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        std::fs::create_dir_all(dir.path().join("docs")).unwrap();
+        commit_file(&repo, "docs/guide.md", "# Guide\n", "add guide");
+        let repo_path = dir.path().to_str().unwrap();
+
+        std::fs::remove_dir_all(dir.path().join("docs")).unwrap();
+        restore_file_from_commit(repo_path, "HEAD", "docs/guide.md").unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("docs/guide.md")).unwrap(),
+            "# Guide\n"
+        );
+        assert_eq!(
+            get_path_status(repo_path, "docs/guide.md").unwrap(),
+            PathStatus::Tracked
+        );
+
+        let error = restore_file_from_commit(repo_path, "HEAD", "missing.md").unwrap_err();
+        assert!(error.to_string().contains("不存在"), "{}", error);
+    }
+
+    #[test]
+    fn test_switch_branch_reports_checkout_progress() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let main_ref = repo.head().unwrap().name().unwrap().to_string();
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feature", &base, false).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        for index in 0..5 {
+            commit_file(
+                &repo,
+                &format!("file{}.txt", index),
+                "content\n",
+                "add file",
+            );
+        }
+        repo.set_head(&main_ref).unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let repo_path = dir.path().to_str().unwrap();
+
+        let mut events = Vec::new();
+        let result =
+            switch_branch_with_progress(repo_path, "feature", |progress| events.push(progress))
+                .unwrap();
+
+        assert!(result.success, "{}", result.message);
+        let last = events.last().expect("应至少报告一次检出进度");
+        assert!(last.total_steps >= 5);
+        assert_eq!(last.completed_steps, last.total_steps);
+        assert!(events
+            .iter()
+            .any(|progress| progress.current_path.as_deref() == Some("file0.txt")));
+    }
+
+    #[test]
+    fn test_switch_branch_blocked_by_uncommitted_changes() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let head_ref = repo.head().unwrap().name().unwrap().to_string();
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feature", &base, false).unwrap();
+        std::fs::write(dir.path().join("README.md"), "dirty\n").unwrap();
+        let repo_path = dir.path().to_str().unwrap();
+
+        let result = switch_branch(repo_path, "feature").unwrap();
+
+        assert!(!result.success);
+        assert!(result.has_uncommitted_changes);
+        assert_eq!(result.uncommitted_files, vec!["README.md".to_string()]);
+        // 分支和工作区均保持不变
+        assert_eq!(repo.head().unwrap().name(), Some(head_ref.as_str()));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("README.md")).unwrap(),
+            "dirty\n"
+        );
+    }
+
+    #[test]
+    fn test_get_diff_stat_totals_with_rename() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let body = "line one\nline two\nline three\nline four\nline five\n";
+        commit_file(&repo, "old_name.txt", body, "add file");
+        commit_file(&repo, "notes.txt", "a\nb\n", "add notes");
+        let repo_path = dir.path().to_str().unwrap();
+
+        // 重命名一个文件，并修改另一个文件（1 行删除、2 行新增）
+        std::fs::rename(
+            dir.path().join("old_name.txt"),
+            dir.path().join("new_name.txt"),
+        )
+        .unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("old_name.txt")).unwrap();
+        index.add_path(Path::new("new_name.txt")).unwrap();
+        index.write().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "a\nc\nd\n").unwrap();
+
+        let stat = get_diff_stat(repo_path, None, None).unwrap();
+        assert_eq!(stat.files_changed, 2);
+        assert_eq!(stat.insertions, 2);
+        assert_eq!(stat.deletions, 1);
+
+        let renamed = stat
+            .files
+            .iter()
+            .find(|file| file.path == "new_name.txt")
+            .unwrap();
+        assert_eq!(renamed.status, "renamed");
+        assert_eq!(renamed.old_path.as_deref(), Some("old_name.txt"));
+        assert_eq!((renamed.additions, renamed.deletions), (0, 0));
+
+        let history = get_diff_stat(repo_path, Some("HEAD~1"), Some("HEAD")).unwrap();
+        assert_eq!(history.files_changed, 1);
+        assert_eq!(history.insertions, 2);
+        assert_eq!(history.files[0].status, "added");
+    }
+
+    #[test]
+    fn test_verify_commit_signature_unsigned_and_status_parsing() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let oid = commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let repo_path = dir.path().to_str().unwrap();
+
+        let result = verify_commit_signature(repo_path, &oid.to_string()).unwrap();
+        assert!(!result.signed);
+        assert!(!result.valid);
+
+        let status = concat!(
+            "[GNUPG:] NEWSIG\n",
+            "[GNUPG:] GOODSIG 1234ABCD5678EF90 Alice <alice@example.com>\n",
+            "[GNUPG:] VALIDSIG 0123456789ABCDEF 2024-01-01\n",
+        );
+        assert_eq!(
+            parse_gpg_status(status),
+            (
+                true,
+                Some("Alice <alice@example.com>".to_string()),
+                Some("1234ABCD5678EF90".to_string())
+            )
+        );
+        let (valid, _, key_id) =
+            parse_gpg_status("[GNUPG:] ERRSIG 1234ABCD5678EF90 1 8 00 1700000000 9\n");
+        assert!(!valid);
+        assert_eq!(key_id.as_deref(), Some("1234ABCD5678EF90"));
+
+        let ssh = "Good \"git\" signature for alice@example.com with ED25519 key SHA256:abc123\n";
+        assert_eq!(
+            parse_ssh_verify_output(ssh),
+            (
+                true,
+                Some("alice@example.com".to_string()),
+                Some("SHA256:abc123".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn test_branches_containing_commit() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let base = commit_file(&repo, "README.md", "hello\n", "initial commit");
+        let main_ref = repo.head().unwrap().name().unwrap().to_string();
+        let base_commit = repo.find_commit(base).unwrap();
+        repo.branch("old-release", &base_commit, false).unwrap();
+
+        let fix = commit_file(&repo, "fix.txt", "fix\n", "apply fix");
+        let fix_commit = repo.find_commit(fix).unwrap();
+        repo.branch("hotfix", &fix_commit, false).unwrap();
+        repo.set_head("refs/heads/hotfix").unwrap();
+        commit_file(&repo, "more.txt", "more\n", "follow-up");
+        repo.set_head(&main_ref).unwrap();
+        let repo_path = dir.path().to_str().unwrap();
+
+        let main_name = main_ref.trim_start_matches("refs/heads/").to_string();
+        let mut expected = vec!["hotfix".to_string(), main_name];
+        expected.sort();
+        assert_eq!(
+            branches_containing(repo_path, &fix.to_string(), false).unwrap(),
+            expected
+        );
+
+        let all = branches_containing(repo_path, &base.to_string(), true).unwrap();
+        assert!(all.contains(&"old-release".to_string()));
+        assert_eq!(all.len(), 3);
+    }
+
+    #[test]
+    fn test_push_diverged_branch_returns_non_fast_forward() {
+        let dir = TempDir::new().unwrap();
+        let (_bare, local) = setup_bare_remote_and_clone(&dir);
+        let branch = local.head().unwrap().shorthand().unwrap().to_string();
+
+        // 另一个克隆先推送新提交，使远程领先于本地
+        let other_path = dir.path().join("other");
+        let other = Repository::clone(dir.path().join("remote.git").to_str().unwrap(), &other_path)
+            .unwrap();
+        let mut config = other.config().unwrap();
+        config.set_str("user.name", "Other User").unwrap();
+        config.set_str("user.email", "other@example.com").unwrap();
+        commit_file(&other, "other.txt", "other\n", "remote change");
+        let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+        other
+            .find_remote("origin")
+            .unwrap()
+            .push(&[&refspec], None)
+            .unwrap();
+
+        commit_file(&local, "local.txt", "local\n", "local change");
+        let local_path = local.workdir().unwrap().to_str().unwrap().to_string();
+
+        let error = push_remote_with_token(&local_path, Some("origin"), false, None).unwrap_err();
+        assert_eq!(error.error_type(), "non_fast_forward");
+        assert!(matches!(&error, GitError::NonFastForward { branch: b } if *b == branch));
+
+        assert!(is_non_fast_forward_message(
+            "failed to push some refs (fetch first)"
+        ));
+        assert!(!is_non_fast_forward_message(
+            "protected branch hook declined"
+        ));
+    }
+
+    #[test]
+    fn test_get_repository_size_counts_work_tree_and_git_dir() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let content = "x".repeat(4096);
+        commit_file(&repo, "data.txt", &content, "add data");
+        let repo_path = dir.path().to_str().unwrap();
+
+        let size = get_repository_size(repo_path).unwrap();
+        assert!(size.work_tree_bytes >= 4096);
+        assert!(size.git_dir_bytes > 0);
+        assert_eq!(size.total_bytes, size.work_tree_bytes + size.git_dir_bytes);
+        assert_eq!(size.pack_count, 0);
+        // blob、tree、commit 各一个松散对象
+        assert!(size.loose_object_count >= 3);
+
+        // 指向仓库自身的符号链接不会导致死循环，也不会重复计算
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path(), dir.path().join("loop")).unwrap();
+            let with_link = get_repository_size(repo_path).unwrap();
+            assert!(with_link.work_tree_bytes < size.work_tree_bytes + 1024);
+        }
+    }
+
+    #[test]
+    fn test_recent_repository_info_uses_root_and_remote() {
+        let dir = TempDir::new().unwrap();
+        let (_bare, local) = setup_bare_remote_and_clone(&dir);
+        let local = local.workdir().unwrap().to_path_buf();
+        let sub_dir = local.join("nested");
+        std::fs::create_dir(&sub_dir).unwrap();
+
+        let info = recent_repository_info(sub_dir.to_str().unwrap()).unwrap();
+        assert_eq!(
+            Path::new(&info.path).canonicalize().unwrap(),
+            local.canonicalize().unwrap()
+        );
+        assert_eq!(
+            info.name,
+            local.file_name().unwrap().to_string_lossy().to_string()
+        );
+        assert!(info.remote_url.unwrap().contains("remote.git"));
+        assert!(info.last_opened > 0);
+
+        let plain = TempDir::new().unwrap();
+        assert!(recent_repository_info(plain.path().to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_diff_ignores_line_ending_changes() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        repo.config()
+            .unwrap()
+            .set_bool("core.autocrlf", false)
+            .unwrap();
+        commit_file(&repo, "lines.txt", "one\ntwo\nthree\n", "add lines");
+        std::fs::write(dir.path().join("lines.txt"), "one\r\ntwo\r\nthree\r\n").unwrap();
+        let repo_path = dir.path().to_str().unwrap();
+
+        let plain = WhitespaceOptions::default();
+        assert!(!get_file_diff(repo_path, "lines.txt", false, &plain)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            get_unstaged_diff(repo_path, &plain).unwrap()[0].deletions,
+            3
+        );
+
+        let ignore_eol = WhitespaceOptions {
+            ignore_whitespace_eol: true,
+            ..Default::default()
+        };
+        assert!(get_file_diff(repo_path, "lines.txt", false, &ignore_eol)
+            .unwrap()
+            .is_empty());
+        let diffs = get_unstaged_diff(repo_path, &ignore_eol).unwrap();
+        assert!(diffs
+            .iter()
+            .all(|diff| diff.hunks.is_empty() && diff.additions == 0 && diff.deletions == 0));
+        let against = get_file_diff_against(repo_path, "lines.txt", "HEAD", &ignore_eol).unwrap();
+        assert!(against.hunks.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_special_refs() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let first = commit_file(&repo, "a.txt", "one\n", "first");
+        let second = commit_file(&repo, "a.txt", "two\n", "second");
+        let repo_path = dir.path().to_str().unwrap();
+        let branch = repo.head().unwrap().name().unwrap().to_string();
+
+        let head = resolve_ref(repo_path, "HEAD").unwrap().unwrap();
+        assert!(head.is_symbolic);
+        assert_eq!(head.symbolic_target.as_deref(), Some(branch.as_str()));
+        assert_eq!(head.target_sha, Some(second.to_string()));
+
+        // 短名解析为分支全名
+        let short_name = branch.trim_start_matches("refs/heads/");
+        let branch_ref = resolve_ref(repo_path, short_name).unwrap().unwrap();
+        assert_eq!(branch_ref.name, branch);
+        assert!(!branch_ref.is_symbolic);
+
+        // 未在合并中时没有 MERGE_HEAD
+        assert!(resolve_ref(repo_path, "MERGE_HEAD").unwrap().is_none());
+        assert!(resolve_ref(repo_path, "FETCH_HEAD").unwrap().is_none());
+
+        std::fs::write(repo.path().join("ORIG_HEAD"), format!("{}\n", first)).unwrap();
+        let orig_head = resolve_ref(repo_path, "ORIG_HEAD").unwrap().unwrap();
+        assert_eq!(orig_head.target_sha, Some(first.to_string()));
+
+        std::fs::write(
+            repo.path().join("FETCH_HEAD"),
+            format!("{}\t\tbranch 'main' of https://example.com/repo\n", second),
+        )
+        .unwrap();
+        let fetch_head = resolve_ref(repo_path, "FETCH_HEAD").unwrap().unwrap();
+        assert_eq!(fetch_head.target_sha, Some(second.to_string()));
+    }
+
+    #[test]
+    fn test_map_line_to_parent_across_hunks() {
+        // 第 2 行被替换为 2 行，第 6 行后插入 1 行，第 9 行被删除
+        let hunks = [
+            LineHunk {
+                old_start: 2,
+                old_lines: 1,
+                new_start: 2,
+                new_lines: 2,
+            },
+            LineHunk {
+                old_start: 5,
+                old_lines: 0,
+                new_start: 7,
+                new_lines: 1,
+            },
+            LineHunk {
+                old_start: 8,
+                old_lines: 1,
+                new_start: 9,
+                new_lines: 0,
+            },
+        ];
+
+        assert_eq!(map_line_to_parent(&hunks, 1), Some(1));
+        assert_eq!(map_line_to_parent(&hunks, 2), Some(2));
+        assert_eq!(map_line_to_parent(&hunks, 3), Some(2));
+        assert_eq!(map_line_to_parent(&hunks, 4), Some(3));
+        assert_eq!(map_line_to_parent(&hunks, 7), None);
+        assert_eq!(map_line_to_parent(&hunks, 8), Some(6));
+        assert_eq!(map_line_to_parent(&hunks, 10), Some(9));
+        assert_eq!(map_line_to_parent(&[], 4), Some(4));
+    }
+
+    #[test]
+    fn test_blame_skips_ignored_formatting_commit() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let original = commit_file(&repo, "main.rs", "fn main(){\nlet x=1;\n}\n", "add main");
+        let feature = commit_file(
+            &repo,
+            "main.rs",
+            "fn main(){\nlet x=1;\n}\nfn helper(){}\n",
+            "add helper",
+        );
+        let formatting = commit_file(
+            &repo,
+            "main.rs",
+            "fn main() {\n    let x = 1;\n}\nfn helper(){}\n",
+            "format code",
+        );
+        let repo_path = dir.path().to_str().unwrap();
+
+        let lines = blame_file(repo_path, "main.rs", None).unwrap();
+        assert_eq!(lines[0].commit_sha, formatting.to_string());
+        assert!(lines[0].ignored_commit.is_none());
+
+        std::fs::write(
+            dir.path().join(BLAME_IGNORE_REVS_FILE),
+            format!("# 格式化提交\n{}\n", formatting),
+        )
+        .unwrap();
+
+        let lines = blame_file(repo_path, "main.rs", None).unwrap();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0].commit_sha, original.to_string());
+        assert_eq!(lines[0].content, "fn main() {");
+        assert_eq!(lines[1].commit_sha, original.to_string());
+        assert_eq!(
+            lines[1].ignored_commit.as_deref(),
+            Some(formatting.to_string().as_str())
+        );
+        assert_eq!(lines[3].commit_sha, feature.to_string());
+        assert!(lines[3].ignored_commit.is_none());
+    }
+
+    #[test]
+    fn test_ref_exists_resolves_abbreviated_sha_and_tags() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let first = commit_file(&repo, "a.txt", "one\n", "first");
+        let second = commit_file(&repo, "a.txt", "two\n", "second");
+        let repo_path = dir.path().to_str().unwrap();
+
+        let first_commit = repo.find_commit(first).unwrap();
+        repo.tag_lightweight("v1-light", first_commit.as_object(), false)
+            .unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        repo.tag(
+            "v1",
+            first_commit.as_object(),
+            &signature,
+            "release v1",
+            false,
+        )
+        .unwrap();
+
+        let first_sha = first.to_string();
+        assert_eq!(
+            ref_exists(repo_path, &first_sha[..7]).unwrap(),
+            Some(first_sha.clone())
+        );
+        assert_eq!(
+            ref_exists(repo_path, "v1").unwrap(),
+            Some(first_sha.clone())
+        );
+        assert_eq!(
+            ref_exists(repo_path, "v1-light").unwrap(),
+            Some(first_sha.clone())
+        );
+        assert_eq!(
+            ref_exists(repo_path, "refs/tags/v1").unwrap(),
+            Some(first_sha)
+        );
+        assert_eq!(
+            ref_exists(repo_path, "HEAD").unwrap(),
+            Some(second.to_string())
+        );
+        assert_eq!(ref_exists(repo_path, "no-such-ref").unwrap(), None);
+        assert_eq!(ref_exists(repo_path, "deadbeef").unwrap(), None);
+    }
+
+    #[test]
+    fn test_remote_url_selects_token_per_target_remote() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "a.txt", "one\n", "first");
+        repo.remote("origin", "https://github.com/owner/repo.git")
+            .unwrap();
+        repo.remote("upstream", "https://gitlab.internal.example/team/repo.git")
+            .unwrap();
+        let repo_path = dir.path().to_str().unwrap();
+
+        // 按域名存储的Token
+        let tokens: std::collections::HashMap<String, &str> = [
+            ("github.com".to_string(), "github-token"),
+            ("gitlab.internal.example".to_string(), "gitlab-token"),
+        ]
+        .into_iter()
+        .collect();
+        let token_for = |remote_name: Option<&str>| {
+            let url = get_remote_url(repo_path, remote_name).unwrap().unwrap();
+            select_token(&url, &tokens).copied()
+        };
+
+        assert_eq!(token_for(None), Some("github-token"));
+        assert_eq!(token_for(Some("origin")), Some("github-token"));
+        assert_eq!(token_for(Some("upstream")), Some("gitlab-token"));
+        assert!(get_remote_url(repo_path, Some("missing")).is_err());
+
+        // SSH 远程和未配置Token的域名不使用Token
+        assert_eq!(select_token("git@github.com:owner/repo.git", &tokens), None);
+        assert_eq!(
+            select_token("https://gitee.com/owner/repo.git", &tokens),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_host_key_target() {
+        assert_eq!(
+            parse_host_key_target("github.com").unwrap(),
+            ("github.com".to_string(), None)
+        );
+        assert_eq!(
+            parse_host_key_target(" git.example.com:2222 ").unwrap(),
+            ("git.example.com".to_string(), Some(2222))
+        );
+        assert_eq!(
+            parse_host_key_target("[::1]:22").unwrap(),
+            ("::1".to_string(), Some(22))
+        );
+        assert_eq!(
+            parse_host_key_target("[fe80::1]").unwrap(),
+            ("fe80::1".to_string(), None)
+        );
+
+        for host in [
+            "",
+            "-oProxyCommand=touch /tmp/x",
+            "host name",
+            "host;rm -rf /",
+            "github.com:ssh",
+            "github.com:70000",
+            "[::1",
+            "[::1]22",
+            "[github.com]",
+            "fe80::1",
+        ] {
+            assert!(parse_host_key_target(host).is_err(), "{}", host);
+        }
+    }
+
+    #[test]
+    fn test_export_commit_history_csv_and_json() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        commit_file(&repo, "a.txt", "one\n", "first commit");
+        commit_file(&repo, "b.txt", "x\ny\n", "fix: parse \"quoted\", values");
+        let repo_path = dir.path().to_str().unwrap();
+        let csv_path = dir.path().join("history.csv");
+        let json_path = dir.path().join("history.json");
+
+        let result = export_commit_history(
+            repo_path,
+            csv_path.to_str().unwrap(),
+            HistoryExportFormat::Csv,
+            10,
+            &CommitHistoryFilter::default(),
+        )
+        .unwrap();
+        assert_eq!(result.rows, 2);
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "sha,author,email,date,subject,insertions,deletions"
+        );
+        assert!(lines[1].contains(",\"fix: parse \"\"quoted\"\", values\","));
+        assert!(lines[1].ends_with(",2,0"));
+
+        let filter = CommitHistoryFilter {
+            path: Some("a.txt".to_string()),
+            ..Default::default()
+        };
+        let result = export_commit_history(
+            repo_path,
+            json_path.to_str().unwrap(),
+            HistoryExportFormat::Json,
+            10,
+            &filter,
+        )
+        .unwrap();
+        assert_eq!(result.rows, 1);
+        let rows: Vec<CommitExportRow> =
+            serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(rows[0].subject, "first commit");
+        assert_eq!((rows[0].insertions, rows[0].deletions), (1, 0));
+    }
+
+    #[test]
+    fn test_merge_branch_commit_message_names_the_branch() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let base = commit_file(&repo, "a.txt", "base\n", "base");
+        let main_ref = repo.head().unwrap().name().unwrap().to_string();
+        let repo_path = dir.path().to_str().unwrap();
+
+        repo.branch("feature", &repo.find_commit(base).unwrap(), false)
+            .unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        commit_file(&repo, "b.txt", "feature\n", "feature change");
+
+        repo.set_head(&main_ref).unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        commit_file(&repo, "c.txt", "main\n", "main change");
+
+        let result = merge_branch(repo_path, "feature", None).unwrap();
+        assert!(result.success, "{}", result.message);
+
+        let merge_commit = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(merge_commit.parent_count(), 2);
+        assert_eq!(merge_commit.message(), Some("Merge branch 'feature'"));
+    }
+
+    #[test]
+    fn test_local_merge_conflict_then_continue_records_two_parents() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(dir.path());
+        let base = commit_file(&repo, "a.txt", "base\n", "base");
+        let main_ref = repo.head().unwrap().name().unwrap().to_string();
+        let repo_path = dir.path().to_str().unwrap();
+
+        // 在 feature 分支上修改同一行
+        repo.branch("feature", &repo.find_commit(base).unwrap(), false)
+            .unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let feature = commit_file(&repo, "a.txt", "feature\n", "feature change");
+
+        repo.set_head(&main_ref).unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        let main = commit_file(&repo, "a.txt", "main\n", "main change");
+
+        let result = merge_branch(repo_path, "feature", None).unwrap();
+        assert!(!result.success);
+        assert!(result.has_conflicts);
+        assert_eq!(result.conflict_files, vec!["a.txt".to_string()]);
+        assert_eq!(
+            resolve_ref(repo_path, "MERGE_HEAD")
+                .unwrap()
+                .unwrap()
+                .target_sha,
+            Some(feature.to_string())
+        );
+        let conflicted = std::fs::read_to_string(dir.path().join("a.txt")).unwrap();
+        assert!(conflicted.contains("<<<<<<<"));
+
+        // 未解决冲突时不能完成合并
+        assert!(continue_merge(repo_path, None).is_err());
+
+        std::fs::write(dir.path().join("a.txt"), "main\nfeature\n").unwrap();
+        stage_files(repo_path, &["a.txt".to_string()]).unwrap();
+        assert!(
+            continue_merge(repo_path, Some("merge feature"))
+                .unwrap()
+                .success
+        );
+
+        let merge_commit = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(merge_commit.parent_count(), 2);
+        assert_eq!(merge_commit.parent_id(0).unwrap(), main);
+        assert_eq!(merge_commit.parent_id(1).unwrap(), feature);
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+    }
+
+    #[test]
+    fn test_preview_pull_changes_flags_dirty_files() {
+        let dir = TempDir::new().unwrap();
+        let (bare, local) = setup_bare_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_path_buf();
+        let local_path_str = local_path.to_str().unwrap();
+
+        // 另一个克隆向远程推送修改
+        let other_path = dir.path().join("other");
+        let other = Repository::clone(bare.path().to_str().unwrap(), &other_path).unwrap();
+        commit_file(&other, "README.md", "hello\nupdated\n", "update readme");
+        commit_file(&other, "new.txt", "new\n", "add new file");
+        let branch = other.head().unwrap().name().unwrap().to_string();
+        other
+            .find_remote("origin")
+            .unwrap()
+            .push(&[format!("{}:{}", branch, branch)], None)
+            .unwrap();
+
+        // 本地未提交地修改 README.md
+        std::fs::write(local_path.join("README.md"), "hello\nlocal\n").unwrap();
+
+        let files = preview_pull_changes(local_path_str, None, None, true).unwrap();
+        assert_eq!(files.len(), 2);
+        let readme = files.iter().find(|f| f.path == "README.md").unwrap();
+        assert_eq!(readme.status, "modified");
+        assert_eq!(readme.additions, 1);
+        assert!(readme.potential_conflict);
+        let new_file = files.iter().find(|f| f.path == "new.txt").unwrap();
+        assert_eq!(new_file.status, "added");
+        assert!(!new_file.potential_conflict);
+
+        // 预览不会修改本地分支和工作区
+        assert_eq!(
+            std::fs::read_to_string(local_path.join("README.md")).unwrap(),
+            "hello\nlocal\n"
+        );
+        assert!(!local_path.join("new.txt").exists());
+    }
+
+    #[test]
+    fn test_selected_ssh_key_used_only_for_ssh_remotes() {
+        let ssh_allowed = git2::CredentialType::SSH_KEY;
+        let auth = ssh_key_auth(Some("/home/user/.ssh/id_work".to_string()), None).unwrap();
+        assert!(matches!(auth.auth_type, AuthType::Ssh));
+        assert_eq!(
+            auth.ssh_key_path.as_deref(),
+            Some("/home/user/.ssh/id_work")
+        );
+        assert!(auth.ssh_key_passphrase.is_none());
+        assert!(ssh_key_auth(None, Some("secret".to_string())).is_none());
+
+        // SSH 远程只尝试指定的私钥，被拒绝后停止
+        assert_eq!(
+            choose_credential(1, 5, false, false, true, ssh_allowed),
+            CredentialChoice::SshKey
+        );
+        assert!(matches!(
+            choose_credential(2, 5, false, false, true, ssh_allowed),
+            CredentialChoice::Fail(_)
+        ));
+
+        // HTTPS 远程忽略指定的私钥
+        let http_allowed = git2::CredentialType::USER_PASS_PLAINTEXT;
+        assert_eq!(
+            choose_credential(1, 5, true, true, true, http_allowed),
+            CredentialChoice::TokenAsUsername
+        );
+
+        // URL 未包含用户名时先提供用户名，之后再使用指定的私钥
+        assert_eq!(
+            choose_credential(1, 5, false, false, true, git2::CredentialType::USERNAME),
+            CredentialChoice::SshUsername
+        );
+    }
+
+    #[test]
+    fn test_authenticated_callbacks_offer_username_then_selected_key() {
+        let dir = TempDir::new().unwrap();
+        let key_path = dir.path().join("id_work");
+        std::fs::write(&key_path, "private key").unwrap();
+        let ssh_auth = ssh_key_auth(Some(key_path.to_string_lossy().to_string()), None);
+
+        let attempts = AuthAttempts::new(DEFAULT_MAX_AUTH_ATTEMPTS);
+        let mut resolver = CredentialResolver::new(None, ssh_auth, &attempts);
+        let url = "ssh://github.com/owner/repo.git";
+
+        // libgit2 先单独请求用户名，不计入认证尝试
+        let username = resolver
+            .resolve(url, None, git2::CredentialType::USERNAME)
+            .unwrap();
+        assert_eq!(username.credtype(), git2::CredentialType::USERNAME.bits());
+        assert!(attempts.methods_tried().is_empty());
+
+        let ssh_allowed = git2::CredentialType::SSH_KEY | git2::CredentialType::SSH_CUSTOM;
+        let key = resolver.resolve(url, Some("git"), ssh_allowed).unwrap();
+        assert_eq!(key.credtype(), git2::CredentialType::SSH_KEY.bits());
+        assert_eq!(attempts.methods_tried(), vec!["ssh_key".to_string()]);
+
+        // 私钥被拒绝后不再重试
+        assert!(resolver.resolve(url, Some("git"), ssh_allowed).is_err());
+        let error = attempts.to_error(git2::Error::new(
+            git2::ErrorCode::Auth,
+            git2::ErrorClass::Ssh,
+            "rejected",
+        ));
+        assert_eq!(error.error_type(), "authentication");
+    }
+
+    #[test]
+    fn test_fetch_reports_behind_for_branch_without_upstream() {
+        let dir = TempDir::new().unwrap();
+        let (bare, local) = setup_bare_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_string_lossy().to_string();
+
+        // 本地分支未配置上游
+        let head_commit = local.head().unwrap().peel_to_commit().unwrap();
+        local.branch("feature", &head_commit, false).unwrap();
+        local.set_head("refs/heads/feature").unwrap();
+        assert!(local.branch_upstream_name("refs/heads/feature").is_err());
+
+        // 远程同名分支有一个新提交
+        let base = bare.find_commit(head_commit.id()).unwrap();
+        let sig = git2::Signature::now("Remote User", "remote@example.com").unwrap();
+        bare.commit(
+            Some("refs/heads/feature"),
+            &sig,
+            &sig,
+            "remote update",
+            &base.tree().unwrap(),
+            &[&base],
+        )
+        .unwrap();
+
+        let result = fetch_remote(&local_path, Some("origin"), false, None).unwrap();
+        assert_eq!((result.ahead, result.behind), (0, 1));
+        assert_eq!(
+            get_fetched_ahead_behind(&local_path, "origin").unwrap(),
+            (0, 1)
+        );
+    }
+
+    #[test]
+    fn test_fetch_compares_against_upstream_before_same_name_branch() {
+        let dir = TempDir::new().unwrap();
+        let (bare, local) = setup_bare_remote_and_clone(&dir);
+        let local_path = local.workdir().unwrap().to_string_lossy().to_string();
+        let default_branch = local.head().unwrap().shorthand().unwrap().to_string();
+
+        // 本地 topic 跟踪 origin/<默认分支>，远程同时存在同名的 topic 分支
+        let head_commit = local.head().unwrap().peel_to_commit().unwrap();
+        let base = bare.find_commit(head_commit.id()).unwrap();
+        bare.branch("topic", &base, false).unwrap();
+        let mut topic = local.branch("topic", &head_commit, false).unwrap();
+        topic
+            .set_upstream(Some(&format!("origin/{}", default_branch)))
+            .unwrap();
+        local.set_head("refs/heads/topic").unwrap();
+
+        // 只有上游分支有新提交
+        let sig = git2::Signature::now("Remote User", "remote@example.com").unwrap();
+        bare.commit(
+            Some(&format!("refs/heads/{}", default_branch)),
+            &sig,
+            &sig,
+            "remote update",
+            &base.tree().unwrap(),
+            &[&base],
+        )
+        .unwrap();
+
+        let result = fetch_remote(&local_path, Some("origin"), false, None).unwrap();
+        assert_eq!((result.ahead, result.behind), (0, 1));
+        assert_eq!(
+            get_fetched_ahead_behind(&local_path, "origin").unwrap(),
+            (0, 1)
+        );
+    }
+
+    #[test]
+    fn test_parse_repository_url_variants() {
+        let cases = [
+            (
+                "https://github.com/octo/hello.git",
+                "https",
+                "github.com",
+                "octo",
+                "hello",
+            ),
+            (
+                "http://git.example.com:8080/team/tool",
+                "http",
+                "git.example.com",
+                "team",
+                "tool",
+            ),
+            (
+                "git@github.com:octo/hello.git",
+                "ssh",
+                "github.com",
+                "octo",
+                "hello",
+            ),
+            (
+                "ssh://git@example.com:2222/octo/hello.git",
+                "ssh",
+                "example.com",
+                "octo",
+                "hello",
+            ),
+            (
+                "https://gitlab.com/group/subgroup/project.git",
+                "https",
+                "gitlab.com",
+                "group/subgroup",
+                "project",
+            ),
+            (
+                "git@gitlab.com:group/sub/deeper/project.git",
+                "ssh",
+                "gitlab.com",
+                "group/sub/deeper",
+                "project",
+            ),
+            (
+                "org-1@github.com:octo/hello.git",
+                "ssh",
+                "github.com",
+                "octo",
+                "hello",
+            ),
+        ];
+        for (url, protocol, host, owner, repo) in cases {
+            let parsed = parse_repository_url(url);
+            assert!(parsed.valid, "{}", url);
+            assert_eq!(parsed.protocol.as_deref(), Some(protocol), "{}", url);
+            assert_eq!(parsed.host.as_deref(), Some(host), "{}", url);
+            assert_eq!(parsed.owner.as_deref(), Some(owner), "{}", url);
+            assert_eq!(parsed.repo.as_deref(), Some(repo), "{}", url);
+            assert_eq!(parsed.suggested_directory.as_deref(), Some(repo), "{}", url);
+        }
+
+        for url in [
+            "",
+            "ftp://example.com/a/b.git",
+            "https://github.com/",
+            "git@github.com",
+            "/srv/git/repo.git",
+            "C:/repos/repo.git",
+        ] {
+            assert!(!parse_repository_url(url).valid, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_quote_shell_path_escapes_single_quotes() {
+        assert_eq!(
+            quote_shell_path(Path::new("/tmp/O'Brien/todo")),
+            "'/tmp/O'\\''Brien/todo'"
+        );
+        assert_eq!(
+            quote_shell_path(Path::new("C:\\Users\\dev\\todo")),
+            "'C:/Users/dev/todo'"
+        );
+    }
+
+    #[test]
+    fn test_rebase_todo_runs_from_path_with_single_quote() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(&dir.path().join("repo"));
+        let base = commit_file(&repo, "a.txt", "a\n", "base");
+        let reworded = commit_file(&repo, "b.txt", "b\n", "old message");
+        let dropped = commit_file(&repo, "c.txt", "c\n", "to drop");
+
+        let work_dir = dir.path().join("O'Brien $(touch injected)");
+        std::fs::create_dir_all(&work_dir).unwrap();
+        let todo = vec![
+            RebaseStep {
+                action: RebaseAction::Reword,
+                sha: reworded.to_string(),
+                message: Some("new message".to_string()),
+            },
+            RebaseStep {
+                action: RebaseAction::Drop,
+                sha: dropped.to_string(),
+                message: None,
+            },
+        ];
+        let todo_path = write_rebase_todo(&work_dir, &todo).unwrap();
+        let todo_list = std::fs::read_to_string(&todo_path).unwrap();
+        assert!(todo_list.starts_with(&format!("pick {}\nexec git commit", reworded)));
+        assert!(todo_list.ends_with(&format!("drop {}\n", dropped)));
+
+        let output = match std::process::Command::new("git")
+            .env(
+                "GIT_SEQUENCE_EDITOR",
+                format!("cp {}", quote_shell_path(&todo_path)),
+            )
+            .env("GIT_EDITOR", "true")
+            .args(["rebase", "-i", &base.to_string()])
+            .current_dir(repo.workdir().unwrap())
+            .output()
+        {
+            Ok(output) => output,
+            // 系统未安装Git
+            Err(_) => return,
+        };
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message().unwrap().trim(), "new message");
+        assert_eq!(head.parent_id(0).unwrap(), base);
+        assert!(!repo.workdir().unwrap().join("c.txt").exists());
+        assert!(!repo.workdir().unwrap().join("injected").exists());
+        assert!(!dir.path().join("injected").exists());
+    }
+}
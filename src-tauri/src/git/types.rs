@@ -34,6 +34,23 @@ pub struct AuthConfig {
     pub ssh_key_passphrase: Option<String>,
 }
 
+/// 已存储凭据的索引信息（不包含密码、Token 等敏感内容）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCredentialRef {
+    /// 规范化后的凭据键（host/owner/repo）
+    pub key: String,
+    /// 存储凭据时使用的仓库 URL
+    pub url: String,
+    /// 主机名
+    pub host: String,
+    /// 用户名
+    pub username: Option<String>,
+    /// 认证类型
+    pub auth_type: AuthType,
+    /// 存储时间戳
+    pub stored_at: i64,
+}
+
 /// 认证类型
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AuthType {
@@ -47,6 +64,108 @@ pub enum AuthType {
     None,
 }
 
+/// SSH 密钥信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshKeyInfo {
+    /// 私钥路径
+    pub path: String,
+    /// 密钥类型（如 ED25519、RSA）
+    pub key_type: Option<String>,
+    /// SHA256 指纹
+    pub fingerprint: Option<String>,
+}
+
+/// SSH 公钥信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicKeyInfo {
+    /// 公钥文件内容
+    pub content: String,
+    /// SHA256 指纹
+    pub fingerprint: Option<String>,
+    /// 密钥类型（如 ssh-ed25519）
+    pub key_type: String,
+    /// 公钥注释
+    pub comment: Option<String>,
+}
+
+/// 仓库修复结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepairReport {
+    /// 是否删除了过期的 index.lock
+    pub removed_lock: bool,
+    /// 发现的问题列表
+    pub issues: Vec<String>,
+    /// `git fsck` 的输出（系统Git不可用时为 None）
+    pub fsck_output: Option<String>,
+}
+
+/// 归档格式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    Tar,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// `git archive --format` 参数值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Tar => "tar",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+}
+
+/// 归档导出结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveResult {
+    /// 归档文件路径
+    pub output_path: String,
+    /// 归档文件大小（字节）
+    pub size: u64,
+}
+
+/// 仓库访问测试结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessResult {
+    /// 是否可以连接到远程仓库
+    pub reachable: bool,
+    /// 远程仓库是否要求认证
+    pub requires_auth: bool,
+    /// 远程默认分支
+    pub default_branch: Option<String>,
+    /// 错误信息
+    pub error: Option<String>,
+}
+
+/// 远程引用类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum RemoteRefKind {
+    /// 远程 HEAD（指向默认分支）
+    Head,
+    /// 分支
+    Branch,
+    /// 标签
+    Tag,
+}
+
+/// 远程引用信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteRef {
+    /// 短名称（如 main、v1.0）
+    pub name: String,
+    /// 完整引用名称（如 refs/heads/main）
+    pub full_name: String,
+    /// 指向的对象 SHA
+    pub oid: String,
+    /// 引用类型
+    pub kind: RemoteRefKind,
+    /// 符号引用目标（仅 HEAD）
+    pub symref_target: Option<String>,
+    /// 是否为远程默认分支
+    pub is_default: bool,
+}
+
 /// 克隆进度信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CloneProgress {
@@ -62,6 +181,8 @@ pub struct CloneProgress {
     pub network_progress: Option<NetworkProgress>,
     /// 检出进度（可选）
     pub checkout_progress: Option<CheckoutProgress>,
+    /// 错误类型（仅 Error 阶段），与 GitError 序列化后的 type 字段一致
+    pub error_type: Option<String>,
 }
 
 /// 克隆阶段
@@ -96,6 +217,34 @@ pub struct NetworkProgress {
     pub indexed_objects: usize,
 }
 
+/// 推送进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushProgress {
+    /// 仓库路径
+    pub repo_path: String,
+    /// 当前阶段（packing：打包对象，uploading：上传对象）
+    pub stage: String,
+    /// 已处理对象数
+    pub current: usize,
+    /// 总对象数
+    pub total: usize,
+    /// 已上传字节数
+    pub bytes: usize,
+}
+
+/// 向前端请求凭据的事件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialRequest {
+    /// 请求 ID，提供凭据时原样传回
+    pub request_id: String,
+    /// 需要认证的远程 URL
+    pub url: String,
+    /// URL 中携带的用户名
+    pub username: Option<String>,
+    /// 服务器允许的认证类型（userpass、ssh_key、default 等）
+    pub allowed_types: Vec<String>,
+}
+
 /// 检出进度
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckoutProgress {
@@ -147,7 +296,13 @@ pub enum GitError {
     Io(#[from] std::io::Error),
 
     #[error("认证失败: {message}")]
-    AuthenticationFailed { message: String },
+    AuthenticationFailed {
+        message: String,
+        /// 已尝试过的认证方式
+        methods_tried: Vec<String>,
+        /// 最后一次失败的底层错误
+        last_error: Option<String>,
+    },
 
     #[error("无效的仓库 URL: {url}")]
     InvalidUrl { url: String },
@@ -163,6 +318,68 @@ pub enum GitError {
 
     #[error("系统Git执行失败: {message}")]
     SystemGitFailed { message: String },
+
+    #[error("未找到 ssh-keygen，请安装 OpenSSH 客户端")]
+    SshKeygenNotFound,
+
+    #[error("私钥旁未找到对应的公钥文件: {path}")]
+    PublicKeyNotFound { path: String },
+
+    #[error("分支 {branch} 未设置上游分支")]
+    NoUpstream { branch: String },
+
+    #[error(
+        "索引文件已被锁定（{path}），可能有其他 Git 进程正在运行或上次操作异常退出，请尝试修复仓库"
+    )]
+    IndexLocked { path: String },
+
+    #[error("远程仓库不存在或无权访问: {url}")]
+    RepositoryNotFound { url: String },
+
+    #[error("网络错误: {message}")]
+    NetworkError { message: String },
+
+    #[error("{refs} 之间没有共同祖先（历史不相关）")]
+    NoMergeBase { refs: String },
+
+    #[error("未配置 user.name 和 user.email，且未指定提交身份，请先设置提交使用的姓名和邮箱")]
+    IdentityNotConfigured,
+
+    #[error("提交 {sha} 已推送到上游，修改它会改写已发布的历史")]
+    PublishedCommit { sha: String },
+
+    #[error("{path} 是文件而不是目录，请选择其他克隆位置")]
+    NotADirectory { path: String },
+
+    #[error("没有权限写入 {path}，请选择其他克隆位置")]
+    PermissionDenied { path: String },
+
+    #[error("路径过长: {path}，请选择更短的克隆位置")]
+    PathTooLong { path: String },
+
+    #[error("无效的分支名称 '{name}': {reason}")]
+    InvalidBranchName { name: String, reason: String },
+
+    #[error(
+        "磁盘空间不足：预计需要 {:.1} MB，可用 {:.1} MB",
+        *required as f64 / 1024.0 / 1024.0,
+        *available as f64 / 1024.0 / 1024.0
+    )]
+    InsufficientDiskSpace { required: u64, available: u64 },
+
+    #[error(
+        "推送被拒绝：远程分支 {branch} 包含本地没有的提交，请先拉取合并后再推送，或使用强制推送"
+    )]
+    NonFastForward { branch: String },
+
+    #[error("未找到 {url} 的凭据")]
+    CredentialNotFound { url: String },
+
+    #[error("操作已取消")]
+    OperationCancelled,
+
+    #[error("操作 {operation_id} 正在进行中")]
+    DuplicateOperation { operation_id: String },
 }
 
 impl Serialize for GitError {
@@ -171,9 +388,25 @@ impl Serialize for GitError {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("GitError", 2)?;
+
+        // 认证失败时额外输出尝试过的认证方式，便于前端给出针对性的提示
+        let auth_details = match self {
+            GitError::AuthenticationFailed {
+                methods_tried,
+                last_error,
+                ..
+            } => Some((methods_tried, last_error)),
+            _ => None,
+        };
+
+        let len = if auth_details.is_some() { 4 } else { 2 };
+        let mut state = serializer.serialize_struct("GitError", len)?;
         state.serialize_field("type", &self.error_type())?;
         state.serialize_field("message", &self.to_string())?;
+        if let Some((methods_tried, last_error)) = auth_details {
+            state.serialize_field("methods_tried", methods_tried)?;
+            state.serialize_field("last_error", last_error)?;
+        }
         state.end()
     }
 }
@@ -189,6 +422,24 @@ impl GitError {
             GitError::Unknown { .. } => "unknown",
             GitError::SystemGitNotFound => "system_git_not_found",
             GitError::SystemGitFailed { .. } => "system_git_failed",
+            GitError::SshKeygenNotFound => "ssh_keygen_not_found",
+            GitError::PublicKeyNotFound { .. } => "public_key_not_found",
+            GitError::NoUpstream { .. } => "no_upstream",
+            GitError::IndexLocked { .. } => "index_locked",
+            GitError::RepositoryNotFound { .. } => "repository_not_found",
+            GitError::NetworkError { .. } => "network",
+            GitError::NoMergeBase { .. } => "no_merge_base",
+            GitError::IdentityNotConfigured => "identity_not_configured",
+            GitError::PublishedCommit { .. } => "published_commit",
+            GitError::NotADirectory { .. } => "not_a_directory",
+            GitError::PermissionDenied { .. } => "permission_denied",
+            GitError::PathTooLong { .. } => "path_too_long",
+            GitError::InvalidBranchName { .. } => "invalid_branch_name",
+            GitError::InsufficientDiskSpace { .. } => "insufficient_disk_space",
+            GitError::NonFastForward { .. } => "non_fast_forward",
+            GitError::CredentialNotFound { .. } => "credential_not_found",
+            GitError::OperationCancelled => "operation_cancelled",
+            GitError::DuplicateOperation { .. } => "duplicate_operation",
         }
     }
 }
@@ -225,10 +476,217 @@ pub struct RepositoryStatus {
     pub behind: u32,
     /// 是否干净（无变更）
     pub is_clean: bool,
+    /// 是否存在未解决的冲突
+    pub has_conflicts: bool,
+    /// 正在进行中的操作（merge、rebase、cherry_pick、revert 等），无则为 None
+    pub operation_state: Option<String>,
 }
 
-/// 文件状态
+/// 仓库当前所处的操作状态（对应 `git2::RepositoryState`）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RepositoryStateKind {
+    Clean,
+    Merge,
+    Revert,
+    RevertSequence,
+    CherryPick,
+    CherryPickSequence,
+    Bisect,
+    Rebase,
+    RebaseInteractive,
+    RebaseMerge,
+    ApplyMailbox,
+    ApplyMailboxOrRebase,
+}
+
+impl From<git2::RepositoryState> for RepositoryStateKind {
+    fn from(state: git2::RepositoryState) -> Self {
+        use git2::RepositoryState;
+
+        match state {
+            RepositoryState::Clean => Self::Clean,
+            RepositoryState::Merge => Self::Merge,
+            RepositoryState::Revert => Self::Revert,
+            RepositoryState::RevertSequence => Self::RevertSequence,
+            RepositoryState::CherryPick => Self::CherryPick,
+            RepositoryState::CherryPickSequence => Self::CherryPickSequence,
+            RepositoryState::Bisect => Self::Bisect,
+            RepositoryState::Rebase => Self::Rebase,
+            RepositoryState::RebaseInteractive => Self::RebaseInteractive,
+            RepositoryState::RebaseMerge => Self::RebaseMerge,
+            RepositoryState::ApplyMailbox => Self::ApplyMailbox,
+            RepositoryState::ApplyMailboxOrRebase => Self::ApplyMailboxOrRebase,
+        }
+    }
+}
+
+impl RepositoryStateKind {
+    /// 仓库状态中使用的操作名称（merge、rebase 等），空闲时为 None
+    pub fn operation_name(self) -> Option<&'static str> {
+        match self {
+            Self::Clean => None,
+            Self::Merge => Some("merge"),
+            Self::Revert | Self::RevertSequence => Some("revert"),
+            Self::CherryPick | Self::CherryPickSequence => Some("cherry_pick"),
+            Self::Bisect => Some("bisect"),
+            Self::Rebase | Self::RebaseInteractive | Self::RebaseMerge => Some("rebase"),
+            Self::ApplyMailbox | Self::ApplyMailboxOrRebase => Some("apply_mailbox"),
+        }
+    }
+}
+
+/// 仓库进行中的操作及其元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoOperationState {
+    /// 操作状态
+    pub state: RepositoryStateKind,
+    /// 变基中的分支（如 refs/heads/feature）
+    pub head_name: Option<String>,
+    /// 变基的目标提交
+    pub onto: Option<String>,
+    /// 合并、拣选或还原中的对方提交（MERGE_HEAD / CHERRY_PICK_HEAD / REVERT_HEAD）
+    pub operation_head: Option<String>,
+}
+
+/// 引用解析结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefInfo {
+    /// 引用全名（如 HEAD、refs/heads/main）
+    pub name: String,
+    /// 最终指向的提交，未出生分支上的 HEAD 为 None
+    pub target_sha: Option<String>,
+    /// 是否为符号引用
+    pub is_symbolic: bool,
+    /// 符号引用指向的引用名（如 refs/heads/main）
+    pub symbolic_target: Option<String>,
+}
+
+/// 差异的空白字符处理选项，用于“隐藏空白变更”
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WhitespaceOptions {
+    /// 忽略所有空白字符（git diff -w）
+    #[serde(default)]
+    pub ignore_whitespace: bool,
+    /// 忽略空白字符数量的变化（git diff -b）
+    #[serde(default)]
+    pub ignore_whitespace_change: bool,
+    /// 忽略行尾空白，包括 CRLF 与 LF 的差异
+    #[serde(default)]
+    pub ignore_whitespace_eol: bool,
+    /// 忽略空行的增删
+    #[serde(default)]
+    pub ignore_blank_lines: bool,
+}
+
+/// 单个文件的结构化差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// 文件路径（新路径）
+    pub path: String,
+    /// 重命名前的路径
+    pub old_path: Option<String>,
+    /// 变更类型（added、modified、deleted、renamed 等）
+    pub status: String,
+    /// 是否为二进制文件
+    pub is_binary: bool,
+    /// 新增行数
+    pub additions: u32,
+    /// 删除行数
+    pub deletions: u32,
+    /// 差异块列表
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// 单个文件的变更统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiffStat {
+    /// 文件路径（新路径）
+    pub path: String,
+    /// 重命名前的路径
+    pub old_path: Option<String>,
+    /// 变更类型（added、modified、deleted、renamed 等）
+    pub status: String,
+    /// 是否为二进制文件
+    pub is_binary: bool,
+    /// 新增行数
+    pub additions: u32,
+    /// 删除行数
+    pub deletions: u32,
+}
+
+/// 差异统计摘要（类似 `git diff --stat`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffStat {
+    /// 变更文件数
+    pub files_changed: u32,
+    /// 新增行数
+    pub insertions: u32,
+    /// 删除行数
+    pub deletions: u32,
+    /// 各文件的统计
+    pub files: Vec<FileDiffStat>,
+}
+
+/// 差异块
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// 块头（如 @@ -1,3 +1,4 @@）
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    /// 块内的行
+    pub lines: Vec<DiffLine>,
+}
+
+/// 差异行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    /// 行类型：+ 新增，- 删除，空格为上下文
+    pub origin: char,
+    /// 行内容（不含换行符）
+    pub content: String,
+    /// 旧文件中的行号
+    pub old_lineno: Option<u32>,
+    /// 新文件中的行号
+    pub new_lineno: Option<u32>,
+}
+
+/// 仓库概要状态（用于多仓库面板，不包含逐文件统计）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoSummary {
+    /// 仓库路径
+    pub path: String,
+    /// 当前分支
+    pub current_branch: String,
+    /// 是否干净（无变更）
+    pub is_clean: bool,
+    /// 领先提交数
+    pub ahead: u32,
+    /// 落后提交数
+    pub behind: u32,
+    /// 变更文件数量
+    pub changed_count: usize,
+    /// 获取状态失败时的错误信息
+    pub error: Option<String>,
+}
+
+/// 变更数量统计（用于提交按钮角标，不包含逐文件信息）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChangeCounts {
+    /// 已暂存的文件数
+    pub staged: u32,
+    /// 未暂存的已跟踪文件数
+    pub unstaged: u32,
+    /// 未跟踪的文件数
+    pub untracked: u32,
+    /// 冲突文件数
+    pub conflicted: u32,
+}
+
+/// 文件状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FileStatus {
     /// 文件路径
     pub path: String,
@@ -240,10 +698,30 @@ pub struct FileStatus {
     pub additions: u32,
     /// 删除行数
     pub deletions: u32,
+    /// 拉取预览中该文件同时有未提交的本地变更，拉取时可能冲突
+    #[serde(default)]
+    pub potential_conflict: bool,
+}
+
+/// 单个路径的 Git 状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PathStatus {
+    /// 已跟踪且没有变更
+    Tracked,
+    /// 工作区有未暂存的修改（含删除）
+    Modified,
+    /// 有已暂存的变更
+    Staged,
+    /// 未跟踪
+    Untracked,
+    /// 被忽略规则匹配
+    Ignored,
+    /// 存在合并冲突
+    Conflicted,
 }
 
 /// 提交选项
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct CommitOptions {
     /// 提交消息
     pub message: String,
@@ -254,9 +732,78 @@ pub struct CommitOptions {
     /// 作者邮箱
     pub author_email: Option<String>,
     /// 是否修正上次提交
+    #[serde(default)]
     pub amend: bool,
     /// 是否添加签名
+    #[serde(default)]
     pub signoff: bool,
+    /// 仅提交指定路径的暂存内容（类似 `git commit -- <paths>`），None 表示提交整个索引
+    pub only_paths: Option<Vec<String>>,
+    /// 作者时间（Unix 秒），None 表示当前时间
+    pub author_date: Option<i64>,
+    /// 提交者时间（Unix 秒），None 表示当前时间
+    pub committer_date: Option<i64>,
+    /// 指定时间使用的时区偏移（分钟），默认为 0
+    pub date_offset_minutes: Option<i32>,
+    /// 追加到提交消息末尾的 `Key: Value` trailer（签名 trailer 之外）
+    pub trailers: Option<Vec<(String, String)>>,
+    /// 提交前自动暂存所有已跟踪文件的修改和删除（类似 `git commit -a`），不包含未跟踪文件
+    #[serde(default)]
+    pub stage_tracked: bool,
+}
+
+/// 提交消息检查规则
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommitLintRules {
+    /// 标题最大长度，超出时报错（常用 72）
+    pub max_subject_length: Option<usize>,
+    /// 标题建议长度，超出时给出警告（常用 50）
+    pub warn_subject_length: Option<usize>,
+    /// 要求标题与正文之间有空行
+    #[serde(default)]
+    pub require_blank_line: bool,
+    /// 要求标题符合 Conventional Commits 格式（如 `feat(scope): subject`）
+    #[serde(default)]
+    pub conventional: bool,
+    /// 允许的 Conventional Commits 类型，None 时使用常见类型列表
+    pub allowed_types: Option<Vec<String>>,
+}
+
+/// 提交消息检查问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LintSeverity {
+    /// 错误，严格模式下会阻止提交
+    Error,
+    /// 警告
+    Warning,
+}
+
+/// 提交消息检查发现的问题
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintIssue {
+    /// 触发的规则（subject_empty、subject_length、blank_line、conventional）
+    pub rule: String,
+    /// 严重程度
+    pub severity: LintSeverity,
+    /// 问题描述
+    pub message: String,
+    /// 问题所在行（从 1 开始）
+    pub line: usize,
+}
+
+/// 提交签名验证结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureVerification {
+    /// 提交是否带有签名
+    pub signed: bool,
+    /// 签名是否有效且签名者受信任
+    pub valid: bool,
+    /// 签名格式（gpg、ssh、x509），未签名时为空
+    pub format: Option<String>,
+    /// 签名者（GPG 用户 ID 或 SSH principal）
+    pub signer: Option<String>,
+    /// 签名密钥 ID（GPG 长 ID 或 SSH 密钥指纹）
+    pub key_id: Option<String>,
 }
 
 /// 提交历史项
@@ -282,8 +829,40 @@ pub struct CommitHistoryItem {
     pub parent_count: usize,
 }
 
-/// 同步操作结果
+/// 超过大小阈值的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LargeFile {
+    /// 文件路径
+    pub path: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 是否为暂存区中的内容（否则为工作区文件）
+    pub staged: bool,
+}
+
+/// 推送预览结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushPreview {
+    /// 远程名称
+    pub remote_name: String,
+    /// 本地分支名称
+    pub branch: String,
+    /// 远程跟踪分支（远程尚无该分支时为 None）
+    pub remote_ref: Option<String>,
+    /// 将要推送的提交数
+    pub ahead: u32,
+    /// 远程领先本地的提交数（大于 0 时需要强制推送）
+    pub behind: u32,
+    /// 是否为快进推送
+    pub is_fast_forward: bool,
+    /// 是否需要强制推送
+    pub requires_force: bool,
+    /// 将要推送的提交
+    pub commits: Vec<CommitHistoryItem>,
+}
+
+/// 同步操作结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SyncResult {
     /// 操作是否成功
     pub success: bool,
@@ -296,6 +875,50 @@ pub struct SyncResult {
     /// 更新后的ahead/behind状态
     pub ahead: u32,
     pub behind: u32,
+    /// 拉取前后HEAD之间变更的文件数（无变化时为None）
+    pub files_changed: Option<u32>,
+    /// 拉取带来的新增行数
+    pub insertions: Option<u32>,
+    /// 拉取带来的删除行数
+    pub deletions: Option<u32>,
+}
+
+impl SyncResult {
+    /// 因冲突未能完成的同步结果
+    pub fn conflicts(message: impl Into<String>, conflict_files: Vec<String>) -> Self {
+        Self {
+            success: false,
+            message: message.into(),
+            has_conflicts: true,
+            conflict_files,
+            ..Default::default()
+        }
+    }
+}
+
+/// 获取远程变更后的新提交报告（不修改本地分支）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchReport {
+    /// 当前分支落后上游的提交数
+    pub behind: u32,
+    /// 上游中尚未合并到当前分支的提交（从新到旧）
+    pub new_commits: Vec<CommitHistoryItem>,
+}
+
+/// 提交并推送的结果
+///
+/// 提交失败时直接返回错误；提交成功但推送失败时 `pushed` 为 false，
+/// 本地提交会被保留，前端可以只重试推送
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitAndPushResult {
+    /// 新创建的本地提交 SHA
+    pub commit_sha: String,
+    /// 是否推送成功
+    pub pushed: bool,
+    /// 推送成功时的同步结果
+    pub push_result: Option<SyncResult>,
+    /// 推送失败时的错误信息
+    pub push_error: Option<String>,
 }
 
 /// Pull策略
@@ -307,6 +930,95 @@ pub enum PullStrategy {
     Rebase,
 }
 
+/// 交互式变基的操作类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RebaseAction {
+    /// 保留提交
+    Pick,
+    /// 合并到上一个提交
+    Squash,
+    /// 丢弃提交
+    Drop,
+    /// 保留提交并修改提交信息
+    Reword,
+}
+
+/// 索引变更事件（`repository-index-changed`）的负载
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexChangedEvent {
+    /// 仓库路径
+    pub repo_path: String,
+    /// 受影响的文件，为空表示整个索引都可能发生了变化（如提交）
+    pub files: Vec<String>,
+}
+
+/// 树条目（文件浏览用）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeEntry {
+    /// 条目名称
+    pub name: String,
+    /// 相对仓库根目录的路径
+    pub path: String,
+    /// 是否为目录
+    pub is_dir: bool,
+    /// 文件大小（仅文件）
+    pub size: Option<u64>,
+    /// 文件模式（如 0o100644、0o040000）
+    pub mode: i32,
+}
+
+/// 路径对应的对象类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PathKind {
+    /// 文件
+    Blob,
+    /// 目录
+    Tree,
+    /// 子模块
+    Submodule,
+}
+
+/// 路径在某个版本中的元数据（不读取文件内容）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathStat {
+    /// 对象类型
+    pub kind: PathKind,
+    /// 文件大小（仅文件）
+    pub size: Option<u64>,
+    /// 目录下的条目数（仅目录）
+    pub entry_count: Option<usize>,
+    /// 文件模式（如 0o100644、0o040000、0o160000）
+    pub mode: i32,
+    /// 对象 SHA（子模块为其指向的提交）
+    pub sha: String,
+}
+
+/// 储藏（stash）条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashEntry {
+    /// 储藏索引（0 为最新）
+    pub index: usize,
+    /// 储藏消息
+    pub message: String,
+    /// 储藏提交SHA
+    pub sha: String,
+    /// 创建储藏时所在的分支
+    pub branch: Option<String>,
+    /// 创建时间戳
+    pub timestamp: i64,
+}
+
+/// 交互式变基的单个步骤
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebaseStep {
+    /// 操作类型
+    pub action: RebaseAction,
+    /// 提交 SHA
+    pub sha: String,
+    /// 新的提交信息（Reword 必填，Squash 可选）
+    pub message: Option<String>,
+}
+
 /// 远程分支信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteBranchInfo {
@@ -318,6 +1030,164 @@ pub struct RemoteBranchInfo {
     pub ahead: u32,
     /// 本地落后提交数
     pub behind: u32,
+    /// 远程默认分支（远程 HEAD 指向的分支）
+    pub default_branch: Option<String>,
     /// 最后同步时间
     pub last_sync: Option<i64>,
 }
+
+/// 当前分支的上游跟踪状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackingStatus {
+    /// 本地分支名称
+    pub branch: String,
+    /// 上游分支名称（如 origin/main）
+    pub upstream: String,
+    /// 本地领先提交数
+    pub ahead: u32,
+    /// 本地落后提交数
+    pub behind: u32,
+}
+
+/// 仓库占用的磁盘空间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoSize {
+    /// 工作区大小（不含 .git 目录，裸仓库为 0）
+    pub work_tree_bytes: u64,
+    /// Git 目录大小
+    pub git_dir_bytes: u64,
+    /// 总大小
+    pub total_bytes: u64,
+    /// 包文件数量
+    pub pack_count: u32,
+    /// 松散对象数量
+    pub loose_object_count: u32,
+}
+
+/// 仓库垃圾回收（git gc）结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GcResult {
+    /// 是否使用了 --aggressive
+    pub aggressive: bool,
+    /// 回收前的仓库大小
+    pub before: RepoSize,
+    /// 回收后的仓库大小
+    pub after: RepoSize,
+    /// 释放的字节数（Git 目录变大时为 0）
+    pub freed_bytes: u64,
+}
+
+/// 垃圾回收阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GcStage {
+    Started,
+    Finished,
+}
+
+/// 垃圾回收开始/结束事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GcEvent {
+    pub repo_path: String,
+    pub stage: GcStage,
+    /// 结束时是否成功，开始时为 None
+    pub success: Option<bool>,
+    /// 失败原因
+    pub message: Option<String>,
+}
+
+/// 最近打开的仓库
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentRepo {
+    /// 仓库根目录
+    pub path: String,
+    /// 仓库名称（根目录名）
+    pub name: String,
+    /// 最后打开时间（Unix 秒）
+    pub last_opened: i64,
+    /// 默认远程的 URL
+    pub remote_url: Option<String>,
+}
+
+/// 文件逐行的追溯（blame）信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameLine {
+    /// 行号（从 1 开始）
+    pub line_number: usize,
+    /// 最后修改该行的提交SHA
+    pub commit_sha: String,
+    /// 作者姓名
+    pub author_name: String,
+    /// 作者邮箱
+    pub author_email: String,
+    /// 作者时间戳
+    pub author_date: i64,
+    /// 提交标题
+    pub summary: String,
+    /// 行内容
+    pub content: String,
+    /// 因忽略列表而被跳过的提交；为 Some 时表示该行的追溯已越过此提交
+    pub ignored_commit: Option<String>,
+}
+
+/// 提交历史过滤条件，未设置的条件不参与过滤
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommitHistoryFilter {
+    /// 作者姓名或邮箱包含的文本（不区分大小写）
+    pub author: Option<String>,
+    /// 提交消息包含的文本（不区分大小写）
+    pub message: Option<String>,
+    /// 起始时间（作者时间，Unix 秒，含）
+    pub since: Option<i64>,
+    /// 截止时间（作者时间，Unix 秒，含）
+    pub until: Option<i64>,
+    /// 只包含修改了该路径（文件或目录）的提交
+    pub path: Option<String>,
+}
+
+/// 提交历史导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum HistoryExportFormat {
+    Json,
+    Csv,
+}
+
+/// 导出的单条提交记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitExportRow {
+    pub sha: String,
+    pub author: String,
+    pub email: String,
+    /// 作者时间（RFC 3339）
+    pub date: String,
+    pub subject: String,
+    /// 相对第一个父提交的新增行数
+    pub insertions: u32,
+    /// 相对第一个父提交的删除行数
+    pub deletions: u32,
+}
+
+/// 提交历史导出结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryExportResult {
+    /// 导出文件路径
+    pub output_path: String,
+    /// 写入的提交数
+    pub rows: usize,
+}
+
+/// 仓库URL解析结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedRepoUrl {
+    /// 是否为可克隆的仓库URL（可识别的协议且包含主机、所有者和仓库名）
+    pub valid: bool,
+    /// 协议：http、https 或 ssh
+    pub protocol: Option<String>,
+    /// 主机名（不含端口）
+    pub host: Option<String>,
+    /// 所有者，GitLab 嵌套群组时为完整命名空间（如 group/subgroup）
+    pub owner: Option<String>,
+    /// 仓库名（不含 .git 后缀）
+    pub repo: Option<String>,
+    /// 建议的克隆目标目录名
+    pub suggested_directory: Option<String>,
+}
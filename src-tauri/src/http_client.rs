@@ -1,13 +1,108 @@
-use reqwest::{Client, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, RequestBuilder, Response};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
 use tauri::command;
+use tokio_util::sync::CancellationToken;
 
-// 创建一个HTTP客户端单例
+// 默认 User-Agent，可通过 set_default_headers 覆盖
+pub const DEFAULT_USER_AGENT: &str = concat!("GitHub-Heart/", env!("CARGO_PKG_VERSION"));
+
+// 创建一个HTTP客户端单例，设置默认请求头时整体重建
 lazy_static::lazy_static! {
-    static ref HTTP_CLIENT: Client = Client::builder()
+    static ref HTTP_CLIENT: RwLock<Client> = RwLock::new(
+        build_client(&HashMap::new()).expect("Failed to create HTTP client")
+    );
+
+    // 当前生效的持久默认请求头（不含内置的 User-Agent）
+    static ref DEFAULT_HEADERS: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+
+    // 正在进行中的可取消请求（request_id -> 登记信息）
+    static ref IN_FLIGHT_REQUESTS: Mutex<HashMap<String, InFlightRequest>> =
+        Mutex::new(HashMap::new());
+}
+
+// 为每次登记分配的序号，用于区分先后使用同一 request_id 的请求
+static NEXT_REQUEST_SERIAL: AtomicU64 = AtomicU64::new(0);
+
+// 正在进行中的请求
+struct InFlightRequest {
+    serial: u64,
+    token: CancellationToken,
+}
+
+// 请求结束（包括被丢弃）时注销登记；条目已被取消或由其他请求重新登记时保持不变
+struct InFlightGuard {
+    request_id: String,
+    serial: u64,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut requests = IN_FLIGHT_REQUESTS.lock().unwrap();
+        if requests
+            .get(&self.request_id)
+            .is_some_and(|request| request.serial == self.serial)
+        {
+            requests.remove(&self.request_id);
+        }
+    }
+}
+
+// 登记可取消的请求，同一 request_id 的请求仍在进行中时拒绝
+fn register_request(request_id: &str) -> Result<(InFlightGuard, CancellationToken), String> {
+    let mut requests = IN_FLIGHT_REQUESTS.lock().unwrap();
+    if requests.contains_key(request_id) {
+        return Err(format!("请求ID已在使用中: {}", request_id));
+    }
+
+    let serial = NEXT_REQUEST_SERIAL.fetch_add(1, Ordering::Relaxed);
+    let token = CancellationToken::new();
+    requests.insert(
+        request_id.to_string(),
+        InFlightRequest {
+            serial,
+            token: token.clone(),
+        },
+    );
+    Ok((
+        InFlightGuard {
+            request_id: request_id.to_string(),
+            serial,
+        },
+        token,
+    ))
+}
+
+// 用户主动取消请求时返回的错误信息
+pub const REQUEST_CANCELLED: &str = "Request cancelled";
+
+// 获取当前的HTTP客户端（内部为引用计数，克隆开销很小）
+pub(crate) fn http_client() -> Client {
+    HTTP_CLIENT.read().unwrap().clone()
+}
+
+// 根据默认请求头构建客户端；请求级别设置的同名请求头优先于默认值
+fn build_client(headers: &HashMap<String, String>) -> Result<Client, String> {
+    let mut default_headers = HeaderMap::new();
+    default_headers.insert(
+        reqwest::header::USER_AGENT,
+        HeaderValue::from_static(DEFAULT_USER_AGENT),
+    );
+    for (key, value) in headers {
+        let name = HeaderName::from_bytes(key.as_bytes())
+            .map_err(|e| format!("无效的请求头名称 {}: {}", key, e))?;
+        let value =
+            HeaderValue::from_str(value).map_err(|e| format!("无效的请求头值 {}: {}", key, e))?;
+        default_headers.insert(name, value);
+    }
+
+    Client::builder()
+        .default_headers(default_headers)
         .build()
-        .expect("Failed to create HTTP client");
+        .map_err(|e| format!("创建HTTP客户端失败: {}", e))
 }
 
 // 通用响应结构
@@ -17,6 +112,18 @@ pub struct ApiResponse<T> {
     pub code: u32,
     pub message: Option<String>,
     pub data: Option<T>,
+    pub rate_limit: Option<RateLimit>,
+}
+
+// API 速率限制信息（来自 X-RateLimit-* 与 Retry-After 响应头）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    // 限额重置时间（Unix 秒）
+    pub reset: Option<u64>,
+    // 建议的重试等待秒数
+    pub retry_after: Option<u64>,
 }
 
 // OAuth令牌响应
@@ -29,29 +136,54 @@ pub struct TokenResponse {
     pub scope: Option<String>,
 }
 
+// Authorization 认证方式，由后端设置请求头，避免 Token 出现在前端构造的请求头中
+#[derive(Debug, Clone, Deserialize)]
+pub enum HttpAuth {
+    Bearer(String),
+    Basic(String, Option<String>),
+}
+
+// 添加请求头和认证信息；提供 auth 时忽略 headers 中的 Authorization，以 auth 为准
+fn apply_headers(
+    mut request: RequestBuilder,
+    headers: Option<HashMap<String, String>>,
+    auth: Option<HttpAuth>,
+) -> RequestBuilder {
+    if let Some(headers_map) = headers {
+        for (key, value) in headers_map {
+            if auth.is_some() && key.eq_ignore_ascii_case("authorization") {
+                continue;
+            }
+            request = request.header(key, value);
+        }
+    }
+
+    match auth {
+        Some(HttpAuth::Bearer(token)) => request.bearer_auth(token),
+        Some(HttpAuth::Basic(username, password)) => request.basic_auth(username, password),
+        None => request,
+    }
+}
+
 // 通用HTTP GET请求
 #[command]
 pub async fn http_get(
     url: String,
     headers: Option<HashMap<String, String>>,
+    auth: Option<HttpAuth>,
     data: Option<HashMap<String, serde_json::Value>>, // 新增参数
+    request_id: Option<String>,
 ) -> Result<ApiResponse<serde_json::Value>, String> {
-    let mut request = HTTP_CLIENT.get(&url);
+    let mut request = http_client().get(&url);
 
     // 添加查询参数
     if let Some(params_map) = data {
         request = request.query(&params_map);
     }
 
-    if let Some(headers_map) = headers {
-        for (key, value) in headers_map {
-            request = request.header(key, value);
-        }
-    }
-
-    let response = request.send().await.map_err(|e| e.to_string())?;
+    request = apply_headers(request, headers, auth);
 
-    handle_response(response).await
+    execute_request(request, request_id).await
 }
 
 // 通用HTTP POST请求
@@ -60,23 +192,19 @@ pub async fn http_post(
     url: String,
     data: Option<serde_json::Value>,
     headers: Option<HashMap<String, String>>,
+    auth: Option<HttpAuth>,
+    request_id: Option<String>,
 ) -> Result<ApiResponse<serde_json::Value>, String> {
     // 使用具体类型 serde_json::Value
-    let mut request = HTTP_CLIENT.post(&url);
+    let mut request = http_client().post(&url);
 
-    if let Some(headers_map) = headers {
-        for (key, value) in headers_map {
-            request = request.header(key, value);
-        }
-    }
+    request = apply_headers(request, headers, auth);
 
     if let Some(json_body) = data {
         request = request.json(&json_body);
     }
 
-    let response = request.send().await.map_err(|e| e.to_string())?;
-
-    handle_response(response).await
+    execute_request(request, request_id).await
 }
 
 // 通用HTTP PUT请求
@@ -85,22 +213,18 @@ pub async fn http_put(
     url: String,
     data: Option<serde_json::Value>,
     headers: Option<HashMap<String, String>>,
+    auth: Option<HttpAuth>,
+    request_id: Option<String>,
 ) -> Result<ApiResponse<serde_json::Value>, String> {
-    let mut request = HTTP_CLIENT.put(&url);
+    let mut request = http_client().put(&url);
 
-    if let Some(headers_map) = headers {
-        for (key, value) in headers_map {
-            request = request.header(key, value);
-        }
-    }
+    request = apply_headers(request, headers, auth);
 
     if let Some(json_body) = data {
         request = request.json(&json_body);
     }
 
-    let response = request.send().await.map_err(|e| e.to_string())?;
-
-    handle_response(response).await
+    execute_request(request, request_id).await
 }
 
 // 通用HTTP PATCH请求
@@ -109,22 +233,18 @@ pub async fn http_patch(
     url: String,
     data: Option<serde_json::Value>,
     headers: Option<HashMap<String, String>>,
+    auth: Option<HttpAuth>,
+    request_id: Option<String>,
 ) -> Result<ApiResponse<serde_json::Value>, String> {
-    let mut request = HTTP_CLIENT.patch(&url);
+    let mut request = http_client().patch(&url);
 
-    if let Some(headers_map) = headers {
-        for (key, value) in headers_map {
-            request = request.header(key, value);
-        }
-    }
+    request = apply_headers(request, headers, auth);
 
     if let Some(json_body) = data {
         request = request.json(&json_body);
     }
 
-    let response = request.send().await.map_err(|e| e.to_string())?;
-
-    handle_response(response).await
+    execute_request(request, request_id).await
 }
 
 // 通用HTTP DELETE请求
@@ -133,27 +253,144 @@ pub async fn http_delete(
     url: String,
     data: Option<serde_json::Value>,
     headers: Option<HashMap<String, String>>,
+    auth: Option<HttpAuth>,
+    request_id: Option<String>,
 ) -> Result<ApiResponse<serde_json::Value>, String> {
-    let mut request = HTTP_CLIENT.delete(&url);
+    let mut request = http_client().delete(&url);
 
-    if let Some(headers_map) = headers {
-        for (key, value) in headers_map {
-            request = request.header(key, value);
-        }
-    }
+    request = apply_headers(request, headers, auth);
 
     if let Some(json_body) = data {
         request = request.json(&json_body);
     }
 
-    let response = request.send().await.map_err(|e| e.to_string())?;
+    execute_request(request, request_id).await
+}
+
+// 取消正在进行中的HTTP请求
+#[command]
+pub async fn cancel_http_request(request_id: String) -> Result<bool, String> {
+    let request = IN_FLIGHT_REQUESTS.lock().unwrap().remove(&request_id);
+    match request {
+        Some(request) => {
+            request.token.cancel();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+// 设置持久的默认请求头（如 Accept-Language、代理网关要求的自定义头），替换之前的设置；
+// 传入空表即恢复为仅含默认 User-Agent
+#[command]
+pub async fn set_default_headers(headers: HashMap<String, String>) -> Result<(), String> {
+    let client = build_client(&headers)?;
+    let mut current = DEFAULT_HEADERS.lock().unwrap();
+    *HTTP_CLIENT.write().unwrap() = client;
+    *current = headers;
+    Ok(())
+}
+
+// 查询当前的默认请求头，Authorization 等敏感值会被隐藏
+#[command]
+pub async fn get_default_headers() -> Result<HashMap<String, String>, String> {
+    let mut headers = DEFAULT_HEADERS.lock().unwrap().clone();
+    if !headers
+        .keys()
+        .any(|key| key.eq_ignore_ascii_case("user-agent"))
+    {
+        headers.insert("User-Agent".to_string(), DEFAULT_USER_AGENT.to_string());
+    }
+    for (key, value) in headers.iter_mut() {
+        if key.eq_ignore_ascii_case("authorization")
+            || key.eq_ignore_ascii_case("proxy-authorization")
+            || key.eq_ignore_ascii_case("cookie")
+        {
+            *value = "******".to_string();
+        }
+    }
+    Ok(headers)
+}
+
+// 发送请求并处理响应；提供 request_id 时可通过 cancel_http_request 中途取消
+async fn execute_request(
+    request: RequestBuilder,
+    request_id: Option<String>,
+) -> Result<ApiResponse<serde_json::Value>, String> {
+    let request_id = match request_id {
+        Some(id) => id,
+        None => {
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            return handle_response(response).await;
+        }
+    };
+
+    let (_guard, token) = register_request(&request_id)?;
+
+    tokio::select! {
+        _ = token.cancelled() => Err(REQUEST_CANCELLED.to_string()),
+        result = async {
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            handle_response(response).await
+        } => result,
+    }
+}
+
+// 解析速率限制响应头，没有相关响应头时返回 None
+fn parse_rate_limit(headers: &reqwest::header::HeaderMap) -> Option<RateLimit> {
+    let header_u64 = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+    };
+
+    let rate_limit = RateLimit {
+        limit: header_u64("x-ratelimit-limit"),
+        remaining: header_u64("x-ratelimit-remaining"),
+        reset: header_u64("x-ratelimit-reset"),
+        retry_after: header_u64("retry-after"),
+    };
+
+    if rate_limit.limit.is_none()
+        && rate_limit.remaining.is_none()
+        && rate_limit.reset.is_none()
+        && rate_limit.retry_after.is_none()
+    {
+        None
+    } else {
+        Some(rate_limit)
+    }
+}
+
+// 计算被限流时需要等待的秒数，未被限流时返回 None
+fn rate_limited_wait(status: reqwest::StatusCode, rate_limit: Option<&RateLimit>) -> Option<u64> {
+    if status != reqwest::StatusCode::FORBIDDEN && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+    {
+        return None;
+    }
 
-    handle_response(response).await
+    let rate_limit = rate_limit?;
+    if let Some(retry_after) = rate_limit.retry_after {
+        return Some(retry_after);
+    }
+
+    // 剩余次数为 0 时根据重置时间计算等待时长
+    if rate_limit.remaining == Some(0) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        return Some(rate_limit.reset.unwrap_or(now).saturating_sub(now));
+    }
+
+    None
 }
 
 // 处理HTTP响应
 async fn handle_response(response: Response) -> Result<ApiResponse<serde_json::Value>, String> {
     let status = response.status();
+    let rate_limit = parse_rate_limit(response.headers());
 
     if status.is_success() {
         // 获取响应文本
@@ -166,6 +403,7 @@ async fn handle_response(response: Response) -> Result<ApiResponse<serde_json::V
                 code: 0,
                 message: Some("Request successful".to_string()),
                 data: None,
+                rate_limit,
             });
         }
 
@@ -176,6 +414,7 @@ async fn handle_response(response: Response) -> Result<ApiResponse<serde_json::V
                 code: 0,
                 message: Some("Request successful".to_string()),
                 data: Some(data),
+                rate_limit,
             }),
             Err(_e) => {
                 // 如果 JSON 解析失败，但状态码是成功的，可能是非 JSON 响应
@@ -185,6 +424,7 @@ async fn handle_response(response: Response) -> Result<ApiResponse<serde_json::V
                     code: 0,
                     message: Some("Request successful (non-JSON response)".to_string()),
                     data: Some(serde_json::Value::String(response_text)),
+                    rate_limit,
                 })
             }
         }
@@ -193,14 +433,40 @@ async fn handle_response(response: Response) -> Result<ApiResponse<serde_json::V
             .text()
             .await
             .unwrap_or_else(|_| "Unknown error".to_string());
+
+        let message = match rate_limited_wait(status, rate_limit.as_ref()) {
+            Some(seconds) => format!("Rate limited, retry after {} seconds", seconds),
+            None => format!("Request failed with status {}: {}", status, error_text),
+        };
+
         Ok(ApiResponse {
             success: false,
             code: status.as_u16() as u32,
-            message: Some(format!(
-                "Request failed with status {}: {}",
-                status, error_text
-            )),
+            message: Some(message),
             data: None,
+            rate_limit,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_id_is_exclusive_until_finished() {
+        let (guard, token) = register_request("req-1").unwrap();
+        assert!(register_request("req-1").is_err());
+
+        // 取消后同一ID可以被新的请求登记，旧请求结束时不会移除新请求的登记
+        assert!(cancel_http_request("req-1".to_string()).await.unwrap());
+        assert!(token.is_cancelled());
+        let (new_guard, new_token) = register_request("req-1").unwrap();
+        drop(guard);
+        assert!(IN_FLIGHT_REQUESTS.lock().unwrap().contains_key("req-1"));
+
+        drop(new_guard);
+        assert!(!new_token.is_cancelled());
+        assert!(!cancel_http_request("req-1".to_string()).await.unwrap());
+    }
+}
@@ -1,6 +1,7 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 use tauri::{Emitter, Manager};
 mod commands;
+mod forge;
 mod git;
 mod http_client; // 导入新模块
 mod utils;
@@ -15,8 +16,125 @@ use warp::Filter;
 // 全局服务器状态
 static SERVER_STATE: Mutex<Option<tokio::task::JoinHandle<()>>> = Mutex::new(None);
 
+// OAuth 回调页面默认使用的深度链接协议名
+const DEFAULT_OAUTH_SCHEME: &str = "atomic-heart";
+
+// 授权成功页面的默认模板
+const DEFAULT_OAUTH_SUCCESS_HTML: &str = r#"<html>
+                    <head><title>授权成功</title></head>
+                    <body>
+                        <h1>授权成功！</h1>
+                        <p>正在返回应用...</p>
+                        <script>
+                            // 尝试通过深度链接唤起应用
+                            const deepLink = '{{deep_link}}';
+                            window.location.href = deepLink;
+
+                            // 如果深度链接失败，显示手动操作提示
+                            setTimeout(() => {
+                                document.body.innerHTML = '<h1>授权成功！</h1><p>请返回应用继续操作。如果应用没有自动打开，请手动打开应用。</p>';
+                            }, 3000);
+                        </script>
+                    </body>
+                    </html>"#;
+
+// 用户拒绝授权或授权失败时的默认模板
+const DEFAULT_OAUTH_DENIED_HTML: &str = r#"<html>
+                    <head><title>授权失败</title></head>
+                    <body>
+                        <h1>授权未完成</h1>
+                        <p>{{error}}</p>
+                        <p>请返回应用重新发起授权。</p>
+                    </body>
+                    </html>"#;
+
+// OAuth 回调页面配置，未设置的字段使用默认值
+//
+// 模板支持的变量：{{code}}、{{state}}、{{error}}（均经过 HTML 转义）
+// 以及 {{deep_link}}（由协议名和 URL 编码后的参数拼接而成）
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct OAuthCallbackOptions {
+    // 深度链接协议名，如 "atomic-heart"（不含 ://）
+    scheme: Option<String>,
+    success_html: Option<String>,
+    denied_html: Option<String>,
+}
+
+// 校验深度链接协议名（RFC 3986：字母开头，仅含字母、数字、+、-、.）
+fn validate_oauth_scheme(scheme: &str) -> Result<(), String> {
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("无效的深度链接协议名: {}", scheme))
+    }
+}
+
+// 转义 HTML 特殊字符，同时转义引号和反斜杠以便安全地嵌入脚本字符串
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            '\\' => escaped.push_str("&#92;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// 渲染回调页面模板
+fn render_oauth_page(
+    template: &str,
+    scheme: &str,
+    params: &std::collections::HashMap<String, String>,
+) -> String {
+    let get = |key: &str| params.get(key).map(String::as_str).unwrap_or("");
+    let encode =
+        |value: &str| url::form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>();
+    let deep_link = format!(
+        "{}://auth/callback?code={}&state={}",
+        scheme,
+        encode(get("code")),
+        encode(get("state"))
+    );
+
+    template
+        .replace("{{deep_link}}", &deep_link)
+        .replace("{{code}}", &escape_html(get("code")))
+        .replace("{{state}}", &escape_html(get("state")))
+        .replace("{{error}}", &escape_html(get("error")))
+}
+
+// 启动本地 OAuth 回调服务器
+//
+// `options` 可自定义深度链接协议名和成功/拒绝页面模板，便于定制版本替换品牌信息
 #[tauri::command]
-async fn start_oauth_callback_server(app_handle: tauri::AppHandle) -> Result<String, String> {
+async fn start_oauth_callback_server(
+    app_handle: tauri::AppHandle,
+    options: Option<OAuthCallbackOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let scheme = options
+        .scheme
+        .unwrap_or_else(|| DEFAULT_OAUTH_SCHEME.to_string());
+    validate_oauth_scheme(&scheme)?;
+    let templates = Arc::new((
+        scheme,
+        options
+            .success_html
+            .unwrap_or_else(|| DEFAULT_OAUTH_SUCCESS_HTML.to_string()),
+        options
+            .denied_html
+            .unwrap_or_else(|| DEFAULT_OAUTH_DENIED_HTML.to_string()),
+    ));
+
     // 检查是否已有服务器在运行
     {
         let mut state = SERVER_STATE.lock().unwrap();
@@ -31,46 +149,38 @@ async fn start_oauth_callback_server(app_handle: tauri::AppHandle) -> Result<Str
     let callback = warp::path("callback")
         .and(warp::query::<std::collections::HashMap<String, String>>())
         .and(warp::any().map(move || app_handle.clone()))
-        .and_then(|params: std::collections::HashMap<String, String>, app_handle: Arc<tauri::AppHandle>| async move {
-            let code = params.get("code").cloned();
-            let state = params.get("state").cloned();
-
-            // 发送事件到前端
-            if let (Some(ref code_val), Some(ref state_val)) = (&code, &state) {
-                let _ = app_handle.emit("oauth-callback", serde_json::json!({
-                    "code": code_val,
-                    "state": state_val
-                }));
-            }
+        .and(warp::any().map(move || templates.clone()))
+        .and_then(
+            |params: std::collections::HashMap<String, String>,
+             app_handle: Arc<tauri::AppHandle>,
+             templates: Arc<(String, String, String)>| async move {
+                let (scheme, success_html, denied_html) = templates.as_ref();
+                let code = params.get("code").cloned();
+                let state = params.get("state").cloned();
 
-            // 准备用于HTML的值
-            let code_str = code.as_deref().unwrap_or("");
-            let state_str = state.as_deref().unwrap_or("");
+                // 发送事件到前端
+                if let (Some(ref code_val), Some(ref state_val)) = (&code, &state) {
+                    let _ = app_handle.emit(
+                        "oauth-callback",
+                        serde_json::json!({
+                            "code": code_val,
+                            "state": state_val
+                        }),
+                    );
+                }
 
-            Ok::<_, warp::Rejection>(warp::reply::html(
-                format!(
-                    r#"<html>
-                    <head><title>授权成功</title></head>
-                    <body>
-                        <h1>授权成功！</h1>
-                        <p>正在返回应用...</p>
-                        <script>
-                            // 尝试通过深度链接唤起应用
-                            const deepLink = 'atomic-heart://auth/callback?code={}&state={}';
-                            window.location.href = deepLink;
+                // 授权服务器返回 error（如 access_denied）或缺少 code 时显示拒绝页面
+                let template = if params.contains_key("error") || code.is_none() {
+                    denied_html
+                } else {
+                    success_html
+                };
 
-                            // 如果深度链接失败，显示手动操作提示
-                            setTimeout(() => {{
-                                document.body.innerHTML = '<h1>授权成功！</h1><p>请返回应用继续操作。如果应用没有自动打开，请手动打开应用。</p>';
-                            }}, 3000);
-                        </script>
-                    </body>
-                    </html>"#,
-                    code_str,
-                    state_str
-                )
-            ))
-        });
+                Ok::<_, warp::Rejection>(warp::reply::html(render_oauth_page(
+                    template, scheme, &params,
+                )))
+            },
+        );
 
     let port = 8081;
     let handle = tokio::spawn(async move {
@@ -91,52 +201,120 @@ async fn open_url(url: String) -> Result<(), String> {
     open::that(url).map_err(|e| e.to_string())
 }
 
-// 处理深度链接的命令
-#[tauri::command]
-async fn handle_deep_link(app_handle: tauri::AppHandle, url: String) -> Result<(), String> {
-    println!("收到深度链接: {}", url);
+// 深度链接解析结果
+#[derive(Debug, Clone, serde::Serialize)]
+struct DeepLinkPayload {
+    url: String,
+    path: String,
+    params: std::collections::HashMap<String, String>,
+}
 
-    // 解析URL参数
-    if let Ok(parsed_url) = url::Url::parse(&url) {
-        let mut params = std::collections::HashMap::new();
+// 深度链接协议前缀
+const DEEP_LINK_SCHEME: &str = "atomic-heart://";
 
-        // 提取查询参数
-        for (key, value) in parsed_url.query_pairs() {
-            params.insert(key.to_string(), value.to_string());
-        }
+// 启动参数中的深度链接是否已被前端取走
+static STARTUP_DEEP_LINK_CONSUMED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
 
-        // 提取路径
-        let path = parsed_url.path();
-
-        // 发送事件到前端
-        let _ = app_handle.emit(
-            "deep-link-received",
-            serde_json::json!({
-                "url": url,
-                "path": path,
-                "params": params
-            }),
-        );
+// 解析深度链接的路径和查询参数
+fn parse_deep_link(url: &str) -> Option<DeepLinkPayload> {
+    let parsed_url = url::Url::parse(url).ok()?;
+
+    // 提取查询参数
+    let params = parsed_url
+        .query_pairs()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    Some(DeepLinkPayload {
+        url: url.to_string(),
+        path: parsed_url.path().to_string(),
+        params,
+    })
+}
+
+// 允许多实例运行的环境变量（值为 1/true/yes 时生效）
+//
+// 默认启用单实例：第二个进程会把参数转交给已运行的实例后退出。
+// 开启多实例后每个进程独立运行，但 OAuth 回调服务器固定监听 8081 端口，
+// 且 atomic-heart:// 深度链接只会交给操作系统选中的某一个进程，
+// 因此同时运行多个实例时登录授权只会在其中一个实例中完成。
+const ALLOW_MULTI_INSTANCE_ENV: &str = "GITHUB_HEART_ALLOW_MULTI";
+
+// 最近获得焦点的窗口标签，深度链接优先投递到该窗口
+static LAST_FOCUSED_WINDOW: Mutex<Option<String>> = Mutex::new(None);
 
-        // 如果窗口被最小化或隐藏，则显示并聚焦
-        if let Some(window) = app_handle.get_webview_window("main") {
+// 是否允许多实例运行
+fn multi_instance_allowed() -> bool {
+    std::env::var(ALLOW_MULTI_INSTANCE_ENV)
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+// 获取深度链接的目标窗口：最近获得焦点的窗口，不存在时回退到主窗口
+fn deep_link_target_window(app: &tauri::AppHandle) -> Option<tauri::WebviewWindow> {
+    let last_focused = LAST_FOCUSED_WINDOW.lock().unwrap().clone();
+    last_focused
+        .and_then(|label| app.get_webview_window(&label))
+        .or_else(|| app.get_webview_window("main"))
+}
+
+// 将深度链接发送到目标窗口并将其置于前台
+fn route_deep_link(app: &tauri::AppHandle, payload: DeepLinkPayload) {
+    match deep_link_target_window(app) {
+        Some(window) => {
+            let _ = window.emit_to(window.label(), "deep-link-received", payload);
             let _ = window.show();
             let _ = window.set_focus();
             let _ = window.unminimize();
         }
+        None => {
+            let _ = app.emit("deep-link-received", payload);
+        }
+    }
+}
 
+// 在参数列表中查找并解析深度链接
+fn find_deep_link<I, S>(args: I) -> Option<DeepLinkPayload>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter()
+        .find(|arg| arg.as_ref().starts_with(DEEP_LINK_SCHEME))
+        .and_then(|arg| parse_deep_link(arg.as_ref()))
+}
+
+// 处理深度链接的命令
+#[tauri::command]
+async fn handle_deep_link(app_handle: tauri::AppHandle, url: String) -> Result<(), String> {
+    println!("收到深度链接: {}", url);
+
+    // 解析URL参数，发送到最近获得焦点的窗口并将其显示、聚焦
+    if let Some(payload) = parse_deep_link(&url) {
+        route_deep_link(&app_handle, payload);
         Ok(())
     } else {
         Err("无效的URL格式".to_string())
     }
 }
 
+// 获取冷启动时携带的深度链接（只会返回一次）
+#[tauri::command]
+async fn consume_startup_deep_link() -> Result<Option<DeepLinkPayload>, String> {
+    if STARTUP_DEEP_LINK_CONSUMED.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        return Ok(None);
+    }
+
+    Ok(find_deep_link(std::env::args()))
+}
+
 // 注册自定义协议的命令
 #[tauri::command]
 async fn register_protocol_handler() -> Result<String, String> {
     // 在Windows上，协议会在安装时自动注册
     // 这个命令主要用于获取协议信息
-    Ok("atomic-heart://".to_string())
+    Ok(DEEP_LINK_SCHEME.to_string())
 }
 
 // 获取启动参数的命令
@@ -146,38 +324,60 @@ async fn get_startup_args() -> Result<Vec<String>, String> {
     Ok(args)
 }
 
+// 更换应用标识前使用的模板标识，应用数据目录以此命名
+const LEGACY_APP_IDENTIFIER: &str = "com.tauri-app.app";
+
+// 将旧应用标识数据目录中的存储文件复制到当前数据目录（仅在当前目录尚不存在时）
+fn migrate_legacy_app_data(app_data_dir: &std::path::Path) -> std::io::Result<usize> {
+    let Some(legacy_dir) = app_data_dir
+        .parent()
+        .map(|parent| parent.join(LEGACY_APP_IDENTIFIER))
+    else {
+        return Ok(0);
+    };
+    if app_data_dir.exists() || !legacy_dir.is_dir() {
+        return Ok(0);
+    }
+
+    std::fs::create_dir_all(app_data_dir)?;
+    let mut copied = 0;
+    for entry in std::fs::read_dir(&legacy_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            std::fs::copy(entry.path(), app_data_dir.join(entry.file_name()))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let mut builder = tauri::Builder::default();
 
-    // 配置单实例模式（仅桌面平台）
+    // 配置单实例模式（仅桌面平台，可通过 GITHUB_HEART_ALLOW_MULTI 关闭）
     #[cfg(desktop)]
     {
-        builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
-            println!("检测到新实例启动，参数: {argv:?}");
-
-            // 将现有窗口置于前台
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.show();
-                let _ = window.set_focus();
-                let _ = window.unminimize();
-            }
+        if multi_instance_allowed() {
+            println!("已允许多实例运行（{}）", ALLOW_MULTI_INSTANCE_ENV);
+        } else {
+            builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+                println!("检测到新实例启动，参数: {argv:?}");
 
-            // 处理深度链接参数
-            for arg in argv.iter() {
-                if arg.starts_with("atomic-heart://") {
-                    // 发送深度链接事件到现有实例
-                    let _ = app.emit(
-                        "deep-link-received",
-                        serde_json::json!({
-                            "url": arg,
-                            "source": "new_instance"
-                        }),
-                    );
-                    break;
+                // 处理深度链接参数，发送深度链接事件到现有实例
+                match find_deep_link(&argv) {
+                    Some(payload) => route_deep_link(app, payload),
+                    None => {
+                        // 将现有窗口置于前台
+                        if let Some(window) = deep_link_target_window(app) {
+                            let _ = window.show();
+                            let _ = window.set_focus();
+                            let _ = window.unminimize();
+                        }
+                    }
                 }
-            }
-        }));
+            }));
+        }
     }
 
     builder
@@ -187,16 +387,56 @@ pub fn run() {
         .plugin(tauri_plugin_oauth::init())
         .plugin(tauri_plugin_deep_link::init())
         .manage(commands::git::GitState::default())
+        .on_window_event(|window, event| {
+            // 记录最近获得焦点的窗口
+            if let tauri::WindowEvent::Focused(true) = event {
+                *LAST_FOCUSED_WINDOW.lock().unwrap() = Some(window.label().to_string());
+            }
+        })
         .setup(|app| {
             // 设置深度链接处理
             use tauri_plugin_deep_link::DeepLinkExt;
 
             // 监听深度链接事件
-            app.deep_link().on_open_url(|event| {
+            let app_handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
                 println!("深度链接 URLs: {:?}", event.urls());
-                // 这里的事件会被单实例插件处理，主要用于日志记录
+
+                // 单实例模式下事件由单实例插件处理，这里仅记录日志；
+                // 多实例模式下由本进程直接投递到最近获得焦点的窗口
+                if multi_instance_allowed() {
+                    if let Some(payload) =
+                        find_deep_link(event.urls().iter().map(|url| url.as_str()))
+                    {
+                        route_deep_link(&app_handle, payload);
+                    }
+                }
             });
 
+            // 凭据存储在以应用标识命名的密钥环服务下
+            git::AuthManager::set_keyring_service(&app.config().identifier);
+
+            // 沿用旧应用标识下保存的 Token、仓库列表等存储文件
+            match app.path().app_data_dir() {
+                Ok(app_data_dir) => match migrate_legacy_app_data(&app_data_dir) {
+                    Ok(0) => {}
+                    Ok(copied) => println!("已迁移旧版应用数据文件: {} 个", copied),
+                    Err(e) => eprintln!("迁移旧版应用数据失败: {}", e),
+                },
+                Err(e) => eprintln!("无法获取应用数据目录: {}", e),
+            }
+
+            // 远程操作缺少凭据时，通过事件请求前端输入
+            let prompt_handle = app.handle().clone();
+            git::credential_prompt::install(git::credential_prompt::CredentialPrompter::new(
+                git::credential_prompt::CREDENTIAL_PROMPT_TIMEOUT,
+                move |request| {
+                    prompt_handle
+                        .emit(git::credential_prompt::CREDENTIAL_REQUEST_EVENT, request)
+                        .is_ok()
+                },
+            ));
+
             // 在开发模式下注册深度链接（仅限 Windows 和 Linux）
             #[cfg(any(windows, target_os = "linux"))]
             {
@@ -212,6 +452,7 @@ pub fn run() {
             open_url,
             start_oauth_callback_server, // 添加新命令
             handle_deep_link,
+            consume_startup_deep_link,
             register_protocol_handler,
             get_startup_args,
             http_client::http_get,
@@ -219,65 +460,219 @@ pub fn run() {
             http_client::http_put,
             http_client::http_patch,
             http_client::http_delete,
+            http_client::cancel_http_request,
+            http_client::set_default_headers,
+            http_client::get_default_headers,
+            forge::create_pull_request,
             // Git 命令
             commands::git::clone_repository,
+            commands::git::smart_clone_repository,
             commands::git::validate_repository_url,
+            commands::git::parse_repository_url,
+            commands::git::test_repository_access,
+            commands::git::list_remote_refs,
             commands::git::detect_auth_type,
             commands::git::get_default_ssh_keys,
             commands::git::validate_ssh_key,
+            commands::git::generate_ssh_key,
+            commands::git::list_ssh_keys,
+            commands::git::get_public_key,
             commands::git::store_credentials,
             commands::git::load_credentials,
             commands::git::delete_credentials,
+            commands::git::list_stored_credentials,
+            commands::git::delete_all_credentials,
             commands::git::extract_username_from_url,
+            commands::git::provide_credentials,
             commands::git::cancel_clone_operation,
+            commands::git::cancel_git_operation,
             commands::git::get_clone_operation_status,
             commands::git::cleanup_clone_operation,
             commands::git::select_directory,
             commands::git::select_ssh_key_file,
             commands::git::validate_clone_directory,
             // 新增的 Git 信息获取命令
+            commands::git::init_repository,
             commands::git::is_git_repository,
+            commands::git::get_repository_root,
             commands::git::get_repository_info,
             commands::git::get_current_branch,
             commands::git::get_remote_url,
             commands::git::open_folder,
+            commands::git::open_file,
+            commands::git::open_in_editor,
             // Git 操作命令
             commands::git::get_repository_status,
+            commands::git::get_repositories_summary,
+            commands::git::get_change_counts,
+            commands::git::get_repository_state,
+            commands::git::resolve_ref,
+            commands::git::abort_operation,
+            commands::git::merge_branch,
+            commands::git::continue_merge,
+            commands::git::cleanup_repository_state,
+            commands::git::repair_repository,
+            commands::git::get_repository_size,
+            commands::git::gc_repository,
             commands::git::stage_files,
             commands::git::unstage_files,
+            commands::git::find_large_files,
+            commands::git::add_to_gitignore,
+            commands::git::is_path_ignored,
+            commands::git::get_path_status,
             commands::git::create_commit,
+            commands::git::validate_commit_message,
+            commands::git::verify_commit_signature,
+            commands::git::reword_head,
+            commands::git::set_user_identity,
+            commands::git::get_commit_template,
+            commands::git::stash_save,
+            commands::git::stash_list,
+            commands::git::stash_show,
             commands::git::get_commit_history,
+            commands::git::export_commit_history,
             commands::git::get_file_diff,
+            commands::git::blame_file,
+            commands::git::get_file_diff_against,
+            commands::git::get_diff_stat,
+            commands::git::get_staged_diff,
+            commands::git::get_unstaged_diff,
             // 同步操作命令
             commands::git::fetch_remote,
+            commands::git::prune_remote,
             commands::git::pull_remote,
             commands::git::push_remote,
             commands::git::get_remote_info,
+            commands::git::refresh_remote_default_branch,
+            commands::git::commit_and_push,
+            commands::git::push_preview,
+            commands::git::list_tree,
+            commands::git::stat_path,
+            commands::git::restore_file_from_commit,
+            commands::git::get_merge_base,
+            commands::git::get_merge_bases,
+            commands::git::get_tracking_status,
             // 智能Git操作（支持Token认证）
             commands::git::smart_fetch_remote,
+            commands::git::fetch_and_report,
+            commands::git::preview_pull_changes,
             commands::git::smart_push_remote,
             // 双协议认证系统
             commands::git::detect_repository_protocol,
+            commands::git::detect_repository_remote,
             commands::git::extract_domain_from_url,
             commands::git::store_access_token,
             commands::git::get_access_token,
             commands::git::delete_access_token,
             commands::git::get_all_tokens,
+            commands::git::add_recent_repository,
+            commands::git::get_recent_repositories,
+            commands::git::remove_recent_repository,
             commands::git::update_token_last_used,
             // 系统Git命令
             commands::git::fetch_remote_with_system_git,
+            commands::git::unshallow,
             commands::git::push_remote_with_system_git,
             commands::git::pull_remote_with_system_git,
+            commands::git::scan_host_key,
+            commands::git::archive_repository,
+            commands::git::rebase_interactive,
+            commands::git::continue_rebase,
             // 远程名称检测
             commands::git::detect_repository_remotes,
             commands::git::get_default_remote_name_command,
+            commands::git::get_preferred_remote,
+            commands::git::set_preferred_remote,
             // 分支管理
             commands::git::list_branches,
+            commands::git::list_branches_lite,
+            commands::git::branches_containing,
+            commands::git::ref_exists,
             commands::git::create_branch,
+            commands::git::validate_branch_name,
             commands::git::switch_branch,
+            commands::git::checkout_commit,
             commands::git::delete_branch,
-            commands::git::checkout_remote_branch
+            commands::git::delete_remote_branch,
+            commands::git::checkout_remote_branch,
+            commands::git::checkout_pull_request,
+            commands::git::set_branch_upstream
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_validate_oauth_scheme() {
+        for scheme in ["atomic-heart", "my.app+auth", "A1"] {
+            assert!(validate_oauth_scheme(scheme).is_ok(), "{}", scheme);
+        }
+        for scheme in ["", "1app", "-app", "app://", "my app", "app/x", "应用"] {
+            assert!(validate_oauth_scheme(scheme).is_err(), "{}", scheme);
+        }
+    }
+
+    #[test]
+    fn test_render_oauth_page_escapes_parameters() {
+        let params: HashMap<String, String> = [
+            ("code".to_string(), "a&b=c".to_string()),
+            ("state".to_string(), "x'y\\z".to_string()),
+            (
+                "error".to_string(),
+                "<script>alert(\"x\")</script>".to_string(),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let page = render_oauth_page(
+            "<a href='{{deep_link}}'>{{code}}|{{state}}</a><p>{{error}}</p>",
+            "atomic-heart",
+            &params,
+        );
+
+        assert_eq!(
+            page,
+            "<a href='atomic-heart://auth/callback?code=a%26b%3Dc&state=x%27y%5Cz'>\
+             a&amp;b=c|x&#39;y&#92;z</a>\
+             <p>&lt;script&gt;alert(&quot;x&quot;)&lt;/script&gt;</p>"
+        );
+    }
+
+    #[test]
+    fn test_migrate_legacy_app_data_copies_store_files_once() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let legacy_dir = dir.path().join(LEGACY_APP_IDENTIFIER);
+        std::fs::create_dir_all(legacy_dir.join("cache")).unwrap();
+        std::fs::write(legacy_dir.join("tokens.json"), "{}").unwrap();
+        let app_data_dir = dir.path().join("com.githubheart.app");
+
+        assert_eq!(migrate_legacy_app_data(&app_data_dir).unwrap(), 1);
+        assert!(app_data_dir.join("tokens.json").exists());
+        assert!(!app_data_dir.join("cache").exists());
+
+        // 当前数据目录已存在时不再覆盖
+        std::fs::write(legacy_dir.join("tokens.json"), "{\"stale\":1}").unwrap();
+        assert_eq!(migrate_legacy_app_data(&app_data_dir).unwrap(), 0);
+        assert_eq!(
+            std::fs::read_to_string(app_data_dir.join("tokens.json")).unwrap(),
+            "{}"
+        );
+    }
+
+    #[test]
+    fn test_render_oauth_page_with_missing_parameters() {
+        let page = render_oauth_page(
+            DEFAULT_OAUTH_DENIED_HTML,
+            DEFAULT_OAUTH_SCHEME,
+            &HashMap::new(),
+        );
+        assert!(page.contains("<p></p>"));
+        assert!(!page.contains("{{"));
+    }
+}
@@ -0,0 +1,290 @@
+use crate::git::types::GitError;
+use std::collections::HashMap;
+use std::process::{ExitStatus, Output, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio_util::sync::CancellationToken;
+
+/// 子进程退出后等待读取剩余输出的最长时间
+///
+/// SSH 等孙进程可能在 Git 退出后仍持有管道
+const PIPE_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 系统Git子进程的执行结果
+#[derive(Debug)]
+pub enum GitProcessOutcome {
+    /// 子进程正常退出（退出码可能非零）
+    Finished(Output),
+    /// 被 `cancel` 取消，子进程已终止
+    Cancelled,
+    /// 超时，子进程已终止
+    TimedOut,
+}
+
+/// 正在运行的系统Git子进程，按操作ID登记以便中途取消
+#[derive(Default)]
+pub struct GitProcesses {
+    running: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl GitProcesses {
+    /// 运行Git命令并收集输出，超时或被取消时终止子进程及其进程组
+    ///
+    /// 提供 `operation_id` 时子进程在运行期间登记，可通过 `cancel` 中途取消；
+    /// 同一ID的操作仍在运行时返回 `GitError::DuplicateOperation`
+    pub async fn run(
+        &self,
+        operation_id: Option<&str>,
+        mut cmd: tokio::process::Command,
+        timeout: Duration,
+    ) -> Result<GitProcessOutcome, GitError> {
+        let token = CancellationToken::new();
+        let _registration = match operation_id {
+            Some(id) => Some(self.register(id, token.clone())?),
+            None => None,
+        };
+
+        cmd.stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // 子进程作为新进程组的组长，终止时连同 SSH 等孙进程一起终止
+        #[cfg(unix)]
+        cmd.process_group(0);
+        let mut child = cmd.spawn().map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => GitError::SystemGitNotFound,
+            _ => GitError::Io(e),
+        })?;
+
+        let stdout = tokio::spawn(read_pipe(child.stdout.take()));
+        let stderr = tokio::spawn(read_pipe(child.stderr.take()));
+
+        let event = tokio::select! {
+            status = child.wait() => ProcessEvent::Exited(status),
+            _ = token.cancelled() => ProcessEvent::Cancelled,
+            _ = tokio::time::sleep(timeout) => ProcessEvent::TimedOut,
+        };
+
+        let outcome = match event {
+            ProcessEvent::Exited(status) => {
+                let status = status.map_err(GitError::Io)?;
+                return Ok(GitProcessOutcome::Finished(Output {
+                    status,
+                    stdout: drain_pipe(stdout).await,
+                    stderr: drain_pipe(stderr).await,
+                }));
+            }
+            ProcessEvent::Cancelled => GitProcessOutcome::Cancelled,
+            ProcessEvent::TimedOut => GitProcessOutcome::TimedOut,
+        };
+
+        kill_process_group(&child);
+        let _ = child.kill().await;
+        // 孙进程可能仍持有管道，不再等待读取
+        stdout.abort();
+        stderr.abort();
+        Ok(outcome)
+    }
+
+    /// 登记正在运行的操作，ID 已被占用时拒绝
+    fn register(
+        &self,
+        operation_id: &str,
+        token: CancellationToken,
+    ) -> Result<Registration<'_>, GitError> {
+        let mut running = self.running.lock().unwrap();
+        if running.contains_key(operation_id) {
+            return Err(GitError::DuplicateOperation {
+                operation_id: operation_id.to_string(),
+            });
+        }
+        running.insert(operation_id.to_string(), token);
+        Ok(Registration {
+            processes: self,
+            operation_id: operation_id.to_string(),
+        })
+    }
+
+    /// 取消正在运行的操作，操作不存在或已结束时返回 false
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self.running.lock().unwrap().remove(operation_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+enum ProcessEvent {
+    Exited(std::io::Result<ExitStatus>),
+    Cancelled,
+    TimedOut,
+}
+
+/// 操作结束时注销登记
+struct Registration<'a> {
+    processes: &'a GitProcesses,
+    operation_id: String,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.processes
+            .running
+            .lock()
+            .unwrap()
+            .remove(&self.operation_id);
+    }
+}
+
+/// 终止子进程所在的进程组（子进程以自身为组长启动）
+#[cfg(unix)]
+fn kill_process_group(child: &tokio::process::Child) {
+    if let Some(pid) = child.id() {
+        // SAFETY: killpg 只向指定进程组发送信号，不涉及内存访问
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_child: &tokio::process::Child) {}
+
+/// 等待读取管道的任务结束，超过 `PIPE_DRAIN_TIMEOUT` 时放弃剩余输出
+async fn drain_pipe(mut task: tokio::task::JoinHandle<Vec<u8>>) -> Vec<u8> {
+    match tokio::time::timeout(PIPE_DRAIN_TIMEOUT, &mut task).await {
+        Ok(output) => output.unwrap_or_default(),
+        Err(_) => {
+            log::warn!("Git进程已退出，但输出管道仍被占用，放弃读取剩余输出");
+            task.abort();
+            Vec::new()
+        }
+    }
+}
+
+async fn read_pipe<R: tokio::io::AsyncRead + Unpin>(pipe: Option<R>) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buffer).await;
+    }
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    fn git_command(args: &[&str]) -> tokio::process::Command {
+        let mut cmd = tokio::process::Command::new("git");
+        cmd.args(args);
+        cmd
+    }
+
+    #[tokio::test]
+    async fn test_cancel_kills_running_git_process() {
+        let processes = Arc::new(GitProcesses::default());
+        let cancel = {
+            let processes = processes.clone();
+            tokio::spawn(async move {
+                while !processes.cancel("op-1") {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+            })
+        };
+
+        let started = Instant::now();
+        let outcome = match processes
+            .run(
+                Some("op-1"),
+                git_command(&["-c", "alias.wait=!sleep 30", "wait"]),
+                Duration::from_secs(60),
+            )
+            .await
+        {
+            Ok(outcome) => outcome,
+            // 系统未安装Git
+            Err(GitError::SystemGitNotFound) => return,
+            Err(e) => panic!("{}", e),
+        };
+        cancel.await.unwrap();
+
+        assert!(matches!(outcome, GitProcessOutcome::Cancelled));
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(!processes.cancel("op-1"));
+    }
+
+    #[tokio::test]
+    async fn test_finished_process_is_unregistered() {
+        let processes = GitProcesses::default();
+        let outcome = match processes
+            .run(
+                Some("op-2"),
+                git_command(&["--version"]),
+                Duration::from_secs(30),
+            )
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(GitError::SystemGitNotFound) => return,
+            Err(e) => panic!("{}", e),
+        };
+
+        match outcome {
+            GitProcessOutcome::Finished(output) => {
+                assert!(output.status.success());
+                assert!(String::from_utf8_lossy(&output.stdout).starts_with("git version"));
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+        assert!(!processes.cancel("op-2"));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_operation_id_is_rejected() {
+        let processes = Arc::new(GitProcesses::default());
+        let running = {
+            let processes = processes.clone();
+            tokio::spawn(async move {
+                processes
+                    .run(
+                        Some("op-3"),
+                        git_command(&["-c", "alias.wait=!sleep 30", "wait"]),
+                        Duration::from_secs(60),
+                    )
+                    .await
+            })
+        };
+
+        // 等待第一个操作登记后，同一ID的操作被拒绝
+        let started = Instant::now();
+        loop {
+            let result = processes
+                .run(
+                    Some("op-3"),
+                    git_command(&["--version"]),
+                    Duration::from_secs(30),
+                )
+                .await;
+            match result {
+                Err(GitError::DuplicateOperation { operation_id }) => {
+                    assert_eq!(operation_id, "op-3");
+                    break;
+                }
+                Err(GitError::SystemGitNotFound) => return,
+                _ => {
+                    assert!(started.elapsed() < Duration::from_secs(10));
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+            }
+        }
+
+        assert!(processes.cancel("op-3"));
+        let outcome = running.await.unwrap().unwrap();
+        assert!(matches!(outcome, GitProcessOutcome::Cancelled));
+    }
+}
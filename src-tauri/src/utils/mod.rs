@@ -1,4 +1,7 @@
 /// 系统命令工具模块
 ///
 /// 提供跨平台的系统命令执行功能，特别是在Windows平台上隐藏控制台窗口
+pub mod git_process;
+pub mod progress;
+pub mod repo_lock;
 pub mod system_command;
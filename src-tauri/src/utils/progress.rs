@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+/// 进度事件的最小发送间隔
+pub const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 进度事件节流器
+///
+/// libgit2 的进度回调触发非常频繁，逐次发送事件会占满 webview 的 IPC 通道，
+/// 节流后每个间隔内最多发送一次，最终进度总是发送
+///
+/// libgit2 在完成后可能多次重复报告最终进度，最终进度只在首次出现时绕过节流，
+/// 之后出现非最终进度（例如进入下一阶段）时重新生效
+pub struct ProgressThrottle {
+    interval: Duration,
+    last_emit: Option<Instant>,
+    final_emitted: bool,
+}
+
+impl Default for ProgressThrottle {
+    fn default() -> Self {
+        Self::new(PROGRESS_EMIT_INTERVAL)
+    }
+}
+
+impl ProgressThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_emit: None,
+            final_emitted: false,
+        }
+    }
+
+    /// 判断本次进度是否需要发送，`is_final` 为 true 时首次总是发送
+    pub fn should_emit(&mut self, is_final: bool) -> bool {
+        self.should_emit_at(Instant::now(), is_final)
+    }
+
+    fn should_emit_at(&mut self, now: Instant, is_final: bool) -> bool {
+        let throttled = matches!(
+            self.last_emit,
+            Some(last) if now.duration_since(last) < self.interval
+        );
+        let bypass = is_final && !self.final_emitted;
+        if !is_final {
+            self.final_emitted = false;
+        }
+        if throttled && !bypass {
+            return false;
+        }
+
+        if is_final {
+            self.final_emitted = true;
+        }
+        self.last_emit = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emit_rate_is_bounded_under_rapid_progress() {
+        let mut throttle = ProgressThrottle::default();
+        let start = Instant::now();
+
+        // 一秒内产生 10000 次进度回调，最后一次为最终进度
+        let total = 10_000u64;
+        let emitted: Vec<u64> = (1..=total)
+            .filter(|&step| {
+                let now = start + Duration::from_micros(step * 100);
+                throttle.should_emit_at(now, step == total)
+            })
+            .collect();
+
+        assert_eq!(emitted.first(), Some(&1));
+        assert_eq!(emitted.last(), Some(&total));
+        assert!(emitted.len() <= 12, "发送了 {} 次进度事件", emitted.len());
+    }
+
+    #[test]
+    fn test_repeated_final_progress_bypasses_throttle_once() {
+        let mut throttle = ProgressThrottle::default();
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+
+        assert!(throttle.should_emit_at(at(0), false));
+        assert!(throttle.should_emit_at(at(1), true));
+        // 重复的最终进度按节流处理
+        assert!(!throttle.should_emit_at(at(2), true));
+        assert!(!throttle.should_emit_at(at(3), true));
+        assert!(throttle.should_emit_at(at(200), true));
+
+        // 进入下一阶段后，该阶段的最终进度再次绕过节流
+        assert!(!throttle.should_emit_at(at(201), false));
+        assert!(throttle.should_emit_at(at(202), true));
+    }
+}
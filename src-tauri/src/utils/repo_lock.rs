@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+/// 按仓库划分的写操作锁
+///
+/// 同一仓库上的写操作（暂存、提交、拉取、推送、合并、变基、分支操作等）依次排队执行，
+/// 不同仓库之间以及只读操作不受影响
+#[derive(Default)]
+pub struct RepositoryLocks {
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl RepositoryLocks {
+    /// 获取仓库写锁，若已有写操作在进行则等待其完成
+    ///
+    /// 仓库子目录与仓库根目录共用同一把锁
+    pub async fn lock(&self, repo_path: &str) -> OwnedMutexGuard<()> {
+        let lock = self.lock_for(repo_path);
+        lock.lock_owned().await
+    }
+
+    /// 尝试立即获取仓库写锁，仓库忙时返回 None
+    pub fn try_lock(&self, repo_path: &str) -> Option<OwnedMutexGuard<()>> {
+        self.lock_for(repo_path).try_lock_owned().ok()
+    }
+
+    fn lock_for(&self, repo_path: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let key = lock_key(repo_path);
+        let mut locks = self.locks.lock().unwrap();
+        locks.entry(key).or_default().clone()
+    }
+}
+
+/// 计算锁的键：优先使用仓库根目录的规范路径
+fn lock_key(repo_path: &str) -> String {
+    let root = crate::git::operations::get_repository_root(repo_path)
+        .unwrap_or_else(|| repo_path.to_string());
+    Path::new(&root)
+        .canonicalize()
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Repository;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_lock_serializes_tasks_on_repository_root_and_subdirectory() {
+        let dir = TempDir::new().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let sub_dir = dir.path().join("src");
+        std::fs::create_dir(&sub_dir).unwrap();
+
+        let locks = Arc::new(RepositoryLocks::default());
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let mut handles = Vec::new();
+        for (name, path) in [
+            ("root", dir.path().to_string_lossy().to_string()),
+            ("subdir", sub_dir.to_string_lossy().to_string()),
+        ] {
+            let locks = locks.clone();
+            let events = events.clone();
+            handles.push(tokio::spawn(async move {
+                let _guard = locks.lock(&path).await;
+                events.lock().unwrap().push(format!("{name}:start"));
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                events.lock().unwrap().push(format!("{name}:end"));
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 4);
+        for pair in events.chunks(2) {
+            let name = pair[0].trim_end_matches(":start");
+            assert_eq!(pair[1], format!("{name}:end"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_different_repositories_do_not_block() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let locks = RepositoryLocks::default();
+
+        let _guard = locks.lock(&first.path().to_string_lossy()).await;
+        assert!(locks.try_lock(&first.path().to_string_lossy()).is_none());
+        assert!(locks.try_lock(&second.path().to_string_lossy()).is_some());
+    }
+}
@@ -93,6 +93,50 @@ pub async fn execute_hidden_command_async(
     cmd.output().await
 }
 
+/// 构建系统Git使用的SSH命令（GIT_SSH_COMMAND）
+///
+/// 未指定密钥时不添加 `-i`，OpenSSH 会按 `~/.ssh/config` 解析主机别名和密钥。
+/// 主机密钥校验选项只在选择了密钥、指定了 known_hosts 文件或要求严格校验时添加：
+/// 选择了密钥时默认使用 `StrictHostKeyChecking=accept-new`（与克隆流程一致），
+/// `strict` 为 true 时要求主机密钥必须已存在于 known_hosts 中。
+///
+/// `respect_environment` 为 true 时返回 None，完全不覆盖用户环境中的 GIT_SSH_COMMAND
+pub fn build_git_ssh_command(
+    ssh_key_path: Option<&str>,
+    known_hosts_file: Option<&str>,
+    strict: Option<bool>,
+    respect_environment: bool,
+) -> Option<String> {
+    if respect_environment {
+        log::debug!("使用用户环境中的SSH配置");
+        return None;
+    }
+
+    let mut ssh_command = String::from("ssh");
+
+    if let Some(ssh_key) = ssh_key_path {
+        ssh_command.push_str(&format!(" -i \"{}\"", ssh_key));
+        log::debug!("使用SSH密钥: {}", ssh_key);
+    }
+
+    let checking = match strict {
+        Some(true) => Some("yes"),
+        _ if ssh_key_path.is_some() => Some("accept-new"),
+        _ => None,
+    };
+    if let Some(checking) = checking {
+        ssh_command.push_str(&format!(" -o StrictHostKeyChecking={}", checking));
+    }
+
+    if let Some(known_hosts) = known_hosts_file {
+        ssh_command.push_str(&format!(" -o UserKnownHostsFile=\"{}\"", known_hosts));
+        log::debug!("使用known_hosts文件: {}", known_hosts);
+    }
+
+    ssh_command.push_str(" -o ConnectTimeout=10 -o BatchMode=yes");
+    Some(ssh_command)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,4 +161,36 @@ mod tests {
             assert!(version_str.contains("git version"));
         }
     }
+
+    #[test]
+    fn test_build_git_ssh_command_without_key_uses_ssh_config() {
+        let command = build_git_ssh_command(None, None, None, false).unwrap();
+        assert_eq!(command, "ssh -o ConnectTimeout=10 -o BatchMode=yes");
+
+        let command = build_git_ssh_command(None, None, Some(true), false).unwrap();
+        assert_eq!(
+            command,
+            "ssh -o StrictHostKeyChecking=yes -o ConnectTimeout=10 -o BatchMode=yes"
+        );
+    }
+
+    #[test]
+    fn test_build_git_ssh_command_with_key() {
+        let command =
+            build_git_ssh_command(Some("/keys/id_ed25519"), Some("/keys/known"), None, false)
+                .unwrap();
+        assert_eq!(
+            command,
+            "ssh -i \"/keys/id_ed25519\" -o StrictHostKeyChecking=accept-new \
+             -o UserKnownHostsFile=\"/keys/known\" -o ConnectTimeout=10 -o BatchMode=yes"
+        );
+    }
+
+    #[test]
+    fn test_build_git_ssh_command_respects_environment() {
+        assert_eq!(
+            build_git_ssh_command(Some("/keys/id_ed25519"), None, Some(true), true),
+            None
+        );
+    }
 }